@@ -0,0 +1,614 @@
+//! `kobafin-admin`: protocol administration for kobafin_escrow.
+//!
+//! Signs with the keypair given by `--keypair`, which must hold the role the
+//! command needs (upgrade admin, fee admin, pause guardian or registry admin);
+//! read-only commands need no keypair.
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, AccountDeserialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use clap::{Parser, Subcommand, ValueEnum};
+use kobafin_escrow::{Config, ConfigRole, FeeTreasury, OutflowBreaker, PodPolicy, ProtocolRegistry, Vault, VALUATION_EMA};
+use kobafin_escrow_client::{accounts, build, build_with_remaining, fetch, fetch_config, instruction, pda, ClientError};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(name = "kobafin-admin", about = "Protocol administration for kobafin_escrow")]
+struct Cli {
+    #[arg(long, env = "SOLANA_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    url: String,
+
+    /// Keypair file of the role holder.
+    #[arg(long, env = "KOBA_ADMIN_KEYPAIR")]
+    keypair: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Role {
+    UpgradeAdmin,
+    FeeAdmin,
+    PauseGuardian,
+    RegistryAdmin,
+}
+
+impl From<Role> for ConfigRole {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::UpgradeAdmin => ConfigRole::UpgradeAdmin,
+            Role::FeeAdmin => ConfigRole::FeeAdmin,
+            Role::PauseGuardian => ConfigRole::PauseGuardian,
+            Role::RegistryAdmin => ConfigRole::RegistryAdmin,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates the Config PDA with the signer, who must be the program upgrade authority, as admin.
+    InitConfig {
+        #[arg(long, default_value_t = 0)]
+        referral_bps: u16,
+    },
+    UpdateConfig {
+        #[arg(long)]
+        referral_bps: u16,
+    },
+    /// Starts handing `role` to `new_holder` (signed by the current holder;
+    /// the default pubkey cancels).
+    ProposeRole { role: Role, new_holder: Pubkey },
+    /// Completes a pending hand-over (signed by the new holder).
+    AcceptRole { role: Role },
+    SetPerformanceFee {
+        #[arg(long)]
+        bps: u16,
+    },
+    SetCompoundParams {
+        #[arg(long)]
+        min_amount: u64,
+        #[arg(long)]
+        cooldown_secs: i64,
+    },
+    SetComplianceThreshold { threshold: u64 },
+    SetMemoThreshold { threshold: u64 },
+    /// Largest token balance (raw units) `sweep_dust` may clear.
+    SetDustThreshold { threshold: u64 },
+    SetSolPriceFeed { feed: Pubkey },
+    /// Sets or rotates this cluster's canonical USDC mint for new vaults.
+    SetUsdcMint { mint: Pubkey },
+    /// Slippage allowed below the oracle rate on venue swaps (0 = program default).
+    SetMaxSwapSlippage { bps: u16 },
+    /// Minimum deposit and per-pot balance cap, in USDC units (0 disables either).
+    SetDepositLimits {
+        #[arg(long, default_value_t = 0)]
+        min_deposit: u64,
+        #[arg(long, default_value_t = 0)]
+        max_pot_balance: u64,
+    },
+    /// Exempts a whitelisted vault from the deposit limits (`--revoke` to undo).
+    ExemptVault {
+        vault: Pubkey,
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Publishes a new per-vault parameter set; roll it out with `migrate-vaults`.
+    SetVaultParams {
+        #[arg(long)]
+        performance_fee_bps: u16,
+        #[arg(long, default_value_t = 0)]
+        exec_cooldown_slots: u64,
+    },
+    /// Applies the current vault parameter set to every vault still below it.
+    MigrateVaults {
+        #[arg(long, default_value_t = 20)]
+        batch: usize,
+    },
+    /// Creates the global OutflowBreaker counter; enable it with `set-outflow-limits`.
+    InitOutflowBreaker,
+    /// Withdrawals above the per-user cap need the guardian's co-sign once the
+    /// window's outflows pass the threshold (`--window-secs 0` disables).
+    SetOutflowLimits {
+        #[arg(long)]
+        window_secs: i64,
+        #[arg(long, default_value_t = 0)]
+        lamports_threshold: u64,
+        #[arg(long, default_value_t = 0)]
+        lamports_user_cap: u64,
+        #[arg(long, default_value_t = 0)]
+        usdc_threshold: u64,
+        #[arg(long, default_value_t = 0)]
+        usdc_user_cap: u64,
+    },
+    /// Creates the FeeTreasury PDA and routes all protocol fees to it.
+    InitFeeTreasury,
+    /// Sweeps the treasury's SOL (or, with `--mint`, that token) to `destination`.
+    SweepFees {
+        destination: Pubkey,
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Caps an owner's withdrawal fee (0 = fee-free) until `--until` (0 = no expiry).
+    AssignFeeTier {
+        owner: Pubkey,
+        #[arg(long)]
+        max_fee_bps: u16,
+        #[arg(long, default_value_t = 0)]
+        until: i64,
+    },
+    RevokeFeeTier { owner: Pubkey },
+    /// Creates the protocol registry PDA.
+    InitRegistry,
+    /// Adds or updates a venue in the protocol registry.
+    SetProtocol {
+        #[arg(long)]
+        venue: u8,
+        #[arg(long)]
+        program: Pubkey,
+        #[arg(long, default_value_t = Pubkey::default())]
+        mint: Pubkey,
+        #[arg(long)]
+        disabled: bool,
+    },
+    /// Blocks withdrawals from a vault (guardian keypair).
+    Pause { vault: Pubkey },
+    /// Lifts a guardian freeze (guardian keypair).
+    Unpause { vault: Pubkey },
+    ShowConfig,
+    ShowRegistry,
+    /// Prints any kobafin_escrow vault, policy, Config or registry account.
+    Inspect { account: Pubkey },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+    let signer = || -> Result<Keypair, Box<dyn std::error::Error>> {
+        let path = cli.keypair.as_deref().ok_or("--keypair is required for this command")?;
+        read_keypair_file(path).map_err(|e| format!("reading {path}: {e}").into())
+    };
+    let (config, _) = pda::config_pda();
+
+    match cli.command {
+        Command::InitConfig { referral_bps } => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::InitConfig {
+                    admin: admin.pubkey(),
+                    config,
+                    program: kobafin_escrow::ID,
+                    program_data: pda::program_data_pda().0,
+                    system_program: system_program::ID,
+                },
+                instruction::InitConfig { referral_bps },
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::UpdateConfig { referral_bps } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_fee_config(&admin, instruction::UpdateConfig { referral_bps }))
+        }
+        Command::ProposeRole { role, new_holder } => {
+            let holder = signer()?;
+            let ix = build(
+                accounts::ProposeRole { holder: holder.pubkey(), config },
+                instruction::ProposeRole { role: role.into(), new_holder },
+            );
+            send(&rpc, &holder, ix)
+        }
+        Command::AcceptRole { role } => {
+            let pending = signer()?;
+            let ix = build(
+                accounts::AcceptRole { pending: pending.pubkey(), config },
+                instruction::AcceptRole { role: role.into() },
+            );
+            send(&rpc, &pending, ix)
+        }
+        Command::SetPerformanceFee { bps } => {
+            let admin = signer()?;
+            let args = instruction::SetPerformanceFee { performance_fee_bps: bps };
+            send(&rpc, &admin, update_fee_config(&admin, args))
+        }
+        Command::SetCompoundParams { min_amount, cooldown_secs } => {
+            let admin = signer()?;
+            let args = instruction::SetCompoundParams {
+                min_compound_amount: min_amount,
+                compound_cooldown_secs: cooldown_secs,
+            };
+            send(&rpc, &admin, update_config(&admin, args))
+        }
+        Command::SetComplianceThreshold { threshold } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetComplianceThreshold { threshold }))
+        }
+        Command::SetMemoThreshold { threshold } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetMemoThreshold { threshold }))
+        }
+        Command::SetDustThreshold { threshold } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetDustThreshold { threshold }))
+        }
+        Command::SetSolPriceFeed { feed } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetSolPriceFeed { feed }))
+        }
+        Command::SetUsdcMint { mint } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetUsdcMint { usdc_mint: mint }))
+        }
+        Command::SetMaxSwapSlippage { bps } => {
+            let admin = signer()?;
+            send(&rpc, &admin, update_config(&admin, instruction::SetMaxSwapSlippage { bps }))
+        }
+        Command::SetDepositLimits { min_deposit, max_pot_balance } => {
+            let admin = signer()?;
+            let args = instruction::SetDepositLimits {
+                min_deposit_usdc: min_deposit,
+                max_pot_balance_usdc: max_pot_balance,
+            };
+            send(&rpc, &admin, update_config(&admin, args))
+        }
+        Command::ExemptVault { vault, revoke } => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::AdminVault { admin: admin.pubkey(), config, vault },
+                instruction::SetVaultLimitsExempt { exempt: !revoke },
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::SetVaultParams { performance_fee_bps, exec_cooldown_slots } => {
+            let admin = signer()?;
+            let params = kobafin_escrow::VaultParams {
+                performance_fee_bps,
+                external_exec_cooldown_slots: exec_cooldown_slots,
+            };
+            send(&rpc, &admin, update_fee_config(&admin, instruction::SetVaultParams { params }))
+        }
+        Command::MigrateVaults { batch } => {
+            let admin = signer()?;
+            let version = fetch_config(&rpc)?.vault_params_version;
+            let pending = vaults_below_version(&rpc, version)?;
+            println!("{} vaults below version {version}", pending.len());
+            for chunk in pending.chunks(batch.max(1)) {
+                let ix = build_with_remaining(
+                    accounts::MigrateVaultParams { admin: admin.pubkey(), config, system_program: system_program::ID },
+                    instruction::MigrateVaultParams {},
+                    chunk.iter().map(|key| AccountMeta::new(*key, false)).collect(),
+                );
+                send(&rpc, &admin, ix)?;
+            }
+            Ok(())
+        }
+        Command::InitFeeTreasury => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::InitFeeTreasury {
+                    admin: admin.pubkey(),
+                    config,
+                    fee_treasury: pda::fee_treasury_pda().0,
+                    system_program: system_program::ID,
+                },
+                instruction::InitFeeTreasury {},
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::InitOutflowBreaker => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::InitOutflowBreaker {
+                    admin: admin.pubkey(),
+                    config,
+                    outflow_breaker: pda::outflow_breaker_pda().0,
+                    system_program: system_program::ID,
+                },
+                instruction::InitOutflowBreaker {},
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::SetOutflowLimits {
+            window_secs,
+            lamports_threshold,
+            lamports_user_cap,
+            usdc_threshold,
+            usdc_user_cap,
+        } => {
+            let admin = signer()?;
+            let limits = kobafin_escrow::OutflowLimits {
+                window_secs,
+                lamports_threshold,
+                lamports_user_cap,
+                usdc_threshold,
+                usdc_user_cap,
+            };
+            send(&rpc, &admin, update_config(&admin, instruction::SetOutflowLimits { limits }))
+        }
+        Command::SweepFees { destination, mint } => {
+            let admin = signer()?;
+            let fee_treasury = pda::fee_treasury_pda().0;
+            let ix = build(
+                accounts::SweepFees {
+                    admin: admin.pubkey(),
+                    config,
+                    fee_treasury,
+                    destination,
+                    treasury_token: mint.map(|m| get_associated_token_address(&fee_treasury, &m)),
+                    destination_token: mint.map(|m| get_associated_token_address(&destination, &m)),
+                    token_program: mint.map(|_| spl_token::ID),
+                },
+                instruction::SweepFees {},
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::AssignFeeTier { owner, max_fee_bps, until } => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::AssignFeeTier {
+                    admin: admin.pubkey(),
+                    config,
+                    fee_tier: pda::fee_tier_pda(&owner).0,
+                    system_program: system_program::ID,
+                },
+                instruction::AssignFeeTier { owner, max_fee_bps, until },
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::RevokeFeeTier { owner } => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::RevokeFeeTier { admin: admin.pubkey(), config, fee_tier: pda::fee_tier_pda(&owner).0 },
+                instruction::RevokeFeeTier { owner },
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::InitRegistry => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::InitProtocolRegistry {
+                    admin: admin.pubkey(),
+                    config,
+                    registry: pda::protocol_registry_pda().0,
+                    system_program: system_program::ID,
+                },
+                instruction::InitProtocolRegistry {},
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::SetProtocol { venue, program, mint, disabled } => {
+            let admin = signer()?;
+            let ix = build(
+                accounts::SetProtocol { admin: admin.pubkey(), config, registry: pda::protocol_registry_pda().0 },
+                instruction::SetProtocol { venue_id: venue, program_id: program, mint, enabled: !disabled },
+            );
+            send(&rpc, &admin, ix)
+        }
+        Command::Pause { vault } => {
+            let guardian = signer()?;
+            let ix = build(
+                accounts::GuardianVault { guardian: guardian.pubkey(), config, vault },
+                instruction::FreezeVault {},
+            );
+            send(&rpc, &guardian, ix)
+        }
+        Command::Unpause { vault } => {
+            let guardian = signer()?;
+            let ix = build(
+                accounts::GuardianVault { guardian: guardian.pubkey(), config, vault },
+                instruction::UnfreezeVault {},
+            );
+            send(&rpc, &guardian, ix)
+        }
+        Command::ShowConfig => {
+            print_config(&config, &fetch_config(&rpc)?);
+            Ok(())
+        }
+        Command::ShowRegistry => {
+            let key = pda::protocol_registry_pda().0;
+            print_registry(&key, &fetch(&rpc, &key)?);
+            Ok(())
+        }
+        Command::Inspect { account } => inspect(&rpc, &account),
+    }
+}
+
+fn update_config<D: anchor_lang::InstructionData>(admin: &Keypair, args: D) -> Instruction {
+    build(accounts::UpdateConfig { admin: admin.pubkey(), config: pda::config_pda().0 }, args)
+}
+
+fn update_fee_config<D: anchor_lang::InstructionData>(admin: &Keypair, args: D) -> Instruction {
+    build(accounts::UpdateFeeConfig { admin: admin.pubkey(), config: pda::config_pda().0 }, args)
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, ix: Instruction) -> Result<(), Box<dyn std::error::Error>> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx)?;
+    println!("{sig}");
+    Ok(())
+}
+
+fn vaults_below_version(rpc: &RpcClient, version: u32) -> Result<Vec<Pubkey>, Box<dyn std::error::Error>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, Vault::DISCRIMINATOR.to_vec()))]),
+        ..Default::default()
+    };
+    let mut keys = Vec::new();
+    for (key, account) in rpc.get_program_accounts_with_config(&kobafin_escrow::ID, config)? {
+        // Older, shorter layouts don't decode until the migration grows them.
+        if account.data.len() < Vault::SPACE {
+            keys.push(key);
+            continue;
+        }
+        match Vault::try_deserialize(&mut account.data.as_slice()) {
+            Ok(v) if v.params_version < version => keys.push(key),
+            Ok(_) => {}
+            Err(e) => eprintln!("skipping {key}: {e}"),
+        }
+    }
+    Ok(keys)
+}
+
+fn inspect(rpc: &RpcClient, key: &Pubkey) -> Result<(), Box<dyn std::error::Error>> {
+    let account = rpc.get_account(key)?;
+    if account.owner != kobafin_escrow::ID {
+        return Err(format!("{key} is owned by {}, not kobafin_escrow", account.owner).into());
+    }
+    let data = account.data.as_slice();
+    let disc = data.get(..8).ok_or(ClientError::BadAccount(*key))?;
+    if disc == Vault::DISCRIMINATOR {
+        print_vault(key, &Vault::try_deserialize(&mut &data[..])?);
+    } else if disc == PodPolicy::DISCRIMINATOR {
+        let body = data.get(8..PodPolicy::SPACE).ok_or(ClientError::BadAccount(*key))?;
+        print_policy(key, &bytemuck::pod_read_unaligned(body));
+    } else if disc == Config::DISCRIMINATOR {
+        print_config(key, &Config::try_deserialize(&mut &data[..])?);
+    } else if disc == OutflowBreaker::DISCRIMINATOR {
+        let b = OutflowBreaker::try_deserialize(&mut &data[..])?;
+        println!("OutflowBreaker {key}");
+        println!("  window_start          {}", b.window_start);
+        println!("  window_lamports       {}", b.window_lamports);
+        println!("  window_usdc           {}", b.window_usdc);
+    } else if disc == FeeTreasury::DISCRIMINATOR {
+        print_treasury(key, &FeeTreasury::try_deserialize(&mut &data[..])?);
+    } else if disc == ProtocolRegistry::DISCRIMINATOR {
+        print_registry(key, &ProtocolRegistry::try_deserialize(&mut &data[..])?);
+    } else {
+        println!("{key}: unrecognized kobafin_escrow account ({} bytes)", data.len());
+    }
+    Ok(())
+}
+
+fn print_vault(key: &Pubkey, v: &Vault) {
+    println!("Vault {key}");
+    println!("  owner                 {}", v.owner);
+    println!("  seq                   {}", v.seq);
+    println!("  pot_hash              {}", hex(&v.pot_hash));
+    println!("  pod_hash              {}", hex(&v.pod_hash));
+    println!("  usdc_mint             {}", v.usdc_mint);
+    println!("  usdc_vault            {}", v.usdc_vault);
+    println!("  msol_amount           {}", v.msol_amount);
+    println!("  sol_staked_lamports   {}", v.sol_staked_lamports);
+    println!("  accrued_yield         {}", v.accrued_yield);
+    println!("  pending_yield_usdc    {}", v.pending_yield_usdc);
+    println!("  net_yield_usdc        {}", v.net_yield_usdc);
+    println!("  fee_hwm_usdc          {}", v.fee_hwm_usdc);
+    println!("  stream_locked_usdc    {}", v.stream_locked_usdc);
+    println!("  no_withdraw_until     {}", v.no_withdraw_until);
+    println!("  frozen_until          {}", v.frozen_until);
+    println!("  sequence              {}", v.sequence);
+    println!("  op_nonce              {}", v.op_nonce);
+    println!("  total_in_usdc         {}", v.total_in_usdc);
+    println!("  total_out_usdc        {}", v.total_out_usdc);
+    println!("  limits_exempt         {}", v.limits_exempt);
+    println!("  share_mint            {}", v.share_mint);
+    println!("  public_deposits       {}", v.public_deposits);
+    println!("  params_version        {}", v.params_version);
+    if v.crowdfund_active() {
+        println!(
+            "  crowdfund             round {} raised {} of {} by {}",
+            v.crowdfund_round, v.crowdfund_raised_usdc, v.crowdfund_goal_usdc, v.crowdfund_deadline
+        );
+    }
+    for mint in &v.registered_mints {
+        println!("  registered_mint       {mint}");
+    }
+}
+
+fn print_policy(key: &Pubkey, p: &PodPolicy) {
+    println!("PodPolicy {key}");
+    println!("  authority             {}", p.authority);
+    println!("  pending_authority     {}", p.pending_authority);
+    println!("  policy_manager        {}", p.policy_manager);
+    println!("  pod_hash              {}", hex(&p.pod_hash));
+    println!("  risk_state            {}", p.risk_state);
+    println!("  usdc_in_lulo_bps      {}", p.usdc_in_lulo_bps);
+    println!("  max_drift_bps         {}", p.drift_threshold_bps());
+    println!("  valuation_mode        {}", if p.valuation_mode == VALUATION_EMA { "ema" } else { "spot" });
+    println!("  updated_at            {}", p.updated_at);
+    for t in p.targets() {
+        println!("  target                {} {} bps", t.mint, t.bps);
+    }
+    for v in p.venue_allocations() {
+        println!("  venue                 {} -> {} {} bps", v.mint, v.venue_id, v.bps);
+    }
+}
+
+fn print_config(key: &Pubkey, c: &Config) {
+    println!("Config {key}");
+    println!("  admin                 {}", c.admin);
+    println!("  fee_admin             {}", c.role_holder(ConfigRole::FeeAdmin));
+    println!("  guardian              {}", c.guardian);
+    println!("  registry_admin        {}", c.role_holder(ConfigRole::RegistryAdmin));
+    for (role, pending) in [
+        ("admin", c.pending_admin),
+        ("fee_admin", c.pending_fee_admin),
+        ("guardian", c.pending_guardian),
+        ("registry_admin", c.pending_registry_admin),
+    ] {
+        if pending != Pubkey::default() {
+            println!("  pending {role:<14}{pending}");
+        }
+    }
+    println!("  referral_bps          {}", c.referral_bps);
+    println!("  performance_fee_bps   {}", c.performance_fee_bps);
+    println!("  fee_treasury          {}", c.fee_treasury);
+    println!("  usdc_mint             {}", c.usdc_mint);
+    println!("  min_compound_amount   {}", c.min_compound_amount);
+    println!("  compound_cooldown     {}s", c.compound_cooldown_secs);
+    println!("  compliance_threshold  {}", c.compliance_threshold);
+    println!("  memo_threshold        {}", c.memo_threshold);
+    println!("  sol_usd_price_feed    {}", c.sol_usd_price_feed);
+    println!("  dust_threshold        {}", c.dust_threshold);
+    println!("  swap_slippage_bps     {}", c.swap_slippage_bps());
+    println!("  min_deposit_usdc      {}", c.min_deposit_usdc);
+    println!("  max_pot_balance_usdc  {}", c.max_pot_balance_usdc);
+    println!(
+        "  vault_params          v{} fee {} bps, exec cooldown {} slots",
+        c.vault_params_version, c.vault_params.performance_fee_bps, c.vault_params.external_exec_cooldown_slots
+    );
+    let o = &c.outflow_limits;
+    if o.window_secs > 0 {
+        println!(
+            "  outflow_limits        {}s window, SOL {} (cap {}), USDC {} (cap {})",
+            o.window_secs, o.lamports_threshold, o.lamports_user_cap, o.usdc_threshold, o.usdc_user_cap
+        );
+    }
+    for issuer in &c.attestation_issuers {
+        println!("  attestation_issuer    {issuer}");
+    }
+}
+
+fn print_treasury(key: &Pubkey, t: &FeeTreasury) {
+    println!("FeeTreasury {key}");
+    for total in &t.totals {
+        let asset = if total.mint == Pubkey::default() { "SOL".to_string() } else { total.mint.to_string() };
+        println!("  {asset:<44} collected {} swept {}", total.collected, total.swept);
+    }
+}
+
+fn print_registry(key: &Pubkey, r: &ProtocolRegistry) {
+    println!("ProtocolRegistry {key}");
+    for e in &r.entries {
+        let state = if e.enabled { "enabled" } else { "disabled" };
+        println!("  venue {:<3} {} mint {} ({state})", e.venue_id, e.program_id, e.mint);
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
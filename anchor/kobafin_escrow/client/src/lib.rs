@@ -0,0 +1,270 @@
+//! Off-chain helpers for `kobafin_escrow`: PDA derivation, typed instruction
+//! builders and account fetchers over `solana-client`.
+//!
+//! Every instruction is built from the program's own generated types, so argument
+//! and account lists can't drift from the deployed IDL:
+//!
+//! ```ignore
+//! let ix = build(
+//!     accounts::Deposit { owner, vault, system_program, receipt: None, config: Some(config), attestation: None, sol_price: None, activity_log: None, deposit_marker: None },
+//!     instruction::Deposit { pot_hash, lamports, idempotency_key: None },
+//! );
+//! ```
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, AccountDeserialize, Discriminator, InstructionData, ToAccountMetas, ZeroCopy};
+use solana_client::rpc_client::RpcClient;
+
+pub use kobafin_escrow::{accounts, instruction, seeds, ID};
+use kobafin_escrow::{ActivityEntry, ActivityLog, Config, PodPolicy, Vault};
+
+pub mod pda {
+    use super::*;
+
+    pub fn vault_pda(owner: &Pubkey, pot_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POT_VAULT, owner.as_ref(), pot_hash.as_ref()], &ID)
+    }
+
+    pub fn policy_pda(pod_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POD_POLICY, pod_hash.as_ref()], &ID)
+    }
+
+    pub fn policy_history_pda(pod_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POLICY_HISTORY, pod_hash.as_ref()], &ID)
+    }
+
+    pub fn pot_policy_pda(vault: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POT_POLICY, vault.as_ref()], &ID)
+    }
+
+    pub fn config_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::CONFIG], &ID)
+    }
+
+    /// The program's `ProgramData`; `init_config` checks its upgrade authority.
+    pub fn program_data_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID)
+    }
+
+    pub fn protocol_registry_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::PROTOCOL_REGISTRY], &ID)
+    }
+
+    pub fn blocklist_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::BLOCKLIST], &ID)
+    }
+
+    /// Lamport custody for every compressed pot; the pots themselves live at Light
+    /// addresses derived from `seeds::COMPRESSED_VAULT`.
+    pub fn compressed_pool_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::COMPRESSED_POOL], &ID)
+    }
+
+    pub fn owner_registry_pda(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::OWNER_REGISTRY, owner.as_ref()], &ID)
+    }
+
+    pub fn position_pda(vault: &Pubkey, venue: u8) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POSITION, vault.as_ref(), &[venue]], &ID)
+    }
+
+    pub fn snapshot_pda(vault: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::SNAPSHOT_BUFFER, vault.as_ref()], &ID)
+    }
+
+    pub fn activity_log_pda(vault: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::ACTIVITY_LOG, vault.as_ref()], &ID)
+    }
+
+    pub fn rebalance_plan_pda(vault: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::REBALANCE_PLAN, vault.as_ref()], &ID)
+    }
+
+    pub fn attestation_pda(issuer: &Pubkey, subject: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::ATTESTATION, issuer.as_ref(), subject.as_ref()], &ID)
+    }
+
+    pub fn pod_pda(pod_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POD, pod_hash.as_ref()], &ID)
+    }
+
+    pub fn pod_vault_pda(pod_hash: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::POD_VAULT, pod_hash.as_ref()], &ID)
+    }
+
+    pub fn contribution_pda(pod: &Pubkey, member: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::CONTRIBUTION, pod.as_ref(), member.as_ref()], &ID)
+    }
+
+    pub fn fee_treasury_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::FEE_TREASURY], &ID)
+    }
+
+    pub fn outflow_breaker_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::OUTFLOW_BREAKER], &ID)
+    }
+
+    pub fn deposit_marker_pda(vault: &Pubkey, idempotency_key: &[u8; 16]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::DEPOSIT_MARKER, vault.as_ref(), idempotency_key.as_ref()], &ID)
+    }
+
+    pub fn fee_tier_pda(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[seeds::FEE_TIER, owner.as_ref()], &ID)
+    }
+}
+
+/// Builds any `kobafin_escrow` instruction from its generated `accounts::*` and
+/// `instruction::*` structs.
+pub fn build<A: ToAccountMetas, D: InstructionData>(accounts: A, args: D) -> Instruction {
+    build_with_remaining(accounts, args, Vec::new())
+}
+
+/// `build` for instructions that take `remaining_accounts` (passthrough CPIs,
+/// batches, snapshot positions).
+pub fn build_with_remaining<A: ToAccountMetas, D: InstructionData>(
+    accounts: A,
+    args: D,
+    remaining: Vec<AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining);
+    Instruction {
+        program_id: ID,
+        accounts: metas,
+        data: args.data(),
+    }
+}
+
+/// SOL deposit into `owner`'s pot, with the Config account the compliance gate needs.
+/// Pass an `idempotency_key` from retrying backends so a resubmission can't credit twice.
+pub fn deposit(
+    owner: Pubkey,
+    pot_hash: [u8; 32],
+    lamports: u64,
+    attestation: Option<Pubkey>,
+    idempotency_key: Option<[u8; 16]>,
+) -> Instruction {
+    let vault = pda::vault_pda(&owner, &pot_hash).0;
+    build(
+        accounts::Deposit {
+            owner,
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(pda::config_pda().0),
+            attestation,
+            sol_price: None,
+            activity_log: None,
+            deposit_marker: idempotency_key.map(|key| pda::deposit_marker_pda(&vault, &key).0),
+        },
+        instruction::Deposit { pot_hash, lamports, idempotency_key },
+    )
+}
+
+/// SOL withdrawal from `owner`'s pot. `memo_hash` is zero below `Config::memo_threshold`;
+/// `outflow_breaker` is `pda::outflow_breaker_pda()` while the circuit breaker is on.
+pub fn withdraw(
+    owner: Pubkey,
+    pot_hash: [u8; 32],
+    lamports: u64,
+    memo_hash: [u8; 32],
+    attestation: Option<Pubkey>,
+    outflow_breaker: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::Withdraw {
+            owner,
+            vault: pda::vault_pda(&owner, &pot_hash).0,
+            system_program: system_program::ID,
+            config: Some(pda::config_pda().0),
+            penalty_destination: None,
+            attestation,
+            outflow_breaker,
+            guardian: None,
+            activity_log: None,
+        },
+        instruction::Withdraw { pot_hash, lamports, memo_hash },
+    )
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Rpc(solana_client::client_error::ClientError),
+    Anchor(anchor_lang::error::Error),
+    /// The account exists but isn't the expected type or size.
+    BadAccount(Pubkey),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(e)
+    }
+}
+
+impl From<anchor_lang::error::Error> for ClientError {
+    fn from(e: anchor_lang::error::Error) -> Self {
+        ClientError::Anchor(e)
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Rpc(e) => write!(f, "rpc: {e}"),
+            ClientError::Anchor(e) => write!(f, "deserialize: {e}"),
+            ClientError::BadAccount(key) => write!(f, "unexpected account data at {key}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Fetches and deserializes a Borsh (`#[account]`) account.
+pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, key: &Pubkey) -> Result<T, ClientError> {
+    let data = rpc.get_account_data(key)?;
+    Ok(T::try_deserialize(&mut data.as_slice())?)
+}
+
+/// Fetches a zero-copy (`#[account(zero_copy)]`) account.
+pub fn fetch_zero_copy<T: ZeroCopy + Discriminator>(rpc: &RpcClient, key: &Pubkey) -> Result<T, ClientError> {
+    let data = rpc.get_account_data(key)?;
+    let size = std::mem::size_of::<T>();
+    if data.len() < 8 + size || data[..8] != *T::DISCRIMINATOR {
+        return Err(ClientError::BadAccount(*key));
+    }
+    Ok(bytemuck::pod_read_unaligned(&data[8..8 + size]))
+}
+
+pub fn fetch_vault(rpc: &RpcClient, owner: &Pubkey, pot_hash: &[u8; 32]) -> Result<Vault, ClientError> {
+    fetch(rpc, &pda::vault_pda(owner, pot_hash).0)
+}
+
+pub fn fetch_policy(rpc: &RpcClient, pod_hash: &[u8; 32]) -> Result<PodPolicy, ClientError> {
+    fetch_zero_copy(rpc, &pda::policy_pda(pod_hash).0)
+}
+
+/// A vault's recorded deposits and withdrawals, newest first.
+pub fn fetch_activity(rpc: &RpcClient, vault: &Pubkey) -> Result<Vec<ActivityEntry>, ClientError> {
+    let key = pda::activity_log_pda(vault).0;
+    let data = rpc.get_account_data(&key)?;
+    let header = std::mem::size_of::<ActivityLog>();
+    let entry = std::mem::size_of::<ActivityEntry>();
+    if data.len() < ActivityLog::SPACE || data[..8] != *ActivityLog::DISCRIMINATOR {
+        return Err(ClientError::BadAccount(key));
+    }
+    let log: ActivityLog = bytemuck::pod_read_unaligned(&data[8..8 + header]);
+    let capacity = ActivityLog::CAPACITY as usize;
+    Ok((1..=log.len as usize)
+        .map(|back| {
+            let slot = (log.head as usize + capacity - back) % capacity;
+            let off = 8 + header + slot * entry;
+            bytemuck::pod_read_unaligned(&data[off..off + entry])
+        })
+        .collect())
+}
+
+pub fn fetch_config(rpc: &RpcClient) -> Result<Config, ClientError> {
+    fetch(rpc, &pda::config_pda().0)
+}
@@ -0,0 +1,34 @@
+#![no_main]
+
+use anchor_lang::prelude::Pubkey;
+use arbitrary::Arbitrary;
+use kobafin_escrow::{validate_policy_targets, AssetTarget, MAX_POLICY_ASSETS};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    risk_state: u8,
+    // Small mint ids so duplicate mints come up often.
+    assets: Vec<(u8, u16)>,
+    usdc_in_lulo_bps: u16,
+}
+
+fuzz_target!(|input: Input| {
+    let assets: Vec<AssetTarget> = input
+        .assets
+        .iter()
+        .map(|(id, bps)| AssetTarget { mint: Pubkey::new_from_array([id % 16; 32]), bps: *bps })
+        .collect();
+    let ok = validate_policy_targets(input.risk_state, &assets, input.usdc_in_lulo_bps).is_ok();
+
+    let mut mints: Vec<Pubkey> = assets.iter().map(|a| a.mint).collect();
+    mints.sort();
+    mints.dedup();
+    let expected = input.risk_state <= 2
+        && !assets.is_empty()
+        && assets.len() <= MAX_POLICY_ASSETS
+        && mints.len() == assets.len()
+        && assets.iter().map(|a| a.bps as u32).sum::<u32>() == 10_000
+        && input.usdc_in_lulo_bps <= assets[0].bps;
+    assert_eq!(ok, expected, "validate_policy_targets disagrees with the spec for {input:?}");
+});
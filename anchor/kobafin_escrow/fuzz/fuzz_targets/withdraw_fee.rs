@@ -0,0 +1,37 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use kobafin_escrow::{split_withdraw_fee, withdrawable_lamports, FeeSplit};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    vault_balance: u64,
+    rent_minimum: u64,
+    amount: u64,
+    fee: u64,
+    referral_bps: Option<u16>,
+}
+
+fuzz_target!(|input: Input| {
+    // Mirrors withdraw_with_fee: the balance check, then the payout split.
+    if withdrawable_lamports(input.vault_balance, input.rent_minimum) < input.amount {
+        return;
+    }
+    let after = input.vault_balance - input.amount;
+    assert!(after >= input.rent_minimum, "vault dropped below rent-exempt minimum");
+
+    let Ok(FeeSplit { net, admin_fee, referrer_cut }) =
+        split_withdraw_fee(input.amount, input.fee, input.referral_bps)
+    else {
+        // Only an oversized fee or an out-of-range referral share may be rejected.
+        assert!(input.fee > input.amount || input.referral_bps.is_some_and(|bps| bps > 10_000));
+        return;
+    };
+    let paid = net as u128 + admin_fee as u128 + referrer_cut as u128;
+    assert_eq!(paid, input.amount as u128, "transfers must equal the debited amount");
+    assert_eq!(admin_fee + referrer_cut, input.fee);
+    if input.referral_bps.is_none() {
+        assert_eq!(referrer_cut, 0);
+    }
+});
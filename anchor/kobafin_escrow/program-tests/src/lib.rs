@@ -0,0 +1,338 @@
+//! `solana-program-test` fixture for kobafin_escrow.
+//!
+//! Loads the compiled program (`cargo test-sbf`, or `anchor build` then point
+//! `SBF_OUT_DIR` at `target/deploy`), creates a 6-decimal mock USDC mint, initializes
+//! Config with it as the canonical USDC mint, and registers a mock Lulo at the real Lulo program id so `lulo_execute`
+//! can be driven end to end.
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, pubkey, system_instruction, system_program, sysvar};
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+pub const LULO_PROGRAM_ID: Pubkey = pubkey!("FL3X2pRsQ9zHENpZSKDRREtccwJuei8yg9fwDu9UN69Q");
+pub const USDC: u64 = 1_000_000;
+pub const SOL: u64 = 1_000_000_000;
+
+pub struct Fixture {
+    pub ctx: ProgramTestContext,
+    pub admin: Keypair,
+    pub usdc_mint: Pubkey,
+    /// Mock Lulo's USDC pool, owned by `mock_lulo::pool_authority()`.
+    pub lulo_pool: Pubkey,
+}
+
+impl Fixture {
+    pub async fn new() -> Self {
+        let mut pt = ProgramTest::new("kobafin_escrow", kobafin_escrow::ID, None);
+        pt.add_program("mock_lulo", LULO_PROGRAM_ID, processor!(mock_lulo::process_instruction));
+        let ctx = pt.start_with_context().await;
+
+        let mut f = Fixture {
+            ctx,
+            admin: Keypair::new(),
+            usdc_mint: Pubkey::default(),
+            lulo_pool: Pubkey::default(),
+        };
+        let admin = f.admin.insecure_clone();
+        f.airdrop(&admin.pubkey(), 10 * SOL).await;
+        f.usdc_mint = f.create_mint(&admin).await;
+        f.lulo_pool = f.create_ata(&mock_lulo::pool_authority()).await;
+
+        f.set_upgrade_authority(&admin.pubkey()).await;
+
+        let ix = Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::InitConfig {
+                admin: admin.pubkey(),
+                config: config_pda(),
+                program: kobafin_escrow::ID,
+                program_data: program_data_pda(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitConfig { referral_bps: 0 }.data(),
+        };
+        let set_mint = Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::UpdateConfig { admin: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::SetUsdcMint { usdc_mint: f.usdc_mint }.data(),
+        };
+        let init_treasury = Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::InitFeeTreasury {
+                admin: admin.pubkey(),
+                config: config_pda(),
+                fee_treasury: fee_treasury_pda(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitFeeTreasury {}.data(),
+        };
+        f.send(&[ix, set_mint, init_treasury], &[&admin]).await.expect("init_config");
+        f
+    }
+
+    /// Rewrites the program's `ProgramData` so `authority` is its upgrade authority,
+    /// as the deployer is on a real cluster; `init_config` requires it.
+    pub async fn set_upgrade_authority(&mut self, authority: &Pubkey) {
+        let key = program_data_pda();
+        let mut account = self.ctx.banks_client.get_account(key).await.unwrap().expect("program data");
+        // UpgradeableLoaderState::ProgramData: tag (4), slot (8), Option<Pubkey> (1 + 32).
+        account.data[12] = 1;
+        account.data[13..45].copy_from_slice(authority.as_ref());
+        self.ctx.set_account(&key, &account.into());
+    }
+
+    /// A funded user with a USDC ATA holding `usdc`.
+    pub async fn user(&mut self, lamports: u64, usdc: u64) -> Keypair {
+        let user = Keypair::new();
+        self.airdrop(&user.pubkey(), lamports).await;
+        let ata = self.create_ata(&user.pubkey()).await;
+        if usdc > 0 {
+            self.mint_usdc(&ata, usdc).await;
+        }
+        user
+    }
+
+    /// Fee payer is the context payer, so signers' balances move only by what the
+    /// program does.
+    pub async fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await?;
+        let mut all: Vec<&Keypair> = vec![&self.ctx.payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&self.ctx.payer.pubkey()), &all, blockhash);
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let ix = system_instruction::transfer(&self.ctx.payer.pubkey(), to, lamports);
+        self.send(&[ix], &[]).await.expect("airdrop");
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    /// Moves the cluster clock forward by `secs`.
+    pub async fn warp_secs(&mut self, secs: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += secs;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    pub async fn lamports(&mut self, key: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    pub async fn exists(&mut self, key: &Pubkey) -> bool {
+        self.ctx.banks_client.get_account(*key).await.unwrap().is_some()
+    }
+
+    pub async fn token_balance(&mut self, key: &Pubkey) -> u64 {
+        let account = self.ctx.banks_client.get_account(*key).await.unwrap().expect("token account");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    pub async fn fetch<T: AccountDeserialize>(&mut self, key: &Pubkey) -> T {
+        let account = self.ctx.banks_client.get_account(*key).await.unwrap().expect("account");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn mint_usdc(&mut self, to: &Pubkey, amount: u64) {
+        let mint = self.usdc_mint;
+        self.mint_to(&mint, to, amount).await;
+    }
+
+    /// Mints from any mint created by the fixture (admin is the mint authority).
+    pub async fn mint_to(&mut self, mint: &Pubkey, to: &Pubkey, amount: u64) {
+        let admin = self.admin.insecure_clone();
+        let ix = spl_token::instruction::mint_to(&spl_token::ID, mint, to, &admin.pubkey(), &[], amount).unwrap();
+        self.send(&[ix], &[&admin]).await.expect("mint_to");
+    }
+
+    pub async fn create_ata(&mut self, owner: &Pubkey) -> Pubkey {
+        let mint = self.usdc_mint;
+        self.create_ata_for(owner, &mint).await
+    }
+
+    pub async fn create_ata_for(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let ata = get_associated_token_address(owner, mint);
+        // CreateIdempotent
+        let ix = Instruction {
+            program_id: associated_token::ID,
+            accounts: vec![
+                AccountMeta::new(self.ctx.payer.pubkey(), true),
+                AccountMeta::new(ata, false),
+                AccountMeta::new_readonly(*owner, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ],
+            data: vec![1],
+        };
+        self.send(&[ix], &[]).await.expect("create ata");
+        ata
+    }
+
+    /// A 6-decimal mint with `authority` as mint authority.
+    pub async fn create_mint(&mut self, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let ixs = [
+            system_instruction::create_account(
+                &self.ctx.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &authority.pubkey(), None, 6)
+                .unwrap(),
+        ];
+        self.send(&ixs, &[&mint]).await.expect("create mint");
+        mint.pubkey()
+    }
+
+    /// `init_pot_vault` for `owner`; returns the vault PDA.
+    pub async fn init_pot(&mut self, owner: &Keypair, pot_hash: [u8; 32]) -> Pubkey {
+        let vault = vault_pda(&owner.pubkey(), &pot_hash);
+        let ix = Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::InitPotVault {
+                owner: owner.pubkey(),
+                vault,
+                usdc_mint: self.usdc_mint,
+                vault_usdc: get_associated_token_address(&vault, &self.usdc_mint),
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                rent: sysvar::rent::ID,
+                system_program: system_program::ID,
+                owner_registry: owner_registry_pda(&owner.pubkey()),
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: instruction::InitPotVault { pot_hash }.data(),
+        };
+        self.send(&[ix], &[owner]).await.expect("init_pot_vault");
+        vault
+    }
+
+    /// `lulo_execute` forwarding a mock Lulo instruction over the vault's USDC ATA and
+    /// the mock pool.
+    pub fn lulo_execute(&self, owner: &Pubkey, pot_hash: [u8; 32], max_usdc_out: u64, lulo_ix: Vec<u8>) -> Instruction {
+        let vault = vault_pda(owner, &pot_hash);
+        let mut metas = accounts::LuloExecute {
+            owner: *owner,
+            vault,
+            lulo_program: LULO_PROGRAM_ID,
+            position: position_pda(&vault, kobafin_escrow::VENUE_LULO),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        metas.extend([
+            AccountMeta::new(vault, false),
+            AccountMeta::new(get_associated_token_address(&vault, &self.usdc_mint), false),
+            AccountMeta::new(self.lulo_pool, false),
+            AccountMeta::new_readonly(mock_lulo::pool_authority(), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]);
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: metas,
+            data: instruction::LuloExecute { pot_hash, max_usdc_out, ix_data: lulo_ix }.data(),
+        }
+    }
+}
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::CONFIG], &kobafin_escrow::ID).0
+}
+
+/// The program's `ProgramData` account under the upgradeable loader.
+pub fn program_data_pda() -> Pubkey {
+    Pubkey::find_program_address(&[kobafin_escrow::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn fee_treasury_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::FEE_TREASURY], &kobafin_escrow::ID).0
+}
+
+pub fn outflow_breaker_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::OUTFLOW_BREAKER], &kobafin_escrow::ID).0
+}
+
+pub fn vault_pda(owner: &Pubkey, pot_hash: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::POT_VAULT, owner.as_ref(), pot_hash.as_ref()], &kobafin_escrow::ID).0
+}
+
+pub fn owner_registry_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::OWNER_REGISTRY, owner.as_ref()], &kobafin_escrow::ID).0
+}
+
+pub fn position_pda(vault: &Pubkey, venue: u8) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::POSITION, vault.as_ref(), &[venue]], &kobafin_escrow::ID).0
+}
+
+/// Stand-in for Lulo: moves USDC between the vault's ATA and a pool it controls.
+/// Accounts: `[vault, vault_usdc, pool, pool_authority, token_program]`.
+pub mod mock_lulo {
+    use super::*;
+    use anchor_lang::solana_program::account_info::AccountInfo;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::solana_program::program::{invoke, invoke_signed};
+    use anchor_lang::solana_program::program_error::ProgramError;
+
+    pub const DEPOSIT: u8 = 0;
+    pub const WITHDRAW: u8 = 1;
+
+    pub fn pool_authority() -> Pubkey {
+        Pubkey::find_program_address(&[b"pool"], &LULO_PROGRAM_ID).0
+    }
+
+    pub fn deposit(amount: u64) -> Vec<u8> {
+        [&[DEPOSIT][..], &amount.to_le_bytes()].concat()
+    }
+
+    pub fn withdraw(amount: u64) -> Vec<u8> {
+        [&[WITHDRAW][..], &amount.to_le_bytes()].concat()
+    }
+
+    pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let [vault, vault_usdc, pool, pool_authority, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let amount = u64::from_le_bytes(data[1..9].try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+        match data[0] {
+            DEPOSIT => {
+                let ix = spl_token::instruction::transfer(token_program.key, vault_usdc.key, pool.key, vault.key, &[], amount)?;
+                invoke(&ix, &[vault_usdc.clone(), pool.clone(), vault.clone(), token_program.clone()])
+            }
+            WITHDRAW => {
+                let (_, bump) = Pubkey::find_program_address(&[b"pool"], program_id);
+                let ix = spl_token::instruction::transfer(
+                    token_program.key,
+                    pool.key,
+                    vault_usdc.key,
+                    pool_authority.key,
+                    &[],
+                    amount,
+                )?;
+                invoke_signed(
+                    &ix,
+                    &[pool.clone(), vault_usdc.clone(), pool_authority.clone(), token_program.clone()],
+                    &[&[b"pool", &[bump]]],
+                )
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
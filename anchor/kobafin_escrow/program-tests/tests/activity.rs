@@ -0,0 +1,80 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, seeds, ActivityEntry, ActivityLog, Vault, ACTIVITY_DEPOSIT, ACTIVITY_WITHDRAW};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL};
+use solana_sdk::signature::Signer;
+
+const POT: [u8; 32] = [4; 32];
+
+#[tokio::test]
+async fn deposits_and_withdrawals_land_in_the_activity_log() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let log = Pubkey::find_program_address(&[seeds::ACTIVITY_LOG, vault.as_ref()], &kobafin_escrow::ID).0;
+
+    let init = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::InitActivityLog {
+            payer: user.pubkey(),
+            vault,
+            activity_log: log,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitActivityLog {}.data(),
+    };
+    let deposit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Deposit {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            activity_log: Some(log),
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { pot_hash: POT, lamports: 2 * SOL, idempotency_key: None }.data(),
+    };
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Withdraw {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            config: Some(config_pda()),
+            penalty_destination: None,
+            attestation: None,
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: Some(log),
+        }
+        .to_account_metas(None),
+        data: instruction::Withdraw { pot_hash: POT, lamports: SOL / 2, memo_hash: [0; 32] }.data(),
+    };
+    f.send(&[init, deposit, withdraw], &[&user]).await.unwrap();
+
+    let data = f.ctx.banks_client.get_account(log).await.unwrap().unwrap().data;
+    assert_eq!(data.len(), ActivityLog::SPACE);
+    let header = std::mem::size_of::<ActivityLog>();
+    let header: ActivityLog = bytemuck::pod_read_unaligned(&data[8..8 + header]);
+    assert_eq!((header.vault, header.len, header.head), (vault, 2, 2));
+
+    let entry = |i: usize| -> ActivityEntry {
+        let len = std::mem::size_of::<ActivityEntry>();
+        let off = 8 + std::mem::size_of::<ActivityLog>() + i * len;
+        bytemuck::pod_read_unaligned(&data[off..off + len])
+    };
+    let (first, second) = (entry(0), entry(1));
+    assert_eq!((first.kind, first.amount, first.counterparty), (ACTIVITY_DEPOSIT, 2 * SOL, user.pubkey()));
+    assert_eq!((second.kind, second.amount, second.mint), (ACTIVITY_WITHDRAW, SOL / 2, Pubkey::default()));
+
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.seq, 2, "one bump per vault mutation");
+}
@@ -0,0 +1,105 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, ConfigRole, OutflowLimits};
+use kobafin_escrow_tests::{config_pda, outflow_breaker_pda, vault_pda, Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [9; 32];
+const WINDOW: i64 = 3_600;
+
+fn withdraw(owner: &Keypair, lamports: u64, breaker: Option<Pubkey>, guardian: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Withdraw {
+            owner: owner.pubkey(),
+            vault: vault_pda(&owner.pubkey(), &POT),
+            system_program: system_program::ID,
+            config: Some(config_pda()),
+            penalty_destination: None,
+            attestation: None,
+            outflow_breaker: breaker,
+            guardian,
+            activity_log: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Withdraw { pot_hash: POT, lamports, memo_hash: [0; 32] }.data(),
+    }
+}
+
+#[tokio::test]
+async fn large_withdrawals_need_guardian_once_window_threshold_is_passed() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let guardian = f.user(SOL, 0).await;
+    let user = f.user(10 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+
+    let deposit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Deposit {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { pot_hash: POT, lamports: 6 * SOL, idempotency_key: None }.data(),
+    };
+    let limits = OutflowLimits {
+        window_secs: WINDOW,
+        lamports_threshold: 2 * SOL,
+        lamports_user_cap: SOL / 2,
+        usdc_threshold: 0,
+        usdc_user_cap: 0,
+    };
+    let setup = [
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::InitOutflowBreaker {
+                admin: admin.pubkey(),
+                config: config_pda(),
+                outflow_breaker: outflow_breaker_pda(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitOutflowBreaker {}.data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::UpdateConfig { admin: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::SetOutflowLimits { limits }.data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::ProposeRole { holder: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::ProposeRole { role: ConfigRole::PauseGuardian, new_holder: guardian.pubkey() }.data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::AcceptRole { pending: guardian.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::AcceptRole { role: ConfigRole::PauseGuardian }.data(),
+        },
+    ];
+    f.send(&setup, &[&admin, &guardian]).await.unwrap();
+    f.send(&[deposit], &[&user]).await.unwrap();
+
+    let breaker = Some(outflow_breaker_pda());
+    assert!(f.send(&[withdraw(&user, SOL, None, None)], &[&user]).await.is_err(), "breaker account required");
+    f.send(&[withdraw(&user, 3 * SOL / 2, breaker, None)], &[&user]).await.unwrap();
+
+    // The window now holds 1.5 SOL; another 1 SOL would pass the 2 SOL threshold.
+    assert!(f.send(&[withdraw(&user, SOL, breaker, None)], &[&user]).await.is_err());
+    f.send(&[withdraw(&user, 2 * SOL / 5, breaker, None)], &[&user]).await.unwrap();
+    assert!(f.send(&[withdraw(&user, 3 * SOL / 5, breaker, None)], &[&user]).await.is_err());
+    f.send(&[withdraw(&user, SOL, breaker, Some(guardian.pubkey()))], &[&user, &guardian]).await.unwrap();
+
+    f.warp_secs(WINDOW).await;
+    f.send(&[withdraw(&user, 7 * SOL / 5, breaker, None)], &[&user]).await.unwrap();
+}
@@ -0,0 +1,90 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction};
+use kobafin_escrow_tests::{owner_registry_pda, vault_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [9; 32];
+
+fn close_pot(f: &Fixture, owner: &Keypair, extra: &[Pubkey]) -> Instruction {
+    let vault = vault_pda(&owner.pubkey(), &POT);
+    let mut metas = accounts::ClosePotVault {
+        owner: owner.pubkey(),
+        vault,
+        vault_usdc: get_associated_token_address(&vault, &f.usdc_mint),
+        owner_registry: owner_registry_pda(&owner.pubkey()),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    metas.extend(extra.iter().map(|k| AccountMeta::new(*k, false)));
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: metas,
+        data: instruction::ClosePotVault { pot_hash: POT }.data(),
+    }
+}
+
+#[tokio::test]
+async fn close_returns_exact_rent_for_every_account() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let admin = f.admin.insecure_clone();
+    let other_mint = f.create_mint(&admin).await;
+    let vault_other = f.create_ata_for(&vault, &other_mint).await;
+    let registry = owner_registry_pda(&user.pubkey());
+
+    let owner_before = f.lamports(&user.pubkey()).await;
+    let closed = f.lamports(&vault).await + f.lamports(&vault_usdc).await + f.lamports(&vault_other).await;
+    let registry_before = f.lamports(&registry).await;
+
+    let ix = close_pot(&f, &user, &[vault_other]);
+    f.send(&[ix], &[&user]).await.unwrap();
+
+    let registry_refund = registry_before - f.lamports(&registry).await;
+    assert_eq!(f.lamports(&user.pubkey()).await, owner_before + closed + registry_refund);
+    assert!(!f.exists(&vault).await);
+    assert!(!f.exists(&vault_usdc).await);
+    assert!(!f.exists(&vault_other).await);
+}
+
+#[tokio::test]
+async fn non_empty_token_account_aborts_the_whole_close() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let admin = f.admin.insecure_clone();
+    let other_mint = f.create_mint(&admin).await;
+    let vault_other = f.create_ata_for(&vault, &other_mint).await;
+    f.mint_to(&other_mint, &vault_other, USDC).await;
+
+    let owner_before = f.lamports(&user.pubkey()).await;
+    // The USDC ATA closes before the dusty one is reached; the failure must undo it.
+    let ix = close_pot(&f, &user, &[vault_other]);
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+
+    assert_eq!(f.lamports(&user.pubkey()).await, owner_before);
+    assert!(f.exists(&vault).await);
+    assert!(f.exists(&vault_usdc).await);
+    assert_eq!(f.token_balance(&vault_other).await, USDC);
+}
+
+#[tokio::test]
+async fn token_account_not_held_by_the_vault_is_rejected() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let user_usdc = get_associated_token_address(&user.pubkey(), &f.usdc_mint);
+
+    let ix = close_pot(&f, &user, &[user_usdc]);
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+    assert!(f.exists(&vault).await);
+    assert!(f.exists(&user_usdc).await);
+}
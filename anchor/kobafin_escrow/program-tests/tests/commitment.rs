@@ -0,0 +1,53 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction};
+use kobafin_escrow_tests::{config_pda, fee_treasury_pda, Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [13; 32];
+
+fn withdraw_to(owner: &Keypair, vault: Pubkey, recipient: Pubkey, penalty_destination: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawTo {
+            owner: owner.pubkey(),
+            vault,
+            recipient,
+            config: Some(config_pda()),
+            attestation: None,
+            blocklist: None,
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_destination,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawTo { pot_hash: POT, lamports: SOL, memo_hash: [0; 32] }.data(),
+    }
+}
+
+#[tokio::test]
+async fn early_withdraw_to_pays_the_penalty_instead_of_failing() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    f.airdrop(&vault, 2 * SOL).await;
+
+    let commit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::CommitNoWithdraw { owner: user.pubkey(), vault }.to_account_metas(None),
+        data: instruction::CommitNoWithdraw { pot_hash: POT, duration_secs: 86_400, penalty_bps: 1_000 }.data(),
+    };
+    f.send(&[commit], &[&user]).await.unwrap();
+
+    let recipient = Keypair::new().pubkey();
+    let ix = withdraw_to(&user, vault, recipient, None);
+    assert!(f.send(&[ix], &[&user]).await.is_err(), "penalty destination required");
+
+    let treasury_before = f.lamports(&fee_treasury_pda()).await;
+    let ix = withdraw_to(&user, vault, recipient, Some(fee_treasury_pda()));
+    f.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(f.lamports(&recipient).await, SOL - SOL / 10);
+    assert_eq!(f.lamports(&fee_treasury_pda()).await, treasury_before + SOL / 10);
+}
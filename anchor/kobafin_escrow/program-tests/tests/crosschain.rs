@@ -0,0 +1,67 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, seeds, CrosschainRecipient};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [11; 32];
+const ETHEREUM: u32 = 0;
+const BASE: u32 = 6;
+
+fn domains_pda() -> Pubkey {
+    Pubkey::find_program_address(&[seeds::CROSSCHAIN_DOMAINS], &kobafin_escrow::ID).0
+}
+
+fn recipient_pda(vault: &Pubkey, domain: u32, recipient: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[seeds::CROSSCHAIN_RECIPIENT, vault.as_ref(), &domain.to_le_bytes(), recipient.as_ref()],
+        &kobafin_escrow::ID,
+    )
+    .0
+}
+
+fn add_recipient(owner: &Keypair, vault: Pubkey, domain: u32, recipient: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::AddCrosschainRecipient {
+            owner: owner.pubkey(),
+            vault,
+            domains: domains_pda(),
+            recipient: recipient_pda(&vault, domain, &recipient),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AddCrosschainRecipient { pot_hash: POT, domain, recipient }.data(),
+    }
+}
+
+#[tokio::test]
+async fn recipients_only_on_allowlisted_domains() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SetCrosschainDomain {
+            admin: admin.pubkey(),
+            config: config_pda(),
+            domains: domains_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SetCrosschainDomain { domain: ETHEREUM, allowed: true }.data(),
+    };
+    f.send(&[ix], &[&admin]).await.unwrap();
+
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let mut evm = [0u8; 32];
+    evm[12..].copy_from_slice(&[0xab; 20]);
+
+    f.send(&[add_recipient(&user, vault, ETHEREUM, evm)], &[&user]).await.unwrap();
+    let r: CrosschainRecipient = f.fetch(&recipient_pda(&vault, ETHEREUM, &evm)).await;
+    assert_eq!((r.vault, r.domain, r.recipient), (vault, ETHEREUM, evm));
+
+    assert!(f.send(&[add_recipient(&user, vault, BASE, evm)], &[&user]).await.is_err());
+}
@@ -0,0 +1,130 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, Donation, Vault};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [8; 32];
+const MEMO: [u8; 32] = [1; 32];
+
+fn set_public(owner: &Keypair, vault: Pubkey, enabled: bool) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SetPublicDeposits { owner: owner.pubkey(), vault }.to_account_metas(None),
+        data: instruction::SetPublicDeposits { pot_hash: POT, enabled }.data(),
+    }
+}
+
+#[tokio::test]
+async fn anyone_can_donate_to_a_public_pot() {
+    let mut f = Fixture::new().await;
+    let owner = f.user(5 * SOL, 0).await;
+    let donor = f.user(5 * SOL, 50 * USDC).await;
+    let vault = f.init_pot(&owner, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let donate = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DonateUsdc {
+            donor: donor.pubkey(),
+            vault,
+            donor_usdc: get_associated_token_address(&donor.pubkey(), &f.usdc_mint),
+            vault_usdc,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            config: config_pda(),
+            attestation: None,
+            sol_price: None,
+            donation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DonateUsdc { amount: 20 * USDC, memo_hash: MEMO }.data(),
+    };
+
+    assert!(f.send(&[donate.clone()], &[&donor]).await.is_err(), "pot is private by default");
+    // Only the owner can open the pot.
+    assert!(f.send(&[set_public(&donor, vault, true)], &[&donor]).await.is_err());
+    f.send(&[set_public(&owner, vault, true)], &[&owner]).await.unwrap();
+
+    f.send(&[donate], &[&donor]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 20 * USDC);
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.total_in_usdc, 20 * USDC);
+}
+
+#[tokio::test]
+async fn missed_crowdfund_goal_refunds_donors() {
+    let mut f = Fixture::new().await;
+    let owner = f.user(5 * SOL, 0).await;
+    let donor = f.user(5 * SOL, 50 * USDC).await;
+    let vault = f.init_pot(&owner, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let donor_usdc = get_associated_token_address(&donor.pubkey(), &f.usdc_mint);
+    let (donor_key, round) = (donor.pubkey(), 1u64.to_le_bytes());
+    let donation_seeds: &[&[u8]] = &[seeds::DONATION, vault.as_ref(), donor_key.as_ref(), &round];
+    let donation = Pubkey::find_program_address(donation_seeds, &kobafin_escrow::ID).0;
+
+    let deadline = f.now().await + 3600;
+    let start = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::OwnerCrowdfund { owner: owner.pubkey(), vault }.to_account_metas(None),
+        data: instruction::StartCrowdfund { pot_hash: POT, goal_usdc: 100 * USDC, deadline }.data(),
+    };
+    f.send(&[set_public(&owner, vault, true), start], &[&owner]).await.unwrap();
+
+    let donate = |donation: Option<Pubkey>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DonateUsdc {
+            donor: donor.pubkey(),
+            vault,
+            donor_usdc,
+            vault_usdc,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            config: config_pda(),
+            attestation: None,
+            sol_price: None,
+            donation,
+        }
+        .to_account_metas(None),
+        data: instruction::DonateUsdc { amount: 30 * USDC, memo_hash: MEMO }.data(),
+    };
+    assert!(f.send(&[donate(None)], &[&donor]).await.is_err(), "crowdfunds track each donor");
+    f.send(&[donate(Some(donation))], &[&donor]).await.unwrap();
+    let d: Donation = f.fetch(&donation).await;
+    assert_eq!(d.amount, 30 * USDC);
+
+    let claim = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::OwnerCrowdfund { owner: owner.pubkey(), vault }.to_account_metas(None),
+        data: instruction::ClaimCrowdfund { pot_hash: POT }.data(),
+    };
+    assert!(f.send(&[claim], &[&owner]).await.is_err(), "goal not reached");
+
+    let refund = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::RefundDonor {
+            vault,
+            donation,
+            donor: donor.pubkey(),
+            donor_usdc,
+            vault_usdc,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RefundDonor {}.data(),
+    };
+    assert!(f.send(&[refund.clone()], &[]).await.is_err(), "deadline not reached");
+    f.warp_secs(3601).await;
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    f.send(&[refund], &[]).await.unwrap();
+
+    assert_eq!(f.token_balance(&donor_usdc).await, 50 * USDC);
+    assert_eq!(f.token_balance(&vault_usdc).await, 0);
+    assert!(!f.exists(&donation).await);
+    let v: Vault = f.fetch(&vault).await;
+    assert!(!v.crowdfund_active());
+}
@@ -0,0 +1,132 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, ConfigRole, Vault};
+use kobafin_escrow_tests::{config_pda, fee_treasury_pda, vault_pda, Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [3; 32];
+const THRESHOLD: u64 = 100;
+
+async fn configure(f: &mut Fixture) {
+    let admin = f.admin.insecure_clone();
+    let update = |data: Vec<u8>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdateConfig { admin: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+        data,
+    };
+    f.send(&[update(instruction::SetDustThreshold { threshold: THRESHOLD }.data())], &[&admin]).await.unwrap();
+}
+
+fn sweep(owner: &Keypair, pairs: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut metas = accounts::SweepDust {
+        owner: owner.pubkey(),
+        vault: vault_pda(&owner.pubkey(), &POT),
+        config: config_pda(),
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    for (account, target) in pairs {
+        metas.push(AccountMeta::new(*account, false));
+        metas.push(AccountMeta::new(*target, false));
+    }
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: metas,
+        data: instruction::SweepDust { pot_hash: POT }.data(),
+    }
+}
+
+#[tokio::test]
+async fn dust_goes_to_treasury_or_is_burned_and_accounts_close() {
+    let mut f = Fixture::new().await;
+    let treasury = fee_treasury_pda();
+    configure(&mut f).await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let admin = f.admin.insecure_clone();
+
+    let kept_mint = f.create_mint(&admin).await;
+    let vault_kept = f.create_ata_for(&vault, &kept_mint).await;
+    let treasury_kept = f.create_ata_for(&treasury, &kept_mint).await;
+    f.mint_to(&kept_mint, &vault_kept, THRESHOLD).await;
+
+    let junk_mint = f.create_mint(&admin).await;
+    let vault_junk = f.create_ata_for(&vault, &junk_mint).await;
+    f.mint_to(&junk_mint, &vault_junk, 7).await;
+
+    let owner_before = f.lamports(&user.pubkey()).await;
+    let rent = f.lamports(&vault_kept).await + f.lamports(&vault_junk).await;
+    let ix = sweep(&user, &[(vault_kept, treasury_kept), (vault_junk, junk_mint)]);
+    f.send(&[ix], &[&user]).await.unwrap();
+
+    assert_eq!(f.token_balance(&treasury_kept).await, THRESHOLD);
+    assert!(!f.exists(&vault_kept).await);
+    assert!(!f.exists(&vault_junk).await);
+    assert_eq!(f.lamports(&user.pubkey()).await, owner_before + rent);
+}
+
+#[tokio::test]
+async fn balance_above_threshold_is_not_dust() {
+    let mut f = Fixture::new().await;
+    let treasury = fee_treasury_pda();
+    configure(&mut f).await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let admin = f.admin.insecure_clone();
+
+    let mint = f.create_mint(&admin).await;
+    let vault_ata = f.create_ata_for(&vault, &mint).await;
+    let treasury_ata = f.create_ata_for(&treasury, &mint).await;
+    f.mint_to(&mint, &vault_ata, THRESHOLD + 1).await;
+
+    let ix = sweep(&user, &[(vault_ata, treasury_ata)]);
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+    assert_eq!(f.token_balance(&vault_ata).await, THRESHOLD + 1);
+}
+
+#[tokio::test]
+async fn frozen_vault_cannot_sweep_until_the_freeze_lapses() {
+    let mut f = Fixture::new().await;
+    let treasury = fee_treasury_pda();
+    configure(&mut f).await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let admin = f.admin.insecure_clone();
+    let guardian = f.user(SOL, 0).await;
+
+    let mint = f.create_mint(&admin).await;
+    let vault_ata = f.create_ata_for(&vault, &mint).await;
+    let treasury_ata = f.create_ata_for(&treasury, &mint).await;
+    f.mint_to(&mint, &vault_ata, THRESHOLD).await;
+
+    let setup = [
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::ProposeRole { holder: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::ProposeRole { role: ConfigRole::PauseGuardian, new_holder: guardian.pubkey() }.data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::AcceptRole { pending: guardian.pubkey(), config: config_pda() }.to_account_metas(None),
+            data: instruction::AcceptRole { role: ConfigRole::PauseGuardian }.data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::GuardianVault { guardian: guardian.pubkey(), config: config_pda(), vault }
+                .to_account_metas(None),
+            data: instruction::FreezeVault {}.data(),
+        },
+    ];
+    f.send(&setup, &[&admin, &guardian]).await.unwrap();
+
+    let ix = sweep(&user, &[(vault_ata, treasury_ata)]);
+    assert!(f.send(&[ix.clone()], &[&user]).await.is_err());
+    assert_eq!(f.token_balance(&vault_ata).await, THRESHOLD);
+
+    f.warp_secs(Vault::FREEZE_SECS).await;
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    f.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&treasury_ata).await, THRESHOLD);
+}
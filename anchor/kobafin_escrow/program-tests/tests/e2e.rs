@@ -0,0 +1,277 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, FeeTreasury, Position, Vault};
+use kobafin_escrow_tests::{
+    config_pda, fee_treasury_pda, mock_lulo, owner_registry_pda, position_pda, vault_pda, Fixture, SOL, USDC,
+};
+use solana_sdk::signature::Signer;
+
+const POT: [u8; 32] = [7; 32];
+const NO_MEMO: [u8; 32] = [0; 32];
+
+#[tokio::test]
+async fn sol_deposit_then_withdraw_with_fee() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let treasury = fee_treasury_pda();
+
+    let deposit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Deposit {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { pot_hash: POT, lamports: 2 * SOL, idempotency_key: None }.data(),
+    };
+    f.send(&[deposit], &[&user]).await.unwrap();
+
+    let vault_before = f.lamports(&vault).await;
+    let user_before = f.lamports(&user.pubkey()).await;
+    let treasury_before = f.lamports(&treasury).await;
+
+    let fee = SOL / 100;
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawWithFee {
+            owner: user.pubkey(),
+            vault,
+            fee_treasury: treasury,
+            config: Some(config_pda()),
+            referral: None,
+            referrer: None,
+            system_program: system_program::ID,
+            attestation: None,
+            fee_tier: None,
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_destination: None,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawWithFee { pot_hash: POT, lamports: SOL, fee_lamports: fee, memo_hash: NO_MEMO }
+            .data(),
+    };
+    f.send(&[withdraw], &[&user]).await.unwrap();
+
+    assert_eq!(f.lamports(&vault).await, vault_before - SOL);
+    assert_eq!(f.lamports(&user.pubkey()).await, user_before + SOL - fee);
+    assert_eq!(f.lamports(&treasury).await, treasury_before + fee);
+}
+
+#[tokio::test]
+async fn usdc_through_lulo_and_withdraw_with_fee() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 100 * USDC).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let user_usdc = get_associated_token_address(&user.pubkey(), &f.usdc_mint);
+    let admin = f.admin.insecure_clone();
+    let admin_usdc = f.create_ata(&admin.pubkey()).await;
+    let treasury_usdc = f.create_ata(&fee_treasury_pda()).await;
+
+    let deposit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DepositUsdc {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc,
+            vault_usdc,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            share_mint: None,
+            owner_shares: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositUsdc { pot_hash: POT, amount: 100 * USDC, idempotency_key: None }.data(),
+    };
+    f.send(&[deposit], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 100 * USDC);
+
+    // Supply 60 to Lulo.
+    let ix = f.lulo_execute(&user.pubkey(), POT, 60 * USDC, mock_lulo::deposit(60 * USDC));
+    f.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 40 * USDC);
+    let position: Position = f.fetch(&position_pda(&vault, kobafin_escrow::VENUE_LULO)).await;
+    assert_eq!(position.principal, 60 * USDC);
+
+    // Lulo accrues 5, then everything comes back.
+    let pool = f.lulo_pool;
+    f.mint_usdc(&pool, 5 * USDC).await;
+    let ix = f.lulo_execute(&user.pubkey(), POT, 0, mock_lulo::withdraw(65 * USDC));
+    f.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 105 * USDC);
+    let position: Position = f.fetch(&position_pda(&vault, kobafin_escrow::VENUE_LULO)).await;
+    assert_eq!(position.principal, 0);
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.pending_yield_usdc, 5 * USDC);
+
+    let fee = USDC;
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawUsdcWithFee {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc,
+            vault_usdc,
+            fee_treasury: fee_treasury_pda(),
+            treasury_usdc,
+            config: Some(config_pda()),
+            referral: None,
+            referrer_usdc: None,
+            token_program: spl_token::ID,
+            attestation: None,
+            fee_tier: None,
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_usdc: None,
+            share_mint: None,
+            owner_shares: None,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawUsdcWithFee { pot_hash: POT, amount: 100 * USDC, fee_amount: fee, memo_hash: NO_MEMO }
+            .data(),
+    };
+    f.send(&[withdraw], &[&user]).await.unwrap();
+
+    assert_eq!(f.token_balance(&vault_usdc).await, 5 * USDC);
+    assert_eq!(f.token_balance(&user_usdc).await, 100 * USDC - fee);
+    assert_eq!(f.token_balance(&treasury_usdc).await, fee);
+
+    let sweep = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SweepFees {
+            admin: admin.pubkey(),
+            config: config_pda(),
+            fee_treasury: fee_treasury_pda(),
+            destination: admin.pubkey(),
+            treasury_token: Some(treasury_usdc),
+            destination_token: Some(admin_usdc),
+            token_program: Some(spl_token::ID),
+        }
+        .to_account_metas(None),
+        data: instruction::SweepFees {}.data(),
+    };
+    f.send(&[sweep], &[&admin]).await.unwrap();
+    assert_eq!(f.token_balance(&treasury_usdc).await, 0);
+    assert_eq!(f.token_balance(&admin_usdc).await, fee);
+    let treasury: FeeTreasury = f.fetch(&fee_treasury_pda()).await;
+    let usdc = treasury.totals.iter().find(|t| t.mint == f.usdc_mint).unwrap();
+    assert_eq!((usdc.collected, usdc.swept), (fee, fee));
+}
+
+#[tokio::test]
+async fn lulo_execute_rejects_drawdown_above_max() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 10 * USDC).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    f.mint_usdc(&vault_usdc, 10 * USDC).await;
+
+    let ix = f.lulo_execute(&user.pubkey(), POT, 5 * USDC, mock_lulo::deposit(10 * USDC));
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+    assert_eq!(f.token_balance(&vault_usdc).await, 10 * USDC);
+}
+
+#[tokio::test]
+async fn lulo_execute_rejects_accounts_delegated_to_the_vault() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let victim = f.user(SOL, 10 * USDC).await;
+    let victim_usdc = get_associated_token_address(&victim.pubkey(), &f.usdc_mint);
+    let approve =
+        spl_token::instruction::approve(&spl_token::ID, &victim_usdc, &vault, &victim.pubkey(), &[], 10 * USDC).unwrap();
+    f.send(&[approve], &[&victim]).await.unwrap();
+
+    let mut ix = f.lulo_execute(&user.pubkey(), POT, 0, mock_lulo::deposit(0));
+    ix.accounts.push(AccountMeta::new(victim_usdc, false));
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+    assert_eq!(f.token_balance(&victim_usdc).await, 10 * USDC);
+}
+
+#[tokio::test]
+async fn idempotent_init_tolerates_retries() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = vault_pda(&user.pubkey(), &POT);
+    let init = |usdc_mint| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::InitPotVaultIdempotent {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint,
+            vault_usdc: get_associated_token_address(&vault, &usdc_mint),
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            owner_registry: owner_registry_pda(&user.pubkey()),
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: instruction::InitPotVaultIdempotent { pot_hash: POT }.data(),
+    };
+
+    let usdc_mint = f.usdc_mint;
+    f.send(&[init(usdc_mint)], &[&user]).await.unwrap();
+    let balance = f.lamports(&user.pubkey()).await;
+    // A retried transaction (new blockhash) is a no-op, not "already in use".
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    f.send(&[init(usdc_mint)], &[&user]).await.unwrap();
+    assert_eq!(f.lamports(&user.pubkey()).await, balance);
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.owner, user.pubkey());
+
+    // A non-canonical USDC mint for the same pot is still rejected.
+    let admin = f.admin.insecure_clone();
+    let other_mint = f.create_mint(&admin).await;
+    assert!(f.send(&[init(other_mint)], &[&user]).await.is_err());
+}
+
+#[tokio::test]
+async fn init_rejects_non_canonical_usdc_mint() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let admin = f.admin.insecure_clone();
+    let fake_usdc = f.create_mint(&admin).await;
+    let vault = vault_pda(&user.pubkey(), &POT);
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::InitPotVault {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: fake_usdc,
+            vault_usdc: get_associated_token_address(&vault, &fake_usdc),
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            system_program: system_program::ID,
+            owner_registry: owner_registry_pda(&user.pubkey()),
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: instruction::InitPotVault { pot_hash: POT }.data(),
+    };
+    assert!(f.send(&[ix], &[&user]).await.is_err());
+    assert!(!f.exists(&vault).await);
+}
@@ -0,0 +1,133 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds};
+use kobafin_escrow_tests::{config_pda, fee_treasury_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [12; 32];
+
+fn fee_tier_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::FEE_TIER, owner.as_ref()], &kobafin_escrow::ID).0
+}
+
+/// 0.5% promo tier.
+fn assign_promo(admin: &Keypair, owner: Pubkey) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::AssignFeeTier {
+            admin: admin.pubkey(),
+            config: config_pda(),
+            fee_tier: fee_tier_pda(&owner),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AssignFeeTier { owner, max_fee_bps: 50, until: 0 }.data(),
+    }
+}
+
+#[tokio::test]
+async fn assigned_tier_caps_the_withdrawal_fee() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let user_usdc = f.create_ata(&user.pubkey()).await;
+    let treasury_usdc = f.create_ata(&fee_treasury_pda()).await;
+    f.mint_usdc(&vault_usdc, 100 * USDC).await;
+    f.send(&[assign_promo(&admin, user.pubkey())], &[&admin]).await.unwrap();
+
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawUsdcWithFee {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc,
+            vault_usdc,
+            fee_treasury: fee_treasury_pda(),
+            treasury_usdc,
+            config: Some(config_pda()),
+            referral: None,
+            referrer_usdc: None,
+            token_program: spl_token::ID,
+            attestation: None,
+            fee_tier: Some(fee_tier_pda(&user.pubkey())),
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_usdc: None,
+            share_mint: None,
+            owner_shares: None,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawUsdcWithFee { pot_hash: POT, amount: 100 * USDC, fee_amount: 2 * USDC, memo_hash: [0; 32] }
+            .data(),
+    };
+    f.send(&[withdraw], &[&user]).await.unwrap();
+
+    assert_eq!(f.token_balance(&treasury_usdc).await, USDC / 2);
+    assert_eq!(f.token_balance(&user_usdc).await, 100 * USDC - USDC / 2);
+    let tier: kobafin_escrow::FeeTier = f.fetch(&fee_tier_pda(&user.pubkey())).await;
+    assert_eq!(tier.volume_usdc, 100 * USDC);
+}
+
+#[tokio::test]
+async fn sol_withdrawals_are_capped_but_earn_no_volume() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    f.send(&[assign_promo(&admin, user.pubkey())], &[&admin]).await.unwrap();
+
+    let deposit = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Deposit {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { pot_hash: POT, lamports: 2 * SOL, idempotency_key: None }.data(),
+    };
+    f.send(&[deposit], &[&user]).await.unwrap();
+
+    let treasury_before = f.lamports(&fee_treasury_pda()).await;
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawWithFee {
+            owner: user.pubkey(),
+            vault,
+            fee_treasury: fee_treasury_pda(),
+            config: Some(config_pda()),
+            referral: None,
+            referrer: None,
+            system_program: system_program::ID,
+            attestation: None,
+            fee_tier: Some(fee_tier_pda(&user.pubkey())),
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_destination: None,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawWithFee { pot_hash: POT, lamports: SOL, fee_lamports: SOL / 50, memo_hash: [0; 32] }
+            .data(),
+    };
+    f.send(&[withdraw], &[&user]).await.unwrap();
+
+    // The cap applies, but volume is counted in USDC only.
+    assert_eq!(f.lamports(&fee_treasury_pda()).await, treasury_before + SOL / 200);
+    let tier: kobafin_escrow::FeeTier = f.fetch(&fee_tier_pda(&user.pubkey())).await;
+    assert_eq!(tier.volume_usdc, 0);
+}
@@ -0,0 +1,52 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, seeds, DepositMarker};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL};
+use solana_sdk::signature::Signer;
+
+const POT: [u8; 32] = [6; 32];
+const KEY: [u8; 16] = [0xab; 16];
+
+#[tokio::test]
+async fn replayed_idempotency_key_is_rejected() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let marker = Pubkey::find_program_address(&[seeds::DEPOSIT_MARKER, vault.as_ref(), KEY.as_ref()], &kobafin_escrow::ID).0;
+
+    let deposit = |lamports, key: Option<[u8; 16]>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::Deposit {
+            owner: user.pubkey(),
+            vault,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            activity_log: None,
+            deposit_marker: key.map(|_| marker),
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit { pot_hash: POT, lamports, idempotency_key: key }.data(),
+    };
+
+    f.send(&[deposit(SOL, Some(KEY))], &[&user]).await.unwrap();
+    let m: DepositMarker = f.fetch(&marker).await;
+    assert_eq!(m.vault, vault);
+    let after_first = f.lamports(&vault).await;
+
+    // A backend retry (new blockhash, same key) must not credit again.
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    assert!(f.send(&[deposit(SOL, Some(KEY))], &[&user]).await.is_err());
+    assert_eq!(f.lamports(&vault).await, after_first);
+
+    // A key without its marker account is rejected; no key needs none.
+    let mut missing = deposit(SOL, Some([1; 16]));
+    missing.accounts.pop();
+    assert!(f.send(&[missing], &[&user]).await.is_err());
+    f.send(&[deposit(SOL, None)], &[&user]).await.unwrap();
+    assert_eq!(f.lamports(&vault).await, after_first + SOL);
+}
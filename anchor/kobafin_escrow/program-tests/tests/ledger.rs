@@ -0,0 +1,130 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, Vault};
+use kobafin_escrow_tests::{config_pda, owner_registry_pda, vault_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const MAIN: [u8; 32] = [14; 32];
+const SIDE: [u8; 32] = [15; 32];
+
+/// `total_in_usdc - total_out_usdc` must match what the vault ATA holds.
+async fn assert_ledger_balanced(f: &mut Fixture, vault: &Pubkey) {
+    let v: Vault = f.fetch(vault).await;
+    let balance = f.token_balance(&v.usdc_vault).await;
+    assert_eq!(v.total_in_usdc - v.total_out_usdc, balance);
+}
+
+fn deposit_usdc(f: &Fixture, owner: &Keypair, vault: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DepositUsdc {
+            owner: owner.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc: get_associated_token_address(&owner.pubkey(), &f.usdc_mint),
+            vault_usdc: get_associated_token_address(&vault, &f.usdc_mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            share_mint: None,
+            owner_shares: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositUsdc { pot_hash: MAIN, amount, idempotency_key: None }.data(),
+    }
+}
+
+#[tokio::test]
+async fn split_merge_and_sweep_keep_the_usdc_ledger_balanced() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 100 * USDC).await;
+    let owner = user.pubkey();
+    let main = f.init_pot(&user, MAIN).await;
+    let side = vault_pda(&owner, &SIDE);
+    let main_usdc = get_associated_token_address(&main, &f.usdc_mint);
+    let side_usdc = get_associated_token_address(&side, &f.usdc_mint);
+    f.send(&[deposit_usdc(&f, &user, main, 100 * USDC)], &[&user]).await.unwrap();
+
+    let split = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SplitPot {
+            owner,
+            source_vault: main,
+            new_vault: side,
+            usdc_mint: f.usdc_mint,
+            source_usdc: main_usdc,
+            new_usdc: side_usdc,
+            source_msol: None,
+            new_msol: None,
+            owner_registry: owner_registry_pda(&owner),
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            config: Some(config_pda()),
+            attestation: None,
+            outflow_breaker: None,
+            guardian: None,
+        }
+        .to_account_metas(None),
+        data: instruction::SplitPot {
+            source_pot_hash: MAIN,
+            new_pot_hash: SIDE,
+            lamports: 0,
+            usdc_amount: 40 * USDC,
+            msol_amount: 0,
+        }
+        .data(),
+    };
+    f.send(&[split], &[&user]).await.unwrap();
+    assert_ledger_balanced(&mut f, &main).await;
+    assert_ledger_balanced(&mut f, &side).await;
+
+    let merge = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::MergePots {
+            owner,
+            source_vault: side,
+            destination_vault: main,
+            source_usdc: side_usdc,
+            destination_usdc: main_usdc,
+            source_msol: None,
+            destination_msol: None,
+            owner_registry: owner_registry_pda(&owner),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            config: Some(config_pda()),
+            attestation: None,
+            outflow_breaker: None,
+            guardian: None,
+        }
+        .to_account_metas(None),
+        data: instruction::MergePots { source_pot_hash: SIDE, destination_pot_hash: MAIN }.data(),
+    };
+    f.send(&[merge], &[&user]).await.unwrap();
+    assert_ledger_balanced(&mut f, &main).await;
+
+    let mut metas = accounts::SweepAll {
+        owner,
+        user_usdc: get_associated_token_address(&owner, &f.usdc_mint),
+        token_program: spl_token::ID,
+        config: Some(config_pda()),
+        attestation: None,
+        outflow_breaker: None,
+        guardian: None,
+    }
+    .to_account_metas(None);
+    metas.extend([AccountMeta::new(main, false), AccountMeta::new(main_usdc, false)]);
+    let sweep = Instruction { program_id: kobafin_escrow::ID, accounts: metas, data: instruction::SweepAll {}.data() };
+    f.send(&[sweep], &[&user]).await.unwrap();
+    assert_ledger_balanced(&mut f, &main).await;
+    assert_eq!(f.token_balance(&main_usdc).await, 0);
+}
@@ -0,0 +1,72 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction};
+use kobafin_escrow_tests::{config_pda, vault_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [5; 32];
+
+fn deposit_usdc(f: &Fixture, owner: &Keypair, amount: u64) -> Instruction {
+    let vault = vault_pda(&owner.pubkey(), &POT);
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DepositUsdc {
+            owner: owner.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc: get_associated_token_address(&owner.pubkey(), &f.usdc_mint),
+            vault_usdc: get_associated_token_address(&vault, &f.usdc_mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            share_mint: None,
+            owner_shares: None,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositUsdc { pot_hash: POT, amount, idempotency_key: None }.data(),
+    }
+}
+
+#[tokio::test]
+async fn deposits_respect_minimum_and_pot_cap_unless_exempt() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdateConfig { admin: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+        data: instruction::SetDepositLimits { min_deposit_usdc: 10 * USDC, max_pot_balance_usdc: 50 * USDC }.data(),
+    };
+    f.send(&[ix], &[&admin]).await.unwrap();
+
+    let user = f.user(5 * SOL, 100 * USDC).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+
+    let ix = deposit_usdc(&f, &user, 5 * USDC);
+    assert!(f.send(&[ix], &[&user]).await.is_err(), "below the minimum");
+    let ix = deposit_usdc(&f, &user, 40 * USDC);
+    f.send(&[ix], &[&user]).await.unwrap();
+    let ix = deposit_usdc(&f, &user, 20 * USDC);
+    assert!(f.send(&[ix], &[&user]).await.is_err(), "above the pot cap");
+    assert_eq!(f.token_balance(&vault_usdc).await, 40 * USDC);
+
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::AdminVault { admin: admin.pubkey(), config: config_pda(), vault }.to_account_metas(None),
+        data: instruction::SetVaultLimitsExempt { exempt: true }.data(),
+    };
+    f.send(&[ix], &[&admin]).await.unwrap();
+    let ix = deposit_usdc(&f, &user, 5 * USDC);
+    f.send(&[ix], &[&user]).await.unwrap();
+    let ix = deposit_usdc(&f, &user, 20 * USDC);
+    f.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 65 * USDC);
+}
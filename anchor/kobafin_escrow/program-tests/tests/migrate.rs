@@ -0,0 +1,94 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{rent::Rent, system_program};
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, Vault, VaultParams};
+use kobafin_escrow_tests::{config_pda, vault_pda, Fixture, SOL};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn migrate(admin: &Keypair, vaults: &[Pubkey]) -> Instruction {
+    let mut metas = accounts::MigrateVaultParams {
+        admin: admin.pubkey(),
+        config: config_pda(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    metas.extend(vaults.iter().map(|v| AccountMeta::new(*v, false)));
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: metas,
+        data: instruction::MigrateVaultParams {}.data(),
+    }
+}
+
+fn publish_params(admin: &Keypair, params: VaultParams) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdateFeeConfig { admin: admin.pubkey(), config: config_pda() }.to_account_metas(None),
+        data: instruction::SetVaultParams { params }.data(),
+    }
+}
+
+#[tokio::test]
+async fn migration_applies_params_once_per_version() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let alice = f.user(5 * SOL, 0).await;
+    let bob = f.user(5 * SOL, 0).await;
+    let a = f.init_pot(&alice, [1; 32]).await;
+    let b = f.init_pot(&bob, [2; 32]).await;
+
+    assert!(f.send(&[migrate(&admin, &[a])], &[&admin]).await.is_err(), "nothing published yet");
+
+    let params = VaultParams { performance_fee_bps: 1_500, external_exec_cooldown_slots: 10 };
+    f.send(&[publish_params(&admin, params)], &[&admin]).await.unwrap();
+
+    f.send(&[migrate(&admin, &[a])], &[&admin]).await.unwrap();
+    let v: Vault = f.fetch(&a).await;
+    assert_eq!((v.params_version, v.performance_fee_bps, v.external_exec_cooldown_slots), (1, 1_500, 10));
+
+    // An overlapping batch only touches the vault that's still behind.
+    f.send(&[migrate(&admin, &[a, b])], &[&admin]).await.unwrap();
+    let v: Vault = f.fetch(&b).await;
+    assert_eq!(v.params_version, 1);
+
+    assert!(f.send(&[migrate(&alice, &[a])], &[&alice]).await.is_err(), "admin only");
+}
+
+#[tokio::test]
+async fn migration_grows_vaults_from_the_original_layout() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let owner = Pubkey::new_unique();
+    let pot_hash = [7; 32];
+    let key = vault_pda(&owner, &pot_hash);
+    let (_, bump) = Pubkey::find_program_address(&[kobafin_escrow::seeds::POT_VAULT, owner.as_ref(), &pot_hash], &kobafin_escrow::ID);
+    let usdc_vault = Pubkey::new_unique();
+
+    // discriminator, owner, pot_hash, bump, usdc_mint, usdc_vault: 137 bytes.
+    let mut data = Vault::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&pot_hash);
+    data.push(bump);
+    data.extend_from_slice(f.usdc_mint.as_ref());
+    data.extend_from_slice(usdc_vault.as_ref());
+    assert_eq!(data.len(), 137);
+    let legacy = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: kobafin_escrow::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    f.ctx.set_account(&key, &legacy.into());
+
+    let params = VaultParams { performance_fee_bps: 500, external_exec_cooldown_slots: 0 };
+    f.send(&[publish_params(&admin, params)], &[&admin]).await.unwrap();
+    f.send(&[migrate(&admin, &[key])], &[&admin]).await.unwrap();
+
+    let v: Vault = f.fetch(&key).await;
+    assert_eq!((v.owner, v.pot_hash, v.bump, v.usdc_vault), (owner, pot_hash, bump, usdc_vault));
+    assert_eq!((v.params_version, v.performance_fee_bps), (1, 500));
+    assert!(v.registered_mints.is_empty());
+}
@@ -0,0 +1,47 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, seeds, MilestoneEscrow};
+use kobafin_escrow_tests::{Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const ESCROW_ID: [u8; 32] = [4; 32];
+
+#[tokio::test]
+async fn arbiter_must_be_a_third_party() {
+    let mut f = Fixture::new().await;
+    let payer = f.user(5 * SOL, 0).await;
+    let payee = Keypair::new().pubkey();
+    let escrow = Pubkey::find_program_address(
+        &[seeds::MILESTONE_ESCROW, payer.pubkey().as_ref(), ESCROW_ID.as_ref()],
+        &kobafin_escrow::ID,
+    )
+    .0;
+    let open = |arbiter: Pubkey| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::OpenMilestoneEscrow {
+            payer: payer.pubkey(),
+            payee,
+            escrow,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::OpenMilestoneEscrow {
+            escrow_id: ESCROW_ID,
+            arbiter,
+            milestone_amounts: vec![SOL],
+        }
+        .data(),
+    };
+
+    for arbiter in [payer.pubkey(), payee, Pubkey::default()] {
+        f.ctx.get_new_latest_blockhash().await.unwrap();
+        assert!(f.send(&[open(arbiter)], &[&payer]).await.is_err());
+    }
+
+    let arbiter = Keypair::new().pubkey();
+    f.send(&[open(arbiter)], &[&payer]).await.unwrap();
+    let e: MilestoneEscrow = f.fetch(&escrow).await;
+    assert_eq!((e.payee, e.arbiter), (payee, arbiter));
+}
@@ -0,0 +1,100 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, PotCategory};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [12; 32];
+const INTERVAL: i64 = 30 * 24 * 60 * 60;
+
+fn pda(seed: &[u8], vault: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed, vault.as_ref()], &kobafin_escrow::ID).0
+}
+
+#[tokio::test]
+async fn installments_start_at_target_date_and_stop_when_drained() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let landlord = Keypair::new().pubkey();
+    let landlord_usdc = f.create_ata(&landlord).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    f.mint_usdc(&vault_usdc, 70 * USDC).await;
+    let (pot_metadata, payout_plan) = (pda(seeds::POT_METADATA, &vault), pda(seeds::PAYOUT_PLAN, &vault));
+
+    let target_date = f.now().await + 60;
+    let setup = [
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::SetPotMetadata {
+                owner: user.pubkey(),
+                vault,
+                pot_metadata,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::SetPotMetadata {
+                pot_hash: POT,
+                name: "Rent".to_string(),
+                category: PotCategory::Housing,
+                target_date,
+                icon_index: 0,
+            }
+            .data(),
+        },
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::CreatePayoutPlan {
+                owner: user.pubkey(),
+                vault,
+                pot_metadata,
+                payout_plan,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreatePayoutPlan {
+                pot_hash: POT,
+                recipient: landlord,
+                installment_usdc: 30 * USDC,
+                interval_secs: INTERVAL,
+            }
+            .data(),
+        },
+    ];
+    f.send(&setup, &[&user]).await.unwrap();
+
+    let crank = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::CrankPayout {
+            vault,
+            payout_plan,
+            vault_usdc,
+            recipient_usdc: landlord_usdc,
+            owner: user.pubkey(),
+            token_program: spl_token::ID,
+            config: Some(config_pda()),
+            attestation: None,
+            outflow_breaker: None,
+            guardian: None,
+            penalty_usdc: None,
+        }
+        .to_account_metas(None),
+        data: instruction::CrankPayout {}.data(),
+    };
+    assert!(f.send(&[crank.clone()], &[]).await.is_err(), "before the target date");
+
+    let mut paid = 0;
+    for (warp, installment) in [(60, 30 * USDC), (INTERVAL, 30 * USDC), (INTERVAL, 10 * USDC)] {
+        f.warp_secs(warp).await;
+        f.ctx.get_new_latest_blockhash().await.unwrap();
+        f.send(&[crank.clone()], &[]).await.unwrap();
+        paid += installment;
+        assert_eq!(f.token_balance(&landlord_usdc).await, paid);
+    }
+    assert_eq!(f.token_balance(&vault_usdc).await, 0);
+    assert!(!f.exists(&payout_plan).await, "drained plan is closed");
+}
@@ -0,0 +1,66 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, AssetTarget, PodPolicy};
+use kobafin_escrow_client::pda::policy_pda;
+use kobafin_escrow_tests::{Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POD: [u8; 32] = [11; 32];
+
+fn update(signer: &Keypair, mint: Pubkey, risk_state: u8) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdatePolicy {
+            authority: signer.pubkey(),
+            pod_policy: policy_pda(&POD).0,
+            system_program: system_program::ID,
+            policy_history: None,
+            pod: None,
+            pod_membership: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdatePolicy {
+            pod_hash: POD,
+            risk_state,
+            assets: vec![AssetTarget { mint, bps: 10_000 }],
+            usdc_in_lulo_bps: 0,
+        }
+        .data(),
+    }
+}
+
+fn set_manager(signer: &Keypair, manager: Pubkey) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::TransferPolicyAuthority { authority: signer.pubkey(), pod_policy: policy_pda(&POD).0 }
+            .to_account_metas(None),
+        data: instruction::SetPolicyManager { pod_hash: POD, manager }.data(),
+    }
+}
+
+#[tokio::test]
+async fn manager_edits_allocation_until_revoked() {
+    let mut f = Fixture::new().await;
+    let authority = f.user(5 * SOL, 0).await;
+    let manager = f.user(SOL, 0).await;
+    let usdc = f.usdc_mint;
+    f.send(&[update(&authority, usdc, 0)], &[&authority]).await.unwrap();
+
+    // Not appointed yet.
+    assert!(f.send(&[update(&manager, usdc, 1)], &[&manager]).await.is_err());
+
+    f.send(&[set_manager(&authority, manager.pubkey())], &[&authority]).await.unwrap();
+    f.send(&[update(&manager, usdc, 1)], &[&manager]).await.unwrap();
+    let data = f.ctx.banks_client.get_account(policy_pda(&POD).0).await.unwrap().unwrap().data;
+    let policy: PodPolicy = bytemuck::pod_read_unaligned(&data[8..PodPolicy::SPACE]);
+    assert_eq!((policy.risk_state, policy.authority), (1, authority.pubkey()));
+
+    // The manager can't appoint anyone, itself included.
+    assert!(f.send(&[set_manager(&manager, manager.pubkey())], &[&manager]).await.is_err());
+
+    f.send(&[set_manager(&authority, Pubkey::default())], &[&authority]).await.unwrap();
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    assert!(f.send(&[update(&manager, usdc, 2)], &[&manager]).await.is_err());
+}
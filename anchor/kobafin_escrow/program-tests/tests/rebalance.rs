@@ -0,0 +1,129 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, AssetTarget, Position, RebalancePlan, REBALANCE_SUPPLY, VENUE_LULO};
+use kobafin_escrow_tests::{config_pda, mock_lulo, position_pda, Fixture, LULO_PROGRAM_ID, SOL, USDC};
+use solana_sdk::signature::Signer;
+
+const POT: [u8; 32] = [3; 32];
+const POD: [u8; 32] = [8; 32];
+
+#[tokio::test]
+async fn planned_lulo_supply_runs_once_and_in_order() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let keeper = f.user(SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    f.mint_usdc(&vault_usdc, 100 * USDC).await;
+    let pod_policy = Pubkey::find_program_address(&[seeds::POD_POLICY, POD.as_ref()], &kobafin_escrow::ID).0;
+    let plan = Pubkey::find_program_address(&[seeds::REBALANCE_PLAN, vault.as_ref()], &kobafin_escrow::ID).0;
+
+    // All-USDC policy with half of it in Lulo.
+    let policy = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdatePolicy {
+            authority: user.pubkey(),
+            pod_policy,
+            system_program: system_program::ID,
+            policy_history: None,
+            pod: None,
+            pod_membership: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdatePolicy {
+            pod_hash: POD,
+            risk_state: 0,
+            assets: vec![AssetTarget { mint: f.usdc_mint, bps: 10_000 }],
+            usdc_in_lulo_bps: 5_000,
+        }
+        .data(),
+    };
+    let set_pod = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SetVaultPod { owner: user.pubkey(), vault }.to_account_metas(None),
+        data: instruction::SetVaultPod { pot_hash: POT, pod_hash: POD }.data(),
+    };
+    f.send(&[policy, set_pod], &[&user]).await.unwrap();
+
+    let plan_ix = || {
+        let mut metas = accounts::PlanRebalance {
+            keeper: keeper.pubkey(),
+            policy: accounts::GetPolicyEffective {
+                vault,
+                vault_usdc,
+                config: config_pda(),
+                pod_policy,
+                sol_price: None,
+                pot_policy: None,
+                vault_wsol: None,
+            },
+            plan,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        metas.extend((0..5).map(|venue| AccountMeta::new_readonly(position_pda(&vault, venue), false)));
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: metas,
+            data: instruction::PlanRebalance {}.data(),
+        }
+    };
+    f.send(&[plan_ix()], &[&keeper]).await.unwrap();
+    let p: RebalancePlan = f.fetch(&plan).await;
+    assert_eq!(p.steps.len(), 1);
+    assert_eq!((p.steps[0].kind, p.steps[0].amount), (REBALANCE_SUPPLY, 50 * USDC));
+
+    let usdc_mint = f.usdc_mint;
+    let lulo_pool = f.lulo_pool;
+    let execute = |index: u8, amount: u64| {
+        let mut metas = accounts::ExecuteRebalanceStep {
+            keeper: keeper.pubkey(),
+            vault,
+            plan,
+            config: config_pda(),
+            target_program: LULO_PROGRAM_ID,
+            vault_usdc,
+            vault_wsol: None,
+            sol_price: None,
+            position: Some(position_pda(&vault, VENUE_LULO)),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        metas.extend([
+            AccountMeta::new(vault, false),
+            AccountMeta::new(get_associated_token_address(&vault, &usdc_mint), false),
+            AccountMeta::new(lulo_pool, false),
+            AccountMeta::new_readonly(mock_lulo::pool_authority(), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]);
+        Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: metas,
+            data: instruction::ExecuteRebalanceStep { index, ix_data: mock_lulo::deposit(amount) }.data(),
+        }
+    };
+
+    // Out of order, or a CPI that moves less than planned, is rejected.
+    assert!(f.send(&[execute(1, 50 * USDC)], &[&keeper]).await.is_err());
+    assert!(f.send(&[execute(0, 10 * USDC)], &[&keeper]).await.is_err());
+    // A second plan can't replace the unfinished one.
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    assert!(f.send(&[plan_ix()], &[&keeper]).await.is_err());
+
+    f.send(&[execute(0, 50 * USDC)], &[&keeper]).await.unwrap();
+    assert_eq!(f.token_balance(&vault_usdc).await, 50 * USDC);
+    let position: Position = f.fetch(&position_pda(&vault, VENUE_LULO)).await;
+    assert_eq!(position.principal, 50 * USDC);
+    let p: RebalancePlan = f.fetch(&plan).await;
+    assert!(p.steps[0].done && p.next_step == 1);
+
+    // The step can't be replayed, and the vault is now on target.
+    f.ctx.get_new_latest_blockhash().await.unwrap();
+    assert!(f.send(&[execute(0, 50 * USDC)], &[&keeper]).await.is_err());
+    assert!(f.send(&[plan_ix()], &[&keeper]).await.is_err());
+}
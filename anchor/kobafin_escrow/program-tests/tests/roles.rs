@@ -0,0 +1,53 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use kobafin_escrow::{accounts, instruction, Config, ConfigRole};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn set_fee(signer: &Keypair, bps: u16) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::UpdateFeeConfig { admin: signer.pubkey(), config: config_pda() }.to_account_metas(None),
+        data: instruction::SetPerformanceFee { performance_fee_bps: bps }.data(),
+    }
+}
+
+fn propose(holder: &Keypair, role: ConfigRole, new_holder: &Keypair) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::ProposeRole { holder: holder.pubkey(), config: config_pda() }.to_account_metas(None),
+        data: instruction::ProposeRole { role, new_holder: new_holder.pubkey() }.data(),
+    }
+}
+
+fn accept(pending: &Keypair, role: ConfigRole) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::AcceptRole { pending: pending.pubkey(), config: config_pda() }.to_account_metas(None),
+        data: instruction::AcceptRole { role }.data(),
+    }
+}
+
+#[tokio::test]
+async fn fee_admin_hand_over_is_two_step_and_exclusive() {
+    let mut f = Fixture::new().await;
+    let admin = f.admin.insecure_clone();
+    let ops = f.user(SOL, 0).await;
+
+    // Unset roles fall back to the upgrade admin.
+    f.send(&[set_fee(&admin, 100)], &[&admin]).await.unwrap();
+    assert!(f.send(&[set_fee(&ops, 200)], &[&ops]).await.is_err());
+
+    assert!(f.send(&[propose(&ops, ConfigRole::FeeAdmin, &ops)], &[&ops]).await.is_err(), "not the holder");
+    f.send(&[propose(&admin, ConfigRole::FeeAdmin, &ops)], &[&admin]).await.unwrap();
+    assert!(f.send(&[set_fee(&ops, 200)], &[&ops]).await.is_err(), "not accepted yet");
+    assert!(f.send(&[accept(&ops, ConfigRole::RegistryAdmin)], &[&ops]).await.is_err(), "wrong role");
+    f.send(&[accept(&ops, ConfigRole::FeeAdmin)], &[&ops]).await.unwrap();
+
+    f.send(&[set_fee(&ops, 200)], &[&ops]).await.unwrap();
+    assert!(f.send(&[set_fee(&admin, 300)], &[&admin]).await.is_err(), "admin no longer holds the fee role");
+    let config: Config = f.fetch(&config_pda()).await;
+    assert_eq!(config.performance_fee_bps, 200);
+    assert_eq!((config.fee_admin, config.pending_fee_admin), (ops.pubkey(), Default::default()));
+    assert_eq!(config.admin, admin.pubkey());
+}
@@ -0,0 +1,138 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, Vault};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [6; 32];
+const NO_MEMO: [u8; 32] = [0; 32];
+
+#[tokio::test]
+async fn shares_mint_on_deposit_and_burn_on_withdraw() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 100 * USDC).await;
+    let vault = f.init_pot(&user, POT).await;
+    let share_mint = Pubkey::find_program_address(&[seeds::SHARE_MINT, vault.as_ref()], &kobafin_escrow::ID).0;
+    let owner_shares = get_associated_token_address(&user.pubkey(), &share_mint);
+    let user_usdc = get_associated_token_address(&user.pubkey(), &f.usdc_mint);
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    let usdc_mint = f.usdc_mint;
+
+    let enable = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::EnableShareMint {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            share_mint,
+            owner_shares,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::EnableShareMint { pot_hash: POT }.data(),
+    };
+    f.send(&[enable], &[&user]).await.unwrap();
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.share_mint, share_mint);
+
+    let deposit = |share_mint: Option<Pubkey>, owner_shares: Option<Pubkey>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::DepositUsdc {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint,
+            user_usdc,
+            vault_usdc,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            receipt: None,
+            config: Some(config_pda()),
+            attestation: None,
+            sol_price: None,
+            share_mint,
+            owner_shares,
+            activity_log: None,
+            deposit_marker: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DepositUsdc { pot_hash: POT, amount: 100 * USDC, idempotency_key: None }.data(),
+    };
+    // Once enabled, deposits without the share accounts are rejected.
+    assert!(f.send(&[deposit(None, None)], &[&user]).await.is_err());
+    f.send(&[deposit(Some(share_mint), Some(owner_shares))], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&owner_shares).await, 100 * USDC);
+
+    let withdraw = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawUsdc {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            user_usdc,
+            vault_usdc,
+            token_program: spl_token::ID,
+            config: Some(config_pda()),
+            penalty_usdc: None,
+            attestation: None,
+            share_mint: Some(share_mint),
+            owner_shares: Some(owner_shares),
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawUsdc { pot_hash: POT, amount: 40 * USDC, memo_hash: NO_MEMO }.data(),
+    };
+    f.send(&[withdraw], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&owner_shares).await, 60 * USDC);
+    assert_eq!(f.token_balance(&vault_usdc).await, 60 * USDC);
+
+    let recipient = Keypair::new().pubkey();
+    let recipient_usdc = f.create_ata(&recipient).await;
+    let withdraw_to = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::WithdrawUsdcTo {
+            owner: user.pubkey(),
+            vault,
+            usdc_mint: f.usdc_mint,
+            vault_usdc,
+            recipient_usdc,
+            token_program: spl_token::ID,
+            config: Some(config_pda()),
+            attestation: None,
+            blocklist: None,
+            outflow_breaker: None,
+            guardian: None,
+            activity_log: None,
+            penalty_usdc: None,
+            share_mint: Some(share_mint),
+            owner_shares: Some(owner_shares),
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawUsdcTo { pot_hash: POT, amount: 10 * USDC, memo_hash: NO_MEMO }.data(),
+    };
+    f.send(&[withdraw_to], &[&user]).await.unwrap();
+    assert_eq!(f.token_balance(&owner_shares).await, 50 * USDC);
+
+    // Paths that can't burn the owner's shares are closed while the share mint is on.
+    let mut metas = accounts::SweepAll {
+        owner: user.pubkey(),
+        user_usdc,
+        token_program: spl_token::ID,
+        config: Some(config_pda()),
+        attestation: None,
+        outflow_breaker: None,
+        guardian: None,
+    }
+    .to_account_metas(None);
+    metas.extend([AccountMeta::new(vault, false), AccountMeta::new(vault_usdc, false)]);
+    let sweep = Instruction { program_id: kobafin_escrow::ID, accounts: metas, data: instruction::SweepAll {}.data() };
+    assert!(f.send(&[sweep], &[&user]).await.is_err());
+    assert_eq!(f.token_balance(&vault_usdc).await, 50 * USDC);
+}
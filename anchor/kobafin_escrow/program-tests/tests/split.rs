@@ -0,0 +1,60 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, SplitEntry, Vault};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::Signer;
+
+const SPENDING: [u8; 32] = [10; 32];
+const SAVINGS: [u8; 32] = [11; 32];
+
+#[tokio::test]
+async fn deposit_is_split_across_pots_by_rule() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 101 * USDC).await;
+    let spending = f.init_pot(&user, SPENDING).await;
+    let savings = f.init_pot(&user, SAVINGS).await;
+    let owner = user.pubkey();
+    let split_rule = Pubkey::find_program_address(&[seeds::SPLIT_RULE, owner.as_ref()], &kobafin_escrow::ID).0;
+
+    let set_rule = |entries: Vec<SplitEntry>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::SetSplitRule { owner: user.pubkey(), split_rule, system_program: system_program::ID }
+            .to_account_metas(None),
+        data: instruction::SetSplitRule { entries }.data(),
+    };
+    let bad = vec![SplitEntry { pot_hash: SPENDING, bps: 7_000 }, SplitEntry { pot_hash: SAVINGS, bps: 2_000 }];
+    assert!(f.send(&[set_rule(bad)], &[&user]).await.is_err(), "shares must add up to 100%");
+    let rule = vec![SplitEntry { pot_hash: SPENDING, bps: 7_000 }, SplitEntry { pot_hash: SAVINGS, bps: 3_000 }];
+    f.send(&[set_rule(rule)], &[&user]).await.unwrap();
+
+    let spending_usdc = get_associated_token_address(&spending, &f.usdc_mint);
+    let savings_usdc = get_associated_token_address(&savings, &f.usdc_mint);
+    let mut metas = accounts::DepositWithSplit {
+        owner: user.pubkey(),
+        split_rule,
+        user_usdc: get_associated_token_address(&user.pubkey(), &f.usdc_mint),
+        token_program: spl_token::ID,
+        config: config_pda(),
+        attestation: None,
+        sol_price: None,
+    }
+    .to_account_metas(None);
+    for key in [spending, spending_usdc, savings, savings_usdc] {
+        metas.push(AccountMeta::new(key, false));
+    }
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: metas,
+        data: instruction::DepositWithSplit { amount: 100 * USDC + 1 }.data(),
+    };
+    f.send(&[ix], &[&user]).await.unwrap();
+
+    assert_eq!(f.token_balance(&spending_usdc).await, 70 * USDC);
+    assert_eq!(f.token_balance(&savings_usdc).await, 30 * USDC + 1);
+    let v: Vault = f.fetch(&savings).await;
+    assert_eq!(v.total_in_usdc, 30 * USDC + 1);
+}
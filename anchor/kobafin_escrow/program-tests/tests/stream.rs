@@ -0,0 +1,89 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, Stream, Vault};
+use kobafin_escrow_tests::{Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [12; 32];
+const RATE: u64 = 10_000;
+
+fn stream_pda(vault: &Pubkey, recipient: &Pubkey, stream_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[seeds::STREAM, vault.as_ref(), recipient.as_ref(), &stream_id.to_le_bytes()],
+        &kobafin_escrow::ID,
+    )
+    .0
+}
+
+#[tokio::test]
+async fn streams_to_one_recipient_are_independent_and_cancel_refunds_rent() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    f.mint_usdc(&vault_usdc, 100 * USDC).await;
+    let recipient = Keypair::new().pubkey();
+    let recipient_usdc = f.create_ata(&recipient).await;
+
+    let end_ts = f.now().await + 1_000;
+    for stream_id in [0, 1] {
+        let ix = Instruction {
+            program_id: kobafin_escrow::ID,
+            accounts: accounts::CreateStream {
+                owner: user.pubkey(),
+                vault,
+                vault_usdc,
+                stream: stream_pda(&vault, &recipient, stream_id),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreateStream { pot_hash: POT, recipient, stream_id, rate_per_second: RATE, end_ts }
+                .data(),
+        };
+        f.send(&[ix], &[&user]).await.unwrap();
+    }
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.stream_locked_usdc, 20 * USDC);
+
+    f.warp_secs(400).await;
+    let first = stream_pda(&vault, &recipient, 0);
+    let s: Stream = f.fetch(&first).await;
+    let paid = RATE * (f.now().await - s.start_ts) as u64;
+    let owner_before = f.lamports(&user.pubkey()).await;
+    let rent = f.lamports(&first).await;
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::CancelStream {
+            owner: user.pubkey(),
+            vault,
+            stream: first,
+            vault_usdc,
+            recipient_usdc,
+            token_program: spl_token::ID,
+            share_mint: None,
+            owner_shares: None,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelStream { pot_hash: POT }.data(),
+    };
+    f.send(&[ix], &[&user]).await.unwrap();
+
+    assert_eq!(f.token_balance(&recipient_usdc).await, paid);
+    assert!(!f.exists(&first).await);
+    assert!(f.lamports(&user.pubkey()).await > owner_before + rent - SOL / 100, "rent returned less fees");
+    let v: Vault = f.fetch(&vault).await;
+    assert_eq!(v.stream_locked_usdc, 10 * USDC, "only the second stream is still reserved");
+    assert!(f.exists(&stream_pda(&vault, &recipient, 1)).await);
+
+    let close = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::CloseStream { owner: user.pubkey(), vault, stream: stream_pda(&vault, &recipient, 1) }
+            .to_account_metas(None),
+        data: instruction::CloseStream { pot_hash: POT }.data(),
+    };
+    assert!(f.send(&[close], &[&user]).await.is_err(), "recipient is still owed");
+}
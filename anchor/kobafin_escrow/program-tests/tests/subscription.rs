@@ -0,0 +1,83 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use kobafin_escrow::{accounts, instruction, seeds, Subscription};
+use kobafin_escrow_tests::{config_pda, Fixture, SOL, USDC};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POT: [u8; 32] = [16; 32];
+const INTERVAL: i64 = 100;
+const GRACE: i64 = 10;
+
+fn create(owner: &Keypair, vault: Pubkey, merchant: Pubkey, merchant_usdc: Pubkey, first_charge_ts: i64) -> Instruction {
+    Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::CreateSubscription {
+            owner: owner.pubkey(),
+            vault,
+            subscription: subscription_pda(&vault, &merchant),
+            merchant_usdc,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateSubscription {
+            pot_hash: POT,
+            merchant,
+            amount: 5 * USDC,
+            interval_secs: INTERVAL,
+            grace_secs: GRACE,
+            first_charge_ts,
+        }
+        .data(),
+    }
+}
+
+fn subscription_pda(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::SUBSCRIPTION, vault.as_ref(), merchant.as_ref()], &kobafin_escrow::ID).0
+}
+
+#[tokio::test]
+async fn long_missed_stretch_is_skipped_in_one_charge() {
+    let mut f = Fixture::new().await;
+    let user = f.user(5 * SOL, 0).await;
+    let vault = f.init_pot(&user, POT).await;
+    let vault_usdc = get_associated_token_address(&vault, &f.usdc_mint);
+    f.mint_usdc(&vault_usdc, 100 * USDC).await;
+    let merchant = Keypair::new();
+    let merchant_usdc = f.create_ata(&merchant.pubkey()).await;
+
+    let start = f.now().await;
+    let ix = create(&user, vault, merchant.pubkey(), merchant_usdc, start - 1);
+    assert!(f.send(&[ix], &[&user]).await.is_err(), "first charge in the past");
+    f.send(&[create(&user, vault, merchant.pubkey(), merchant_usdc, start + 10)], &[&user]).await.unwrap();
+
+    // 50 cycles lapse; the clock lands 5s into the 51st.
+    f.warp_secs(10 + 50 * INTERVAL + 5).await;
+    let charge = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: accounts::ChargeSubscription {
+            merchant: merchant.pubkey(),
+            vault,
+            subscription: subscription_pda(&vault, &merchant.pubkey()),
+            vault_usdc,
+            merchant_usdc,
+            token_program: spl_token::ID,
+            config: Some(config_pda()),
+            attestation: None,
+            outflow_breaker: None,
+            guardian: None,
+            penalty_usdc: None,
+        }
+        .to_account_metas(None),
+        data: instruction::ChargeSubscription { amount: 5 * USDC }.data(),
+    };
+    f.send(&[charge], &[&merchant]).await.unwrap();
+
+    assert_eq!(f.token_balance(&merchant_usdc).await, 5 * USDC);
+    let s: Subscription = f.fetch(&subscription_pda(&vault, &merchant.pubkey())).await;
+    assert_eq!(s.missed_charges, 50);
+    assert_eq!(s.next_charge_ts, start + 10 + 51 * INTERVAL);
+}
@@ -0,0 +1,103 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token::native_mint;
+use kobafin_escrow::{
+    accounts, instruction, AssetTarget, PodPolicy, VenueAllocation, VENUE_KAMINO, VENUE_LULO, VENUE_MARINADE,
+};
+use kobafin_escrow_client::pda::policy_pda;
+use kobafin_escrow_tests::{Fixture, SOL};
+use solana_sdk::signature::{Keypair, Signer};
+
+const POD: [u8; 32] = [9; 32];
+
+fn policy_accounts(authority: &Keypair) -> Vec<anchor_lang::solana_program::instruction::AccountMeta> {
+    accounts::UpdatePolicy {
+        authority: authority.pubkey(),
+        pod_policy: policy_pda(&POD).0,
+        system_program: system_program::ID,
+        policy_history: None,
+        pod: None,
+        pod_membership: None,
+    }
+    .to_account_metas(None)
+}
+
+#[tokio::test]
+async fn venue_allocations_are_validated_per_asset() {
+    let mut f = Fixture::new().await;
+    let authority = f.user(5 * SOL, 0).await;
+    let usdc = f.usdc_mint;
+    let sol = native_mint::ID;
+
+    let update = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: policy_accounts(&authority),
+        data: instruction::UpdatePolicy {
+            pod_hash: POD,
+            risk_state: 1,
+            assets: vec![AssetTarget { mint: usdc, bps: 6_000 }, AssetTarget { mint: sol, bps: 4_000 }],
+            usdc_in_lulo_bps: 2_000,
+        }
+        .data(),
+    };
+    f.send(&[update], &[&authority]).await.unwrap();
+
+    let set = |allocations: Vec<VenueAllocation>| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: policy_accounts(&authority),
+        data: instruction::SetVenueAllocations { pod_hash: POD, allocations }.data(),
+    };
+    let alloc = |mint, venue_id, bps| VenueAllocation { mint, venue_id, bps };
+
+    // USDC split 2_500 / 3_500 fills its target exactly; SOL stakes half of its.
+    let split = vec![
+        alloc(usdc, VENUE_LULO, 2_500),
+        alloc(usdc, VENUE_KAMINO, 3_500),
+        alloc(sol, VENUE_MARINADE, 2_000),
+    ];
+    f.send(&[set(split.clone())], &[&authority]).await.unwrap();
+    let data = f.ctx.banks_client.get_account(policy_pda(&POD).0).await.unwrap().unwrap().data;
+    let policy: PodPolicy = bytemuck::pod_read_unaligned(&data[8..PodPolicy::SPACE]);
+    assert_eq!(policy.venue_allocations(), split);
+    assert_eq!(policy.usdc_in_lulo_bps, 2_500);
+    assert_eq!(policy.cash_venue(), Some(VENUE_KAMINO));
+
+    // Retargeting keeps the split while the new targets still hold it.
+    let retarget = |usdc_bps, sol_bps| Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: policy_accounts(&authority),
+        data: instruction::UpdatePolicy {
+            pod_hash: POD,
+            risk_state: 1,
+            assets: vec![AssetTarget { mint: usdc, bps: usdc_bps }, AssetTarget { mint: sol, bps: sol_bps }],
+            usdc_in_lulo_bps: 2_000,
+        }
+        .data(),
+    };
+    f.send(&[retarget(7_000, 3_000)], &[&authority]).await.unwrap();
+    let data = f.ctx.banks_client.get_account(policy_pda(&POD).0).await.unwrap().unwrap().data;
+    let policy: PodPolicy = bytemuck::pod_read_unaligned(&data[8..PodPolicy::SPACE]);
+    assert_eq!(policy.venue_allocations(), split);
+    assert_eq!(policy.usdc_in_lulo_bps, 2_500);
+    assert!(f.send(&[retarget(9_000, 1_000)], &[&authority]).await.is_err(), "SOL target under its Marinade leg");
+
+    // Over the asset's target, SOL-only venue on USDC, and a repeated pair all fail.
+    for bad in [
+        vec![alloc(usdc, VENUE_LULO, 4_000), alloc(usdc, VENUE_KAMINO, 2_001)],
+        vec![alloc(usdc, VENUE_MARINADE, 1_000)],
+        vec![alloc(sol, VENUE_MARINADE, 1_000), alloc(sol, VENUE_MARINADE, 1_000)],
+    ] {
+        f.ctx.get_new_latest_blockhash().await.unwrap();
+        assert!(f.send(&[set(bad)], &[&authority]).await.is_err());
+    }
+
+    // Someone other than the policy authority can't touch the split.
+    let stranger = f.user(SOL, 0).await;
+    let ix = Instruction {
+        program_id: kobafin_escrow::ID,
+        accounts: policy_accounts(&stranger),
+        data: instruction::SetVenueAllocations { pod_hash: POD, allocations: Vec::new() }.data(),
+    };
+    assert!(f.send(&[ix], &[&stranger]).await.is_err());
+}
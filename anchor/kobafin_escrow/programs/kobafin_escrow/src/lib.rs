@@ -1,35 +1,363 @@
+// Instructions outside the core pot vault are gated by `full`, `lulo` and `oracle`;
+// a `lite` build leaves the helpers behind those gates unused.
+#![cfg_attr(not(feature = "full"), allow(dead_code, unused_imports))]
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
 use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::ed25519_program;
 use anchor_lang::solana_program::program::invoke_signed;
-use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use std::str::FromStr;
+use anchor_lang::solana_program::pubkey;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, SyncNative, Token, TokenAccount, Transfer};
+#[cfg(feature = "compressed")]
+use light_sdk::{
+    account::LightAccount,
+    address::v1::derive_address,
+    cpi::{CpiAccounts, CpiInputs, CpiSigner},
+    derive_light_cpi_signer,
+    instruction::{account_meta::CompressedAccountMeta, PackedAddressTreeInfo, ValidityProof},
+    LightDiscriminator, LightHasher,
+};
 
 declare_id!("8igAph8Ypy6YZh1QLhzzkvVkzGybzjCyBawAtHpWtVLX");
 
-const LULO_PROGRAM_ID: &str = "FL3X2pRsQ9zHENpZSKDRREtccwJuei8yg9fwDu9UN69Q";
+/// The program's CPI authority for the Light system program.
+#[cfg(feature = "compressed")]
+const LIGHT_CPI_SIGNER: CpiSigner = derive_light_cpi_signer!("8igAph8Ypy6YZh1QLhzzkvVkzGybzjCyBawAtHpWtVLX");
+
+const LULO_PROGRAM_ID: Pubkey = pubkey!("FL3X2pRsQ9zHENpZSKDRREtccwJuei8yg9fwDu9UN69Q");
+const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+const MSOL_MINT: Pubkey = pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+
+// Anchor discriminators of Marinade's `deposit` and `liquid_unstake`.
+const MARINADE_DEPOSIT_IX: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
+const MARINADE_LIQUID_UNSTAKE_IX: [u8; 8] = [30, 30, 119, 240, 191, 227, 12, 16];
+const KAMINO_DEPOSIT_IX: [u8; 8] = [169, 201, 30, 126, 6, 205, 102, 68];
+const KAMINO_REDEEM_IX: [u8; 8] = [234, 117, 181, 125, 185, 142, 220, 29];
+// Circle CCTP: TokenMessengerMinter `deposit_for_burn` and its MessageTransmitter.
+const CCTP_TOKEN_MESSENGER_MINTER_ID: Pubkey = pubkey!("CCTPiPYPc6AsJuwueEnWgSgucamXDZwBd53dQ11YiKX3");
+const CCTP_MESSAGE_TRANSMITTER_ID: Pubkey = pubkey!("CCTPmbSD7gX1bxKPAmg77w8oFzNFpaQiQUWD43TKaecd");
+const CCTP_DEPOSIT_FOR_BURN_IX: [u8; 8] = [215, 60, 61, 46, 114, 55, 128, 176];
+const MARGINFI_INIT_ACCOUNT_IX: [u8; 8] = [43, 78, 61, 255, 148, 52, 249, 154];
+const MARGINFI_DEPOSIT_IX: [u8; 8] = [171, 94, 235, 103, 82, 64, 212, 140];
+const MARGINFI_WITHDRAW_IX: [u8; 8] = [36, 72, 74, 19, 210, 210, 192, 192];
+const DRIFT_INIT_USER_STATS_IX: [u8; 8] = [254, 243, 72, 98, 251, 130, 168, 213];
+const DRIFT_INIT_IF_STAKE_IX: [u8; 8] = [187, 179, 243, 70, 248, 90, 92, 147];
+const DRIFT_ADD_IF_STAKE_IX: [u8; 8] = [251, 144, 115, 11, 222, 47, 62, 236];
+const DRIFT_REQUEST_REMOVE_IF_STAKE_IX: [u8; 8] = [142, 70, 204, 92, 73, 106, 180, 52];
+const DRIFT_REMOVE_IF_STAKE_IX: [u8; 8] = [128, 166, 142, 9, 254, 187, 143, 174];
+const BUBBLEGUM_MINT_TO_COLLECTION_IX: [u8; 8] = [153, 18, 178, 47, 197, 158, 86, 15];
+
+const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+const SPL_NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+const SPL_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+const RECEIPT_NAME: &str = "Kobafin Savings Receipt";
+const RECEIPT_SYMBOL: &str = "KOBA";
+const ORAO_VRF_PROGRAM_ID: Pubkey = pubkey!("VRFzZoJdhFWL8rkvu87LpKM3RbcVezpMEc6X5GVDr7y");
+const ORAO_REQUEST_IX: [u8; 8] = [46, 101, 67, 11, 76, 137, 12, 173];
+const ORAO_RANDOMNESS_SEED: &[u8] = b"orao-vrf-randomness-request";
+/// `Randomness { seed: [u8; 32], randomness: [u8; 64], .. }` after the discriminator.
+const ORAO_RANDOMNESS_OFFSET: usize = 8 + 32;
+const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// `PriceFeedMessage` in a fully verified `PriceUpdateV2`.
+const PYTH_PRICE_MESSAGE_OFFSET: usize = 8 + 32 + 1;
+/// `price` and `ema_price` within the message.
+const PYTH_SPOT_PRICE_OFFSET: usize = 32;
+const PYTH_EMA_PRICE_OFFSET: usize = 68;
+const MAX_PRICE_AGE_SECS: i64 = 120;
+/// Swaps are checked against a fresher price than valuations: a stale mid lets a
+/// moved market pass as in-band.
+const MAX_SWAP_PRICE_AGE_SECS: i64 = 30;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Idle USDC, one bucket per USDC venue in `SNAPSHOT_POSITION_VENUES` order, then SOL.
+const SNAPSHOT_BUCKETS: usize = 6;
+const SNAPSHOT_POSITION_VENUES: [u8; 5] = [VENUE_LULO, VENUE_KAMINO, VENUE_MARGINFI, VENUE_DRIFT, VENUE_SANCTUM];
+
+// Venue ids used as keys in the ProtocolRegistry and Position seeds.
+pub const VENUE_LULO: u8 = 0;
+pub const VENUE_KAMINO: u8 = 1;
+pub const VENUE_MARGINFI: u8 = 2;
+pub const VENUE_DRIFT: u8 = 3;
+pub const VENUE_SANCTUM: u8 = 4;
+pub const VENUE_MARINADE: u8 = 5;
+
+// Action kinds recorded in an ActivityLog.
+pub const ACTIVITY_DEPOSIT: u8 = 0;
+pub const ACTIVITY_WITHDRAW: u8 = 1;
+pub const ACTIVITY_WITHDRAW_TO: u8 = 2;
+
+/// `RebalanceStep::kind` values, in the order `plan_rebalance` schedules them.
+pub const REBALANCE_REDEEM: u8 = 0;
+pub const REBALANCE_SELL_SOL: u8 = 1;
+pub const REBALANCE_BUY_SOL: u8 = 2;
+pub const REBALANCE_SUPPLY: u8 = 3;
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// Marinade quotes msol_price as lamports per mSOL scaled by 2^32.
+pub const MSOL_PRICE_DENOMINATOR: u128 = 0x1_0000_0000;
+const MARINADE_STATE_MSOL_PRICE_OFFSET: usize = 512;
+
+// SPL stake-pool programs (SPL, Sanctum single- and multi-validator) and the
+// StakePool fields read for LST valuation.
+const STAKE_POOL_PROGRAM_IDS: [Pubkey; 3] = [
+    pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
+    pubkey!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY"),
+    pubkey!("SPMBzsVUuoHA4Jm6KunbsotaahvVikZs1JyTW6iJvbn"),
+];
+const STAKE_POOL_MINT_OFFSET: usize = 1 + 32 * 3 + 1 + 32 * 2;
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 1 + 32 * 3 + 1 + 32 * 5;
+const STAKE_POOL_SUPPLY_OFFSET: usize = STAKE_POOL_TOTAL_LAMPORTS_OFFSET + 8;
+
+// DEX and swap-router programs (Jupiter v6, Orca Whirlpool, Raydium AMM v4 and CLMM,
+// Meteora DLMM, Phoenix, Sanctum router and Infinity). A guarded swap refuses to
+// share its transaction with direct calls into any of them.
+const DEX_PROGRAM_IDS: [Pubkey; 8] = [
+    pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"),
+    pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"),
+    pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"),
+    pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"),
+    pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"),
+    pubkey!("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY"),
+    pubkey!("stkitrT1Uoy18Dk1fTrgPw8W6MVzoCfYoAFT4MLsmhq"),
+    pubkey!("5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx"),
+];
+/// Used when `Config::max_swap_slippage_bps` is unset.
+pub const DEFAULT_SWAP_SLIPPAGE_BPS: u16 = 100;
+/// Used when `PodPolicy::max_drift_bps` is unset.
+pub const DEFAULT_MAX_DRIFT_BPS: u16 = 500;
+/// `PodPolicy::valuation_mode`: SOL valued at the Pyth spot price.
+pub const VALUATION_SPOT: u8 = 0;
+/// `PodPolicy::valuation_mode`: SOL valued at the Pyth EMA price, which smooths
+/// daily snapshots against intraday swings.
+pub const VALUATION_EMA: u8 = 1;
+
+pub const MAX_POLICY_ASSETS: usize = 8;
+/// Venue allocations a pod policy can hold across all of its assets.
+pub const MAX_POLICY_VENUES: usize = 8;
+pub const MAX_BATCH_POTS: usize = 16;
+pub const MAX_ROUNDUP_BATCH: usize = 64;
+
+/// Allocation per risk state (0 conservative, 1 balanced, 2 aggressive) as
+/// [usdc, btc, eth, sol, usdc_in_lulo] bps.
+pub const RISK_PRESETS: [[u16; 5]; 3] = [
+    [7_000, 1_000, 500, 1_500, 5_000],
+    [4_000, 2_000, 1_500, 2_500, 3_000],
+    [1_500, 3_000, 2_500, 3_000, 1_000],
+];
+
+/// Values clients need to agree with the program on, exported to the IDL as Anchor
+/// constants.
+pub mod constants {
+    use super::*;
+
+    /// PDA seed prefixes, for clients and CPI callers deriving program addresses.
+    pub mod seeds {
+        use anchor_lang::prelude::*;
+
+        #[constant]
+        pub const ACTIVITY_LOG: &[u8] = b"activity";
+        #[constant]
+        pub const ATTESTATION: &[u8] = b"attestation";
+        #[constant]
+        pub const BLOCKLIST: &[u8] = b"blocklist";
+        #[constant]
+        pub const CNFT_RECEIPT: &[u8] = b"cnft_receipt";
+        #[constant]
+        pub const COMPRESSED_POOL: &[u8] = b"compressed_pool";
+        #[constant]
+        pub const COMPRESSED_VAULT: &[u8] = b"compressed_vault";
+        #[constant]
+        pub const COND_ESCROW: &[u8] = b"cond_escrow";
+        #[constant]
+        pub const CONFIG: &[u8] = b"config";
+        #[constant]
+        pub const CONTRIBUTION: &[u8] = b"contribution";
+        #[constant]
+        pub const DEPOSIT_RECEIPT: &[u8] = b"deposit_receipt";
+        #[constant]
+        pub const DEPOSIT_MARKER: &[u8] = b"deposit_marker";
+        #[constant]
+        pub const DONATION: &[u8] = b"donation";
+        #[constant]
+        pub const ENVELOPE: &[u8] = b"envelope";
+        #[constant]
+        pub const GOAL_BASKET: &[u8] = b"goal_basket";
+        #[constant]
+        pub const CROSSCHAIN_DOMAINS: &[u8] = b"crosschain_domains";
+        #[constant]
+        pub const STABLE_ROUTE: &[u8] = b"stable_route";
+        #[constant]
+        pub const FEE_TIER: &[u8] = b"fee_tier";
+        #[constant]
+        pub const FEE_TREASURY: &[u8] = b"fee_treasury";
+        #[constant]
+        pub const FEE_TIER_SCHEDULE: &[u8] = b"fee_tier_schedule";
+        #[constant]
+        pub const OUTFLOW_BREAKER: &[u8] = b"outflow_breaker";
+        #[constant]
+        pub const CROSSCHAIN_RECIPIENT: &[u8] = b"crosschain_recipient";
+        #[constant]
+        pub const MILESTONE_ESCROW: &[u8] = b"milestone_escrow";
+        #[constant]
+        pub const PAYOUT_PLAN: &[u8] = b"payout_plan";
+        #[constant]
+        pub const POD: &[u8] = b"pod";
+        #[constant]
+        pub const POD_INVITE: &[u8] = b"pod_invite";
+        #[constant]
+        pub const POD_POLICY: &[u8] = b"pod_policy";
+        #[constant]
+        pub const POD_VAULT: &[u8] = b"pod_vault";
+        #[constant]
+        pub const POLICY_HISTORY: &[u8] = b"policy_history";
+        #[constant]
+        pub const POSITION: &[u8] = b"position";
+        #[constant]
+        pub const POT_COMMITMENT: &[u8] = b"pot_commitment";
+        #[constant]
+        pub const POT_METADATA: &[u8] = b"pot_metadata";
+        #[constant]
+        pub const POT_POLICY: &[u8] = b"pot_policy";
+        #[constant]
+        pub const POT_VAULT: &[u8] = b"pot_vault";
+        #[constant]
+        pub const REBALANCE_PLAN: &[u8] = b"rebalance_plan";
+        #[constant]
+        pub const PROTOCOL_REGISTRY: &[u8] = b"protocol_registry";
+        #[constant]
+        pub const RECEIPT_AUTHORITY: &[u8] = b"receipt_authority";
+        #[constant]
+        pub const REFERRAL: &[u8] = b"referral";
+        #[constant]
+        pub const OWNER_REGISTRY: &[u8] = b"registry";
+        #[constant]
+        pub const REMOVAL: &[u8] = b"removal";
+        #[constant]
+        pub const SHARE_MINT: &[u8] = b"share_mint";
+        #[constant]
+        pub const SNAPSHOT_BUFFER: &[u8] = b"snapshots";
+        #[constant]
+        pub const SOL_STAGE: &[u8] = b"sol_stage";
+        #[constant]
+        pub const SPLIT_RULE: &[u8] = b"split_rule";
+        #[constant]
+        pub const STREAM: &[u8] = b"stream";
+        #[constant]
+        pub const SUBSCRIPTION: &[u8] = b"subscription";
+        #[constant]
+        pub const SWAP_OFFER: &[u8] = b"swap_offer";
+    }
+
+    #[constant]
+    pub const USDC_MINT_MAINNET: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+    #[constant]
+    pub const USDC_MINT_DEVNET: Pubkey = pubkey!("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU");
+
+    // Account sizes, discriminator included.
+    #[constant]
+    pub const VAULT_SPACE: u64 = Vault::SPACE as u64;
+    #[constant]
+    pub const CONFIG_SPACE: u64 = Config::SPACE as u64;
+    #[constant]
+    pub const POD_POLICY_SPACE: u64 = PodPolicy::SPACE as u64;
+    #[constant]
+    pub const POT_POLICY_SPACE: u64 = PotPolicy::SPACE as u64;
+    #[constant]
+    pub const POSITION_SPACE: u64 = Position::SPACE as u64;
+    #[constant]
+    pub const POD_SPACE: u64 = Pod::SPACE as u64;
+    #[constant]
+    pub const CONTRIBUTION_SPACE: u64 = Contribution::SPACE as u64;
+    #[constant]
+    pub const ATTESTATION_SPACE: u64 = Attestation::SPACE as u64;
+    #[constant]
+    pub const BLOCKLIST_SPACE: u64 = Blocklist::SPACE as u64;
+    #[constant]
+    pub const SNAPSHOT_BUFFER_SPACE: u64 = SnapshotBuffer::SPACE as u64;
+    #[constant]
+    pub const ACTIVITY_LOG_SPACE: u64 = ActivityLog::SPACE as u64;
+}
+
+pub use constants::seeds;
 
 #[program]
 pub mod kobafin_escrow {
     use super::*;
 
     pub fn init_pot_vault(ctx: Context<InitPotVault>, pot_hash: [u8; 32]) -> Result<()> {
-        let v = &mut ctx.accounts.vault;
-
-        v.owner = ctx.accounts.owner.key();
-        v.pot_hash = pot_hash;
-        v.bump = ctx.bumps.vault;
-        v.usdc_mint = ctx.accounts.usdc_mint.key();
-        v.usdc_vault = ctx.accounts.vault_usdc.key();
+        let a = ctx.accounts;
+        let owner = a.owner.key();
+        a.vault.init(owner, pot_hash, ctx.bumps.vault, a.usdc_mint.key(), a.vault_usdc.key());
+        registry_init_and_add(
+            &mut a.owner_registry,
+            &a.owner.to_account_info(),
+            &a.system_program.to_account_info(),
+            pot_hash,
+            ctx.bumps.owner_registry,
+        )
+    }
 
-        Ok(())
+    /// `init_pot_vault` for client retries: a no-op when the vault already exists for
+    /// this owner, pot and USDC mint, instead of failing with "already in use".
+    /// Policies and pot metadata need no variant; their setters already create on
+    /// first use.
+    pub fn init_pot_vault_idempotent(ctx: Context<InitPotVaultIdempotent>, pot_hash: [u8; 32]) -> Result<()> {
+        let a = ctx.accounts;
+        if a.vault.owner != Pubkey::default() {
+            require_keys_eq!(a.vault.owner, a.owner.key(), EscrowError::Unauthorized);
+            require!(a.vault.pot_hash == pot_hash, EscrowError::BadPot);
+            require_keys_eq!(a.vault.usdc_mint, a.usdc_mint.key(), EscrowError::BadMint);
+            return Ok(());
+        }
+        let owner = a.owner.key();
+        a.vault.init(owner, pot_hash, ctx.bumps.vault, a.usdc_mint.key(), a.vault_usdc.key());
+        registry_init_and_add(
+            &mut a.owner_registry,
+            &a.owner.to_account_info(),
+            &a.system_program.to_account_info(),
+            pot_hash,
+            ctx.bumps.owner_registry,
+        )
     }
 
-    pub fn deposit(ctx: Context<Deposit>, pot_hash: [u8; 32], lamports: u64) -> Result<()> {
+    /// `idempotency_key`, when set, is claimed through `deposit_marker` so a
+    /// retried submission fails with `DuplicateOperation` instead of crediting twice.
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        pot_hash: [u8; 32],
+        lamports: u64,
+        idempotency_key: Option<[u8; 16]>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
         require!(lamports > 0, EscrowError::InvalidAmount);
+        claim_idempotency_key(
+            idempotency_key,
+            ctx.accounts.deposit_marker.as_ref(),
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program,
+        )?;
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            lamports,
+        )?;
 
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        check_deposit_limits(
+            ctx.accounts.config.as_ref(),
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            lamports,
+            0,
+        )?;
 
         let ix = anchor_lang::solana_program::system_instruction::transfer(
             &ctx.accounts.owner.key(),
@@ -46,35 +374,110 @@ pub mod kobafin_escrow {
             ],
         )?;
 
+        record_deposit(
+            &mut ctx.accounts.vault,
+            ctx.accounts.receipt.as_mut(),
+            ctx.accounts.owner.key(),
+            Pubkey::default(),
+            lamports,
+        )?;
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_DEPOSIT,
+            ctx.accounts.owner.key(),
+            Pubkey::default(),
+            lamports,
+        )?;
+
         Ok(())
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, pot_hash: [u8; 32], lamports: u64) -> Result<()> {
+    pub fn withdraw(ctx: Context<Withdraw>, pot_hash: [u8; 32], lamports: u64, memo_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            lamports,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), lamports, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            lamports,
+        )?;
 
         let rent = Rent::get()?;
         let min = rent.minimum_balance(Vault::SPACE);
         let current = ctx.accounts.vault.to_account_info().lamports();
-        require!(current.saturating_sub(min) >= lamports, EscrowError::InsufficientFunds);
+        require!(withdrawable_lamports(current, min) >= lamports, EscrowError::InsufficientFunds);
+
+        let penalty = slash_lamports(
+            &ctx.accounts.vault,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_destination.as_ref(),
+            lamports,
+        )?;
 
         let vault_info = ctx.accounts.vault.to_account_info();
         let owner_info = ctx.accounts.owner.to_account_info();
         let mut vault_lamports = vault_info.try_borrow_mut_lamports()?;
         let mut owner_lamports = owner_info.try_borrow_mut_lamports()?;
-        **vault_lamports -= lamports;
-        **owner_lamports += lamports;
+        let net = lamports.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?;
+        **vault_lamports = vault_lamports.checked_sub(net).ok_or(EscrowError::MathUnderflow)?;
+        **owner_lamports = owner_lamports.checked_add(net).ok_or(EscrowError::MathOverflow)?;
+
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: Pubkey::default(),
+            amount: lamports,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW,
+            ctx.accounts.owner.key(),
+            Pubkey::default(),
+            lamports,
+        )?;
         Ok(())
     }
 
+    #[cfg(feature = "full")]
     pub fn withdraw_with_fee(
         ctx: Context<WithdrawWithFee>,
         pot_hash: [u8; 32],
         lamports: u64,
         fee_lamports: u64,
+        memo_hash: [u8; 32],
     ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
         require!(lamports > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            lamports,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), lamports, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            lamports,
+        )?;
         require!(fee_lamports <= lamports, EscrowError::InvalidFee);
+        let fee_lamports = apply_fee_tier(ctx.accounts.fee_tier.as_ref(), lamports, fee_lamports)?;
 
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
@@ -82,29 +485,256 @@ pub mod kobafin_escrow {
         let rent = Rent::get()?;
         let min = rent.minimum_balance(Vault::SPACE);
         let current = ctx.accounts.vault.to_account_info().lamports();
-        require!(current.saturating_sub(min) >= lamports, EscrowError::InsufficientFunds);
+        require!(withdrawable_lamports(current, min) >= lamports, EscrowError::InsufficientFunds);
 
-        let net = lamports.saturating_sub(fee_lamports);
+        let referral_bps = match ctx.accounts.referral.as_ref() {
+            Some(referral) => {
+                let config = ctx.accounts.config.as_ref().ok_or(EscrowError::MissingConfig)?;
+                let referrer = ctx.accounts.referrer.as_ref().ok_or(EscrowError::MissingReferrer)?;
+                require_keys_eq!(referrer.key(), referral.referrer, EscrowError::BadReferrer);
+                Some(config.referral_bps)
+            }
+            None => None,
+        };
+        let FeeSplit { net, admin_fee, referrer_cut } = split_withdraw_fee(lamports, fee_lamports, referral_bps)?;
+        // An early-withdraw penalty comes out of the owner's share, after fees.
+        let penalty = slash_lamports(
+            &ctx.accounts.vault,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_destination.as_ref(),
+            lamports,
+        )?;
+        let net = net.checked_sub(penalty).ok_or(EscrowError::PenaltyExceedsPayout)?;
+        let remaining = lamports.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?;
+
+        {
+            let vault_info = ctx.accounts.vault.to_account_info();
+            let owner_info = ctx.accounts.owner.to_account_info();
+            let admin_info = ctx.accounts.fee_treasury.to_account_info();
+            let mut vault_lamports = vault_info.try_borrow_mut_lamports()?;
+            let mut owner_lamports = owner_info.try_borrow_mut_lamports()?;
+            let mut admin_lamports = admin_info.try_borrow_mut_lamports()?;
+            **vault_lamports = vault_lamports.checked_sub(remaining).ok_or(EscrowError::MathUnderflow)?;
+            **owner_lamports = owner_lamports.checked_add(net).ok_or(EscrowError::MathOverflow)?;
+            **admin_lamports = admin_lamports.checked_add(admin_fee).ok_or(EscrowError::MathOverflow)?;
+        }
+
+        ctx.accounts.fee_treasury.record(Pubkey::default(), admin_fee)?;
+
+        if referrer_cut > 0 {
+            let referrer_info = ctx.accounts.referrer.as_ref().unwrap().to_account_info();
+            let mut referrer_lamports = referrer_info.try_borrow_mut_lamports()?;
+            **referrer_lamports = referrer_lamports.checked_add(referrer_cut).ok_or(EscrowError::MathOverflow)?;
+        }
+
+        if let Some(referral) = ctx.accounts.referral.as_mut() {
+            referral.total_paid_lamports = referral.total_paid_lamports.checked_add(referrer_cut).ok_or(EscrowError::MathOverflow)?;
+            emit!(ReferralFeePaid {
+                owner: referral.owner,
+                referrer: referral.referrer,
+                mint: Pubkey::default(),
+                amount: referrer_cut,
+            });
+        }
+
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: Pubkey::default(),
+            amount: lamports,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW,
+            ctx.accounts.owner.key(),
+            Pubkey::default(),
+            lamports,
+        )?;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn withdraw_usdc_with_fee(
+        ctx: Context<WithdrawUsdcWithFee>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        fee_amount: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), amount, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+        require!(fee_amount <= amount, EscrowError::InvalidFee);
+        let fee_amount = apply_fee_tier(ctx.accounts.fee_tier.as_ref(), amount, fee_amount)?;
+        // Only fee-paying withdrawals count toward the next tier.
+        if let Some(tier) = ctx.accounts.fee_tier.as_mut().filter(|_| fee_amount > 0) {
+            tier.volume_usdc = tier.volume_usdc.saturating_add(amount);
+        }
+
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require_keys_eq!(ctx.accounts.vault.usdc_mint, ctx.accounts.usdc_mint.key(), EscrowError::BadMint);
+        require_keys_eq!(ctx.accounts.vault.usdc_vault, ctx.accounts.vault_usdc.key(), EscrowError::BadVaultAccount);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+
+        let referral_bps = match ctx.accounts.referral.as_ref() {
+            Some(referral) => {
+                let config = ctx.accounts.config.as_ref().ok_or(EscrowError::MissingConfig)?;
+                let referrer_usdc = ctx.accounts.referrer_usdc.as_ref().ok_or(EscrowError::MissingReferrer)?;
+                require_keys_eq!(referrer_usdc.owner, referral.referrer, EscrowError::BadReferrer);
+                Some(config.referral_bps)
+            }
+            None => None,
+        };
+        let FeeSplit { net, admin_fee, referrer_cut } = split_withdraw_fee(amount, fee_amount, referral_bps)?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let pot_hash_bytes = ctx.accounts.vault.pot_hash;
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[
+            seeds::POT_VAULT,
+            owner_key.as_ref(),
+            pot_hash_bytes.as_ref(),
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        // An early-withdraw penalty comes out of the owner's share, after fees.
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            signer_seeds,
+            amount,
+        )?;
+        let net = net.checked_sub(penalty).ok_or(EscrowError::PenaltyExceedsPayout)?;
+
+        let payouts = [
+            (ctx.accounts.user_usdc.to_account_info(), net),
+            (ctx.accounts.treasury_usdc.to_account_info(), admin_fee),
+        ];
+        for (to, value) in payouts {
+            if value == 0 {
+                continue;
+            }
+            let cpi = Transfer {
+                from: ctx.accounts.vault_usdc.to_account_info(),
+                to,
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::transfer(cpi_ctx, value)?;
+        }
+        let usdc_mint_key = ctx.accounts.usdc_mint.key();
+        ctx.accounts.fee_treasury.record(usdc_mint_key, admin_fee)?;
+
+        if referrer_cut > 0 {
+            let cpi = Transfer {
+                from: ctx.accounts.vault_usdc.to_account_info(),
+                to: ctx.accounts.referrer_usdc.as_ref().unwrap().to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::transfer(cpi_ctx, referrer_cut)?;
+        }
+
+        if let Some(referral) = ctx.accounts.referral.as_mut() {
+            referral.total_paid_usdc = referral.total_paid_usdc.checked_add(referrer_cut).ok_or(EscrowError::MathOverflow)?;
+            emit!(ReferralFeePaid {
+                owner: referral.owner,
+                referrer: referral.referrer,
+                mint: ctx.accounts.usdc_mint.key(),
+                amount: referrer_cut,
+            });
+        }
+
+        burn_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: ctx.accounts.usdc_mint.key(),
+            amount,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW,
+            ctx.accounts.owner.key(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
 
-        let vault_info = ctx.accounts.vault.to_account_info();
-        let owner_info = ctx.accounts.owner.to_account_info();
-        let admin_info = ctx.accounts.admin_vault.to_account_info();
-        let mut vault_lamports = vault_info.try_borrow_mut_lamports()?;
-        let mut owner_lamports = owner_info.try_borrow_mut_lamports()?;
-        let mut admin_lamports = admin_info.try_borrow_mut_lamports()?;
-        **vault_lamports -= lamports;
-        **owner_lamports += net;
-        **admin_lamports += fee_lamports;
         Ok(())
     }
 
-    pub fn deposit_usdc(ctx: Context<DepositUsdc>, pot_hash: [u8; 32], amount: u64) -> Result<()> {
+    #[cfg(feature = "full")]
+    /// See `deposit` for `idempotency_key`.
+    pub fn deposit_usdc(
+        ctx: Context<DepositUsdc>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        idempotency_key: Option<[u8; 16]>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
         require!(amount > 0, EscrowError::InvalidAmount);
+        claim_idempotency_key(
+            idempotency_key,
+            ctx.accounts.deposit_marker.as_ref(),
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program,
+        )?;
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
 
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
         require_keys_eq!(ctx.accounts.vault.usdc_mint, ctx.accounts.usdc_mint.key(), EscrowError::BadMint);
         require_keys_eq!(ctx.accounts.vault.usdc_vault, ctx.accounts.vault_usdc.key(), EscrowError::BadVaultAccount);
+        check_deposit_limits(
+            ctx.accounts.config.as_ref(),
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            0,
+            amount,
+        )?;
 
         let cpi = Transfer {
             from: ctx.accounts.user_usdc.to_account_info(),
@@ -114,29 +744,87 @@ pub mod kobafin_escrow {
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi);
         token::transfer(cpi_ctx, amount)?;
 
+        mint_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        record_deposit(
+            &mut ctx.accounts.vault,
+            ctx.accounts.receipt.as_mut(),
+            ctx.accounts.owner.key(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_DEPOSIT,
+            ctx.accounts.owner.key(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+
         Ok(())
     }
 
-    pub fn withdraw_usdc(ctx: Context<WithdrawUsdc>, pot_hash: [u8; 32], amount: u64) -> Result<()> {
+    #[cfg(feature = "full")]
+    pub fn withdraw_usdc(
+        ctx: Context<WithdrawUsdc>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
         require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), amount, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
 
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
         require_keys_eq!(ctx.accounts.vault.usdc_mint, ctx.accounts.usdc_mint.key(), EscrowError::BadMint);
         require_keys_eq!(ctx.accounts.vault.usdc_vault, ctx.accounts.vault_usdc.key(), EscrowError::BadVaultAccount);
-        require!(ctx.accounts.vault_usdc.amount >= amount, EscrowError::InsufficientFunds);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
 
         let owner_key = ctx.accounts.owner.key();
         let pot_hash_bytes = ctx.accounts.vault.pot_hash;
         let bump = ctx.accounts.vault.bump;
         let seeds: &[&[u8]] = &[
-            b"pot_vault",
+            seeds::POT_VAULT,
             owner_key.as_ref(),
             pot_hash_bytes.as_ref(),
             &[bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            signer_seeds,
+            amount,
+        )?;
+
         let cpi = Transfer {
             from: ctx.accounts.vault_usdc.to_account_info(),
             to: ctx.accounts.user_usdc.to_account_info(),
@@ -144,310 +832,14158 @@ pub mod kobafin_escrow {
         };
         let cpi_ctx =
             CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
-        token::transfer(cpi_ctx, amount)?;
+        token::transfer(cpi_ctx, amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?)?;
+
+        burn_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: ctx.accounts.usdc_mint.key(),
+            amount,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW,
+            ctx.accounts.owner.key(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
 
         Ok(())
     }
 
-    pub fn lulo_execute(ctx: Context<LuloExecute>, pot_hash: [u8; 32], ix_data: Vec<u8>) -> Result<()> {
+    /// `max_usdc_out` declares how far the CPI may draw down the vault's USDC ATA;
+    /// the balance and the vault's own data are re-checked once Lulo returns.
+    #[cfg(feature = "lulo")]
+    pub fn lulo_execute(
+        ctx: Context<LuloExecute>,
+        pot_hash: [u8; 32],
+        max_usdc_out: u64,
+        ix_data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
         require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
         require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
 
-        let expected_program = Pubkey::from_str(LULO_PROGRAM_ID).map_err(|_| EscrowError::InvalidProgram)?;
-        require_keys_eq!(ctx.accounts.lulo_program.key(), expected_program, EscrowError::InvalidProgram);
+        require_keys_eq!(ctx.accounts.lulo_program.key(), LULO_PROGRAM_ID, EscrowError::InvalidProgram);
+        check_exec_rate(&mut ctx.accounts.vault)?;
 
-        let mut metas: Vec<AccountMeta> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let vault_key = ctx.accounts.vault.key();
+        let owner_key = ctx.accounts.owner.key();
+        let usdc_vault = ctx.accounts.vault.usdc_vault;
         for acc in ctx.remaining_accounts.iter() {
-            let mut is_signer = acc.is_signer;
-            if acc.key() == ctx.accounts.vault.key() {
-                is_signer = true;
-            }
-            metas.push(AccountMeta {
-                pubkey: *acc.key,
-                is_signer,
-                is_writable: acc.is_writable,
-            });
+            check_lulo_account(acc, vault_key, owner_key, usdc_vault)?;
         }
 
         let ix = Instruction {
-            program_id: ctx.accounts.lulo_program.key(),
-            accounts: metas,
+            program_id: LULO_PROGRAM_ID,
+            accounts: passthrough_metas(ctx.remaining_accounts, vault_key),
             data: ix_data,
         };
 
-        let owner_key = ctx.accounts.owner.key();
         let pot_hash_bytes = ctx.accounts.vault.pot_hash;
         let bump = ctx.accounts.vault.bump;
         let seeds: &[&[u8]] = &[
-            b"pot_vault",
+            seeds::POT_VAULT,
             owner_key.as_ref(),
             pot_hash_bytes.as_ref(),
             &[bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
-        let mut infos: Vec<AccountInfo> = Vec::with_capacity(ctx.remaining_accounts.len());
-        infos.extend_from_slice(ctx.remaining_accounts);
+        let usdc_before = passthrough_token_amount(ctx.remaining_accounts, usdc_vault)?;
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let vault_data_before = vault_info.try_borrow_data()?.to_vec();
+
+        invoke_signed(&ix, ctx.remaining_accounts, signer_seeds)?;
+
+        require!(
+            vault_info.try_borrow_data()?[..] == vault_data_before[..],
+            EscrowError::PostConditionViolated
+        );
+        let (usdc_out, usdc_in) = match usdc_before {
+            Some(before) => {
+                let after = passthrough_token_amount(ctx.remaining_accounts, usdc_vault)?.unwrap_or(0);
+                (before.saturating_sub(after), after.saturating_sub(before))
+            }
+            None => (0, 0),
+        };
+        require!(usdc_out <= max_usdc_out, EscrowError::PostConditionViolated);
+
+        // Lulo balances are USDC-denominated, so shares track principal 1:1. USDC coming
+        // back above principal is realized yield.
+        let p = &mut ctx.accounts.position;
+        if p.vault == Pubkey::default() {
+            p.vault = vault_key;
+            p.venue_id = VENUE_LULO;
+            p.venue_account = ctx.accounts.lulo_program.key();
+            p.bump = ctx.bumps.position;
+        }
+        let principal_out = p.principal.min(usdc_in);
+        let realized = usdc_in - principal_out;
+        p.principal = p
+            .principal
+            .checked_sub(principal_out)
+            .and_then(|v| v.checked_add(usdc_out))
+            .ok_or(EscrowError::MathOverflow)?;
+        p.shares = p.principal;
+        p.last_update = Clock::get()?.unix_timestamp;
+        let principal = p.principal;
 
-        invoke_signed(&ix, &infos, signer_seeds)?;
+        if realized > 0 {
+            let v = &mut ctx.accounts.vault;
+            v.pending_yield_usdc = v.pending_yield_usdc.checked_add(realized).ok_or(EscrowError::MathOverflow)?;
+            book_yield(v, VENUE_LULO, realized, principal)?;
+        }
 
         Ok(())
     }
 
+    #[cfg(feature = "full")]
     pub fn update_policy(
         ctx: Context<UpdatePolicy>,
         pod_hash: [u8; 32],
         risk_state: u8,
-        target_usdc_bps: u16,
-        target_btc_bps: u16,
-        target_eth_bps: u16,
-        target_sol_bps: u16,
+        assets: Vec<AssetTarget>,
         usdc_in_lulo_bps: u16,
     ) -> Result<()> {
-        require!(risk_state <= 2, EscrowError::InvalidRiskState);
-
-        let target_sum = (target_usdc_bps as u32)
-            + (target_btc_bps as u32)
-            + (target_eth_bps as u32)
-            + (target_sol_bps as u32);
-        require!(target_sum == 10_000, EscrowError::InvalidBps);
-        require!(
-            usdc_in_lulo_bps <= target_usdc_bps,
-            EscrowError::InvalidLuloAllocation
-        );
+        validate_policy_targets(risk_state, &assets, usdc_in_lulo_bps)?;
 
-        let policy = &mut ctx.accounts.pod_policy;
+        let mut policy = match ctx.accounts.pod_policy.load_mut() {
+            Ok(policy) => policy,
+            Err(_) => ctx.accounts.pod_policy.load_init()?,
+        };
         let authority = ctx.accounts.authority.key();
         if policy.authority == Pubkey::default() {
             policy.authority = authority;
             policy.bump = ctx.bumps.pod_policy;
         } else {
-            require_keys_eq!(policy.authority, authority, EscrowError::Unauthorized);
+            require_policy_editor(&policy, &ctx.accounts)?;
         }
 
         policy.pod_hash = pod_hash;
         policy.risk_state = risk_state;
-        policy.target_usdc_bps = target_usdc_bps;
-        policy.target_btc_bps = target_btc_bps;
-        policy.target_eth_bps = target_eth_bps;
-        policy.target_sol_bps = target_sol_bps;
         policy.usdc_in_lulo_bps = usdc_in_lulo_bps;
         policy.updated_at = Clock::get()?.unix_timestamp;
 
+        if let Some(history) = ctx.accounts.policy_history.as_ref() {
+            let mut target_bps = [0u16; MAX_POLICY_ASSETS];
+            for (slot, asset) in target_bps.iter_mut().zip(assets.iter()) {
+                *slot = asset.bps;
+            }
+            append_policy_history(
+                history,
+                PolicyHistoryEntry {
+                    timestamp: policy.updated_at,
+                    target_bps,
+                    usdc_in_lulo_bps,
+                    risk_state,
+                    asset_count: assets.len() as u8,
+                    _padding: [0; 4],
+                },
+            )?;
+        }
+        // Allocations point at asset slots, so a venue split in force is re-indexed
+        // against the new list (and keeps `usdc_in_lulo_bps` at its Lulo leg); one
+        // the new targets can't hold must be cleared or replaced with
+        // `set_venue_allocations` first.
+        let venues = (policy.venue_count > 0).then(|| policy.venue_allocations());
+        policy.set_targets(&assets);
+        if let Some(venues) = venues {
+            validate_venue_allocations(&assets, &venues)?;
+            policy.set_venue_allocations(&venues);
+        }
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct InitPotVault<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    /// Per-asset venue split for the pod (e.g. SOL into Marinade, USDC across Lulo
+    /// and Kamino), in portfolio bps. Each asset's allocations sum to at most its
+    /// target; an empty list falls back to `usdc_in_lulo_bps`.
+    #[cfg(feature = "full")]
+    pub fn set_venue_allocations(
+        ctx: Context<UpdatePolicy>,
+        pod_hash: [u8; 32],
+        allocations: Vec<VenueAllocation>,
+    ) -> Result<()> {
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        require!(policy.authority != Pubkey::default(), EscrowError::PolicyNotInitialized);
+        require!(policy.pod_hash == pod_hash, EscrowError::BadPot);
+        require_policy_editor(&policy, &ctx.accounts)?;
 
-    #[account(
-        init,
-        payer = owner,
-        space = Vault::SPACE,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, Vault>,
+        let assets = policy.targets();
+        validate_venue_allocations(&assets, &allocations)?;
+        policy.set_venue_allocations(&allocations);
+        policy.updated_at = Clock::get()?.unix_timestamp;
 
-    pub usdc_mint: Account<'info, Mint>,
+        Ok(())
+    }
 
-    #[account(
-        init_if_needed,
-        payer = owner,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = vault
-    )]
-    pub vault_usdc: Account<'info, TokenAccount>,
+    /// Drift, in bps of portfolio weight, past which `needs_rebalance` says to crank;
+    /// 0 uses `DEFAULT_MAX_DRIFT_BPS`.
+    #[cfg(feature = "full")]
+    pub fn set_max_drift(ctx: Context<UpdatePolicy>, pod_hash: [u8; 32], max_drift_bps: u16) -> Result<()> {
+        require!(max_drift_bps <= 10_000, EscrowError::InvalidBps);
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        require!(policy.authority != Pubkey::default(), EscrowError::PolicyNotInitialized);
+        require!(policy.pod_hash == pod_hash, EscrowError::BadPot);
+        require_policy_editor(&policy, &ctx.accounts)?;
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-}
+        policy.max_drift_bps = max_drift_bps;
+        policy.updated_at = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+    /// Price the pod's vaults are snapshotted at: `VALUATION_SPOT` or `VALUATION_EMA`.
+    #[cfg(feature = "full")]
+    pub fn set_valuation_mode(ctx: Context<UpdatePolicy>, pod_hash: [u8; 32], valuation_mode: u8) -> Result<()> {
+        require!(valuation_mode <= VALUATION_EMA, EscrowError::InvalidValuationMode);
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        require!(policy.authority != Pubkey::default(), EscrowError::PolicyNotInitialized);
+        require!(policy.pod_hash == pod_hash, EscrowError::BadPot);
+        require_policy_editor(&policy, &ctx.accounts)?;
 
-    pub system_program: Program<'info, System>,
-}
+        policy.valuation_mode = valuation_mode;
+        policy.updated_at = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct Withdraw<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+    /// Applies the on-chain preset for `risk_state` instead of client-computed bps.
+    /// `mints` are the USDC, BTC, ETH and SOL mints, in preset order.
+    #[cfg(feature = "full")]
+    pub fn set_risk_preset(
+        ctx: Context<UpdatePolicy>,
+        pod_hash: [u8; 32],
+        risk_state: u8,
+        mints: [Pubkey; 4],
+    ) -> Result<()> {
+        let preset = RISK_PRESETS
+            .get(risk_state as usize)
+            .ok_or(EscrowError::InvalidRiskState)?;
+        let assets = mints
+            .iter()
+            .zip(preset.iter())
+            .map(|(mint, bps)| AssetTarget { mint: *mint, bps: *bps })
+            .collect();
+        update_policy(ctx, pod_hash, risk_state, assets, preset[4])
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Rewrites a policy in an older layout into the current zero-copy one. Policies
+    /// created before `assets` existed map their fixed USDC/BTC/ETH/SOL fields onto
+    /// `mints` in that order; Borsh policies keep their asset list and ignore `mints`,
+    /// and zero-copy policies from before `policy_manager` are grown with no manager.
+    #[cfg(feature = "full")]
+    pub fn migrate_pod_policy(ctx: Context<MigratePodPolicy>, pod_hash: [u8; 32], mints: [Pubkey; 4]) -> Result<()> {
+        let info = ctx.accounts.pod_policy.to_account_info();
+        let policy = {
+            let data = info.try_borrow_data()?;
+            require!(data[..8] == *PodPolicy::DISCRIMINATOR, EscrowError::BadVaultAccount);
+            require!(
+                data.len() == PodPolicy::LEGACY_SPACE
+                    || data.len() == PodPolicy::BORSH_SPACE
+                    || data.len() == PodPolicy::V1_SPACE,
+                EscrowError::AlreadyMigrated
+            );
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct WithdrawWithFee<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+            let authority = Pubkey::try_from(&data[8..40]).unwrap();
+            require_keys_eq!(authority, ctx.accounts.authority.key(), EscrowError::Unauthorized);
+            require!(data[40..72] == pod_hash, EscrowError::BadPot);
 
-    #[account(
-        mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+            let mut policy: PodPolicy = bytemuck::Zeroable::zeroed();
+            policy.authority = authority;
+            policy.pod_hash = pod_hash;
+            if data.len() == PodPolicy::LEGACY_SPACE {
+                let read_u16 = |o: usize| u16::from_le_bytes(data[o..o + 2].try_into().unwrap());
+                let assets: Vec<AssetTarget> = mints
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i == 0 || read_u16(73 + i * 2) > 0)
+                    .map(|(i, mint)| AssetTarget { mint: *mint, bps: read_u16(73 + i * 2) })
+                    .collect();
+                policy.risk_state = data[72];
+                policy.usdc_in_lulo_bps = read_u16(81);
+                policy.bump = data[83];
+                policy.updated_at = i64::from_le_bytes(data[84..92].try_into().unwrap());
+                policy.set_targets(&assets);
+            } else if data.len() == PodPolicy::V1_SPACE {
+                bytemuck::bytes_of_mut(&mut policy)[..PodPolicy::V1_SPACE - 8]
+                    .copy_from_slice(&data[8..PodPolicy::V1_SPACE]);
+            } else {
+                let old = BorshPodPolicy::deserialize(&mut &data[40..])?;
+                policy.risk_state = old.risk_state;
+                policy.usdc_in_lulo_bps = old.usdc_in_lulo_bps;
+                policy.bump = old.bump;
+                policy.updated_at = old.updated_at;
+                policy.pending_authority = old.pending_authority;
+                policy.set_targets(&old.assets);
+            }
+            policy
+        };
 
-    #[account(mut)]
-    pub admin_vault: Account<'info, Vault>,
+        resize_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PodPolicy::SPACE,
+        )?;
+        let mut data = info.try_borrow_mut_data()?;
+        data[8..PodPolicy::SPACE].copy_from_slice(bytemuck::bytes_of(&policy));
 
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct DepositUsdc<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    /// Only the program's upgrade authority may create Config and become its admin.
+    pub fn init_config(ctx: Context<InitConfig>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= 10_000, EscrowError::InvalidFee);
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.referral_bps = referral_bps;
+        config.bump = ctx.bumps.config;
+        config.fee_treasury = ctx.accounts.admin.key();
+
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateFeeConfig>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= 10_000, EscrowError::InvalidFee);
+
+        ctx.accounts.config.referral_bps = referral_bps;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn register_referral(ctx: Context<RegisterReferral>, referrer: Pubkey) -> Result<()> {
+        require!(referrer != Pubkey::default(), EscrowError::BadReferrer);
+        require_keys_neq!(referrer, ctx.accounts.owner.key(), EscrowError::BadReferrer);
+
+        let referral = &mut ctx.accounts.referral;
+        referral.owner = ctx.accounts.owner.key();
+        referral.referrer = referrer;
+        referral.bump = ctx.bumps.referral;
+
+        emit!(ReferralRegistered {
+            owner: referral.owner,
+            referrer,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn open_escrow(
+        ctx: Context<OpenEscrow>,
+        escrow_id: [u8; 32],
+        hashlock: [u8; 32],
+        timelock: i64,
+        lamports: u64,
+    ) -> Result<()> {
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        require!(timelock > Clock::get()?.unix_timestamp, EscrowError::InvalidTimelock);
+        require_keys_neq!(
+            ctx.accounts.counterparty.key(),
+            ctx.accounts.depositor.key(),
+            EscrowError::InvalidCounterparty
+        );
+
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.depositor.key(),
+            &ctx.accounts.escrow.key(),
+            lamports,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.depositor.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let e = &mut ctx.accounts.escrow;
+        e.depositor = ctx.accounts.depositor.key();
+        e.counterparty = ctx.accounts.counterparty.key();
+        e.escrow_id = escrow_id;
+        e.hashlock = hashlock;
+        e.timelock = timelock;
+        e.mint = Pubkey::default();
+        e.amount = lamports;
+        e.bump = ctx.bumps.escrow;
+
+        emit!(EscrowOpened {
+            escrow: e.key(),
+            depositor: e.depositor,
+            counterparty: e.counterparty,
+            mint: e.mint,
+            amount: lamports,
+            hashlock,
+            timelock,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn open_escrow_token(
+        ctx: Context<OpenEscrowToken>,
+        escrow_id: [u8; 32],
+        hashlock: [u8; 32],
+        timelock: i64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(timelock > Clock::get()?.unix_timestamp, EscrowError::InvalidTimelock);
+        require_keys_neq!(
+            ctx.accounts.counterparty.key(),
+            ctx.accounts.depositor.key(),
+            EscrowError::InvalidCounterparty
+        );
+
+        let cpi = Transfer {
+            from: ctx.accounts.depositor_token.to_account_info(),
+            to: ctx.accounts.escrow_token.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi);
+        token::transfer(cpi_ctx, amount)?;
+
+        let e = &mut ctx.accounts.escrow;
+        e.depositor = ctx.accounts.depositor.key();
+        e.counterparty = ctx.accounts.counterparty.key();
+        e.escrow_id = escrow_id;
+        e.hashlock = hashlock;
+        e.timelock = timelock;
+        e.mint = ctx.accounts.mint.key();
+        e.amount = amount;
+        e.bump = ctx.bumps.escrow;
+
+        emit!(EscrowOpened {
+            escrow: e.key(),
+            depositor: e.depositor,
+            counterparty: e.counterparty,
+            mint: e.mint,
+            amount,
+            hashlock,
+            timelock,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn claim_with_preimage(ctx: Context<ClaimWithPreimage>, preimage: Vec<u8>) -> Result<()> {
+        let e = &ctx.accounts.escrow;
+        require!(e.mint == Pubkey::default(), EscrowError::BadMint);
+        require!(Clock::get()?.unix_timestamp < e.timelock, EscrowError::EscrowExpired);
+        require!(hash(&preimage).to_bytes() == e.hashlock, EscrowError::HashlockMismatch);
+
+        let amount = e.amount;
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let counterparty_info = ctx.accounts.counterparty.to_account_info();
+        let mut escrow_lamports = escrow_info.try_borrow_mut_lamports()?;
+        let mut counterparty_lamports = counterparty_info.try_borrow_mut_lamports()?;
+        **escrow_lamports = escrow_lamports.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+        **counterparty_lamports = counterparty_lamports.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+
+        emit!(EscrowClaimed {
+            escrow: ctx.accounts.escrow.key(),
+            counterparty: ctx.accounts.counterparty.key(),
+            amount,
+            preimage,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn claim_with_preimage_token(ctx: Context<ClaimWithPreimageToken>, preimage: Vec<u8>) -> Result<()> {
+        let e = &ctx.accounts.escrow;
+        require_keys_eq!(e.mint, ctx.accounts.mint.key(), EscrowError::BadMint);
+        require!(Clock::get()?.unix_timestamp < e.timelock, EscrowError::EscrowExpired);
+        require!(hash(&preimage).to_bytes() == e.hashlock, EscrowError::HashlockMismatch);
+
+        let amount = e.amount;
+        let depositor_key = e.depositor;
+        let escrow_id = e.escrow_id;
+        let bump = e.bump;
+        let seeds: &[&[u8]] = &[seeds::COND_ESCROW, depositor_key.as_ref(), escrow_id.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi = Transfer {
+            from: ctx.accounts.escrow_token.to_account_info(),
+            to: ctx.accounts.counterparty_token.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::transfer(cpi_ctx, amount)?;
+
+        let cpi = CloseAccount {
+            account: ctx.accounts.escrow_token.to_account_info(),
+            destination: ctx.accounts.depositor.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::close_account(cpi_ctx)?;
+
+        emit!(EscrowClaimed {
+            escrow: ctx.accounts.escrow.key(),
+            counterparty: ctx.accounts.counterparty.key(),
+            amount,
+            preimage,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn refund_after_expiry(ctx: Context<RefundAfterExpiry>) -> Result<()> {
+        let e = &ctx.accounts.escrow;
+        require!(e.mint == Pubkey::default(), EscrowError::BadMint);
+        require!(Clock::get()?.unix_timestamp >= e.timelock, EscrowError::EscrowNotExpired);
+
+        // Closing the escrow returns the locked lamports together with its rent.
+        emit!(EscrowRefunded {
+            escrow: ctx.accounts.escrow.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount: e.amount,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn refund_after_expiry_token(ctx: Context<RefundAfterExpiryToken>) -> Result<()> {
+        let e = &ctx.accounts.escrow;
+        require_keys_eq!(e.mint, ctx.accounts.mint.key(), EscrowError::BadMint);
+        require!(Clock::get()?.unix_timestamp >= e.timelock, EscrowError::EscrowNotExpired);
+
+        let amount = e.amount;
+        let depositor_key = e.depositor;
+        let escrow_id = e.escrow_id;
+        let bump = e.bump;
+        let seeds: &[&[u8]] = &[seeds::COND_ESCROW, depositor_key.as_ref(), escrow_id.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi = Transfer {
+            from: ctx.accounts.escrow_token.to_account_info(),
+            to: ctx.accounts.depositor_token.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::transfer(cpi_ctx, amount)?;
+
+        let cpi = CloseAccount {
+            account: ctx.accounts.escrow_token.to_account_info(),
+            destination: ctx.accounts.depositor.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::close_account(cpi_ctx)?;
+
+        emit!(EscrowRefunded {
+            escrow: ctx.accounts.escrow.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn open_milestone_escrow(
+        ctx: Context<OpenMilestoneEscrow>,
+        escrow_id: [u8; 32],
+        arbiter: Pubkey,
+        milestone_amounts: Vec<u64>,
+    ) -> Result<()> {
+        require!(
+            !milestone_amounts.is_empty() && milestone_amounts.len() <= MilestoneEscrow::MAX_MILESTONES,
+            EscrowError::InvalidMilestones
+        );
+        require!(milestone_amounts.iter().all(|a| *a > 0), EscrowError::InvalidAmount);
+        require_keys_neq!(ctx.accounts.payee.key(), ctx.accounts.payer.key(), EscrowError::InvalidCounterparty);
+        // A party arbitrating its own escrow could claw back or force out any milestone.
+        require!(
+            arbiter != Pubkey::default() && arbiter != ctx.accounts.payer.key() && arbiter != ctx.accounts.payee.key(),
+            EscrowError::InvalidCounterparty
+        );
+
+        let total = milestone_amounts
+            .iter()
+            .try_fold(0u64, |acc, a| acc.checked_add(*a))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.escrow.key(),
+            total,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let e = &mut ctx.accounts.escrow;
+        e.payer = ctx.accounts.payer.key();
+        e.payee = ctx.accounts.payee.key();
+        e.arbiter = arbiter;
+        e.escrow_id = escrow_id;
+        e.total_amount = total;
+        e.released_amount = 0;
+        e.refunded_amount = 0;
+        e.bump = ctx.bumps.escrow;
+        e.milestones = milestone_amounts
+            .iter()
+            .map(|amount| Milestone {
+                amount: *amount,
+                status: MilestoneStatus::Pending,
+            })
+            .collect();
+
+        emit!(MilestoneEscrowOpened {
+            escrow: e.key(),
+            payer: e.payer,
+            payee: e.payee,
+            arbiter,
+            total_amount: total,
+            milestone_count: e.milestones.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn request_milestone(ctx: Context<RequestMilestone>, index: u8) -> Result<()> {
+        let e = &mut ctx.accounts.escrow;
+        let m = e.milestones.get_mut(index as usize).ok_or(EscrowError::InvalidMilestones)?;
+        require!(m.status == MilestoneStatus::Pending, EscrowError::InvalidMilestoneStatus);
+        m.status = MilestoneStatus::Requested;
+
+        emit!(MilestoneUpdated {
+            escrow: e.key(),
+            index,
+            status: MilestoneStatus::Requested,
+            actor: ctx.accounts.payee.key(),
+            amount: 0,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn approve_milestone(ctx: Context<ApproveMilestone>, index: u8) -> Result<()> {
+        let amount = {
+            let e = &mut ctx.accounts.escrow;
+            let m = e.milestones.get_mut(index as usize).ok_or(EscrowError::InvalidMilestones)?;
+            require!(m.status == MilestoneStatus::Requested, EscrowError::InvalidMilestoneStatus);
+            m.status = MilestoneStatus::Released;
+            let amount = m.amount;
+            e.released_amount = e.released_amount.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+            amount
+        };
+
+        transfer_lamports(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.payee.to_account_info(),
+            amount,
+        )?;
+
+        emit!(MilestoneUpdated {
+            escrow: ctx.accounts.escrow.key(),
+            index,
+            status: MilestoneStatus::Released,
+            actor: ctx.accounts.payer.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn dispute_milestone(ctx: Context<DisputeMilestone>, index: u8) -> Result<()> {
+        let actor = ctx.accounts.party.key();
+        let e = &mut ctx.accounts.escrow;
+        require!(actor == e.payer || actor == e.payee, EscrowError::Unauthorized);
+
+        let m = e.milestones.get_mut(index as usize).ok_or(EscrowError::InvalidMilestones)?;
+        require!(
+            m.status == MilestoneStatus::Pending || m.status == MilestoneStatus::Requested,
+            EscrowError::InvalidMilestoneStatus
+        );
+        m.status = MilestoneStatus::Disputed;
+
+        emit!(MilestoneUpdated {
+            escrow: e.key(),
+            index,
+            status: MilestoneStatus::Disputed,
+            actor,
+            amount: 0,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn resolve_milestone(ctx: Context<ResolveMilestone>, index: u8, release_to_payee: bool) -> Result<()> {
+        let (amount, status) = {
+            let e = &mut ctx.accounts.escrow;
+            let m = e.milestones.get_mut(index as usize).ok_or(EscrowError::InvalidMilestones)?;
+            require!(m.status == MilestoneStatus::Disputed, EscrowError::InvalidMilestoneStatus);
+            let amount = m.amount;
+            if release_to_payee {
+                m.status = MilestoneStatus::Released;
+                e.released_amount = e.released_amount.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+            } else {
+                m.status = MilestoneStatus::Refunded;
+                e.refunded_amount = e.refunded_amount.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+            }
+            (amount, m.status)
+        };
+
+        let to = if release_to_payee {
+            ctx.accounts.payee.to_account_info()
+        } else {
+            ctx.accounts.payer.to_account_info()
+        };
+        transfer_lamports(&ctx.accounts.escrow.to_account_info(), &to, amount)?;
+
+        emit!(MilestoneUpdated {
+            escrow: ctx.accounts.escrow.key(),
+            index,
+            status,
+            actor: ctx.accounts.arbiter.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn close_milestone_escrow(ctx: Context<CloseMilestoneEscrow>) -> Result<()> {
+        let e = &ctx.accounts.escrow;
+        require!(
+            e.milestones
+                .iter()
+                .all(|m| m.status == MilestoneStatus::Released || m.status == MilestoneStatus::Refunded),
+            EscrowError::InvalidMilestoneStatus
+        );
+
+        emit!(MilestoneEscrowClosed {
+            escrow: e.key(),
+            released_amount: e.released_amount,
+            refunded_amount: e.refunded_amount,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn swap_offer(
+        ctx: Context<CreateSwapOffer>,
+        pot_hash: [u8; 32],
+        offer_id: [u8; 32],
+        taker_vault: Pubkey,
+        amount_a: u64,
+        mint_b: Pubkey,
+        amount_b: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount_a > 0 && amount_b > 0, EscrowError::InvalidAmount);
+        require!(expires_at > now, EscrowError::InvalidTimelock);
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require_keys_neq!(taker_vault, ctx.accounts.vault.key(), EscrowError::InvalidCounterparty);
+        let mint_a = ctx.accounts.mint_a.key();
+        let is_usdc = mint_a == ctx.accounts.vault.usdc_mint;
+        if is_usdc {
+            require!(!ctx.accounts.vault.crowdfund_active(), EscrowError::CrowdfundActive);
+            require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        }
+        let available = if is_usdc {
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_token_a.amount)
+        } else {
+            ctx.accounts.vault_token_a.amount
+        };
+        require!(available >= amount_a, EscrowError::InsufficientFunds);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount_a,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            mint_a,
+            amount_a,
+        )?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi = Transfer {
+            from: ctx.accounts.vault_token_a.to_account_info(),
+            to: ctx.accounts.offer_token_a.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::transfer(cpi_ctx, amount_a)?;
+        if is_usdc {
+            ctx.accounts.vault.book_usdc_out(amount_a)?;
+        }
+
+        let o = &mut ctx.accounts.offer;
+        o.maker_vault = ctx.accounts.vault.key();
+        o.taker_vault = taker_vault;
+        o.offer_id = offer_id;
+        o.mint_a = ctx.accounts.mint_a.key();
+        o.amount_a = amount_a;
+        o.mint_b = mint_b;
+        o.amount_b = amount_b;
+        o.accepted = false;
+        o.expires_at = expires_at;
+        o.bump = ctx.bumps.offer;
+
+        emit!(SwapOffered {
+            offer: o.key(),
+            maker_vault: o.maker_vault,
+            seq: ctx.accounts.vault.seq,
+            taker_vault,
+            mint_a: o.mint_a,
+            amount_a,
+            mint_b,
+            amount_b,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn accept_swap(ctx: Context<AcceptSwap>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(!ctx.accounts.offer.accepted, EscrowError::SwapAlreadyAccepted);
+        require!(now < ctx.accounts.offer.expires_at, EscrowError::EscrowExpired);
+
+        let (mint_b, amount_b) = (ctx.accounts.offer.mint_b, ctx.accounts.offer.amount_b);
+        let is_usdc = mint_b == ctx.accounts.vault.usdc_mint;
+        let available = if is_usdc {
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_token_b.amount)
+        } else {
+            ctx.accounts.vault_token_b.amount
+        };
+        require!(available >= amount_b, EscrowError::InsufficientFunds);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount_b,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            mint_b,
+            amount_b,
+        )?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi = Transfer {
+            from: ctx.accounts.vault_token_b.to_account_info(),
+            to: ctx.accounts.offer_token_b.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+        token::transfer(cpi_ctx, amount_b)?;
+        if is_usdc {
+            ctx.accounts.vault.book_usdc_out(amount_b)?;
+        }
+
+        ctx.accounts.offer.accepted = true;
+
+        emit!(SwapAccepted {
+            offer: ctx.accounts.offer.key(),
+            taker_vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn settle_swap(ctx: Context<SettleSwap>) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        require!(
+            caller == ctx.accounts.maker_vault.owner || caller == ctx.accounts.taker_vault.owner,
+            EscrowError::Unauthorized
+        );
+        require!(ctx.accounts.offer.accepted, EscrowError::SwapNotAccepted);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !ctx.accounts.maker_vault.is_frozen(now) && !ctx.accounts.taker_vault.is_frozen(now),
+            EscrowError::VaultFrozen
+        );
+
+        let o = &ctx.accounts.offer;
+        let maker_vault_key = o.maker_vault;
+        let offer_id = o.offer_id;
+        let bump = o.bump;
+        let (amount_a, amount_b) = (o.amount_a, o.amount_b);
+        let seeds: &[&[u8]] = &[seeds::SWAP_OFFER, maker_vault_key.as_ref(), offer_id.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let legs = [
+            (
+                ctx.accounts.offer_token_a.to_account_info(),
+                ctx.accounts.taker_vault_token_a.to_account_info(),
+                amount_a,
+                ctx.accounts.maker_owner.to_account_info(),
+            ),
+            (
+                ctx.accounts.offer_token_b.to_account_info(),
+                ctx.accounts.maker_vault_token_b.to_account_info(),
+                amount_b,
+                ctx.accounts.taker_owner.to_account_info(),
+            ),
+        ];
+        for (from, to, amount, rent_to) in legs {
+            let cpi = Transfer {
+                from: from.clone(),
+                to,
+                authority: ctx.accounts.offer.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::transfer(cpi_ctx, amount)?;
+
+            let cpi = CloseAccount {
+                account: from,
+                destination: rent_to,
+                authority: ctx.accounts.offer.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::close_account(cpi_ctx)?;
+        }
+        let (mint_a, mint_b) = (ctx.accounts.mint_a.key(), ctx.accounts.mint_b.key());
+        for (vault, mint, amount) in [
+            (&mut ctx.accounts.taker_vault, mint_a, amount_a),
+            (&mut ctx.accounts.maker_vault, mint_b, amount_b),
+        ] {
+            vault.next_seq();
+            if mint == vault.usdc_mint {
+                vault.book_usdc_in(amount)?;
+            }
+        }
+
+        emit!(SwapSettled {
+            offer: ctx.accounts.offer.key(),
+            maker_vault: maker_vault_key,
+            maker_seq: ctx.accounts.maker_vault.seq,
+            taker_vault: ctx.accounts.taker_vault.key(),
+            taker_seq: ctx.accounts.taker_vault.seq,
+            amount_a,
+            amount_b,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn cancel_swap(ctx: Context<CancelSwap>) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        let o = &ctx.accounts.offer;
+        let expired = Clock::get()?.unix_timestamp >= o.expires_at;
+        if o.accepted {
+            require!(expired, EscrowError::EscrowNotExpired);
+            require!(
+                caller == ctx.accounts.maker_vault.owner || caller == ctx.accounts.taker_vault.owner,
+                EscrowError::Unauthorized
+            );
+        } else {
+            require!(caller == ctx.accounts.maker_vault.owner, EscrowError::Unauthorized);
+        }
+
+        let maker_vault_key = o.maker_vault;
+        let offer_id = o.offer_id;
+        let bump = o.bump;
+        let accepted = o.accepted;
+        let (amount_a, amount_b) = (o.amount_a, o.amount_b);
+        let seeds: &[&[u8]] = &[seeds::SWAP_OFFER, maker_vault_key.as_ref(), offer_id.as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let mut legs = vec![(
+            ctx.accounts.offer_token_a.to_account_info(),
+            ctx.accounts.maker_vault_token_a.to_account_info(),
+            amount_a,
+            ctx.accounts.maker_owner.to_account_info(),
+        )];
+        if accepted {
+            let offer_token_b = ctx.accounts.offer_token_b.as_ref().ok_or(EscrowError::BadVaultAccount)?;
+            let taker_vault_token_b = ctx
+                .accounts
+                .taker_vault_token_b
+                .as_ref()
+                .ok_or(EscrowError::BadVaultAccount)?;
+            require_keys_eq!(
+                taker_vault_token_b.owner,
+                ctx.accounts.taker_vault.key(),
+                EscrowError::BadVaultAccount
+            );
+            require_keys_eq!(taker_vault_token_b.mint, ctx.accounts.offer.mint_b, EscrowError::BadMint);
+            legs.push((
+                offer_token_b.to_account_info(),
+                taker_vault_token_b.to_account_info(),
+                amount_b,
+                ctx.accounts.taker_owner.to_account_info(),
+            ));
+        }
+
+        for (from, to, amount, rent_to) in legs {
+            let cpi = Transfer {
+                from: from.clone(),
+                to,
+                authority: ctx.accounts.offer.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::transfer(cpi_ctx, amount)?;
+
+            let cpi = CloseAccount {
+                account: from,
+                destination: rent_to,
+                authority: ctx.accounts.offer.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, signer_seeds);
+            token::close_account(cpi_ctx)?;
+        }
+        let mint_a = ctx.accounts.mint_a.key();
+        let mint_b = ctx.accounts.offer.mint_b;
+        let maker = &mut ctx.accounts.maker_vault;
+        maker.next_seq();
+        if mint_a == maker.usdc_mint {
+            maker.book_usdc_in(amount_a)?;
+        }
+        if accepted {
+            let taker = &mut ctx.accounts.taker_vault;
+            taker.next_seq();
+            if mint_b == taker.usdc_mint {
+                taker.book_usdc_in(amount_b)?;
+            }
+        }
+
+        emit!(SwapCancelled {
+            offer: ctx.accounts.offer.key(),
+            by: caller,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn stake_sol_marinade(ctx: Context<StakeSolMarinade>, pot_hash: [u8; 32], lamports: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+
+        let rent = Rent::get()?;
+        let min = rent.minimum_balance(Vault::SPACE);
+        let current = ctx.accounts.vault.to_account_info().lamports();
+        require!(withdrawable_lamports(current, min) >= lamports, EscrowError::InsufficientFunds);
+        let msol_price = read_msol_price(&ctx.accounts.marinade_state)?;
+        let slippage_bps = ctx.accounts.config.swap_slippage_bps();
+
+        // Marinade pulls SOL with a system transfer, which cannot debit the data-carrying
+        // vault, so the lamports are staged in a data-less PDA that signs the deposit.
+        transfer_lamports(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.sol_stage.to_account_info(),
+            lamports,
+        )?;
+
+        let msol_before = ctx.accounts.vault_msol.amount;
+
+        let mut data = MARINADE_DEPOSIT_IX.to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.marinade_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.marinade_state.key(), false),
+                AccountMeta::new(ctx.accounts.msol_mint.key(), false),
+                AccountMeta::new(ctx.accounts.liq_pool_sol_leg_pda.key(), false),
+                AccountMeta::new(ctx.accounts.liq_pool_msol_leg.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.liq_pool_msol_leg_authority.key(), false),
+                AccountMeta::new(ctx.accounts.reserve_pda.key(), false),
+                AccountMeta::new(ctx.accounts.sol_stage.key(), true),
+                AccountMeta::new(ctx.accounts.vault_msol.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.msol_mint_authority.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+
+        let vault_key = ctx.accounts.vault.key();
+        let stage_seeds: &[&[u8]] = &[seeds::SOL_STAGE, vault_key.as_ref(), &[ctx.bumps.sol_stage]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.marinade_state.to_account_info(),
+                ctx.accounts.msol_mint.to_account_info(),
+                ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+                ctx.accounts.liq_pool_msol_leg.to_account_info(),
+                ctx.accounts.liq_pool_msol_leg_authority.to_account_info(),
+                ctx.accounts.reserve_pda.to_account_info(),
+                ctx.accounts.sol_stage.to_account_info(),
+                ctx.accounts.vault_msol.to_account_info(),
+                ctx.accounts.msol_mint_authority.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[stage_seeds],
+        )?;
+
+        ctx.accounts.vault_msol.reload()?;
+        let minted = ctx.accounts.vault_msol.amount.saturating_sub(msol_before);
+        let min_minted = oracle_min_out(lamports, MSOL_PRICE_DENOMINATOR as u64, msol_price, slippage_bps)?;
+        require!(minted >= min_minted, EscrowError::SlippageExceeded);
+
+        let v = &mut ctx.accounts.vault;
+        v.msol_amount = v.msol_amount.checked_add(minted).ok_or(EscrowError::MathOverflow)?;
+        v.sol_staked_lamports = v.sol_staked_lamports.checked_add(lamports).ok_or(EscrowError::MathOverflow)?;
+
+        emit!(MarinadeStaked {
+            vault: vault_key,
+            seq: ctx.accounts.vault.seq,
+            lamports,
+            msol_minted: minted,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn unstake_sol_marinade(ctx: Context<UnstakeSolMarinade>, pot_hash: [u8; 32], msol_amount: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(msol_amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(ctx.accounts.vault_msol.amount >= msol_amount, EscrowError::InsufficientFunds);
+        let msol_price = read_msol_price(&ctx.accounts.marinade_state)?;
+        let slippage_bps = ctx.accounts.config.swap_slippage_bps();
+
+        let lamports_before = ctx.accounts.vault.to_account_info().lamports();
+
+        let mut data = MARINADE_LIQUID_UNSTAKE_IX.to_vec();
+        data.extend_from_slice(&msol_amount.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.marinade_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.marinade_state.key(), false),
+                AccountMeta::new(ctx.accounts.msol_mint.key(), false),
+                AccountMeta::new(ctx.accounts.liq_pool_sol_leg_pda.key(), false),
+                AccountMeta::new(ctx.accounts.liq_pool_msol_leg.key(), false),
+                AccountMeta::new(ctx.accounts.treasury_msol_account.key(), false),
+                AccountMeta::new(ctx.accounts.vault_msol.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.vault.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.marinade_state.to_account_info(),
+                ctx.accounts.msol_mint.to_account_info(),
+                ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+                ctx.accounts.liq_pool_msol_leg.to_account_info(),
+                ctx.accounts.treasury_msol_account.to_account_info(),
+                ctx.accounts.vault_msol.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+
+        let received = ctx.accounts.vault.to_account_info().lamports().saturating_sub(lamports_before);
+        let min_received = oracle_min_out(msol_amount, msol_price, MSOL_PRICE_DENOMINATOR as u64, slippage_bps)?;
+        require!(received >= min_received, EscrowError::SlippageExceeded);
+
+        let v = &mut ctx.accounts.vault;
+        let principal_out = if v.msol_amount == 0 {
+            0
+        } else {
+            ((v.sol_staked_lamports as u128) * (msol_amount.min(v.msol_amount) as u128) / (v.msol_amount as u128)) as u64
+        };
+        v.sol_staked_lamports = v.sol_staked_lamports.checked_sub(principal_out).ok_or(EscrowError::MathUnderflow)?;
+        v.msol_amount = v.msol_amount.checked_sub(msol_amount).ok_or(EscrowError::MathUnderflow)?;
+
+        emit!(MarinadeUnstaked {
+            vault: v.key(),
+            seq: v.seq,
+            msol_amount,
+            lamports_received: received,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn init_protocol_registry(ctx: Context<InitProtocolRegistry>) -> Result<()> {
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_protocol(
+        ctx: Context<SetProtocol>,
+        venue_id: u8,
+        program_id: Pubkey,
+        mint: Pubkey,
+        enabled: bool,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        match registry.entries.iter_mut().find(|e| e.venue_id == venue_id) {
+            Some(entry) => {
+                entry.program_id = program_id;
+                entry.mint = mint;
+                entry.enabled = enabled;
+            }
+            None => {
+                require!(
+                    registry.entries.len() < ProtocolRegistry::MAX_ENTRIES,
+                    EscrowError::RegistryFull
+                );
+                registry.entries.push(ProtocolEntry {
+                    venue_id,
+                    program_id,
+                    mint,
+                    enabled,
+                });
+            }
+        }
+
+        emit!(ProtocolUpdated {
+            venue_id,
+            program_id,
+            mint,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn kamino_deposit(ctx: Context<KaminoLend>, pot_hash: [u8; 32], amount: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_KAMINO, ctx.accounts.kamino_program.key())?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let shares_before = ctx.accounts.vault_collateral.amount;
+        kamino_deposit_cpi(&ctx.accounts.kamino_cpi(), amount, &[seeds])?;
+        ctx.accounts.vault_collateral.reload()?;
+        let minted = ctx.accounts.vault_collateral.amount.saturating_sub(shares_before);
+
+        let p = &mut ctx.accounts.position;
+        if p.vault == Pubkey::default() {
+            p.vault = ctx.accounts.vault.key();
+            p.venue_id = VENUE_KAMINO;
+            p.venue_account = ctx.accounts.reserve.key();
+            p.bump = ctx.bumps.position;
+        }
+        p.principal = p.principal.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        p.shares = p.shares.checked_add(minted).ok_or(EscrowError::MathOverflow)?;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueDeposited {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_KAMINO,
+            amount,
+            shares: minted,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn kamino_withdraw(ctx: Context<KaminoLend>, pot_hash: [u8; 32], shares: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(shares > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(ctx.accounts.position.shares >= shares, EscrowError::InsufficientFunds);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_KAMINO, ctx.accounts.kamino_program.key())?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        kamino_redeem_cpi(&ctx.accounts.kamino_cpi(), shares, &[seeds])?;
+        ctx.accounts.vault_usdc.reload()?;
+        let received = ctx.accounts.vault_usdc.amount.saturating_sub(usdc_before);
+
+        let p = &mut ctx.accounts.position;
+        let principal_out = ((p.principal as u128) * (shares as u128) / (p.shares as u128)) as u64;
+        p.principal = p.principal.checked_sub(principal_out).ok_or(EscrowError::MathUnderflow)?;
+        p.shares = p.shares.checked_sub(shares).ok_or(EscrowError::MathUnderflow)?;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueWithdrawn {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_KAMINO,
+            shares,
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    /// Redeems the whole position, keeps everything above principal in the vault as
+    /// realized yield and re-deposits the principal.
+    #[cfg(feature = "full")]
+    pub fn kamino_harvest(ctx: Context<KaminoLend>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(ctx.accounts.position.shares > 0, EscrowError::InsufficientFunds);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_KAMINO, ctx.accounts.kamino_program.key())?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        let shares = ctx.accounts.position.shares;
+        kamino_redeem_cpi(&ctx.accounts.kamino_cpi(), shares, &[seeds])?;
+        ctx.accounts.vault_usdc.reload()?;
+        let received = ctx.accounts.vault_usdc.amount.saturating_sub(usdc_before);
+
+        let principal = ctx.accounts.position.principal.min(received);
+        let realized = received - principal;
+        let loss = ctx.accounts.position.principal - principal;
+
+        let shares_before = ctx.accounts.vault_collateral.amount;
+        if principal > 0 {
+            kamino_deposit_cpi(&ctx.accounts.kamino_cpi(), principal, &[seeds])?;
+        }
+        ctx.accounts.vault_collateral.reload()?;
+        let minted = ctx.accounts.vault_collateral.amount.saturating_sub(shares_before);
+
+        let p = &mut ctx.accounts.position;
+        p.principal = principal;
+        p.shares = minted;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_KAMINO, realized, principal)?;
+
+        let config = ctx.accounts.config.as_ref().ok_or(EscrowError::MissingConfig)?;
+        let v = &mut ctx.accounts.vault;
+        v.net_yield_usdc = v
+            .net_yield_usdc
+            .checked_add(realized as i64)
+            .and_then(|n| n.checked_sub(loss as i64))
+            .ok_or(EscrowError::MathOverflow)?;
+        let fee_bps = v.performance_fee_bps(config);
+        let fee = performance_fee(v, fee_bps)?;
+        if fee > 0 {
+            let treasury_usdc = ctx
+                .accounts
+                .fee_treasury_usdc
+                .as_ref()
+                .ok_or(EscrowError::BadFeeTreasury)?;
+            require_keys_eq!(treasury_usdc.owner, config.fee_treasury, EscrowError::BadFeeTreasury);
+            let cpi = Transfer {
+                from: ctx.accounts.vault_usdc.to_account_info(),
+                to: treasury_usdc.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]);
+            token::transfer(cpi_ctx, fee)?;
+            ctx.accounts.vault.book_usdc_out(fee)?;
+
+            emit!(PerformanceFeeCharged {
+                vault: ctx.accounts.vault.key(),
+                seq: ctx.accounts.vault.seq,
+                mint: ctx.accounts.usdc_mint.key(),
+                amount: fee,
+                high_water_mark: ctx.accounts.vault.fee_hwm_usdc,
+            });
+        }
+        let net_realized = realized.checked_sub(fee).ok_or(EscrowError::MathUnderflow)?;
+        ctx.accounts.vault.pending_yield_usdc = ctx.accounts.vault.pending_yield_usdc.checked_add(net_realized).ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn marginfi_init_account(ctx: Context<MarginfiInitAccount>, pot_hash: [u8; 32]) -> Result<()> {
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_MARGINFI, ctx.accounts.marginfi_program.key())?;
+
+        let ix = Instruction {
+            program_id: ctx.accounts.marginfi_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.marginfi_group.key(), false),
+                AccountMeta::new(ctx.accounts.marginfi_account.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.owner.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            ],
+            data: MARGINFI_INIT_ACCOUNT_IX.to_vec(),
+        };
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.marginfi_group.to_account_info(),
+                ctx.accounts.marginfi_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+
+        let p = &mut ctx.accounts.position;
+        p.vault = ctx.accounts.vault.key();
+        p.venue_id = VENUE_MARGINFI;
+        p.venue_account = ctx.accounts.marginfi_account.key();
+        p.last_update = Clock::get()?.unix_timestamp;
+        p.bump = ctx.bumps.position;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn marginfi_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarginfiLend<'info>>,
+        pot_hash: [u8; 32],
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_MARGINFI, ctx.accounts.marginfi_program.key())?;
+
+        let mut data = MARGINFI_DEPOSIT_IX.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(0); // deposit_up_to_limit: None
+        let ix = Instruction {
+            program_id: ctx.accounts.marginfi_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.marginfi_group.key(), false),
+                AccountMeta::new(ctx.accounts.marginfi_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.bank.key(), false),
+                AccountMeta::new(ctx.accounts.vault_usdc.key(), false),
+                AccountMeta::new(ctx.accounts.bank_liquidity_vault.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.marginfi_group.to_account_info(),
+                ctx.accounts.marginfi_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.bank.to_account_info(),
+                ctx.accounts.vault_usdc.to_account_info(),
+                ctx.accounts.bank_liquidity_vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+
+        let shares = assert_marginfi_lend_only(&ctx.accounts.marginfi_account, ctx.accounts.bank.key())?;
+
+        let p = &mut ctx.accounts.position;
+        p.principal = p.principal.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        p.shares = shares;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueDeposited {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_MARGINFI,
+            amount,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn marginfi_withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarginfiLend<'info>>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        withdraw_all: bool,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0 || withdraw_all, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_MARGINFI, ctx.accounts.marginfi_program.key())?;
+
+        let mut data = MARGINFI_WITHDRAW_IX.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&[1, withdraw_all as u8]); // withdraw_all: Some(bool)
+        let mut metas = vec![
+            AccountMeta::new_readonly(ctx.accounts.marginfi_group.key(), false),
+            AccountMeta::new(ctx.accounts.marginfi_account.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+            AccountMeta::new(ctx.accounts.bank.key(), false),
+            AccountMeta::new(ctx.accounts.vault_usdc.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.bank_liquidity_vault_authority.key(), false),
+            AccountMeta::new(ctx.accounts.bank_liquidity_vault.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+        ];
+        let mut infos = vec![
+            ctx.accounts.marginfi_group.to_account_info(),
+            ctx.accounts.marginfi_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.bank.to_account_info(),
+            ctx.accounts.vault_usdc.to_account_info(),
+            ctx.accounts.bank_liquidity_vault_authority.to_account_info(),
+            ctx.accounts.bank_liquidity_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        ];
+        // Bank/oracle pairs for marginfi's health check.
+        for acc in ctx.remaining_accounts.iter() {
+            metas.push(AccountMeta::new_readonly(*acc.key, false));
+            infos.push(acc.clone());
+        }
+        let ix = Instruction {
+            program_id: ctx.accounts.marginfi_program.key(),
+            accounts: metas,
+            data,
+        };
+
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(&ix, &infos, &[seeds])?;
+
+        ctx.accounts.vault_usdc.reload()?;
+        let received = ctx.accounts.vault_usdc.amount.saturating_sub(usdc_before);
+        let shares = assert_marginfi_lend_only(&ctx.accounts.marginfi_account, ctx.accounts.bank.key())?;
+
+        let p = &mut ctx.accounts.position;
+        p.principal = if withdraw_all { 0 } else { p.principal.saturating_sub(received) };
+        p.shares = shares;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueWithdrawn {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_MARGINFI,
+            shares,
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn drift_init(ctx: Context<DriftInit>, pot_hash: [u8; 32], market_index: u16) -> Result<()> {
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_DRIFT, ctx.accounts.drift_program.key())?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        if ctx.accounts.user_stats.data_is_empty() {
+            let ix = Instruction {
+                program_id: ctx.accounts.drift_program.key(),
+                accounts: vec![
+                    AccountMeta::new(ctx.accounts.user_stats.key(), false),
+                    AccountMeta::new(ctx.accounts.drift_state.key(), false),
+                    AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                    AccountMeta::new(ctx.accounts.owner.key(), true),
+                    AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+                    AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+                ],
+                data: DRIFT_INIT_USER_STATS_IX.to_vec(),
+            };
+            invoke_signed(
+                &ix,
+                &[
+                    ctx.accounts.user_stats.to_account_info(),
+                    ctx.accounts.drift_state.to_account_info(),
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.owner.to_account_info(),
+                    ctx.accounts.rent.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
+
+        let mut data = DRIFT_INIT_IF_STAKE_IX.to_vec();
+        data.extend_from_slice(&market_index.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.drift_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.spot_market.key(), false),
+                AccountMeta::new(ctx.accounts.insurance_fund_stake.key(), false),
+                AccountMeta::new(ctx.accounts.user_stats.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.drift_state.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.owner.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            ],
+            data,
+        };
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.spot_market.to_account_info(),
+                ctx.accounts.insurance_fund_stake.to_account_info(),
+                ctx.accounts.user_stats.to_account_info(),
+                ctx.accounts.drift_state.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+
+        let p = &mut ctx.accounts.position;
+        p.vault = ctx.accounts.vault.key();
+        p.venue_id = VENUE_DRIFT;
+        p.venue_account = ctx.accounts.insurance_fund_stake.key();
+        p.last_update = Clock::get()?.unix_timestamp;
+        p.bump = ctx.bumps.position;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn drift_stake(ctx: Context<DriftInsuranceFund>, pot_hash: [u8; 32], market_index: u16, amount: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_DRIFT, ctx.accounts.drift_program.key())?;
+
+        let mut data = DRIFT_ADD_IF_STAKE_IX.to_vec();
+        data.extend_from_slice(&market_index.to_le_bytes());
+        data.extend_from_slice(&amount.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.drift_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.drift_state.key(), false),
+                AccountMeta::new(ctx.accounts.spot_market.key(), false),
+                AccountMeta::new(ctx.accounts.insurance_fund_stake.key(), false),
+                AccountMeta::new(ctx.accounts.user_stats.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.spot_market_vault.key(), false),
+                AccountMeta::new(ctx.accounts.insurance_fund_vault.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.drift_signer.key(), false),
+                AccountMeta::new(ctx.accounts.vault_usdc.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(&ix, &drift_account_infos(&ctx.accounts), &[seeds])?;
+
+        let shares = drift_if_shares(&ctx.accounts.insurance_fund_stake)?;
+        let p = &mut ctx.accounts.position;
+        p.principal = p.principal.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        p.shares = shares;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueDeposited {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_DRIFT,
+            amount,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Starts Drift's insurance-fund unstaking cooldown for `amount` (token units).
+    #[cfg(feature = "full")]
+    pub fn drift_request_unstake(
+        ctx: Context<DriftInsuranceFund>,
+        pot_hash: [u8; 32],
+        market_index: u16,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_DRIFT, ctx.accounts.drift_program.key())?;
+
+        let mut data = DRIFT_REQUEST_REMOVE_IF_STAKE_IX.to_vec();
+        data.extend_from_slice(&market_index.to_le_bytes());
+        data.extend_from_slice(&amount.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.drift_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.spot_market.key(), false),
+                AccountMeta::new(ctx.accounts.insurance_fund_stake.key(), false),
+                AccountMeta::new(ctx.accounts.user_stats.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.insurance_fund_vault.key(), false),
+            ],
+            data,
+        };
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(&ix, &drift_account_infos(&ctx.accounts), &[seeds])?;
+
+        Ok(())
+    }
+
+    /// Completes a pending insurance-fund removal. Drift yield is only realized on
+    /// removal, so whatever exceeds the pro-rata principal of the removed shares is
+    /// booked as yield on the vault.
+    #[cfg(feature = "full")]
+    pub fn drift_harvest(ctx: Context<DriftInsuranceFund>, pot_hash: [u8; 32], market_index: u16) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_DRIFT, ctx.accounts.drift_program.key())?;
+
+        let mut data = DRIFT_REMOVE_IF_STAKE_IX.to_vec();
+        data.extend_from_slice(&market_index.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.drift_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.drift_state.key(), false),
+                AccountMeta::new(ctx.accounts.spot_market.key(), false),
+                AccountMeta::new(ctx.accounts.insurance_fund_stake.key(), false),
+                AccountMeta::new(ctx.accounts.user_stats.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.vault.key(), true),
+                AccountMeta::new(ctx.accounts.insurance_fund_vault.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.drift_signer.key(), false),
+                AccountMeta::new(ctx.accounts.vault_usdc.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data,
+        };
+
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        let shares_before = drift_if_shares(&ctx.accounts.insurance_fund_stake)?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_signed(&ix, &drift_account_infos(&ctx.accounts), &[seeds])?;
+
+        ctx.accounts.vault_usdc.reload()?;
+        let received = ctx.accounts.vault_usdc.amount.saturating_sub(usdc_before);
+        let shares_after = drift_if_shares(&ctx.accounts.insurance_fund_stake)?;
+        let removed = shares_before.saturating_sub(shares_after);
+
+        let p = &mut ctx.accounts.position;
+        let principal_out = if shares_before == 0 {
+            p.principal
+        } else {
+            ((p.principal as u128) * (removed as u128) / (shares_before as u128)) as u64
+        };
+        let realized = received.saturating_sub(principal_out);
+        p.principal = p.principal.checked_sub(principal_out).ok_or(EscrowError::MathUnderflow)?;
+        p.shares = shares_after;
+        p.last_update = Clock::get()?.unix_timestamp;
+        let principal = p.principal;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_DRIFT, realized, principal)?;
+        ctx.accounts.vault.pending_yield_usdc = ctx.accounts.vault.pending_yield_usdc.checked_add(realized).ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// `min_lst_out` is only a floor on top of the stake pool's own exchange rate less
+    /// `Config::max_swap_slippage_bps`. Passing `instructions_sysvar` also rejects
+    /// transactions that call DEX programs directly (see `check_no_dex_instructions`).
+    #[cfg(feature = "full")]
+    pub fn sanctum_stake_lst<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SanctumSwap<'info>>,
+        pot_hash: [u8; 32],
+        lamports: u64,
+        min_lst_out: u64,
+        ix_data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        check_exec_rate(&mut ctx.accounts.vault)?;
+        let entry = ctx
+            .accounts
+            .registry
+            .require_enabled(VENUE_SANCTUM, ctx.accounts.sanctum_program.key())?;
+        require_keys_eq!(entry.mint, ctx.accounts.lst_mint.key(), EscrowError::BadMint);
+        ctx.accounts.check_sandwich_guard()?;
+        let (total_lamports, pool_token_supply) =
+            read_stake_pool_rate(&ctx.accounts.stake_pool, ctx.accounts.lst_mint.key())?;
+        let slippage_bps = ctx.accounts.config.swap_slippage_bps();
+
+        let rent = Rent::get()?;
+        let min = rent.minimum_balance(Vault::SPACE);
+        let current = ctx.accounts.vault.to_account_info().lamports();
+        require!(withdrawable_lamports(current, min) >= lamports, EscrowError::InsufficientFunds);
+
+        // Sanctum routes from wSOL, so wrap the lamports into the vault's wSOL ATA first.
+        transfer_lamports(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.vault_wsol.to_account_info(),
+            lamports,
+        )?;
+        token::sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.vault_wsol.to_account_info(),
+            },
+        ))?;
+        ctx.accounts.vault_wsol.reload()?;
+
+        let wsol_before = ctx.accounts.vault_wsol.amount;
+        let lst_before = ctx.accounts.vault_lst.amount;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_vault_passthrough(
+            &ctx.accounts.sanctum_program,
+            ctx.accounts.vault.key(),
+            ctx.remaining_accounts,
+            ix_data,
+            &[seeds],
+        )?;
+
+        ctx.accounts.vault_wsol.reload()?;
+        ctx.accounts.vault_lst.reload()?;
+        let spent = wsol_before.saturating_sub(ctx.accounts.vault_wsol.amount);
+        let lst_out = ctx.accounts.vault_lst.amount.saturating_sub(lst_before);
+        require!(spent <= lamports, EscrowError::ExcessiveSpend);
+        let oracle_min = oracle_min_out(spent, pool_token_supply, total_lamports, slippage_bps)?;
+        require!(lst_out >= min_lst_out.max(oracle_min), EscrowError::SlippageExceeded);
+
+        let p = &mut ctx.accounts.position;
+        if p.vault == Pubkey::default() {
+            p.vault = ctx.accounts.vault.key();
+            p.venue_id = VENUE_SANCTUM;
+            p.venue_account = ctx.accounts.lst_mint.key();
+            p.bump = ctx.bumps.position;
+        }
+        require_keys_eq!(p.venue_account, ctx.accounts.lst_mint.key(), EscrowError::BadMint);
+        p.principal = p.principal.checked_add(spent).ok_or(EscrowError::MathOverflow)?;
+        p.shares = p.shares.checked_add(lst_out).ok_or(EscrowError::MathOverflow)?;
+        p.last_update = Clock::get()?.unix_timestamp;
+
+        emit!(VenueDeposited {
+            vault: p.vault,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_SANCTUM,
+            amount: spent,
+            shares: lst_out,
+        });
+
+        Ok(())
+    }
+
+    /// Same guards as `sanctum_stake_lst`, with the oracle floor on SOL out.
+    #[cfg(feature = "full")]
+    pub fn sanctum_unstake_lst<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SanctumSwap<'info>>,
+        pot_hash: [u8; 32],
+        lst_amount: u64,
+        min_sol_out: u64,
+        ix_data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(lst_amount > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        require!(ctx.accounts.position.shares >= lst_amount, EscrowError::InsufficientFunds);
+        check_exec_rate(&mut ctx.accounts.vault)?;
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_SANCTUM, ctx.accounts.sanctum_program.key())?;
+        require_keys_eq!(
+            ctx.accounts.position.venue_account,
+            ctx.accounts.lst_mint.key(),
+            EscrowError::BadMint
+        );
+        ctx.accounts.check_sandwich_guard()?;
+        let (total_lamports, pool_token_supply) =
+            read_stake_pool_rate(&ctx.accounts.stake_pool, ctx.accounts.lst_mint.key())?;
+        let slippage_bps = ctx.accounts.config.swap_slippage_bps();
+
+        let wsol_before = ctx.accounts.vault_wsol.amount;
+        let lst_before = ctx.accounts.vault_lst.amount;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_vault_passthrough(
+            &ctx.accounts.sanctum_program,
+            ctx.accounts.vault.key(),
+            ctx.remaining_accounts,
+            ix_data,
+            &[seeds],
+        )?;
+
+        ctx.accounts.vault_wsol.reload()?;
+        ctx.accounts.vault_lst.reload()?;
+        let lst_spent = lst_before.saturating_sub(ctx.accounts.vault_lst.amount);
+        let sol_out = ctx.accounts.vault_wsol.amount.saturating_sub(wsol_before);
+        require!(lst_spent <= lst_amount, EscrowError::ExcessiveSpend);
+        let oracle_min = oracle_min_out(lst_spent, total_lamports, pool_token_supply, slippage_bps)?;
+        require!(sol_out >= min_sol_out.max(oracle_min), EscrowError::SlippageExceeded);
+
+        let p = &mut ctx.accounts.position;
+        let principal_out = ((p.principal as u128) * (lst_spent as u128) / (p.shares as u128)) as u64;
+        let realized = sol_out.saturating_sub(principal_out);
+        p.principal = p.principal.checked_sub(principal_out).ok_or(EscrowError::MathUnderflow)?;
+        p.shares = p.shares.checked_sub(lst_spent).ok_or(EscrowError::MathUnderflow)?;
+        p.last_update = Clock::get()?.unix_timestamp;
+        let vault_key = p.vault;
+        let principal = p.principal;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_SANCTUM, realized, principal)?;
+
+        emit!(VenueWithdrawn {
+            vault: vault_key,
+            seq: ctx.accounts.vault.seq,
+            venue_id: VENUE_SANCTUM,
+            shares: lst_spent,
+            amount: sol_out,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn marginfi_harvest(ctx: Context<MarginfiHarvest>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_MARGINFI, *ctx.accounts.bank.owner)?;
+
+        let value = marginfi_asset_value(&ctx.accounts.marginfi_account, &ctx.accounts.bank)?;
+        let p = &mut ctx.accounts.position;
+        let accrued = value.saturating_sub(p.principal);
+        p.principal = p.principal.max(value);
+        p.last_update = Clock::get()?.unix_timestamp;
+        let principal = p.principal;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_MARGINFI, accrued, principal)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn marinade_harvest(ctx: Context<MarinadeHarvest>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let msol_price = read_msol_price(&ctx.accounts.marinade_state)?;
+
+        let v = &mut ctx.accounts.vault;
+        let value = v.sol_allocation_lamports(0, msol_price);
+        let accrued = value.saturating_sub(v.sol_staked_lamports);
+        v.sol_staked_lamports = v.sol_staked_lamports.max(value);
+        let principal = v.sol_staked_lamports;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_MARINADE, accrued, principal)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn sanctum_harvest(ctx: Context<SanctumHarvest>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let (total_lamports, pool_token_supply) =
+            read_stake_pool_rate(&ctx.accounts.stake_pool, ctx.accounts.position.venue_account)?;
+
+        let p = &mut ctx.accounts.position;
+        let value = ((p.shares as u128) * (total_lamports as u128) / (pool_token_supply as u128)) as u64;
+        let accrued = value.saturating_sub(p.principal);
+        p.principal = p.principal.max(value);
+        p.last_update = Clock::get()?.unix_timestamp;
+        let principal = p.principal;
+
+        book_yield(&mut ctx.accounts.vault, VENUE_SANCTUM, accrued, principal)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_compound_params(ctx: Context<UpdateConfig>, min_compound_amount: u64, compound_cooldown_secs: i64) -> Result<()> {
+        require!(compound_cooldown_secs >= 0, EscrowError::InvalidTimelock);
+
+        let config = &mut ctx.accounts.config;
+        config.min_compound_amount = min_compound_amount;
+        config.compound_cooldown_secs = compound_cooldown_secs;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_vault_pod(ctx: Context<SetVaultPod>, pot_hash: [u8; 32], pod_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+
+        ctx.accounts.vault.pod_hash = pod_hash;
+
+        Ok(())
+    }
+
+    /// Permissionless crank: re-deposits realized USDC yield into the venue the policy
+    /// gives the largest share of the cash leg. Only Kamino is served so far; a policy
+    /// led by any other venue (including the Lulo-only split) is rejected rather than
+    /// compounded somewhere it didn't ask for. mSOL/LST yield already compounds
+    /// through their exchange rates, so only the USDC side needs cranking.
+    #[cfg(feature = "full")]
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let cash_venue = match ctx.accounts.pot_policy.as_ref() {
+            Some(pot_policy) if pot_policy.override_enabled => {
+                (pot_policy.usdc_in_lulo_bps > 0).then_some(VENUE_LULO)
+            }
+            _ => ctx.accounts.pod_policy.load()?.cash_venue(),
+        };
+        require!(cash_venue == Some(VENUE_KAMINO), EscrowError::CompoundNotApplicable);
+        ctx.accounts
+            .registry
+            .require_enabled(VENUE_KAMINO, ctx.accounts.kamino_program.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let config = &ctx.accounts.config;
+        let v = &ctx.accounts.vault;
+        require!(
+            now >= v.last_compound_ts.saturating_add(config.compound_cooldown_secs),
+            EscrowError::CompoundCooldown
+        );
+        let amount = v.pending_yield_usdc.min(v.unlocked_usdc(ctx.accounts.vault_usdc.amount));
+        require!(
+            amount > 0 && amount >= config.min_compound_amount,
+            EscrowError::CompoundTooSmall
+        );
+
+        let owner_key = v.owner;
+        let pot_hash = v.pot_hash;
+        let bump = v.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let shares_before = ctx.accounts.vault_collateral.amount;
+        kamino_deposit_cpi(&ctx.accounts.kamino_cpi(), amount, &[seeds])?;
+        ctx.accounts.vault_collateral.reload()?;
+        let minted = ctx.accounts.vault_collateral.amount.saturating_sub(shares_before);
+
+        let p = &mut ctx.accounts.position;
+        p.principal = p.principal.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        p.shares = p.shares.checked_add(minted).ok_or(EscrowError::MathOverflow)?;
+        p.last_update = now;
+
+        let v = &mut ctx.accounts.vault;
+        v.pending_yield_usdc = v.pending_yield_usdc.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+        v.last_compound_ts = now;
+
+        emit!(YieldCompounded {
+            vault: v.key(),
+            seq: v.seq,
+            venue_id: VENUE_KAMINO,
+            amount,
+            shares: minted,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_performance_fee(ctx: Context<UpdateFeeConfig>, performance_fee_bps: u16) -> Result<()> {
+        require!(
+            performance_fee_bps <= Config::MAX_PERFORMANCE_FEE_BPS,
+            EscrowError::InvalidFee
+        );
+
+        ctx.accounts.config.performance_fee_bps = performance_fee_bps;
+
+        Ok(())
+    }
+
+    /// Per-pot override of the pod's targets. Rebalancing reads this instead of the
+    /// pod policy while `override_enabled` is set.
+    #[cfg(feature = "full")]
+    pub fn set_pot_policy(
+        ctx: Context<SetPotPolicy>,
+        pot_hash: [u8; 32],
+        risk_state: u8,
+        assets: Vec<AssetTarget>,
+        usdc_in_lulo_bps: u16,
+        override_enabled: bool,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        validate_policy_targets(risk_state, &assets, usdc_in_lulo_bps)?;
+
+        let policy = &mut ctx.accounts.pot_policy;
+        policy.vault = ctx.accounts.vault.key();
+        policy.risk_state = risk_state;
+        policy.assets = assets;
+        policy.usdc_in_lulo_bps = usdc_in_lulo_bps;
+        policy.override_enabled = override_enabled;
+        policy.bump = ctx.bumps.pot_policy;
+        policy.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn init_policy_history(ctx: Context<InitPolicyHistory>, pod_hash: [u8; 32], capacity: u32) -> Result<()> {
+        require!(
+            capacity > 0 && capacity <= PolicyHistory::MAX_CAPACITY,
+            EscrowError::InvalidHistoryCapacity
+        );
+
+        let mut history = ctx.accounts.policy_history.load_init()?;
+        history.pod_hash = pod_hash;
+        history.capacity = capacity;
+        history.bump = ctx.bumps.policy_history;
+
+        Ok(())
+    }
+
+    /// Reallocates the ring buffer to `capacity` entries, keeping the most recent
+    /// updates in chronological order. Rent is topped up or refunded to the authority.
+    #[cfg(feature = "full")]
+    pub fn resize_policy_history(ctx: Context<ResizePolicyHistory>, pod_hash: [u8; 32], capacity: u32) -> Result<()> {
+        require!(
+            capacity > 0 && capacity <= PolicyHistory::MAX_CAPACITY,
+            EscrowError::InvalidHistoryCapacity
+        );
+
+        let entries = read_policy_history(&ctx.accounts.policy_history)?;
+        let keep = entries.len().min(capacity as usize);
+
+        let info = ctx.accounts.policy_history.to_account_info();
+        resize_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PolicyHistory::space(capacity),
+        )?;
+
+        {
+            let mut data = info.try_borrow_mut_data()?;
+            for (i, entry) in entries[entries.len() - keep..].iter().enumerate() {
+                let off = POLICY_HISTORY_ENTRIES_OFFSET + i * POLICY_HISTORY_ENTRY_LEN;
+                data[off..off + POLICY_HISTORY_ENTRY_LEN].copy_from_slice(bytemuck::bytes_of(entry));
+            }
+        }
+
+        let mut history = ctx.accounts.policy_history.load_mut()?;
+        require!(history.pod_hash == pod_hash, EscrowError::BadPot);
+        history.capacity = capacity;
+        history.len = keep as u32;
+        history.head = keep as u32 % capacity;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn close_pod_policy(ctx: Context<ClosePodPolicy>, pod_hash: [u8; 32]) -> Result<()> {
+        emit!(PolicyClosed {
+            policy: ctx.accounts.pod_policy.key(),
+            pod_hash,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// First step of an authority hand-over. Passing `Pubkey::default()` cancels a
+    /// pending transfer.
+    #[cfg(feature = "full")]
+    pub fn transfer_policy_authority(
+        ctx: Context<TransferPolicyAuthority>,
+        pod_hash: [u8; 32],
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        policy.pending_authority = new_authority;
+
+        emit!(PolicyAuthorityTransferStarted {
+            policy: ctx.accounts.pod_policy.key(),
+            pod_hash,
+            authority: policy.authority,
+            pending_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    /// The new authority starts without a manager; the previous one's appointment
+    /// doesn't carry over.
+    #[cfg(feature = "full")]
+    pub fn accept_policy_authority(ctx: Context<AcceptPolicyAuthority>, pod_hash: [u8; 32]) -> Result<()> {
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        let previous = policy.authority;
+        policy.authority = ctx.accounts.pending_authority.key();
+        policy.pending_authority = Pubkey::default();
+        policy.policy_manager = Pubkey::default();
+
+        emit!(PolicyAuthorityTransferred {
+            policy: ctx.accounts.pod_policy.key(),
+            pod_hash,
+            previous_authority: previous,
+            authority: policy.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Authority only: appoints a strategy manager who may edit the policy's
+    /// allocation (`update_policy` and the other `UpdatePolicy` setters) but holds
+    /// no vault authority. `Pubkey::default()` revokes.
+    #[cfg(feature = "full")]
+    pub fn set_policy_manager(ctx: Context<TransferPolicyAuthority>, pod_hash: [u8; 32], manager: Pubkey) -> Result<()> {
+        let mut policy = ctx.accounts.pod_policy.load_mut()?;
+        policy.policy_manager = manager;
+
+        emit!(PolicyManagerSet {
+            policy: ctx.accounts.pod_policy.key(),
+            pod_hash,
+            authority: policy.authority,
+            manager,
+        });
+
+        Ok(())
+    }
+
+    /// Deposits into several of the owner's pots at once. `remaining_accounts[i]` is
+    /// the vault for `entries[i]`.
+    #[cfg(feature = "full")]
+    pub fn batch_deposit(ctx: Context<BatchDeposit>, entries: Vec<BatchDepositEntry>, total_lamports: u64) -> Result<()> {
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_BATCH_POTS,
+            EscrowError::InvalidBatch
+        );
+        require!(entries.len() == ctx.remaining_accounts.len(), EscrowError::InvalidBatch);
+
+        let sum = entries
+            .iter()
+            .try_fold(0u64, |acc, e| acc.checked_add(e.lamports))
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(sum == total_lamports, EscrowError::InvalidAmount);
+
+        let owner_key = ctx.accounts.owner.key();
+        check_attestation(Some(&ctx.accounts.config), ctx.accounts.attestation.as_ref(), owner_key, total_lamports)?;
+        let owner_info = ctx.accounts.owner.to_account_info();
+        let system_info = ctx.accounts.system_program.to_account_info();
+        for (entry, vault_info) in entries.iter().zip(ctx.remaining_accounts.iter()) {
+            require!(entry.lamports > 0, EscrowError::InvalidAmount);
+            require!(vault_info.is_writable, EscrowError::BadVaultAccount);
+            let vault = owner_vault_state(vault_info, &owner_key)?;
+            require!(vault.pot_hash == entry.pot_hash, EscrowError::BadPot);
+            check_deposit_limits(
+                Some(&ctx.accounts.config),
+                &vault,
+                vault_info,
+                ctx.accounts.sol_price.as_ref(),
+                entry.lamports,
+                0,
+            )?;
+
+            let ix = anchor_lang::solana_program::system_instruction::transfer(
+                &owner_key,
+                vault_info.key,
+                entry.lamports,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &ix,
+                &[owner_info.clone(), vault_info.clone(), system_info.clone()],
+            )?;
+            bump_vault_seq(vault_info, vault)?;
+        }
+
+        Ok(())
+    }
+
+    /// Folds `source` into `destination`: moves USDC and mSOL, adds up the
+    /// accounting counters and closes the source vault (rent included) into the
+    /// destination. Venue positions of the source must be unwound first.
+    #[cfg(feature = "full")]
+    pub fn merge_pots(
+        ctx: Context<MergePots>,
+        source_pot_hash: [u8; 32],
+        destination_pot_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.destination_vault.next_seq();
+        require_keys_eq!(ctx.accounts.source_vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        require!(!ctx.accounts.source_vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(source_pot_hash != destination_pot_hash, EscrowError::BadPot);
+        require_keys_eq!(
+            ctx.accounts.source_vault.usdc_mint,
+            ctx.accounts.destination_vault.usdc_mint,
+            EscrowError::BadMint
+        );
+        require!(ctx.accounts.source_vault.stream_locked_usdc == 0, EscrowError::VaultNotEmpty);
+        require!(!ctx.accounts.source_vault.crowdfund_active(), EscrowError::CrowdfundActive);
+        require_keys_eq!(ctx.accounts.destination_vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.source_vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), source_pot_hash.as_ref(), &[bump]];
+
+        let usdc = ctx.accounts.source_usdc.amount;
+        let config = ctx.accounts.config.as_ref();
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, usdc)?;
+        let source_lamports = ctx.accounts.source_vault.to_account_info().lamports();
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, source_lamports)?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.source_vault.usdc_mint,
+            usdc,
+        )?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            source_lamports,
+        )?;
+        move_tokens_and_close(
+            &ctx.accounts.token_program,
+            &ctx.accounts.source_usdc.to_account_info(),
+            &ctx.accounts.destination_usdc.to_account_info(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.source_vault.to_account_info(),
+            usdc,
+            &[seeds],
+        )?;
+
+        let msol = match (ctx.accounts.source_msol.as_ref(), ctx.accounts.destination_msol.as_ref()) {
+            (Some(from), Some(to)) => {
+                let amount = from.amount;
+                move_tokens_and_close(
+                    &ctx.accounts.token_program,
+                    &from.to_account_info(),
+                    &to.to_account_info(),
+                    &ctx.accounts.owner.to_account_info(),
+                    &ctx.accounts.source_vault.to_account_info(),
+                    amount,
+                    &[seeds],
+                )?;
+                amount
+            }
+            (None, _) => {
+                require!(ctx.accounts.source_vault.msol_amount == 0, EscrowError::BadVaultAccount);
+                0
+            }
+            (Some(_), None) => return err!(EscrowError::BadVaultAccount),
+        };
+
+        let src = &ctx.accounts.source_vault;
+        let lamports = src.to_account_info().lamports();
+        let (msol_amount, sol_staked, accrued, pending, net_yield, hwm) = (
+            src.msol_amount,
+            src.sol_staked_lamports,
+            src.accrued_yield,
+            src.pending_yield_usdc,
+            src.net_yield_usdc,
+            src.fee_hwm_usdc,
+        );
+        let (commit_until, commit_penalty_bps) = (src.no_withdraw_until, src.early_withdraw_penalty_bps);
+        let (total_in, total_out) = (src.total_in_usdc, src.total_out_usdc);
+
+        let dst = &mut ctx.accounts.destination_vault;
+        dst.msol_amount = dst.msol_amount.checked_add(msol_amount).ok_or(EscrowError::MathOverflow)?;
+        dst.sol_staked_lamports = dst.sol_staked_lamports.checked_add(sol_staked).ok_or(EscrowError::MathOverflow)?;
+        dst.accrued_yield = dst.accrued_yield.checked_add(accrued).ok_or(EscrowError::MathOverflow)?;
+        dst.pending_yield_usdc = dst.pending_yield_usdc.checked_add(pending).ok_or(EscrowError::MathOverflow)?;
+        dst.net_yield_usdc = dst.net_yield_usdc.checked_add(net_yield).ok_or(EscrowError::MathOverflow)?;
+        dst.fee_hwm_usdc = dst.fee_hwm_usdc.checked_add(hwm).ok_or(EscrowError::MathOverflow)?;
+        // The source's USDC ledger comes along with its balance.
+        dst.book_usdc_in(total_in)?;
+        dst.book_usdc_out(total_out)?;
+        // The funds stay with the owner, so a commitment travels with them rather
+        // than being slashed; the stricter of the two pots' terms wins.
+        dst.no_withdraw_until = dst.no_withdraw_until.max(commit_until);
+        dst.early_withdraw_penalty_bps = dst.early_withdraw_penalty_bps.max(commit_penalty_bps);
+
+        registry_remove(
+            &mut ctx.accounts.owner_registry,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            source_pot_hash,
+        )?;
+
+        emit!(PotsMerged {
+            owner: owner_key,
+            source_vault: ctx.accounts.source_vault.key(),
+            destination_vault: dst.key(),
+            seq: dst.seq,
+            lamports,
+            usdc,
+            msol,
+        });
+
+        Ok(())
+    }
+
+    /// Carves a new pot out of an existing one. The new vault is initialized and
+    /// receives `lamports`, `usdc_amount` and `msol_amount` from the source; staked
+    /// SOL accounting follows the mSOL pro rata. The new vault's mSOL ATA must
+    /// already exist when `msol_amount > 0`.
+    #[cfg(feature = "full")]
+    pub fn split_pot(
+        ctx: Context<SplitPot>,
+        source_pot_hash: [u8; 32],
+        new_pot_hash: [u8; 32],
+        lamports: u64,
+        usdc_amount: u64,
+        msol_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.source_vault.next_seq();
+        require_keys_eq!(ctx.accounts.source_vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        require!(!ctx.accounts.source_vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(lamports > 0 || usdc_amount > 0 || msol_amount > 0, EscrowError::InvalidAmount);
+        require!(!ctx.accounts.source_vault.crowdfund_active(), EscrowError::CrowdfundActive);
+
+        let owner_key = ctx.accounts.owner.key();
+        let config = ctx.accounts.config.as_ref();
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, usdc_amount)?;
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, lamports)?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.source_vault.usdc_mint,
+            usdc_amount,
+        )?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            lamports,
+        )?;
+        let v = &mut ctx.accounts.new_vault;
+        v.owner = owner_key;
+        v.pot_hash = new_pot_hash;
+        v.bump = ctx.bumps.new_vault;
+        v.usdc_mint = ctx.accounts.usdc_mint.key();
+        v.usdc_vault = ctx.accounts.new_usdc.key();
+        v.pod_hash = ctx.accounts.source_vault.pod_hash;
+        // Carved-out funds keep the source's commitment.
+        v.no_withdraw_until = ctx.accounts.source_vault.no_withdraw_until;
+        v.early_withdraw_penalty_bps = ctx.accounts.source_vault.early_withdraw_penalty_bps;
+
+        let source_info = ctx.accounts.source_vault.to_account_info();
+        let min = Rent::get()?.minimum_balance(source_info.data_len());
+        require!(
+            source_info.lamports().saturating_sub(min) >= lamports,
+            EscrowError::InsufficientFunds
+        );
+        if lamports > 0 {
+            transfer_lamports(&source_info, &ctx.accounts.new_vault.to_account_info(), lamports)?;
+        }
+
+        let bump = ctx.accounts.source_vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), source_pot_hash.as_ref(), &[bump]];
+
+        if usdc_amount > 0 {
+            require!(
+                ctx.accounts.source_vault.unlocked_usdc(ctx.accounts.source_usdc.amount) >= usdc_amount,
+                EscrowError::InsufficientFunds
+            );
+            let cpi = Transfer {
+                from: ctx.accounts.source_usdc.to_account_info(),
+                to: ctx.accounts.new_usdc.to_account_info(),
+                authority: source_info.clone(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                usdc_amount,
+            )?;
+        }
+
+        let mut staked_moved = 0;
+        if msol_amount > 0 {
+            let from = ctx.accounts.source_msol.as_ref().ok_or(EscrowError::BadVaultAccount)?;
+            let to = ctx.accounts.new_msol.as_ref().ok_or(EscrowError::BadVaultAccount)?;
+            let src = &ctx.accounts.source_vault;
+            require!(
+                from.amount >= msol_amount && src.msol_amount >= msol_amount,
+                EscrowError::InsufficientFunds
+            );
+            staked_moved = ((src.sol_staked_lamports as u128) * (msol_amount as u128)
+                / (src.msol_amount as u128)) as u64;
+
+            let cpi = Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: source_info.clone(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                msol_amount,
+            )?;
+        }
+
+        let src = &mut ctx.accounts.source_vault;
+        src.msol_amount = src.msol_amount.checked_sub(msol_amount).ok_or(EscrowError::MathUnderflow)?;
+        src.sol_staked_lamports = src.sol_staked_lamports.checked_sub(staked_moved).ok_or(EscrowError::MathUnderflow)?;
+        let usdc_left = ctx.accounts.source_usdc.amount.checked_sub(usdc_amount).ok_or(EscrowError::MathUnderflow)?;
+        src.pending_yield_usdc = src.pending_yield_usdc.min(usdc_left);
+        src.book_usdc_out(usdc_amount)?;
+
+        let v = &mut ctx.accounts.new_vault;
+        v.msol_amount = msol_amount;
+        v.sol_staked_lamports = staked_moved;
+        v.book_usdc_in(usdc_amount)?;
+
+        registry_add(
+            &mut ctx.accounts.owner_registry,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            new_pot_hash,
+        )?;
+
+        emit!(PotSplit {
+            owner: owner_key,
+            source_vault: ctx.accounts.source_vault.key(),
+            seq: ctx.accounts.source_vault.seq,
+            new_vault: ctx.accounts.new_vault.key(),
+            lamports,
+            usdc: usdc_amount,
+            msol: msol_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the owner's "pay yourself first" rule: how `deposit_with_split` divides an
+    /// incoming USDC deposit between their pots. Shares must add up to 100%.
+    #[cfg(feature = "full")]
+    pub fn set_split_rule(ctx: Context<SetSplitRule>, entries: Vec<SplitEntry>) -> Result<()> {
+        require!(
+            !entries.is_empty() && entries.len() <= SplitRule::MAX_POTS,
+            EscrowError::InvalidSplit
+        );
+        let mut total: u32 = 0;
+        for (i, e) in entries.iter().enumerate() {
+            require!(e.bps > 0, EscrowError::InvalidSplit);
+            require!(
+                entries[..i].iter().all(|other| other.pot_hash != e.pot_hash),
+                EscrowError::InvalidSplit
+            );
+            total += e.bps as u32;
+        }
+        require!(total == 10_000, EscrowError::InvalidSplit);
+
+        let rule = &mut ctx.accounts.split_rule;
+        rule.owner = ctx.accounts.owner.key();
+        rule.bump = ctx.bumps.split_rule;
+        rule.entries = entries;
+        Ok(())
+    }
+
+    /// Deposits `amount` USDC across the owner's pots by their `SplitRule`.
+    /// `remaining_accounts` are `[vault, vault_usdc]` pairs in rule order; rounding
+    /// dust goes to the last pot.
+    #[cfg(feature = "full")]
+    pub fn deposit_with_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositWithSplit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+        let entries = ctx.accounts.split_rule.entries.clone();
+        require!(ctx.remaining_accounts.len() == entries.len() * 2, EscrowError::InvalidSplit);
+        let owner_key = ctx.accounts.owner.key();
+        check_attestation(Some(&ctx.accounts.config), ctx.accounts.attestation.as_ref(), owner_key, amount)?;
+
+        let mut remaining = amount;
+        for (i, (entry, pair)) in entries.iter().zip(ctx.remaining_accounts.chunks(2)).enumerate() {
+            let share = if i + 1 == entries.len() {
+                remaining
+            } else {
+                ((amount as u128) * (entry.bps as u128) / 10_000) as u64
+            };
+            remaining -= share;
+            if share == 0 {
+                continue;
+            }
+
+            let (vault_info, usdc_info) = (&pair[0], &pair[1]);
+            require!(vault_info.is_writable && usdc_info.is_writable, EscrowError::BadVaultAccount);
+            let mut vault: Account<'info, Vault> = Account::try_from(vault_info)?;
+            require_keys_eq!(vault.owner, owner_key, EscrowError::Unauthorized);
+            require_keys_eq!(vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+            require!(vault.pot_hash == entry.pot_hash, EscrowError::BadPot);
+            require_keys_eq!(*usdc_info.key, vault.usdc_vault, EscrowError::BadVaultAccount);
+            require_keys_eq!(vault.usdc_mint, ctx.accounts.user_usdc.mint, EscrowError::BadMint);
+            check_deposit_limits(
+                Some(&ctx.accounts.config),
+                &vault,
+                vault_info,
+                ctx.accounts.sol_price.as_ref(),
+                0,
+                share,
+            )?;
+
+            let cpi = Transfer {
+                from: ctx.accounts.user_usdc.to_account_info(),
+                to: usdc_info.clone(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi), share)?;
+            let mint = vault.usdc_mint;
+            record_deposit(&mut vault, None, owner_key, mint, share)?;
+            vault.next_seq();
+            vault.exit(&crate::ID)?;
+        }
+
+        emit!(SplitDeposited {
+            owner: owner_key,
+            amount,
+            pot_count: entries.len() as u8,
+        });
+        Ok(())
+    }
+
+    /// Offboarding: empties every pot passed in `remaining_accounts` as
+    /// `[vault, vault_usdc]` pairs, sending lamports above rent and all USDC to the
+    /// owner.
+    #[cfg(feature = "full")]
+    pub fn sweep_all<'info>(ctx: Context<'_, '_, 'info, 'info, SweepAll<'info>>) -> Result<()> {
+        let accounts = ctx.remaining_accounts;
+        require!(
+            !accounts.is_empty() && accounts.len() % 2 == 0 && accounts.len() / 2 <= MAX_BATCH_POTS,
+            EscrowError::InvalidBatch
+        );
+
+        let owner_key = ctx.accounts.owner.key();
+        let rent = Rent::get()?;
+        let now = Clock::get()?.unix_timestamp;
+        let mut total_lamports: u64 = 0;
+        let mut total_usdc: u64 = 0;
+        for pair in accounts.chunks(2) {
+            let (vault_info, usdc_info) = (&pair[0], &pair[1]);
+            require!(vault_info.is_writable && usdc_info.is_writable, EscrowError::BadVaultAccount);
+            let mut vault = owner_vault_state(vault_info, &owner_key)?;
+            require_keys_eq!(vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+            require!(!vault.is_committed(now), EscrowError::CommitmentActive);
+            require!(!vault.is_frozen(now), EscrowError::VaultFrozen);
+            require_keys_eq!(*usdc_info.key, vault.usdc_vault, EscrowError::BadVaultAccount);
+            require_keys_eq!(vault.usdc_mint, ctx.accounts.user_usdc.mint, EscrowError::BadMint);
+
+            let min = rent.minimum_balance(vault_info.data_len());
+            let lamports = vault_info.lamports().saturating_sub(min);
+            if lamports > 0 {
+                transfer_lamports(vault_info, &ctx.accounts.owner.to_account_info(), lamports)?;
+                total_lamports = total_lamports.checked_add(lamports).ok_or(EscrowError::MathOverflow)?;
+            }
+
+            let usdc = {
+                require_keys_eq!(*usdc_info.owner, token::ID, EscrowError::BadVaultAccount);
+                let data = usdc_info.try_borrow_data()?;
+                vault.unlocked_usdc(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+            };
+            if usdc > 0 {
+                let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), vault.pot_hash.as_ref(), &[vault.bump]];
+                let cpi = Transfer {
+                    from: usdc_info.clone(),
+                    to: ctx.accounts.user_usdc.to_account_info(),
+                    authority: vault_info.clone(),
+                };
+                token::transfer(
+                    CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                    usdc,
+                )?;
+                total_usdc = total_usdc.checked_add(usdc).ok_or(EscrowError::MathOverflow)?;
+                vault.book_usdc_out(usdc)?;
+            }
+            bump_vault_seq(vault_info, vault)?;
+        }
+        // Gated on the totals; a failure reverts the whole sweep.
+        let config = ctx.accounts.config.as_ref();
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, total_lamports)?;
+        check_attestation(config, ctx.accounts.attestation.as_ref(), owner_key, total_usdc)?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.user_usdc.mint,
+            total_usdc,
+        )?;
+        check_outflow(
+            config,
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            total_lamports,
+        )?;
+
+        emit!(AllPotsSwept {
+            owner: owner_key,
+            vault_count: (accounts.len() / 2) as u8,
+            lamports: total_lamports,
+            usdc: total_usdc,
+        });
+
+        Ok(())
+    }
+
+    /// Closes an empty pot: the vault's USDC ATA, any other empty vault-owned token
+    /// accounts passed as `remaining_accounts`, and then the vault itself are closed
+    /// to the owner, and the pot is dropped from the owner registry. Fails unless the
+    /// owner ends up with exactly the rent those accounts held; one non-empty token
+    /// account fails the whole close.
+    pub fn close_pot_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClosePotVault<'info>>,
+        pot_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let v = &ctx.accounts.vault;
+        require!(!v.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(
+            v.msol_amount == 0 && ctx.accounts.vault_usdc.amount == 0,
+            EscrowError::VaultNotEmpty
+        );
+        let bump = v.bump;
+
+        // Registry shrink refunds the owner too; done first so it isn't counted below.
+        registry_remove(
+            &mut ctx.accounts.owner_registry,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            pot_hash,
+        )?;
+
+        let owner = ctx.accounts.owner.to_account_info();
+        let vault = ctx.accounts.vault.to_account_info();
+        let owner_before = owner.lamports();
+        let owner_key = owner.key();
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let mut expected = close_empty_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_usdc.to_account_info(),
+            &owner,
+            &vault,
+            &[seeds],
+        )?;
+        for ata in ctx.remaining_accounts.iter() {
+            let lamports = close_empty_token_account(&ctx.accounts.token_program, ata, &owner, &vault, &[seeds])?;
+            expected = expected.checked_add(lamports).ok_or(EscrowError::MathOverflow)?;
+        }
+
+        expected = expected.checked_add(vault.lamports()).ok_or(EscrowError::MathOverflow)?;
+        ctx.accounts.vault.close(owner.clone())?;
+        let owner_after = owner_before.checked_add(expected).ok_or(EscrowError::MathOverflow)?;
+        require!(owner.lamports() == owner_after, EscrowError::RentRefundMismatch);
+
+        emit!(PotVaultClosed {
+            vault: vault.key(),
+            seq: ctx.accounts.vault.seq,
+            owner: owner_key,
+            token_accounts_closed: 1 + ctx.remaining_accounts.len() as u16,
+            lamports_returned: expected,
+        });
+
+        Ok(())
+    }
+
+    /// Clears sub-threshold balances out of vault-held token accounts (swap leftovers)
+    /// and closes them to the owner. `remaining_accounts` are pairs of (vault token
+    /// account, target): the fee treasury's token account for the same mint receives
+    /// the dust, or pass the mint itself to burn worthless dust. Accounts of registered
+    /// mints (USDC included) are never swept.
+    #[cfg(feature = "full")]
+    pub fn sweep_dust<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepDust<'info>>,
+        pot_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        let pairs = ctx.remaining_accounts;
+        require!(!pairs.is_empty() && pairs.len() % 2 == 0, EscrowError::InvalidBatch);
+        let threshold = ctx.accounts.config.dust_threshold;
+        let fee_treasury = ctx.accounts.config.fee_treasury;
+        let owner = ctx.accounts.owner.to_account_info();
+        let vault = ctx.accounts.vault.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let owner_key = owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        for pair in pairs.chunks(2) {
+            let (account, target) = (&pair[0], &pair[1]);
+            require_keys_eq!(*account.owner, token::ID, EscrowError::BadVaultAccount);
+            let (mint, amount) = {
+                let data = account.try_borrow_data()?;
+                require!(data.len() == TokenAccount::LEN, EscrowError::BadVaultAccount);
+                require!(data[32..64] == vault.key.to_bytes(), EscrowError::BadVaultAccount);
+                (
+                    Pubkey::new_from_array(data[0..32].try_into().unwrap()),
+                    u64::from_le_bytes(data[64..72].try_into().unwrap()),
+                )
+            };
+            require!(!ctx.accounts.vault.is_registered_mint(&mint), EscrowError::BadMint);
+            require!(amount <= threshold, EscrowError::NotDust);
+
+            let burned = *target.key == mint;
+            if amount > 0 && burned {
+                let cpi = Burn {
+                    mint: target.clone(),
+                    from: account.clone(),
+                    authority: vault.clone(),
+                };
+                token::burn(CpiContext::new_with_signer(token_program.clone(), cpi, &[seeds]), amount)?;
+            } else if amount > 0 {
+                require_keys_neq!(fee_treasury, Pubkey::default(), EscrowError::BadFeeTreasury);
+                require_keys_eq!(*target.owner, token::ID, EscrowError::BadFeeTreasury);
+                {
+                    let data = target.try_borrow_data()?;
+                    require!(data.len() == TokenAccount::LEN, EscrowError::BadFeeTreasury);
+                    require!(data[0..32] == mint.to_bytes(), EscrowError::BadMint);
+                    require!(data[32..64] == fee_treasury.to_bytes(), EscrowError::BadFeeTreasury);
+                }
+                let cpi = Transfer {
+                    from: account.clone(),
+                    to: target.clone(),
+                    authority: vault.clone(),
+                };
+                token::transfer(CpiContext::new_with_signer(token_program.clone(), cpi, &[seeds]), amount)?;
+            }
+
+            close_empty_token_account(&ctx.accounts.token_program, account, &owner, &vault, &[seeds])?;
+
+            emit!(DustSwept {
+                vault: vault.key(),
+                seq: ctx.accounts.vault.seq,
+                token_account: account.key(),
+                mint,
+                amount,
+                burned,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Backfills the owner registry for vaults created before it existed.
+    #[cfg(feature = "full")]
+    pub fn register_pot(ctx: Context<RegisterPot>, pot_hash: [u8; 32]) -> Result<()> {
+        registry_init_and_add(
+            &mut ctx.accounts.owner_registry,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            pot_hash,
+            ctx.bumps.owner_registry,
+        )
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_pot_metadata(
+        ctx: Context<SetPotMetadata>,
+        pot_hash: [u8; 32],
+        name: String,
+        category: PotCategory,
+        target_date: i64,
+        icon_index: u8,
+    ) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= PotMetadata::MAX_NAME_LEN,
+            EscrowError::InvalidMetadata
+        );
+        require!(target_date >= 0, EscrowError::InvalidMetadata);
+
+        let m = &mut ctx.accounts.pot_metadata;
+        m.vault = ctx.accounts.vault.key();
+        m.name = name;
+        m.category = category;
+        m.target_date = target_date;
+        m.icon_index = icon_index;
+        m.bump = ctx.bumps.pot_metadata;
+
+        Ok(())
+    }
+
+    /// Publishes the SHA-256 preimage of `pot_hash` (e.g. the canonical pot JSON) so
+    /// third parties can audit what the pot represents.
+    #[cfg(feature = "full")]
+    pub fn reveal_pot_commitment(ctx: Context<RevealPotCommitment>, pot_hash: [u8; 32], preimage: Vec<u8>) -> Result<()> {
+        require!(
+            !preimage.is_empty() && preimage.len() <= PotCommitment::MAX_PREIMAGE_LEN,
+            EscrowError::InvalidAmount
+        );
+        require!(hash(&preimage).to_bytes() == pot_hash, EscrowError::HashlockMismatch);
+
+        let c = &mut ctx.accounts.pot_commitment;
+        c.vault = ctx.accounts.vault.key();
+        c.bump = ctx.bumps.pot_commitment;
+        c.preimage = preimage;
+
+        Ok(())
+    }
+
+    /// Like `withdraw`, but pays `recipient` (a merchant, a friend) instead of the owner.
+    #[cfg(feature = "full")]
+    pub fn withdraw_to(
+        ctx: Context<WithdrawTo>,
+        pot_hash: [u8; 32],
+        lamports: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            lamports,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), lamports, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            Pubkey::default(),
+            lamports,
+        )?;
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        check_not_blocked(ctx.accounts.blocklist.as_ref(), &[ctx.accounts.recipient.key()])?;
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let min = Rent::get()?.minimum_balance(vault_info.data_len());
+        require!(vault_info.lamports().saturating_sub(min) >= lamports, EscrowError::InsufficientFunds);
+
+        let penalty = slash_lamports(
+            &ctx.accounts.vault,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_destination.as_ref(),
+            lamports,
+        )?;
+        transfer_lamports(&vault_info, &ctx.accounts.recipient.to_account_info(), lamports.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?)?;
+
+        emit!(WithdrawnTo {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient: ctx.accounts.recipient.key(),
+            mint: Pubkey::default(),
+            amount: lamports,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW_TO,
+            ctx.accounts.recipient.key(),
+            Pubkey::default(),
+            lamports,
+        )?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn withdraw_usdc_to(
+        ctx: Context<WithdrawUsdcTo>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_memo(ctx.accounts.config.as_ref(), amount, &memo_hash)?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+        check_not_blocked(
+            ctx.accounts.blocklist.as_ref(),
+            &[ctx.accounts.recipient_usdc.key(), ctx.accounts.recipient_usdc.owner],
+        )?;
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.recipient_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+
+        burn_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(WithdrawnTo {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient: ctx.accounts.recipient_usdc.owner,
+            mint: ctx.accounts.usdc_mint.key(),
+            amount,
+            memo_hash,
+        });
+
+        append_activity(
+            ctx.accounts.activity_log.as_ref(),
+            ACTIVITY_WITHDRAW_TO,
+            ctx.accounts.recipient_usdc.owner,
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Readies the vault's wSOL ATA for a swap leg of `lamports`: wraps only the part
+    /// its existing wSOL balance doesn't cover, so native SOL stays native until a
+    /// swap actually needs it.
+    #[cfg(feature = "full")]
+    pub fn wrap_sol(ctx: Context<WrapSol>, pot_hash: [u8; 32], lamports: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+
+        let lamports = wsol_shortfall(lamports, ctx.accounts.vault_wsol.amount);
+        if lamports == 0 {
+            return Ok(());
+        }
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let min = Rent::get()?.minimum_balance(vault_info.data_len());
+        require!(withdrawable_lamports(vault_info.lamports(), min) >= lamports, EscrowError::InsufficientFunds);
+
+        transfer_lamports(&vault_info, &ctx.accounts.vault_wsol.to_account_info(), lamports)?;
+        token::sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.vault_wsol.to_account_info(),
+            },
+        ))?;
+
+        Ok(())
+    }
+
+    /// Closes the vault's wSOL ATA, returning its whole balance (and rent) to the
+    /// vault as native SOL.
+    #[cfg(feature = "full")]
+    pub fn unwrap_sol(ctx: Context<UnwrapSol>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require_keys_eq!(ctx.accounts.vault.owner, ctx.accounts.owner.key(), EscrowError::Unauthorized);
+        require!(ctx.accounts.vault.pot_hash == pot_hash, EscrowError::BadPot);
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let cpi = CloseAccount {
+            account: ctx.accounts.vault_wsol.to_account_info(),
+            destination: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi,
+            &[seeds],
+        ))?;
+
+        Ok(())
+    }
+
+    /// Gives the pot an SPL share mint (vault PDA as mint authority) so wallets can
+    /// show the position and other protocols can take it as collateral. The owner
+    /// receives shares 1:1 for the pot's current USDC assets; from then on USDC
+    /// deposits mint and owner-signed withdrawals burn at the share price, which rises
+    /// with realized yield. Flows that can't mint or burn the owner's shares (split
+    /// deposits, donations, merges, splits, sweeps, USDC swap offers and outflows the
+    /// owner doesn't sign) fail with `SharesUnsupported`.
+    #[cfg(feature = "full")]
+    pub fn enable_share_mint(ctx: Context<EnableShareMint>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::ShareMintExists);
+        let shares = ctx.accounts.vault.share_assets_usdc();
+        ctx.accounts.vault.share_mint = ctx.accounts.share_mint.key();
+
+        if shares > 0 {
+            let owner_key = ctx.accounts.owner.key();
+            let bump = ctx.accounts.vault.bump;
+            let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+            let cpi = MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.owner_shares.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            token::mint_to(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                shares,
+            )?;
+        }
+
+        emit!(ShareMintEnabled {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            share_mint: ctx.accounts.share_mint.key(),
+            initial_shares: shares,
+        });
+        Ok(())
+    }
+
+    /// Starts tracking `mint` on the vault and creates the vault's ATA for it.
+    #[cfg(feature = "full")]
+    pub fn register_mint(ctx: Context<RegisterMint>, _pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let mint = ctx.accounts.mint.key();
+        let v = &mut ctx.accounts.vault;
+        require!(!v.is_registered_mint(&mint), EscrowError::MintAlreadyRegistered);
+        require!(v.registered_mints.len() < Vault::MAX_REGISTERED_MINTS, EscrowError::MintLimitReached);
+        v.registered_mints.push(mint);
+
+        emit!(MintRegistered {
+            vault: v.key(),
+            seq: v.seq,
+            mint,
+            token_account: ctx.accounts.vault_token.key(),
+        });
+        Ok(())
+    }
+
+    /// Stops tracking `mint` and closes the vault's (empty) ATA for it to the owner.
+    /// `usdc_mint` can't be deregistered.
+    #[cfg(feature = "full")]
+    pub fn deregister_mint(ctx: Context<DeregisterMint>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let mint = ctx.accounts.mint.key();
+        let v = &mut ctx.accounts.vault;
+        let index = v
+            .registered_mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(EscrowError::MintNotRegistered)?;
+        require!(ctx.accounts.vault_token.amount == 0, EscrowError::VaultNotEmpty);
+        v.registered_mints.swap_remove(index);
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        close_empty_token_account(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &[seeds],
+        )?;
+
+        emit!(MintDeregistered {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint,
+        });
+        Ok(())
+    }
+
+    /// Configures cNFT receipts. The `receipt_authority` PDA must be set as tree
+    /// delegate and collection authority; a default `tree` disables receipts.
+    #[cfg(feature = "full")]
+    pub fn set_receipt_config(
+        ctx: Context<UpdateConfig>,
+        tree: Pubkey,
+        collection: Pubkey,
+        uri: String,
+    ) -> Result<()> {
+        require!(uri.len() <= Config::MAX_RECEIPT_URI_LEN, EscrowError::InvalidMetadata);
+
+        let config = &mut ctx.accounts.config;
+        config.receipt_tree = tree;
+        config.receipt_collection = collection;
+        config.receipt_uri = uri;
+
+        Ok(())
+    }
+
+    /// Mints a compressed proof-of-savings NFT to the payer once a milestone has
+    /// been released. One receipt per milestone.
+    #[cfg(feature = "full")]
+    pub fn mint_milestone_receipt(ctx: Context<MintMilestoneReceipt>, index: u8) -> Result<()> {
+        let m = ctx
+            .accounts
+            .escrow
+            .milestones
+            .get(index as usize)
+            .ok_or(EscrowError::InvalidMilestones)?;
+        require!(m.status == MilestoneStatus::Released, EscrowError::InvalidMilestoneStatus);
+
+        require!(
+            ctx.accounts.config.receipt_tree != Pubkey::default(),
+            EscrowError::ReceiptsDisabled
+        );
+
+        let source = ctx.accounts.escrow.key();
+        let leaf_owner = ctx.accounts.payer.key();
+        let r = &mut ctx.accounts.cnft_receipt;
+        r.source = source;
+        r.index = index;
+        r.leaf_owner = leaf_owner;
+        r.minted_at = Clock::get()?.unix_timestamp;
+
+        let bump = ctx.bumps.receipt_authority;
+        let seeds: &[&[u8]] = &[seeds::RECEIPT_AUTHORITY, &[bump]];
+        bubblegum_mint_cpi(
+            &ctx.accounts.bubblegum_cpi(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.config.receipt_uri,
+            &[seeds],
+        )?;
+
+        emit!(ReceiptMinted {
+            source,
+            index,
+            leaf_owner,
+            tree: ctx.accounts.config.receipt_tree,
+        });
+
+        Ok(())
+    }
+
+    /// Creates or updates a budget envelope: at most `budget` USDC may leave the vault
+    /// through `spend_from_envelope` per `period_secs`.
+    #[cfg(feature = "full")]
+    pub fn set_envelope(
+        ctx: Context<SetEnvelope>,
+        pot_hash: [u8; 32],
+        envelope_id: u8,
+        budget: u64,
+        period_secs: i64,
+    ) -> Result<()> {
+        require!(period_secs > 0, EscrowError::InvalidTimelock);
+
+        let now = Clock::get()?.unix_timestamp;
+        let e = &mut ctx.accounts.envelope;
+        if e.vault == Pubkey::default() {
+            e.vault = ctx.accounts.vault.key();
+            e.envelope_id = envelope_id;
+            e.bump = ctx.bumps.envelope;
+            e.period_start = now;
+        }
+        e.budget = budget;
+        e.period_secs = period_secs;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn spend_from_envelope(
+        ctx: Context<SpendFromEnvelope>,
+        pot_hash: [u8; 32],
+        envelope_id: u8,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.vault.usdc_mint,
+            amount,
+        )?;
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let e = &mut ctx.accounts.envelope;
+        e.roll_period(now);
+        let spent = e.spent.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        require!(spent <= e.budget, EscrowError::BudgetExceeded);
+        e.spent = spent;
+        let remaining = e.budget - spent;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.recipient_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+        burn_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(EnvelopeSpent {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            envelope_id,
+            recipient: ctx.accounts.recipient_usdc.owner,
+            amount,
+            remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Authorizes `merchant` to pull up to `amount` USDC from the vault every
+    /// `interval_secs`, starting at `first_charge_ts`, which may not be in the past.
+    #[cfg(feature = "full")]
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        pot_hash: [u8; 32],
+        merchant: Pubkey,
+        amount: u64,
+        interval_secs: i64,
+        grace_secs: i64,
+        first_charge_ts: i64,
+    ) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(interval_secs > 0, EscrowError::InvalidTimelock);
+        require!(grace_secs >= 0 && grace_secs < interval_secs, EscrowError::InvalidTimelock);
+        require!(first_charge_ts >= Clock::get()?.unix_timestamp, EscrowError::InvalidTimelock);
+
+        let s = &mut ctx.accounts.subscription;
+        s.vault = ctx.accounts.vault.key();
+        s.merchant = merchant;
+        s.merchant_usdc = ctx.accounts.merchant_usdc.key();
+        s.amount = amount;
+        s.interval_secs = interval_secs;
+        s.grace_secs = grace_secs;
+        s.next_charge_ts = first_charge_ts;
+        s.paused = false;
+        s.missed_charges = 0;
+        s.bump = ctx.bumps.subscription;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_subscription_paused(
+        ctx: Context<UpdateSubscription>,
+        pot_hash: [u8; 32],
+        merchant: Pubkey,
+        paused: bool,
+    ) -> Result<()> {
+        ctx.accounts.subscription.paused = paused;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>, pot_hash: [u8; 32], merchant: Pubkey) -> Result<()> {
+        emit!(SubscriptionCancelled {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            merchant,
+        });
+        Ok(())
+    }
+
+    /// Pulls up to the authorized amount once per interval. Cycles that are not
+    /// charged within `grace_secs` of their due date are forfeited. If the vault is
+    /// short inside the grace window the attempt is recorded and the merchant may retry.
+    /// Under a no-withdraw commitment the early-withdraw penalty comes out of the charge.
+    #[cfg(feature = "full")]
+    pub fn charge_subscription(ctx: Context<ChargeSubscription>, amount: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        let s = &mut ctx.accounts.subscription;
+        require!(!s.paused, EscrowError::SubscriptionPaused);
+        require!(amount > 0 && amount <= s.amount, EscrowError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let overdue = now.saturating_sub(s.next_charge_ts.saturating_add(s.grace_secs));
+        if overdue > 0 {
+            // Every cycle whose grace window has closed is forfeited in one step.
+            let missed = (overdue - 1) / s.interval_secs + 1;
+            s.next_charge_ts = missed
+                .checked_mul(s.interval_secs)
+                .and_then(|skip| s.next_charge_ts.checked_add(skip))
+                .ok_or(EscrowError::MathOverflow)?;
+            s.missed_charges = s.missed_charges.saturating_add(u32::try_from(missed).unwrap_or(u32::MAX));
+        }
+        require!(now >= s.next_charge_ts, EscrowError::ChargeNotDue);
+
+        let vault = ctx.accounts.vault.key();
+        if ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) < amount {
+            emit!(SubscriptionChargeFailed {
+                vault,
+                seq: ctx.accounts.vault.seq,
+                merchant: s.merchant,
+                amount,
+                grace_ends: s.next_charge_ts.saturating_add(s.grace_secs),
+            });
+            return Ok(());
+        }
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.vault.owner,
+            amount,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.vault.usdc_mint,
+            amount,
+        )?;
+        s.next_charge_ts = s.next_charge_ts.checked_add(s.interval_secs).ok_or(EscrowError::MathOverflow)?;
+        let next_charge_ts = s.next_charge_ts;
+
+        let owner_key = ctx.accounts.vault.owner;
+        let pot_hash = ctx.accounts.vault.pot_hash;
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.merchant_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(SubscriptionCharged {
+            vault,
+            seq: ctx.accounts.vault.seq,
+            merchant: ctx.accounts.merchant.key(),
+            amount,
+            next_charge_ts,
+        });
+
+        Ok(())
+    }
+
+    /// Streams USDC from the vault to `recipient` at `rate_per_second` until `end_ts`.
+    /// The full amount is reserved in `vault.stream_locked_usdc` up front.
+    /// `stream_id` lets one vault run several streams to the same recipient.
+    #[cfg(feature = "full")]
+    pub fn create_stream(
+        ctx: Context<CreateStream>,
+        pot_hash: [u8; 32],
+        recipient: Pubkey,
+        stream_id: u64,
+        rate_per_second: u64,
+        end_ts: i64,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        require!(rate_per_second > 0, EscrowError::InvalidAmount);
+        require!(end_ts > now, EscrowError::InvalidTimelock);
+
+        let total = rate_per_second
+            .checked_mul((end_ts - now) as u64)
+            .ok_or(EscrowError::MathOverflow)?;
+        let vault = &mut ctx.accounts.vault;
+        require!(!vault.is_committed(now), EscrowError::CommitmentActive);
+        require!(
+            vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= total,
+            EscrowError::InsufficientFunds
+        );
+        vault.stream_locked_usdc = vault.stream_locked_usdc.checked_add(total).ok_or(EscrowError::MathOverflow)?;
+
+        let s = &mut ctx.accounts.stream;
+        s.vault = vault.key();
+        s.recipient = recipient;
+        s.rate_per_second = rate_per_second;
+        s.start_ts = now;
+        s.end_ts = end_ts;
+        s.total = total;
+        s.withdrawn = 0;
+        s.bump = ctx.bumps.stream;
+        s.stream_id = stream_id;
+
+        emit!(StreamCreated {
+            vault: s.vault,
+            seq: ctx.accounts.vault.seq,
+            recipient,
+            rate_per_second,
+            start_ts: now,
+            end_ts,
+            stream_id,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the recipient everything vested and not yet withdrawn.
+    #[cfg(feature = "full")]
+    pub fn withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        let now = Clock::get()?.unix_timestamp;
+        let s = &mut ctx.accounts.stream;
+        let amount = s.vested(now) - s.withdrawn;
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(ctx.accounts.vault_usdc.amount >= amount, EscrowError::InsufficientFunds);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.vault.owner,
+            amount,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.vault.usdc_mint,
+            amount,
+        )?;
+        s.withdrawn = s.withdrawn.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        let withdrawn = s.withdrawn;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.stream_locked_usdc = vault.stream_locked_usdc.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+        vault.book_usdc_out(amount)?;
+
+        let owner_key = vault.owner;
+        let pot_hash = vault.pot_hash;
+        let bump = vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.recipient_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount,
+        )?;
+
+        emit!(StreamWithdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            withdrawn,
+        });
+
+        Ok(())
+    }
+
+    /// Owner stops a stream early: the recipient is paid whatever has vested but
+    /// not been withdrawn, the rest of the reservation is released back to the
+    /// vault, and the stream's rent returns to the owner.
+    #[cfg(feature = "full")]
+    pub fn cancel_stream(ctx: Context<CancelStream>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        let s = &ctx.accounts.stream;
+        let paid = s.vested(now) - s.withdrawn;
+        let released = s.total - s.vested(now);
+        let recipient = s.recipient;
+        require!(ctx.accounts.vault_usdc.amount >= paid, EscrowError::InsufficientFunds);
+
+        if paid > 0 {
+            burn_pot_shares(
+                &ctx.accounts.vault,
+                ctx.accounts.share_mint.as_deref(),
+                ctx.accounts.owner_shares.as_deref(),
+                &ctx.accounts.owner,
+                &ctx.accounts.token_program,
+                paid,
+            )?;
+        }
+        let vault = &mut ctx.accounts.vault;
+        vault.stream_locked_usdc = vault
+            .stream_locked_usdc
+            .checked_sub(paid + released)
+            .ok_or(EscrowError::MathUnderflow)?;
+
+        if paid > 0 {
+            vault.book_usdc_out(paid)?;
+            let owner_key = ctx.accounts.owner.key();
+            let bump = vault.bump;
+            let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+            let cpi = Transfer {
+                from: ctx.accounts.vault_usdc.to_account_info(),
+                to: ctx.accounts.recipient_usdc.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                paid,
+            )?;
+        }
+
+        emit!(StreamCancelled {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient,
+            paid,
+            released,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the rent of a stream the recipient has fully withdrawn to the owner.
+    #[cfg(feature = "full")]
+    pub fn close_stream(ctx: Context<CloseStream>, pot_hash: [u8; 32]) -> Result<()> {
+        let s = &ctx.accounts.stream;
+        require!(s.withdrawn == s.total, EscrowError::StreamNotFinished);
+        emit!(StreamClosed {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient: s.recipient,
+        });
+        Ok(())
+    }
+
+    /// Controlled decumulation for a target-date pot: from `PotMetadata::target_date`
+    /// on, `crank_payout` pays `installment_usdc` to `recipient` every
+    /// `interval_secs` until the pot's USDC runs out.
+    #[cfg(feature = "full")]
+    pub fn create_payout_plan(
+        ctx: Context<CreatePayoutPlan>,
+        pot_hash: [u8; 32],
+        recipient: Pubkey,
+        installment_usdc: u64,
+        interval_secs: i64,
+    ) -> Result<()> {
+        require!(installment_usdc > 0, EscrowError::InvalidAmount);
+        require!(interval_secs > 0, EscrowError::InvalidTimelock);
+        let start = ctx.accounts.pot_metadata.target_date;
+        require!(start > 0, EscrowError::NoTargetDate);
+
+        let plan = &mut ctx.accounts.payout_plan;
+        plan.vault = ctx.accounts.vault.key();
+        plan.recipient = recipient;
+        plan.installment_usdc = installment_usdc;
+        plan.interval_secs = interval_secs;
+        plan.next_payout_ts = start;
+        plan.bump = ctx.bumps.payout_plan;
+
+        emit!(PayoutPlanCreated {
+            vault: plan.vault,
+            seq: ctx.accounts.vault.seq,
+            recipient,
+            installment_usdc,
+            interval_secs,
+            start_ts: start,
+        });
+        Ok(())
+    }
+
+    /// Permissionless: pays one due installment (or whatever unlocked USDC is left).
+    /// Missed intervals can be caught up one crank at a time. Closes the plan to the
+    /// owner once the pot is drained. Under a no-withdraw commitment the recipient
+    /// gets the installment less the early-withdraw penalty.
+    #[cfg(feature = "full")]
+    pub fn crank_payout(ctx: Context<CrankPayout>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        let plan = &mut ctx.accounts.payout_plan;
+        require!(now >= plan.next_payout_ts, EscrowError::PayoutNotDue);
+
+        let available = ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount);
+        let amount = plan.installment_usdc.min(available);
+        require!(amount > 0, EscrowError::InsufficientFunds);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.vault.owner,
+            amount,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.vault.usdc_mint,
+            amount,
+        )?;
+        plan.next_payout_ts = plan.next_payout_ts.checked_add(plan.interval_secs).ok_or(EscrowError::MathOverflow)?;
+        let next_payout_ts = plan.next_payout_ts;
+
+        let v = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, v.owner.as_ref(), v.pot_hash.as_ref(), &[v.bump]];
+        let penalty = slash_tokens(
+            v,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.recipient_usdc.to_account_info(),
+            authority: v.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        let drained = amount == available;
+        emit!(PayoutPaid {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            recipient: ctx.accounts.payout_plan.recipient,
+            amount,
+            next_payout_ts,
+            drained,
+        });
+        if drained {
+            ctx.accounts.payout_plan.close(ctx.accounts.owner.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn cancel_payout_plan(_ctx: Context<CancelPayoutPlan>, _pot_hash: [u8; 32]) -> Result<()> {
+        Ok(())
+    }
+
+    /// "Save the change": books a batch of small round-up amounts with a single
+    /// transfer of their sum. No receipt and no per-amount minimum.
+    #[cfg(feature = "full")]
+    pub fn deposit_roundup(ctx: Context<DepositRoundup>, pot_hash: [u8; 32], amounts: Vec<u64>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!amounts.is_empty() && amounts.len() <= MAX_ROUNDUP_BATCH, EscrowError::InvalidBatch);
+        let mut total: u64 = 0;
+        for amount in amounts.iter() {
+            require!(*amount > 0, EscrowError::InvalidAmount);
+            total = total.checked_add(*amount).ok_or(EscrowError::MathOverflow)?;
+        }
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            total,
+        )?;
+        check_deposit_limits(
+            ctx.accounts.config.as_ref(),
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            0,
+            total,
+        )?;
+
+        let cpi = Transfer {
+            from: ctx.accounts.user_usdc.to_account_info(),
+            to: ctx.accounts.vault_usdc.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi), total)?;
+        mint_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.token_program,
+            total,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.roundup_total = vault.roundup_total.checked_add(total).ok_or(EscrowError::MathOverflow)?;
+        let roundup_total = vault.roundup_total;
+        let mint = vault.usdc_mint;
+        record_deposit(vault, None, ctx.accounts.owner.key(), mint, total)?;
+
+        emit!(RoundupDeposited {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            count: amounts.len() as u16,
+            amount: total,
+            roundup_total,
+        });
+
+        Ok(())
+    }
+
+    /// Commits the vault to no withdrawals for `duration_secs`. Until then every
+    /// withdrawal forfeits `penalty_bps` of its amount to the pod's shared vault (or
+    /// the fee treasury for solo pots); `merge_pots` / `split_pot` carry the
+    /// commitment to the receiving pot instead. An active commitment can only be
+    /// extended or made stricter.
+    #[cfg(feature = "full")]
+    pub fn commit_no_withdraw(
+        ctx: Context<CommitNoWithdraw>,
+        pot_hash: [u8; 32],
+        duration_secs: i64,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(duration_secs > 0, EscrowError::InvalidTimelock);
+        require!(penalty_bps <= 10_000, EscrowError::InvalidFee);
+
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+        let until = now.checked_add(duration_secs).ok_or(EscrowError::InvalidTimelock)?;
+        if vault.is_committed(now) {
+            require!(until >= vault.no_withdraw_until, EscrowError::CommitmentActive);
+            require!(penalty_bps >= vault.early_withdraw_penalty_bps, EscrowError::CommitmentActive);
+        }
+        vault.no_withdraw_until = until;
+        vault.early_withdraw_penalty_bps = penalty_bps;
+
+        emit!(NoWithdrawCommitted {
+            vault: vault.key(),
+            seq: vault.seq,
+            until,
+            penalty_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Creates the on-chain pod account; the creator becomes its admin and first member.
+    #[cfg(feature = "full")]
+    pub fn create_pod(ctx: Context<CreatePod>, pod_hash: [u8; 32], max_members: u16) -> Result<()> {
+        require!(max_members >= 2 && max_members <= Pod::MAX_MEMBERS, EscrowError::InvalidPod);
+
+        let now = Clock::get()?.unix_timestamp;
+        let pod = &mut ctx.accounts.pod;
+        pod.pod_hash = pod_hash;
+        pod.admin = ctx.accounts.admin.key();
+        pod.max_members = max_members;
+        pod.member_count = 1;
+        pod.bump = ctx.bumps.pod;
+        pod.usdc_mint = ctx.accounts.usdc_mint.key();
+        pod.pod_usdc = ctx.accounts.pod_usdc.key();
+        pod.pod_vault_bump = ctx.bumps.pod_vault;
+        pod.weight_updated_at = now;
+
+        let c = &mut ctx.accounts.contribution;
+        c.pod = pod.key();
+        c.member = ctx.accounts.admin.key();
+        c.joined_at = now;
+        c.bump = ctx.bumps.contribution;
+        c.role = PodRole::Admin;
+        c.weight_updated_at = now;
+
+        Ok(())
+    }
+
+    /// Registers `invite_hash = sha256(code)`; the code is shared off-chain.
+    #[cfg(feature = "full")]
+    pub fn create_invite(
+        ctx: Context<CreateInvite>,
+        pod_hash: [u8; 32],
+        invite_hash: [u8; 32],
+        max_uses: u16,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(max_uses > 0, EscrowError::InvalidAmount);
+        require!(expiry > Clock::get()?.unix_timestamp, EscrowError::InvalidTimelock);
+
+        let invite = &mut ctx.accounts.invite;
+        invite.pod = ctx.accounts.pod.key();
+        invite.invite_hash = invite_hash;
+        invite.max_uses = max_uses;
+        invite.uses = 0;
+        invite.expiry = expiry;
+        invite.bump = ctx.bumps.invite;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn join_with_invite(ctx: Context<JoinWithInvite>, pod_hash: [u8; 32], preimage: Vec<u8>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let invite = &mut ctx.accounts.invite;
+        require!(hash(&preimage).to_bytes() == invite.invite_hash, EscrowError::HashlockMismatch);
+        require!(now < invite.expiry, EscrowError::EscrowExpired);
+        require!(invite.uses < invite.max_uses, EscrowError::InviteExhausted);
+        invite.uses += 1;
+
+        let pod = &mut ctx.accounts.pod;
+        require!(pod.rosca_seed == [0u8; 32], EscrowError::RoscaStarted);
+        require!(pod.member_count < pod.max_members, EscrowError::PodFull);
+        pod.member_count += 1;
+
+        let c = &mut ctx.accounts.contribution;
+        c.pod = pod.key();
+        c.member = ctx.accounts.member.key();
+        c.joined_at = now;
+        c.bump = ctx.bumps.contribution;
+        c.role = PodRole::Contributor;
+        c.weight_updated_at = now;
+
+        emit!(PodMemberJoined {
+            pod: pod.key(),
+            member: c.member,
+            member_count: pod.member_count,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of a leaving member's refund that stays in the pod vault.
+    #[cfg(feature = "full")]
+    pub fn set_pod_exit_penalty(ctx: Context<UpdatePod>, pod_hash: [u8; 32], early_exit_penalty_bps: u16) -> Result<()> {
+        require!(early_exit_penalty_bps <= 10_000, EscrowError::InvalidFee);
+        ctx.accounts.pod.early_exit_penalty_bps = early_exit_penalty_bps;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn contribute_to_pod(ctx: Context<ContributeToPod>, pod_hash: [u8; 32], amount: u64) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+
+        let cpi = Transfer {
+            from: ctx.accounts.member_usdc.to_account_info(),
+            to: ctx.accounts.pod_usdc.to_account_info(),
+            authority: ctx.accounts.member.to_account_info(),
+        };
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi), amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let c = &mut ctx.accounts.contribution;
+        c.accrue(now);
+        c.amount = c.amount.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        let pod = &mut ctx.accounts.pod;
+        pod.accrue(now);
+        pod.total_contributed = pod.total_contributed.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Refunds the member's contribution plus their pro-rata share of whatever the
+    /// pod vault holds above total contributions, minus the pod's early-exit penalty
+    /// (which stays with the remaining members), and removes them from the pod.
+    #[cfg(feature = "full")]
+    pub fn leave_pod_and_refund(ctx: Context<LeavePod>, pod_hash: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let credited = remove_member_weight(&mut ctx.accounts.pod, &mut ctx.accounts.contribution, now);
+        let pod = &ctx.accounts.pod;
+        require_keys_neq!(pod.admin, ctx.accounts.member.key(), EscrowError::Unauthorized);
+        require!(pod.rosca_seed == [0u8; 32], EscrowError::RoscaStarted);
+
+        let contributed = ctx.accounts.contribution.amount;
+        let share = pod_member_share(pod, ctx.accounts.pod_usdc.amount, contributed)?;
+        let penalty = ((share as u128) * (pod.early_exit_penalty_bps as u128) / 10_000) as u64;
+        let refund = share - penalty + credited;
+
+        if refund > 0 {
+            let bump = pod.pod_vault_bump;
+            let seeds: &[&[u8]] = &[seeds::POD_VAULT, pod_hash.as_ref(), &[bump]];
+            let cpi = Transfer {
+                from: ctx.accounts.pod_usdc.to_account_info(),
+                to: ctx.accounts.member_usdc.to_account_info(),
+                authority: ctx.accounts.pod_vault.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                refund,
+            )?;
+        }
+
+        let pod = &mut ctx.accounts.pod;
+        pod.total_contributed = pod.total_contributed.checked_sub(contributed).ok_or(EscrowError::MathUnderflow)?;
+        pod.yield_credited = pod.yield_credited.checked_sub(credited).ok_or(EscrowError::MathUnderflow)?;
+        pod.member_count -= 1;
+
+        emit!(PodMemberLeft {
+            pod: pod.key(),
+            member: ctx.accounts.member.key(),
+            refund,
+            penalty,
+        });
+
+        Ok(())
+    }
+
+    /// Fixes the pod's membership and requests ORAO VRF randomness for `vrf_seed`.
+    /// The payout order is derived from the fulfilled value in `finalize_rosca_order`.
+    #[cfg(feature = "full")]
+    pub fn start_rosca(ctx: Context<StartRosca>, pod_hash: [u8; 32], vrf_seed: [u8; 32]) -> Result<()> {
+        let pod = &ctx.accounts.pod;
+        require!(pod.rosca_seed == [0u8; 32], EscrowError::RoscaStarted);
+        require!(pod.member_count >= 2, EscrowError::InvalidPod);
+        require!(pod.round_amount > 0, EscrowError::InvalidPod);
+
+        let (expected, _) = Pubkey::find_program_address(
+            &[ORAO_RANDOMNESS_SEED, vrf_seed.as_ref()],
+            &ctx.accounts.vrf_program.key(),
+        );
+        require_keys_eq!(ctx.accounts.randomness.key(), expected, EscrowError::BadRandomness);
+
+        let mut data = ORAO_REQUEST_IX.to_vec();
+        data.extend_from_slice(&vrf_seed);
+        let ix = Instruction {
+            program_id: ctx.accounts.vrf_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.admin.key(), true),
+                AccountMeta::new(ctx.accounts.network_state.key(), false),
+                AccountMeta::new(ctx.accounts.treasury.key(), false),
+                AccountMeta::new(ctx.accounts.randomness.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            ],
+            data,
+        };
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.admin.to_account_info(),
+                ctx.accounts.network_state.to_account_info(),
+                ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.randomness.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[],
+        )?;
+
+        ctx.accounts.pod.rosca_seed = vrf_seed;
+        Ok(())
+    }
+
+    /// Permissionless once the VRF request is fulfilled. `remaining_accounts` are the
+    /// `Contribution` PDAs of every current member; they are shuffled (Fisher–Yates)
+    /// with the verified randomness into `pod.payout_order`.
+    #[cfg(feature = "full")]
+    pub fn finalize_rosca_order(ctx: Context<FinalizeRoscaOrder>, pod_hash: [u8; 32]) -> Result<()> {
+        let pod_key = ctx.accounts.pod.key();
+        let pod = &ctx.accounts.pod;
+        require!(pod.rosca_seed != [0u8; 32], EscrowError::RoscaNotStarted);
+        require!(pod.payout_order.is_empty(), EscrowError::RoscaStarted);
+
+        let (expected, _) = Pubkey::find_program_address(
+            &[ORAO_RANDOMNESS_SEED, pod.rosca_seed.as_ref()],
+            &ctx.accounts.vrf_program.key(),
+        );
+        let randomness_info = ctx.accounts.randomness.to_account_info();
+        require_keys_eq!(randomness_info.key(), expected, EscrowError::BadRandomness);
+        require_keys_eq!(*randomness_info.owner, ctx.accounts.vrf_program.key(), EscrowError::BadRandomness);
+        let randomness: [u8; 64] = {
+            let data = randomness_info.try_borrow_data()?;
+            require!(data.len() >= ORAO_RANDOMNESS_OFFSET + 64, EscrowError::BadRandomness);
+            data[ORAO_RANDOMNESS_OFFSET..ORAO_RANDOMNESS_OFFSET + 64].try_into().unwrap()
+        };
+        require!(randomness != [0u8; 64], EscrowError::RandomnessNotFulfilled);
+
+        require!(
+            ctx.remaining_accounts.len() == pod.member_count as usize,
+            EscrowError::InvalidBatch
+        );
+        let mut members: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require_keys_eq!(*info.owner, crate::ID, EscrowError::InvalidPod);
+            let c = Contribution::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(c.pod, pod_key, EscrowError::InvalidPod);
+            require!(!members.contains(&c.member), EscrowError::InvalidBatch);
+            members.push(c.member);
+        }
+
+        for i in (1..members.len()).rev() {
+            let word = hash(&[randomness.as_ref(), &(i as u32).to_le_bytes()].concat()).to_bytes();
+            let j = (u64::from_le_bytes(word[..8].try_into().unwrap()) % (i as u64 + 1)) as usize;
+            members.swap(i, j);
+        }
+
+        let pod = &mut ctx.accounts.pod;
+        pod.payout_order = members;
+        pod.rosca_round = 0;
+        pod.rosca_started_at = Clock::get()?.unix_timestamp;
+
+        emit!(RoscaOrderFinalized {
+            pod: pod_key,
+            payout_order: pod.payout_order.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// ROSCA terms: every member pays `round_amount` per round of `round_secs`, due
+    /// within `contribution_window_secs` of the round opening.
+    #[cfg(feature = "full")]
+    pub fn set_rosca_terms(
+        ctx: Context<UpdatePod>,
+        pod_hash: [u8; 32],
+        round_amount: u64,
+        round_secs: i64,
+        contribution_window_secs: i64,
+        late_penalty_bps: u16,
+    ) -> Result<()> {
+        require!(round_amount > 0, EscrowError::InvalidAmount);
+        require!(
+            round_secs > 0 && contribution_window_secs > 0 && contribution_window_secs <= round_secs,
+            EscrowError::InvalidTimelock
+        );
+        require!(late_penalty_bps <= 10_000, EscrowError::InvalidFee);
+
+        let pod = &mut ctx.accounts.pod;
+        require!(pod.rosca_seed == [0u8; 32], EscrowError::RoscaStarted);
+        pod.round_amount = round_amount;
+        pod.round_secs = round_secs;
+        pod.contribution_window_secs = contribution_window_secs;
+        pod.late_penalty_bps = late_penalty_bps;
+
+        Ok(())
+    }
+
+    /// Pays the member's contribution for their next unpaid round.
+    #[cfg(feature = "full")]
+    pub fn rosca_contribute(ctx: Context<RoscaContribute>, pod_hash: [u8; 32]) -> Result<()> {
+        let pod = &ctx.accounts.pod;
+        require!(!pod.payout_order.is_empty(), EscrowError::RoscaNotStarted);
+        let c = &mut ctx.accounts.contribution;
+        require!((c.rounds_paid as usize) < pod.payout_order.len(), EscrowError::RoscaFinished);
+        require!(c.rounds_paid <= pod.rosca_round, EscrowError::InvalidRound);
+
+        let cpi = Transfer {
+            from: ctx.accounts.member_usdc.to_account_info(),
+            to: ctx.accounts.pod_usdc.to_account_info(),
+            authority: ctx.accounts.member.to_account_info(),
+        };
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi), pod.round_amount)?;
+        c.rounds_paid += 1;
+        let round_amount = pod.round_amount;
+        ctx.accounts.pod.rosca_held = ctx.accounts.pod.rosca_held.checked_add(round_amount).ok_or(EscrowError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Permissionless: after the current round's deadline, marks `member` late if they
+    /// have not paid it. The pod's late penalty is applied to their next payout.
+    #[cfg(feature = "full")]
+    pub fn flag_late(ctx: Context<FlagLate>, pod_hash: [u8; 32], member: Pubkey) -> Result<()> {
+        let pod = &ctx.accounts.pod;
+        require!(!pod.payout_order.is_empty(), EscrowError::RoscaNotStarted);
+        let round = pod.rosca_round;
+        require!(Clock::get()?.unix_timestamp > pod.round_deadline(round), EscrowError::InvalidRound);
+
+        let c = &mut ctx.accounts.contribution;
+        require!(c.rounds_paid <= round, EscrowError::NotLate);
+        require!(c.late_flagged_round != round + 1, EscrowError::NotLate);
+        c.late_flagged_round = round + 1;
+        c.late_count += 1;
+        c.pending_penalty_bps = c.pending_penalty_bps.saturating_add(pod.late_penalty_bps).min(10_000);
+
+        emit!(MemberFlaggedLate {
+            pod: pod.key(),
+            member,
+            round,
+            pending_penalty_bps: c.pending_penalty_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the current round's pot to its recipient once the contribution window has
+    /// closed, withholding any pending late penalty (kept by the pod vault).
+    #[cfg(feature = "full")]
+    pub fn rosca_payout(ctx: Context<RoscaPayout>, pod_hash: [u8; 32]) -> Result<()> {
+        require_pod_role(&ctx.accounts.membership, &[PodRole::Admin, PodRole::Treasurer])?;
+        let pod = &ctx.accounts.pod;
+        let round = pod.rosca_round;
+        require!((round as usize) < pod.payout_order.len(), EscrowError::RoscaFinished);
+        require!(Clock::get()?.unix_timestamp > pod.round_deadline(round), EscrowError::InvalidRound);
+        require_keys_eq!(
+            ctx.accounts.contribution.member,
+            pod.payout_order[round as usize],
+            EscrowError::InvalidCounterparty
+        );
+
+        let gross = pod
+            .round_amount
+            .saturating_mul(pod.payout_order.len() as u64)
+            .min(ctx.accounts.pod_usdc.amount);
+        let c = &mut ctx.accounts.contribution;
+        let penalty = ((gross as u128) * (c.pending_penalty_bps as u128) / 10_000) as u64;
+        c.pending_penalty_bps = 0;
+        let amount = gross - penalty;
+
+        let bump = pod.pod_vault_bump;
+        let seeds: &[&[u8]] = &[seeds::POD_VAULT, pod_hash.as_ref(), &[bump]];
+        let cpi = Transfer {
+            from: ctx.accounts.pod_usdc.to_account_info(),
+            to: ctx.accounts.recipient_usdc.to_account_info(),
+            authority: ctx.accounts.pod_vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount,
+        )?;
+
+        let pod = &mut ctx.accounts.pod;
+        pod.rosca_round += 1;
+        pod.rosca_held = pod.rosca_held.checked_sub(gross).ok_or(EscrowError::MathUnderflow)?;
+
+        emit!(RoscaPaidOut {
+            pod: ctx.accounts.pod.key(),
+            round,
+            recipient: ctx.accounts.contribution.member,
+            amount,
+            penalty,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_member_role(ctx: Context<SetMemberRole>, pod_hash: [u8; 32], member: Pubkey, role: PodRole) -> Result<()> {
+        let c = &mut ctx.accounts.contribution;
+        require!(
+            c.member != ctx.accounts.pod.admin || role == PodRole::Admin,
+            EscrowError::Unauthorized
+        );
+        c.role = role;
+
+        emit!(PodRoleChanged {
+            pod: ctx.accounts.pod.key(),
+            member,
+            role,
+        });
+
+        Ok(())
+    }
+
+    /// Share of the other members (bps) whose votes eject a member; 0 means simple majority.
+    #[cfg(feature = "full")]
+    pub fn set_removal_quorum(ctx: Context<UpdatePod>, pod_hash: [u8; 32], removal_quorum_bps: u16) -> Result<()> {
+        require!(removal_quorum_bps <= 10_000, EscrowError::InvalidBps);
+        ctx.accounts.pod.removal_quorum_bps = removal_quorum_bps;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn propose_removal(ctx: Context<ProposeRemoval>, pod_hash: [u8; 32], target: Pubkey) -> Result<()> {
+        require_keys_neq!(ctx.accounts.proposer.key(), target, EscrowError::InvalidCounterparty);
+        require_keys_neq!(ctx.accounts.pod.admin, target, EscrowError::Unauthorized);
+
+        let p = &mut ctx.accounts.proposal;
+        p.pod = ctx.accounts.pod.key();
+        p.target = target;
+        p.proposer = ctx.accounts.proposer.key();
+        p.created_at = Clock::get()?.unix_timestamp;
+        p.voters = vec![ctx.accounts.proposer.key()];
+        p.bump = ctx.bumps.proposal;
+
+        emit!(RemovalProposed {
+            pod: p.pod,
+            target,
+            proposer: p.proposer,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn vote(ctx: Context<VoteRemoval>, pod_hash: [u8; 32], target: Pubkey) -> Result<()> {
+        let voter = ctx.accounts.voter.key();
+        require_keys_neq!(voter, target, EscrowError::InvalidCounterparty);
+        let p = &mut ctx.accounts.proposal;
+        require!(!p.voters.contains(&voter), EscrowError::AlreadyVoted);
+        p.voters.push(voter);
+        Ok(())
+    }
+
+    /// Ejects `target` once the quorum is met: refunds their recorded share (no exit
+    /// penalty), drops their unpaid ROSCA slot so later rounds move up, and closes
+    /// their membership.
+    #[cfg(feature = "full")]
+    pub fn execute_removal(ctx: Context<ExecuteRemoval>, pod_hash: [u8; 32], target: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let credited = remove_member_weight(&mut ctx.accounts.pod, &mut ctx.accounts.contribution, now);
+        let pod = &ctx.accounts.pod;
+        let eligible = pod.member_count.saturating_sub(1) as u64;
+        let votes = ctx.accounts.proposal.voters.len() as u64;
+        let passed = if pod.removal_quorum_bps == 0 {
+            votes * 2 > eligible
+        } else {
+            votes * 10_000 >= eligible * pod.removal_quorum_bps as u64
+        };
+        require!(passed, EscrowError::QuorumNotMet);
+
+        let contributed = ctx.accounts.contribution.amount;
+        let refund = pod_member_share(pod, ctx.accounts.pod_usdc.amount, contributed)? + credited;
+        if refund > 0 {
+            let bump = pod.pod_vault_bump;
+            let seeds: &[&[u8]] = &[seeds::POD_VAULT, pod_hash.as_ref(), &[bump]];
+            let cpi = Transfer {
+                from: ctx.accounts.pod_usdc.to_account_info(),
+                to: ctx.accounts.target_usdc.to_account_info(),
+                authority: ctx.accounts.pod_vault.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                refund,
+            )?;
+        }
+
+        let pod = &mut ctx.accounts.pod;
+        pod.total_contributed = pod.total_contributed.checked_sub(contributed).ok_or(EscrowError::MathUnderflow)?;
+        pod.yield_credited = pod.yield_credited.checked_sub(credited).ok_or(EscrowError::MathUnderflow)?;
+        pod.member_count -= 1;
+        let round = pod.rosca_round as usize;
+        if let Some(slot) = pod.payout_order.iter().position(|m| *m == target) {
+            if slot >= round {
+                pod.payout_order.remove(slot);
+            }
+        }
+
+        emit!(MemberRemoved {
+            pod: pod.key(),
+            member: target,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    /// Allocates the pod vault's undistributed surplus across all members in
+    /// proportion to their time-weighted contributions since the last distribution.
+    /// `remaining_accounts` are every member's `Contribution` PDA or, with `to_ata`,
+    /// `[contribution, member_usdc]` pairs to pay out directly instead of crediting.
+    #[cfg(feature = "full")]
+    pub fn distribute_yield<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeYield<'info>>,
+        pod_hash: [u8; 32],
+        to_ata: bool,
+    ) -> Result<()> {
+        require_pod_role(&ctx.accounts.membership, &[PodRole::Admin, PodRole::Treasurer])?;
+        let now = Clock::get()?.unix_timestamp;
+        let pod_key = ctx.accounts.pod.key();
+        let pod = &mut ctx.accounts.pod;
+        pod.accrue(now);
+        let total_weight = pod.total_weight;
+        let amount = pod.surplus(ctx.accounts.pod_usdc.amount);
+        require!(amount > 0 && total_weight > 0, EscrowError::InvalidAmount);
+
+        let stride = if to_ata { 2 } else { 1 };
+        require!(
+            ctx.remaining_accounts.len() == pod.member_count as usize * stride,
+            EscrowError::InvalidBatch
+        );
+        let bump = pod.pod_vault_bump;
+        let seeds: &[&[u8]] = &[seeds::POD_VAULT, pod_hash.as_ref(), &[bump]];
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(pod.member_count as usize);
+        let mut distributed: u64 = 0;
+        let mut credited: u64 = 0;
+        for chunk in ctx.remaining_accounts.chunks(stride) {
+            let mut c: Account<'info, Contribution> = Account::try_from(&chunk[0])?;
+            require_keys_eq!(c.pod, pod_key, EscrowError::InvalidPod);
+            require!(!seen.contains(&c.member), EscrowError::InvalidBatch);
+            seen.push(c.member);
+
+            c.accrue(now);
+            let share = ((amount as u128) * c.weight / total_weight) as u64;
+            c.weight = 0;
+            if to_ata {
+                let member_usdc: Account<'info, TokenAccount> = Account::try_from(&chunk[1])?;
+                require_keys_eq!(member_usdc.owner, c.member, EscrowError::InvalidCounterparty);
+                require_keys_eq!(member_usdc.mint, pod.usdc_mint, EscrowError::BadMint);
+                if share > 0 {
+                    let cpi = Transfer {
+                        from: ctx.accounts.pod_usdc.to_account_info(),
+                        to: chunk[1].clone(),
+                        authority: ctx.accounts.pod_vault.to_account_info(),
+                    };
+                    token::transfer(
+                        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+                        share,
+                    )?;
+                }
+            } else {
+                c.yield_balance = c.yield_balance.checked_add(share).ok_or(EscrowError::MathOverflow)?;
+                credited += share;
+            }
+            distributed += share;
+            c.exit(&crate::ID)?;
+        }
+
+        pod.total_weight = 0;
+        pod.yield_credited = pod.yield_credited.checked_add(credited).ok_or(EscrowError::MathOverflow)?;
+
+        emit!(PodYieldDistributed {
+            pod: pod_key,
+            amount: distributed,
+            credited,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out the member's internally credited yield.
+    #[cfg(feature = "full")]
+    pub fn claim_pod_yield(ctx: Context<ClaimPodYield>, pod_hash: [u8; 32]) -> Result<()> {
+        let c = &mut ctx.accounts.contribution;
+        let amount = c.yield_balance;
+        require!(amount > 0, EscrowError::InvalidAmount);
+        c.yield_balance = 0;
+        let pod = &mut ctx.accounts.pod;
+        pod.yield_credited = pod.yield_credited.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+
+        let bump = pod.pod_vault_bump;
+        let seeds: &[&[u8]] = &[seeds::POD_VAULT, pod_hash.as_ref(), &[bump]];
+        let cpi = Transfer {
+            from: ctx.accounts.pod_usdc.to_account_info(),
+            to: ctx.accounts.member_usdc.to_account_info(),
+            authority: ctx.accounts.pod_vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// First step of a role hand-over, signed by the role's current holder.
+    /// Passing `Pubkey::default()` cancels a pending transfer.
+    #[cfg(feature = "full")]
+    pub fn propose_role(ctx: Context<ProposeRole>, role: ConfigRole, new_holder: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.has_role(role, &ctx.accounts.holder.key()), EscrowError::Unauthorized);
+        *config.pending_role_mut(role) = new_holder;
+        emit!(RoleTransferStarted {
+            role,
+            holder: ctx.accounts.holder.key(),
+            pending: new_holder,
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn accept_role(ctx: Context<AcceptRole>, role: ConfigRole) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let pending = ctx.accounts.pending.key();
+        require!(
+            pending != Pubkey::default() && *config.pending_role_mut(role) == pending,
+            EscrowError::Unauthorized
+        );
+        let previous = config.role_holder(role);
+        *config.role_mut(role) = pending;
+        *config.pending_role_mut(role) = Pubkey::default();
+        emit!(RoleTransferred {
+            role,
+            previous,
+            holder: pending,
+        });
+        Ok(())
+    }
+
+    /// Guardian brake for a compromised vault: blocks every withdrawal path, including
+    /// moves into or out of yield venues and dust sweeps (deposits still work), for
+    /// `Vault::FREEZE_SECS`. A vault cannot be frozen again until
+    /// `Vault::FREEZE_COOLDOWN_SECS` after the previous freeze ended.
+    #[cfg(feature = "full")]
+    pub fn freeze_vault(ctx: Context<GuardianVault>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+        require!(
+            vault.frozen_until == 0 || now >= vault.frozen_until + Vault::FREEZE_COOLDOWN_SECS,
+            EscrowError::VaultFrozen
+        );
+        vault.frozen_until = now + Vault::FREEZE_SECS;
+
+        emit!(VaultFreezeChanged {
+            vault: vault.key(),
+            seq: vault.seq,
+            frozen_until: vault.frozen_until,
+        });
+
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn unfreeze_vault(ctx: Context<GuardianVault>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.is_frozen(now), EscrowError::InvalidAmount);
+        vault.frozen_until = now;
+
+        emit!(VaultFreezeChanged {
+            vault: vault.key(),
+            seq: vault.seq,
+            frozen_until: now,
+        });
+
+        Ok(())
+    }
+
+    /// Opens (or closes) the pot to deposits from anyone via `donate` / `donate_usdc`.
+    /// Withdrawals stay owner-only.
+    #[cfg(feature = "full")]
+    pub fn set_public_deposits(ctx: Context<SetPublicDeposits>, pot_hash: [u8; 32], enabled: bool) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        ctx.accounts.vault.public_deposits = enabled;
+        Ok(())
+    }
+
+    /// SOL deposit by anyone into a pot with `public_deposits` set.
+    #[cfg(feature = "full")]
+    pub fn donate(ctx: Context<Donate>, lamports: u64, memo_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        require!(ctx.accounts.vault.public_deposits, EscrowError::DepositsNotPublic);
+        require!(!ctx.accounts.vault.crowdfund_active(), EscrowError::CrowdfundUsdcOnly);
+        let config = Some(&ctx.accounts.config);
+        check_attestation(config, ctx.accounts.attestation.as_ref(), ctx.accounts.donor.key(), lamports)?;
+        check_deposit_limits(
+            config,
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            lamports,
+            0,
+        )?;
+
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.donor.key(),
+            &ctx.accounts.vault.key(),
+            lamports,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.donor.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let donor = ctx.accounts.donor.key();
+        record_deposit(&mut ctx.accounts.vault, None, donor, Pubkey::default(), lamports)?;
+        emit!(DonationReceived {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            donor,
+            mint: Pubkey::default(),
+            amount: lamports,
+            memo_hash,
+        });
+        Ok(())
+    }
+
+    /// USDC deposit by anyone into a pot with `public_deposits` set. During a
+    /// crowdfund the donor's running total is kept in their `Donation` PDA, which
+    /// must be passed.
+    #[cfg(feature = "full")]
+    pub fn donate_usdc(ctx: Context<DonateUsdc>, amount: u64, memo_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(ctx.accounts.vault.public_deposits, EscrowError::DepositsNotPublic);
+        let config = Some(&ctx.accounts.config);
+        check_attestation(config, ctx.accounts.attestation.as_ref(), ctx.accounts.donor.key(), amount)?;
+        check_deposit_limits(
+            config,
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            0,
+            amount,
+        )?;
+
+        let cpi = Transfer {
+            from: ctx.accounts.donor_usdc.to_account_info(),
+            to: ctx.accounts.vault_usdc.to_account_info(),
+            authority: ctx.accounts.donor.to_account_info(),
+        };
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi), amount)?;
+
+        let donor = ctx.accounts.donor.key();
+        if ctx.accounts.vault.crowdfund_active() {
+            require!(
+                Clock::get()?.unix_timestamp < ctx.accounts.vault.crowdfund_deadline,
+                EscrowError::CrowdfundEnded
+            );
+            let bump = ctx.bumps.donation.ok_or(EscrowError::MissingDonation)?;
+            let donation = ctx.accounts.donation.as_mut().ok_or(EscrowError::MissingDonation)?;
+            donation.vault = ctx.accounts.vault.key();
+            donation.donor = donor;
+            donation.round = ctx.accounts.vault.crowdfund_round;
+            donation.bump = bump;
+            donation.amount = donation.amount.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+            let v = &mut ctx.accounts.vault;
+            v.crowdfund_raised_usdc = v.crowdfund_raised_usdc.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        }
+        let mint = ctx.accounts.vault.usdc_mint;
+        record_deposit(&mut ctx.accounts.vault, None, donor, mint, amount)?;
+        emit!(DonationReceived {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            donor,
+            mint,
+            amount,
+            memo_hash,
+        });
+        Ok(())
+    }
+
+    /// Starts an all-or-nothing USDC crowdfund on a public pot. Donations are locked
+    /// until `claim_crowdfund` (goal reached) or refunded by `refund_donor` (goal
+    /// missed at `deadline`).
+    #[cfg(feature = "full")]
+    pub fn start_crowdfund(ctx: Context<OwnerCrowdfund>, pot_hash: [u8; 32], goal_usdc: u64, deadline: i64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let v = &mut ctx.accounts.vault;
+        require!(v.public_deposits, EscrowError::DepositsNotPublic);
+        require!(!v.crowdfund_active(), EscrowError::CrowdfundActive);
+        require!(goal_usdc > 0, EscrowError::InvalidAmount);
+        require!(deadline > Clock::get()?.unix_timestamp, EscrowError::InvalidTimelock);
+
+        v.crowdfund_round = v.crowdfund_round.checked_add(1).ok_or(EscrowError::MathOverflow)?;
+        v.crowdfund_goal_usdc = goal_usdc;
+        v.crowdfund_deadline = deadline;
+        v.crowdfund_raised_usdc = 0;
+        emit!(CrowdfundStarted {
+            vault: v.key(),
+            seq: v.seq,
+            round: v.crowdfund_round,
+            goal_usdc,
+            deadline,
+        });
+        Ok(())
+    }
+
+    /// Releases a crowdfund that reached its goal to the owner.
+    #[cfg(feature = "full")]
+    pub fn claim_crowdfund(ctx: Context<OwnerCrowdfund>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let v = &mut ctx.accounts.vault;
+        require!(v.crowdfund_active(), EscrowError::NoCrowdfund);
+        require!(v.crowdfund_raised_usdc >= v.crowdfund_goal_usdc, EscrowError::CrowdfundGoalNotMet);
+
+        emit!(CrowdfundClaimed {
+            vault: v.key(),
+            seq: v.seq,
+            round: v.crowdfund_round,
+            raised_usdc: v.crowdfund_raised_usdc,
+        });
+        v.end_crowdfund();
+        Ok(())
+    }
+
+    /// Permissionless: once a crowdfund has missed its goal at the deadline, returns
+    /// one donor's USDC and closes their `Donation` to them.
+    #[cfg(feature = "full")]
+    pub fn refund_donor(ctx: Context<RefundDonor>) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let v = &ctx.accounts.vault;
+        require!(v.crowdfund_active(), EscrowError::NoCrowdfund);
+        require!(Clock::get()?.unix_timestamp >= v.crowdfund_deadline, EscrowError::CrowdfundNotEnded);
+        require!(v.crowdfund_raised_usdc < v.crowdfund_goal_usdc, EscrowError::CrowdfundGoalMet);
+        require!(ctx.accounts.donation.round == v.crowdfund_round, EscrowError::BadDonation);
+
+        let amount = ctx.accounts.donation.amount;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, v.owner.as_ref(), v.pot_hash.as_ref(), &[v.bump]];
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.donor_usdc.to_account_info(),
+            authority: v.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount,
+        )?;
+
+        let v = &mut ctx.accounts.vault;
+        v.book_usdc_out(amount)?;
+        v.crowdfund_raised_usdc = v.crowdfund_raised_usdc.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+        emit!(DonorRefunded {
+            vault: v.key(),
+            seq: v.seq,
+            donor: ctx.accounts.donation.donor,
+            round: v.crowdfund_round,
+            amount,
+        });
+        // The last refund ends the round so the owner can start another.
+        if v.crowdfund_raised_usdc == 0 {
+            v.end_crowdfund();
+        }
+        ctx.accounts.donation.close(ctx.accounts.donor.to_account_info())
+    }
+
+    /// Permissionless: returns a settled round's `Donation` rent to its donor.
+    #[cfg(feature = "full")]
+    pub fn close_donation(ctx: Context<CloseDonation>) -> Result<()> {
+        let v = &ctx.accounts.vault;
+        require!(
+            !v.crowdfund_active() || ctx.accounts.donation.round != v.crowdfund_round,
+            EscrowError::CrowdfundActive
+        );
+        Ok(())
+    }
+
+    /// Minimum slots between external CPI executions (`lulo_execute`, Sanctum swaps)
+    /// for this vault; 0 disables the limit.
+    #[cfg(feature = "full")]
+    pub fn set_exec_cooldown(ctx: Context<SetExecCooldown>, pot_hash: [u8; 32], cooldown_slots: u64) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        ctx.accounts.vault.external_exec_cooldown_slots = cooldown_slots;
+        Ok(())
+    }
+
+    /// Burns the vault's current `op_nonce`, invalidating every signed payload the
+    /// owner has handed to a relayer but which has not been submitted yet.
+    #[cfg(feature = "full")]
+    pub fn advance_op_nonce(ctx: Context<AdvanceOpNonce>, pot_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let vault = &mut ctx.accounts.vault;
+        vault.op_nonce = vault.op_nonce.checked_add(1).ok_or(EscrowError::MathOverflow)?;
+        emit!(OpNonceAdvanced {
+            vault: vault.key(),
+            seq: vault.seq,
+            op_nonce: vault.op_nonce,
+        });
+        Ok(())
+    }
+
+    /// Gasless USDC deposit: `relayer` pays the fee and the owner authorizes the
+    /// transfer with an Ed25519-signed `SignedOp` placed just before this instruction.
+    /// The owner's USDC ATA must have approved the vault PDA as delegate.
+    #[cfg(feature = "full")]
+    pub fn relay_deposit_usdc(ctx: Context<RelayUsdc>, pot_hash: [u8; 32], op: SignedOp) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(op.amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            op.amount,
+        )?;
+        require!(op.kind == SignedOp::DEPOSIT_USDC, EscrowError::BadSignature);
+        check_deposit_limits(
+            ctx.accounts.config.as_ref(),
+            &ctx.accounts.vault,
+            &ctx.accounts.vault.to_account_info(),
+            ctx.accounts.sol_price.as_ref(),
+            0,
+            op.amount,
+        )?;
+        let vault_key = ctx.accounts.vault.key();
+        accept_signed_op(
+            &mut ctx.accounts.vault,
+            vault_key,
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &op,
+        )?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let cpi = Transfer {
+            from: ctx.accounts.owner_usdc.to_account_info(),
+            to: ctx.accounts.vault_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            op.amount,
+        )?;
+
+        mint_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.token_program,
+            op.amount,
+        )?;
+        record_deposit(
+            &mut ctx.accounts.vault,
+            None,
+            owner_key,
+            ctx.accounts.usdc_mint.key(),
+            op.amount,
+        )?;
+
+        emit!(SignedOpExecuted {
+            vault: vault_key,
+            seq: ctx.accounts.vault.seq,
+            relayer: ctx.accounts.relayer.key(),
+            kind: op.kind,
+            amount: op.amount,
+            nonce: op.nonce,
+        });
+        Ok(())
+    }
+
+    /// Gasless USDC withdrawal to the owner's own ATA, authorized like
+    /// `relay_deposit_usdc`; an active commitment takes its early-withdraw penalty.
+    #[cfg(feature = "full")]
+    pub fn relay_withdraw_usdc(ctx: Context<RelayUsdc>, pot_hash: [u8; 32], op: SignedOp) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require_keys_eq!(ctx.accounts.vault.share_mint, Pubkey::default(), EscrowError::SharesUnsupported);
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        require!(op.amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            op.amount,
+        )?;
+        check_outflow(
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.vault.usdc_mint,
+            op.amount,
+        )?;
+        // Signed ops carry no memo, so large relayed withdrawals must use `withdraw_usdc`.
+        check_memo(ctx.accounts.config.as_ref(), op.amount, &[0u8; 32])?;
+        require!(op.kind == SignedOp::WITHDRAW_USDC, EscrowError::BadSignature);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= op.amount,
+            EscrowError::InsufficientFunds
+        );
+        let vault_key = ctx.accounts.vault.key();
+        accept_signed_op(
+            &mut ctx.accounts.vault,
+            vault_key,
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &op,
+        )?;
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            ctx.accounts.config.as_ref(),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            op.amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_usdc.to_account_info(),
+            to: ctx.accounts.owner_usdc.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            op.amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+        ctx.accounts.vault.book_usdc_out(op.amount)?;
+
+        emit!(SignedOpExecuted {
+            vault: vault_key,
+            seq: ctx.accounts.vault.seq,
+            relayer: ctx.accounts.relayer.key(),
+            kind: op.kind,
+            amount: op.amount,
+            nonce: op.nonce,
+        });
+        Ok(())
+    }
+
+    /// `deposit` for pre-signed (durable nonce) transactions: fails outside `window`.
+    #[cfg(feature = "full")]
+    pub fn deposit_in_window(
+        ctx: Context<Deposit>,
+        pot_hash: [u8; 32],
+        lamports: u64,
+        window: ExecWindow,
+        idempotency_key: Option<[u8; 16]>,
+    ) -> Result<()> {
+        window.check()?;
+        deposit(ctx, pot_hash, lamports, idempotency_key)
+    }
+
+    /// `deposit_usdc` for pre-signed (durable nonce) transactions: fails outside `window`.
+    #[cfg(feature = "full")]
+    pub fn deposit_usdc_in_window(
+        ctx: Context<DepositUsdc>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        window: ExecWindow,
+        idempotency_key: Option<[u8; 16]>,
+    ) -> Result<()> {
+        window.check()?;
+        deposit_usdc(ctx, pot_hash, amount, idempotency_key)
+    }
+
+    /// `lulo_execute` (the rebalance leg of scheduled savings) for pre-signed
+    /// transactions: fails outside `window`.
+    #[cfg(feature = "lulo")]
+    pub fn lulo_execute_in_window(
+        ctx: Context<LuloExecute>,
+        pot_hash: [u8; 32],
+        max_usdc_out: u64,
+        ix_data: Vec<u8>,
+        window: ExecWindow,
+    ) -> Result<()> {
+        window.check()?;
+        lulo_execute(ctx, pot_hash, max_usdc_out, ix_data)
+    }
+
+    /// Deposits and withdrawals above `threshold` require an `Attestation` from an
+    /// allowlisted issuer; 0 turns the compliance gate off.
+    #[cfg(feature = "full")]
+    pub fn set_compliance_threshold(ctx: Context<UpdateConfig>, threshold: u64) -> Result<()> {
+        ctx.accounts.config.compliance_threshold = threshold;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_attestation_issuer(ctx: Context<UpdateConfig>, issuer: Pubkey, allowed: bool) -> Result<()> {
+        let issuers = &mut ctx.accounts.config.attestation_issuers;
+        let existing = issuers.iter().position(|k| *k == issuer);
+        match (allowed, existing) {
+            (true, None) => {
+                require!(issuers.len() < Config::MAX_ATTESTATION_ISSUERS, EscrowError::RegistryFull);
+                issuers.push(issuer);
+            }
+            (false, Some(i)) => {
+                issuers.swap_remove(i);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Records that `issuer` has verified `subject`; `expires_at` of 0 never expires.
+    #[cfg(feature = "full")]
+    pub fn issue_attestation(ctx: Context<IssueAttestation>, subject: Pubkey, expires_at: i64) -> Result<()> {
+        require!(
+            ctx.accounts.config.attestation_issuers.contains(&ctx.accounts.issuer.key()),
+            EscrowError::Unauthorized
+        );
+        let a = &mut ctx.accounts.attestation;
+        a.subject = subject;
+        a.issuer = ctx.accounts.issuer.key();
+        a.expires_at = expires_at;
+        a.bump = ctx.bumps.attestation;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn revoke_attestation(_ctx: Context<RevokeAttestation>, _subject: Pubkey) -> Result<()> {
+        Ok(())
+    }
+
+    /// Adds or removes `address` from the sanctioned-address blocklist enforced on
+    /// `withdraw_to` / `withdraw_usdc_to` destinations.
+    #[cfg(feature = "full")]
+    pub fn set_blocked(ctx: Context<SetBlocked>, address: Pubkey, blocked: bool) -> Result<()> {
+        let list = &mut ctx.accounts.blocklist;
+        list.bump = ctx.bumps.blocklist;
+        let existing = list.addresses.iter().position(|k| *k == address);
+        match (blocked, existing) {
+            (true, None) => {
+                require!(list.addresses.len() < Blocklist::MAX_ADDRESSES, EscrowError::RegistryFull);
+                list.addresses.push(address);
+            }
+            (false, Some(i)) => {
+                list.addresses.swap_remove(i);
+            }
+            _ => {}
+        }
+        emit!(BlocklistUpdated { address, blocked });
+        Ok(())
+    }
+
+    /// Adds or removes a CCTP destination domain from the cross-chain withdrawal
+    /// allowlist.
+    #[cfg(feature = "full")]
+    pub fn set_crosschain_domain(ctx: Context<SetCrosschainDomain>, domain: u32, allowed: bool) -> Result<()> {
+        let list = &mut ctx.accounts.domains;
+        list.bump = ctx.bumps.domains;
+        let existing = list.domains.iter().position(|d| *d == domain);
+        match (allowed, existing) {
+            (true, None) => {
+                require!(list.domains.len() < CrosschainDomains::MAX_DOMAINS, EscrowError::RegistryFull);
+                list.domains.push(domain);
+            }
+            (false, Some(i)) => {
+                list.domains.swap_remove(i);
+            }
+            _ => {}
+        }
+        emit!(CrosschainDomainUpdated { domain, allowed });
+        Ok(())
+    }
+
+    /// Owner allowlists `recipient` (a 32-byte, left-padded address) on `domain`
+    /// for `withdraw_usdc_crosschain` from this pot.
+    #[cfg(feature = "full")]
+    pub fn add_crosschain_recipient(
+        ctx: Context<AddCrosschainRecipient>,
+        pot_hash: [u8; 32],
+        domain: u32,
+        recipient: [u8; 32],
+    ) -> Result<()> {
+        require!(recipient != [0; 32], EscrowError::BadCrosschainRoute);
+        require!(ctx.accounts.domains.domains.contains(&domain), EscrowError::BadCrosschainRoute);
+        let r = &mut ctx.accounts.recipient;
+        r.vault = ctx.accounts.vault.key();
+        r.domain = domain;
+        r.recipient = recipient;
+        r.bump = ctx.bumps.recipient;
+        emit!(CrosschainRecipientAdded {
+            vault: r.vault,
+            seq: ctx.accounts.vault.seq,
+            domain,
+            recipient,
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn remove_crosschain_recipient(
+        _ctx: Context<RemoveCrosschainRecipient>,
+        _pot_hash: [u8; 32],
+        _domain: u32,
+        _recipient: [u8; 32],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Burns `amount` USDC from the vault through Circle CCTP `deposit_for_burn`,
+    /// minting it to an allowlisted recipient on another chain. Subject to the same
+    /// freeze, compliance, memo, lock and early-withdraw penalty checks as
+    /// `withdraw_usdc`. `message_sent_event_data` is a fresh keypair CCTP stores the
+    /// outgoing message in.
+    #[cfg(feature = "full")]
+    pub fn withdraw_usdc_crosschain(
+        ctx: Context<WithdrawUsdcCrosschain>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            Some(&ctx.accounts.config),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_memo(Some(&ctx.accounts.config), amount, &memo_hash)?;
+        check_outflow(
+            Some(&ctx.accounts.config),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.usdc_mint.key(),
+            amount,
+        )?;
+        let route = &ctx.accounts.recipient;
+        require!(ctx.accounts.domains.domains.contains(&route.domain), EscrowError::BadCrosschainRoute);
+        require_keys_eq!(ctx.accounts.vault.usdc_mint, ctx.accounts.usdc_mint.key(), EscrowError::BadMint);
+        require!(
+            ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount,
+            EscrowError::InsufficientFunds
+        );
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_usdc,
+            Some(&ctx.accounts.config),
+            ctx.accounts.penalty_usdc.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        cctp_deposit_for_burn(&ctx.accounts, amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?, route.domain, route.recipient, &[seeds])?;
+
+        burn_pot_shares(
+            &ctx.accounts.vault,
+            ctx.accounts.share_mint.as_deref(),
+            ctx.accounts.owner_shares.as_deref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.vault.book_usdc_out(amount)?;
+
+        emit!(CrosschainWithdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: ctx.accounts.usdc_mint.key(),
+            amount,
+            destination_domain: ctx.accounts.recipient.domain,
+            mint_recipient: ctx.accounts.recipient.recipient,
+            message: ctx.accounts.message_sent_event_data.key(),
+            memo_hash,
+        });
+        Ok(())
+    }
+
+    /// Creates the `FeeTreasury` PDA and points `Config::fee_treasury` at it, so
+    /// withdrawal, performance, penalty and dust fees all land in its ATAs.
+    #[cfg(feature = "full")]
+    pub fn init_fee_treasury(ctx: Context<InitFeeTreasury>) -> Result<()> {
+        ctx.accounts.fee_treasury.bump = ctx.bumps.fee_treasury;
+        ctx.accounts.config.fee_treasury = ctx.accounts.fee_treasury.key();
+        Ok(())
+    }
+
+    /// Admin: moves everything the treasury holds in one asset to `destination`:
+    /// lamports above rent when no token accounts are passed, otherwise the whole
+    /// `treasury_token` balance into `destination_token`. Fees that arrived
+    /// without being recorded (performance, penalty, dust) are reconciled into
+    /// `collected` first.
+    #[cfg(feature = "full")]
+    pub fn sweep_fees(ctx: Context<SweepFees>) -> Result<()> {
+        let treasury_info = ctx.accounts.fee_treasury.to_account_info();
+        let (mint, amount) = match (
+            ctx.accounts.treasury_token.as_ref(),
+            ctx.accounts.destination_token.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        ) {
+            (Some(from), Some(to), Some(token_program)) => {
+                require_keys_eq!(from.owner, treasury_info.key(), EscrowError::BadFeeTreasury);
+                require_keys_eq!(to.owner, ctx.accounts.destination.key(), EscrowError::BadFeeTreasury);
+                require_keys_eq!(from.mint, to.mint, EscrowError::BadMint);
+                let amount = from.amount;
+                if amount > 0 {
+                    let bump = ctx.accounts.fee_treasury.bump;
+                    let seeds: &[&[u8]] = &[seeds::FEE_TREASURY, &[bump]];
+                    let cpi = Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: treasury_info.clone(),
+                    };
+                    token::transfer(CpiContext::new_with_signer(token_program.to_account_info(), cpi, &[seeds]), amount)?;
+                }
+                (from.mint, amount)
+            }
+            (None, None, None) => {
+                let min = Rent::get()?.minimum_balance(FeeTreasury::SPACE);
+                let amount = withdrawable_lamports(treasury_info.lamports(), min);
+                transfer_lamports(&treasury_info, &ctx.accounts.destination.to_account_info(), amount)?;
+                (Pubkey::default(), amount)
+            }
+            _ => return err!(EscrowError::BadFeeTreasury),
+        };
+
+        let totals = ctx.accounts.fee_treasury.sweep(mint, amount)?;
+        emit!(FeesSwept {
+            mint,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            total_collected: totals.collected,
+            total_swept: totals.swept,
+        });
+        Ok(())
+    }
+
+    /// Owner opens their `FeeTier` so fee withdrawals start counting towards the
+    /// volume levels of the `FeeTierSchedule`.
+    #[cfg(feature = "full")]
+    pub fn open_fee_tier(ctx: Context<OpenFeeTier>) -> Result<()> {
+        let tier = &mut ctx.accounts.fee_tier;
+        tier.owner = ctx.accounts.owner.key();
+        tier.earned_fee_bps = FeeTier::NO_CAP;
+        tier.bump = ctx.bumps.fee_tier;
+        Ok(())
+    }
+
+    /// Admin: assigns `owner` a fee cap (promo or loyalty tier) until `until`
+    /// (0 = no expiry); `max_fee_bps` of 0 makes their fee withdrawals free.
+    #[cfg(feature = "full")]
+    pub fn assign_fee_tier(ctx: Context<AssignFeeTier>, owner: Pubkey, max_fee_bps: u16, until: i64) -> Result<()> {
+        require!(max_fee_bps <= 10_000, EscrowError::InvalidFee);
+        let tier = &mut ctx.accounts.fee_tier;
+        if tier.owner == Pubkey::default() {
+            tier.owner = owner;
+            tier.earned_fee_bps = FeeTier::NO_CAP;
+            tier.bump = ctx.bumps.fee_tier;
+        }
+        tier.assigned = true;
+        tier.assigned_fee_bps = max_fee_bps;
+        tier.assigned_until = until;
+        emit!(FeeTierUpdated {
+            owner,
+            assigned_fee_bps: max_fee_bps,
+            assigned_until: until,
+            earned_fee_bps: tier.earned_fee_bps,
+        });
+        Ok(())
+    }
+
+    /// Admin: drops an assigned tier; any earned level stays.
+    #[cfg(feature = "full")]
+    pub fn revoke_fee_tier(ctx: Context<RevokeFeeTier>, _owner: Pubkey) -> Result<()> {
+        let tier = &mut ctx.accounts.fee_tier;
+        tier.assigned = false;
+        tier.assigned_fee_bps = 0;
+        tier.assigned_until = 0;
+        emit!(FeeTierUpdated {
+            owner: tier.owner,
+            assigned_fee_bps: FeeTier::NO_CAP,
+            assigned_until: 0,
+            earned_fee_bps: tier.earned_fee_bps,
+        });
+        Ok(())
+    }
+
+    /// Admin: volume levels owners earn by withdrawing through the fee paths,
+    /// ordered by ascending `min_volume_usdc`.
+    #[cfg(feature = "full")]
+    pub fn set_fee_tier_schedule(ctx: Context<SetFeeTierSchedule>, levels: Vec<FeeTierLevel>) -> Result<()> {
+        require!(levels.len() <= FeeTierSchedule::MAX_LEVELS, EscrowError::InvalidFee);
+        require!(
+            levels.iter().all(|l| l.max_fee_bps <= 10_000)
+                && levels.windows(2).all(|w| w[0].min_volume_usdc < w[1].min_volume_usdc),
+            EscrowError::InvalidFee
+        );
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.bump = ctx.bumps.schedule;
+        schedule.levels = levels;
+        Ok(())
+    }
+
+    /// Permissionless: re-rates an owner's earned level from their volume.
+    #[cfg(feature = "full")]
+    pub fn refresh_fee_tier(ctx: Context<RefreshFeeTier>) -> Result<()> {
+        let tier = &mut ctx.accounts.fee_tier;
+        tier.earned_fee_bps = ctx.accounts.schedule.earned_fee_bps(tier.volume_usdc);
+        emit!(FeeTierUpdated {
+            owner: tier.owner,
+            assigned_fee_bps: if tier.assigned { tier.assigned_fee_bps } else { FeeTier::NO_CAP },
+            assigned_until: tier.assigned_until,
+            earned_fee_bps: tier.earned_fee_bps,
+        });
+        Ok(())
+    }
+
+    /// Admin: sets the DEX route, USD price feed and slippage bound pots use to
+    /// convert between USDC and `alt_mint` (EURC, a local-currency stablecoin).
+    #[cfg(feature = "full")]
+    pub fn set_stable_route(
+        ctx: Context<SetStableRoute>,
+        dex_program: Pubkey,
+        price_feed: Pubkey,
+        max_slippage_bps: u16,
+        enabled: bool,
+    ) -> Result<()> {
+        require!(DEX_PROGRAM_IDS.contains(&dex_program), EscrowError::InvalidProgram);
+        require!(max_slippage_bps <= 10_000, EscrowError::InvalidAmount);
+        let route = &mut ctx.accounts.route;
+        route.alt_mint = ctx.accounts.alt_mint.key();
+        route.alt_decimals = ctx.accounts.alt_mint.decimals;
+        route.dex_program = dex_program;
+        route.price_feed = price_feed;
+        route.max_slippage_bps = max_slippage_bps;
+        route.enabled = enabled;
+        route.bump = ctx.bumps.route;
+        emit!(StableRouteSet {
+            alt_mint: route.alt_mint,
+            dex_program,
+            price_feed,
+            max_slippage_bps,
+            enabled,
+        });
+        Ok(())
+    }
+
+    /// Swaps `amount_in` USDC into the route's alt stablecoin (`to_alt`) or back,
+    /// through the route's DEX with `ix_data`/`remaining_accounts` passed through.
+    /// The output must clear both `min_out` and the Pyth rate less the route's
+    /// `max_slippage_bps`. USDC leaving for the alt stable is booked as an outflow
+    /// and USDC coming back as an inflow. The alt mint must be registered on the pot.
+    #[cfg(feature = "full")]
+    pub fn convert_stable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConvertStable<'info>>,
+        pot_hash: [u8; 32],
+        to_alt: bool,
+        amount_in: u64,
+        min_out: u64,
+        ix_data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(amount_in > 0, EscrowError::InvalidAmount);
+        let route = &ctx.accounts.route;
+        require!(route.enabled, EscrowError::VenueDisabled);
+        require_keys_eq!(ctx.accounts.dex_program.key(), route.dex_program, EscrowError::InvalidProgram);
+        require_keys_eq!(ctx.accounts.price_feed.key(), route.price_feed, EscrowError::StalePrice);
+        require!(ctx.accounts.vault.is_registered_mint(&route.alt_mint), EscrowError::MintNotRegistered);
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        if to_alt {
+            require!(
+                ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_usdc.amount) >= amount_in,
+                EscrowError::InsufficientFunds
+            );
+        }
+        if let Some(sysvar) = ctx.accounts.instructions_sysvar.as_ref() {
+            check_no_dex_instructions(sysvar, route.dex_program)?;
+        }
+        check_exec_rate(&mut ctx.accounts.vault)?;
+        let (price, expo) = read_pyth_price(&ctx.accounts.price_feed, MAX_SWAP_PRICE_AGE_SECS)?;
+        let (usdc_decimals, alt_decimals) = (ctx.accounts.usdc_mint.decimals, ctx.accounts.route.alt_decimals);
+        let slippage_bps = ctx.accounts.route.max_slippage_bps;
+
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        let alt_before = ctx.accounts.vault_alt.amount;
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        invoke_vault_passthrough(
+            &ctx.accounts.dex_program,
+            ctx.accounts.vault.key(),
+            ctx.remaining_accounts,
+            ix_data,
+            &[seeds],
+        )?;
+        ctx.accounts.vault_usdc.reload()?;
+        ctx.accounts.vault_alt.reload()?;
+        let usdc_after = ctx.accounts.vault_usdc.amount;
+        let alt_after = ctx.accounts.vault_alt.amount;
+
+        let (spent, received, oracle_min) = if to_alt {
+            let spent = usdc_before.saturating_sub(usdc_after);
+            let min = stable_min_out(spent, usdc_decimals, alt_decimals, price, expo, true, slippage_bps)?;
+            (spent, alt_after.saturating_sub(alt_before), min)
+        } else {
+            let spent = alt_before.saturating_sub(alt_after);
+            let min = stable_min_out(spent, alt_decimals, usdc_decimals, price, expo, false, slippage_bps)?;
+            (spent, usdc_after.saturating_sub(usdc_before), min)
+        };
+        require!(spent <= amount_in, EscrowError::ExcessiveSpend);
+        require!(received >= min_out.max(oracle_min), EscrowError::SlippageExceeded);
+        if to_alt {
+            ctx.accounts.vault.book_usdc_out(spent)?;
+        } else {
+            ctx.accounts.vault.book_usdc_in(received)?;
+        }
+
+        emit!(StableConverted {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            alt_mint: ctx.accounts.route.alt_mint,
+            to_alt,
+            amount_in: spent,
+            amount_out: received,
+        });
+        Ok(())
+    }
+
+    /// Withdraws the pot's alt stablecoin to the owner's ATA. Compliance and memo
+    /// thresholds apply to `amount` in the alt mint's units; an active commitment's
+    /// early-withdraw penalty is paid in the alt mint.
+    #[cfg(feature = "full")]
+    pub fn withdraw_stable(
+        ctx: Context<WithdrawStable>,
+        pot_hash: [u8; 32],
+        amount: u64,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        require!(!ctx.accounts.vault.is_frozen(Clock::get()?.unix_timestamp), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        check_attestation(
+            Some(&ctx.accounts.config),
+            ctx.accounts.attestation.as_ref(),
+            ctx.accounts.owner.key(),
+            amount,
+        )?;
+        check_memo(Some(&ctx.accounts.config), amount, &memo_hash)?;
+        let usdc_equivalent = u64::try_from(
+            (amount as u128) * 10u128.pow(6) / 10u128.pow(ctx.accounts.alt_mint.decimals as u32),
+        )
+        .map_err(|_| error!(EscrowError::MathOverflow))?;
+        check_outflow(
+            Some(&ctx.accounts.config),
+            ctx.accounts.outflow_breaker.as_mut(),
+            ctx.accounts.guardian.as_ref(),
+            ctx.accounts.alt_mint.key(),
+            usdc_equivalent,
+        )?;
+        require!(ctx.accounts.vault.is_registered_mint(&ctx.accounts.alt_mint.key()), EscrowError::MintNotRegistered);
+        let is_usdc = ctx.accounts.alt_mint.key() == ctx.accounts.vault.usdc_mint;
+        if is_usdc {
+            require!(
+                ctx.accounts.vault.unlocked_usdc(ctx.accounts.vault_alt.amount) >= amount,
+                EscrowError::InsufficientFunds
+            );
+        }
+
+        let owner_key = ctx.accounts.owner.key();
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+        let penalty = slash_tokens(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_alt,
+            Some(&ctx.accounts.config),
+            ctx.accounts.penalty_alt.as_deref(),
+            &ctx.accounts.token_program,
+            &[seeds],
+            amount,
+        )?;
+        let cpi = Transfer {
+            from: ctx.accounts.vault_alt.to_account_info(),
+            to: ctx.accounts.user_alt.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi, &[seeds]),
+            amount.checked_sub(penalty).ok_or(EscrowError::MathUnderflow)?,
+        )?;
+        if is_usdc {
+            burn_pot_shares(
+                &ctx.accounts.vault,
+                ctx.accounts.share_mint.as_deref(),
+                ctx.accounts.owner_shares.as_deref(),
+                &ctx.accounts.owner,
+                &ctx.accounts.token_program,
+                amount,
+            )?;
+            ctx.accounts.vault.book_usdc_out(amount)?;
+        }
+
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            mint: ctx.accounts.alt_mint.key(),
+            amount,
+            memo_hash,
+        });
+        Ok(())
+    }
+
+    /// Withdrawals above `threshold` must carry a non-zero travel-rule `memo_hash`;
+    /// 0 never requires one.
+    #[cfg(feature = "full")]
+    pub fn set_memo_threshold(ctx: Context<UpdateConfig>, threshold: u64) -> Result<()> {
+        ctx.accounts.config.memo_threshold = threshold;
+        Ok(())
+    }
+
+    /// Beta exposure limits, in USDC units; 0 disables either one.
+    #[cfg(feature = "full")]
+    pub fn set_deposit_limits(ctx: Context<UpdateConfig>, min_deposit_usdc: u64, max_pot_balance_usdc: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.min_deposit_usdc = min_deposit_usdc;
+        config.max_pot_balance_usdc = max_pot_balance_usdc;
+        Ok(())
+    }
+
+    /// Exempts (or re-subjects) one vault from the Config deposit limits.
+    #[cfg(feature = "full")]
+    pub fn set_vault_limits_exempt(ctx: Context<AdminVault>, exempt: bool) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        ctx.accounts.vault.limits_exempt = exempt;
+        emit!(VaultLimitsExemptSet {
+            vault: ctx.accounts.vault.key(),
+            seq: ctx.accounts.vault.seq,
+            exempt,
+        });
+        Ok(())
+    }
+
+    /// Sets the program-wide outflow circuit breaker. A zero `window_secs` turns it
+    /// off; a zero threshold leaves that asset unmetered.
+    #[cfg(feature = "full")]
+    pub fn set_outflow_limits(ctx: Context<UpdateConfig>, limits: OutflowLimits) -> Result<()> {
+        require!(limits.window_secs >= 0, EscrowError::InvalidAmount);
+        ctx.accounts.config.outflow_limits = limits;
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn init_outflow_breaker(ctx: Context<InitOutflowBreaker>) -> Result<()> {
+        ctx.accounts.outflow_breaker.bump = ctx.bumps.outflow_breaker;
+        Ok(())
+    }
+
+    /// Publishes a new per-vault parameter set under the next version; vaults pick
+    /// it up as `migrate_vault_params` reaches them.
+    #[cfg(feature = "full")]
+    pub fn set_vault_params(ctx: Context<UpdateFeeConfig>, params: VaultParams) -> Result<()> {
+        require!(
+            params.performance_fee_bps <= Config::MAX_PERFORMANCE_FEE_BPS,
+            EscrowError::InvalidFee
+        );
+        let config = &mut ctx.accounts.config;
+        config.vault_params_version = config.vault_params_version.checked_add(1).ok_or(EscrowError::MathOverflow)?;
+        config.vault_params = params;
+        emit!(VaultParamsPublished {
+            version: config.vault_params_version,
+            performance_fee_bps: params.performance_fee_bps,
+            external_exec_cooldown_slots: params.external_exec_cooldown_slots,
+        });
+        Ok(())
+    }
+
+    /// Admin crank: applies the current `Config::vault_params` to every vault in
+    /// `remaining_accounts` (writable `pot_vault` PDAs). Vaults already at the
+    /// current version are skipped, so batches can be retried or overlap. Vaults
+    /// created under an older, shorter layout are first grown to `Vault::SPACE`,
+    /// with the admin paying the extra rent.
+    #[cfg(feature = "full")]
+    pub fn migrate_vault_params<'info>(ctx: Context<'_, '_, 'info, 'info, MigrateVaultParams<'info>>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let version = config.vault_params_version;
+        require!(version > 0, EscrowError::NoVaultParams);
+        let params = config.vault_params;
+
+        let mut migrated: u16 = 0;
+        let mut resized: u16 = 0;
+        for info in ctx.remaining_accounts {
+            require!(info.is_writable, EscrowError::BadVaultAccount);
+            if grow_legacy_vault(
+                info,
+                &ctx.accounts.admin.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )? {
+                resized += 1;
+            }
+            let mut vault: Account<'info, Vault> = Account::try_from(info)?;
+            let expected = Pubkey::create_program_address(
+                &[seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref(), &[vault.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| EscrowError::BadVaultAccount)?;
+            require_keys_eq!(expected, *info.key, EscrowError::BadVaultAccount);
+            if vault.params_version >= version {
+                continue;
+            }
+            vault.performance_fee_bps = params.performance_fee_bps;
+            vault.external_exec_cooldown_slots = params.external_exec_cooldown_slots;
+            vault.params_version = version;
+            vault.next_seq();
+            vault.exit(&crate::ID)?;
+            migrated += 1;
+        }
+
+        emit!(VaultParamsMigrated {
+            version,
+            migrated,
+            skipped: ctx.remaining_accounts.len() as u16 - migrated,
+            resized,
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    pub fn set_max_swap_slippage(ctx: Context<UpdateConfig>, bps: u16) -> Result<()> {
+        require!(bps <= 10_000, EscrowError::InvalidAmount);
+        ctx.accounts.config.max_swap_slippage_bps = bps;
+        Ok(())
+    }
+
+    /// Token balances at or below `threshold` (raw units) can be cleared by `sweep_dust`;
+    /// 0 only allows closing empty accounts.
+    #[cfg(feature = "full")]
+    pub fn set_dust_threshold(ctx: Context<UpdateConfig>, threshold: u64) -> Result<()> {
+        ctx.accounts.config.dust_threshold = threshold;
+        Ok(())
+    }
+
+    /// Sets (or rotates) the canonical USDC mint `init_pot_vault` accepts. Existing
+    /// vaults keep the mint they were created with.
+    pub fn set_usdc_mint(ctx: Context<UpdateConfig>, usdc_mint: Pubkey) -> Result<()> {
+        require_keys_neq!(usdc_mint, Pubkey::default(), EscrowError::BadMint);
+        let config = &mut ctx.accounts.config;
+        emit!(UsdcMintSet {
+            previous: config.usdc_mint,
+            usdc_mint,
+        });
+        config.usdc_mint = usdc_mint;
+        Ok(())
+    }
+
+    #[cfg(feature = "oracle")]
+    pub fn set_sol_price_feed(ctx: Context<UpdateConfig>, feed: Pubkey) -> Result<()> {
+        ctx.accounts.config.sol_usd_price_feed = feed;
+        Ok(())
+    }
+
+    #[cfg(feature = "oracle")]
+    pub fn init_snapshot_buffer(ctx: Context<InitSnapshotBuffer>) -> Result<()> {
+        let mut buffer = ctx.accounts.snapshot_buffer.load_init()?;
+        buffer.vault = ctx.accounts.vault.key();
+        buffer.bump = ctx.bumps.snapshot_buffer;
+        Ok(())
+    }
+
+    /// Creates the vault's `ActivityLog`; deposits and withdrawals append to it
+    /// whenever it is passed.
+    pub fn init_activity_log(ctx: Context<InitActivityLog>) -> Result<()> {
+        let mut log = ctx.accounts.activity_log.load_init()?;
+        log.vault = ctx.accounts.vault.key();
+        log.bump = ctx.bumps.activity_log;
+        Ok(())
+    }
+
+    /// Denominates the pot's goal in a reference basket (currencies, gold, ...):
+    /// `goal_units` basket units, each worth the `weight_bps`-weighted sum of the
+    /// components' Pyth USD prices. `snapshot` re-prices it every day.
+    #[cfg(feature = "oracle")]
+    pub fn set_goal_basket(
+        ctx: Context<SetGoalBasket>,
+        pot_hash: [u8; 32],
+        goal_units: u64,
+        components: Vec<BasketComponent>,
+    ) -> Result<()> {
+        require!(goal_units > 0, EscrowError::InvalidAmount);
+        require!(
+            !components.is_empty() && components.len() <= GoalBasket::MAX_COMPONENTS,
+            EscrowError::InvalidBasket
+        );
+        let total: u32 = components.iter().map(|c| c.weight_bps as u32).sum();
+        require!(total == 10_000, EscrowError::InvalidBasket);
+
+        let basket = &mut ctx.accounts.goal_basket;
+        basket.vault = ctx.accounts.vault.key();
+        basket.bump = ctx.bumps.goal_basket;
+        basket.goal_units = goal_units;
+        basket.components = components;
+        Ok(())
+    }
+
+    /// Permissionless daily crank: values the vault (see `value_vault` for the
+    /// expected `remaining_accounts`) and appends the result to its `SnapshotBuffer`.
+    /// SOL is valued at the pod policy's `valuation_mode`, spot when none is passed.
+    /// With a `GoalBasket`, its component price feeds follow the positions in
+    /// `remaining_accounts` and the goal's progress is re-priced and emitted.
+    #[cfg(feature = "oracle")]
+    pub fn snapshot(ctx: Context<Snapshot>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let day = now / SECONDS_PER_DAY;
+        {
+            let buffer = ctx.accounts.snapshot_buffer.load()?;
+            require!(buffer.len == 0 || day > buffer.last_day, EscrowError::SnapshotTooSoon);
+        }
+
+        let positions = SNAPSHOT_POSITION_VENUES.len();
+        require!(ctx.remaining_accounts.len() >= positions, EscrowError::BadVaultAccount);
+        let (position_infos, feed_infos) = ctx.remaining_accounts.split_at(positions);
+        let valuation_mode = match ctx.accounts.pod_policy.as_ref() {
+            Some(p) => p.load()?.valuation_mode,
+            None => VALUATION_SPOT,
+        };
+        let vault = &ctx.accounts.vault;
+        let buckets = value_vault(
+            vault,
+            ctx.accounts.vault_usdc.amount,
+            ctx.accounts.vault_wsol.as_ref().map_or(0, |a| a.amount),
+            position_infos,
+            &ctx.accounts.config,
+            ctx.accounts.sol_price.as_ref(),
+            valuation_mode,
+        )?;
+
+        let total = buckets
+            .iter()
+            .try_fold(0u64, |acc, b| acc.checked_add(*b))
+            .ok_or(EscrowError::MathOverflow)?;
+        let mut weight_bps = [0u16; SNAPSHOT_BUCKETS];
+        if total > 0 {
+            for (w, b) in weight_bps.iter_mut().zip(buckets) {
+                *w = ((b as u128) * 10_000 / total as u128) as u16;
+            }
+        }
+
+        append_snapshot(
+            &ctx.accounts.snapshot_buffer,
+            PortfolioSnapshot {
+                day,
+                total_usd: total,
+                yield_to_date: vault.net_yield_usdc,
+                weight_bps,
+                _padding: [0; 4],
+            },
+        )?;
+
+        if let Some(basket) = ctx.accounts.goal_basket.as_mut() {
+            let goal_usd = basket.goal_usd(feed_infos)?;
+            let progress_bps = if goal_usd == 0 {
+                0
+            } else {
+                u32::try_from((total as u128) * 10_000 / goal_usd as u128).unwrap_or(u32::MAX)
+            };
+            basket.last_goal_usd = goal_usd;
+            basket.last_progress_bps = progress_bps;
+            emit!(GoalProgress {
+                vault: basket.vault,
+                seq: ctx.accounts.vault.seq,
+                day,
+                total_usd: total,
+                goal_usd,
+                progress_bps,
+            });
+        } else {
+            require!(feed_infos.is_empty(), EscrowError::BadVaultAccount);
+        }
+        Ok(())
+    }
+
+    /// Read-only: derived withdrawal limits and yield figures for one vault, returned
+    /// via return data for simulation-based reads.
+    #[cfg(feature = "oracle")]
+    pub fn get_vault_summary(ctx: Context<GetVaultSummary>) -> Result<VaultSummary> {
+        let now = Clock::get()?.unix_timestamp;
+        let v = &ctx.accounts.vault;
+        let frozen = v.is_frozen(now);
+        let vault_info = v.to_account_info();
+        let liquid = vault_info
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(vault_info.data_len()));
+        let unlocked_usdc = v.unlocked_usdc(ctx.accounts.vault_usdc.amount);
+
+        Ok(VaultSummary {
+            withdrawable_lamports: if frozen { 0 } else { liquid },
+            withdrawable_usdc: if frozen { 0 } else { unlocked_usdc },
+            early_withdraw_penalty_bps: if v.is_committed(now) { v.early_withdraw_penalty_bps } else { 0 },
+            frozen_until: v.frozen_until,
+            no_withdraw_until: v.no_withdraw_until,
+            stream_locked_usdc: v.stream_locked_usdc,
+            pending_yield_usdc: v.pending_yield_usdc,
+            net_yield_usdc: v.net_yield_usdc,
+            op_nonce: v.op_nonce,
+        })
+    }
+
+    /// Read-only: the policy in force for a vault (pot override or pod policy) and the
+    /// vault's current drift from each target. Takes the same `remaining_accounts` as
+    /// `snapshot`.
+    #[cfg(feature = "oracle")]
+    pub fn get_policy_effective(ctx: Context<GetPolicyEffective>) -> Result<PolicyEffective> {
+        let view = PolicyView::load(&ctx.accounts, ctx.remaining_accounts)?;
+        let targets = view.drifts(ctx.accounts.vault.usdc_mint);
+
+        Ok(PolicyEffective {
+            overridden: view.overridden,
+            risk_state: view.risk_state,
+            usdc_in_lulo_bps: view.usdc_in_lulo_bps,
+            current_lulo_bps: bps_of(view.lulo(), view.usdc()),
+            total_usd: view.total,
+            targets,
+            venues: view.venues,
+        })
+    }
+
+    /// Read-only crank check for keepers: whether any asset's weight is further from
+    /// its target than the pod policy's `max_drift_bps`, with the per-asset drift.
+    #[cfg(feature = "oracle")]
+    pub fn needs_rebalance(ctx: Context<GetPolicyEffective>) -> Result<RebalanceCheck> {
+        let view = PolicyView::load(&ctx.accounts, ctx.remaining_accounts)?;
+        let max_drift_bps = ctx.accounts.pod_policy.load()?.drift_threshold_bps();
+        let drifts = view.drifts(ctx.accounts.vault.usdc_mint);
+        Ok(RebalanceCheck {
+            needs_rebalance: drifts.iter().any(|d| d.drift_bps.unsigned_abs() > max_drift_bps as u32),
+            max_drift_bps,
+            drifts,
+        })
+    }
+
+    /// Read-only preview of a withdrawal: the same checks and math as `withdraw` /
+    /// `withdraw_usdc` (or the `_with_fee` variants when `fee_amount` is non-zero),
+    /// returned via return data without moving funds. `mint` is the default key for
+    /// SOL.
+    #[cfg(feature = "full")]
+    pub fn quote_withdraw(ctx: Context<QuoteWithdraw>, mint: Pubkey, amount: u64, fee_amount: u64) -> Result<WithdrawQuote> {
+        let now = Clock::get()?.unix_timestamp;
+        let v = &ctx.accounts.vault;
+        let config = &ctx.accounts.config;
+        require!(!v.is_frozen(now), EscrowError::VaultFrozen);
+        require!(amount > 0, EscrowError::InvalidAmount);
+
+        let available = if mint == Pubkey::default() {
+            let info = v.to_account_info();
+            withdrawable_lamports(info.lamports(), Rent::get()?.minimum_balance(Vault::SPACE))
+        } else {
+            require_keys_eq!(mint, v.usdc_mint, EscrowError::BadMint);
+            let vault_usdc = ctx.accounts.vault_usdc.as_ref().ok_or(EscrowError::BadVaultAccount)?;
+            v.unlocked_usdc(vault_usdc.amount)
+        };
+        require!(available >= amount, EscrowError::InsufficientFunds);
+
+        let penalty = early_withdraw_penalty(v, amount)?;
+        let mut split = if fee_amount > 0 {
+            require!(fee_amount <= amount, EscrowError::InvalidFee);
+            let fee = apply_fee_tier(ctx.accounts.fee_tier.as_ref(), amount, fee_amount)?;
+            let referral_bps = ctx.accounts.referral.as_ref().map(|_| config.referral_bps);
+            split_withdraw_fee(amount, fee, referral_bps)?
+        } else {
+            FeeSplit {
+                net: amount,
+                admin_fee: 0,
+                referrer_cut: 0,
+            }
+        };
+        split.net = split.net.checked_sub(penalty).ok_or(EscrowError::PenaltyExceedsPayout)?;
+
+        Ok(WithdrawQuote {
+            amount,
+            penalty,
+            admin_fee: split.admin_fee,
+            referrer_cut: split.referrer_cut,
+            net: split.net,
+            withdrawable_after: available - amount,
+            attestation_required: config.compliance_threshold > 0 && amount > config.compliance_threshold,
+            memo_required: config.memo_threshold > 0 && amount > config.memo_threshold,
+        })
+    }
+
+    /// Read-only: the moves that would bring the vault back to the policy in force,
+    /// in USDC base units, from the same valuation as `get_policy_effective`. Venue
+    /// legs cover the USDC venues with their own valuation bucket and are bounded by
+    /// idle USDC (deposits) and the venue position (withdrawals).
+    #[cfg(feature = "oracle")]
+    pub fn quote_rebalance(ctx: Context<GetPolicyEffective>) -> Result<RebalanceQuote> {
+        let view = PolicyView::load(&ctx.accounts, ctx.remaining_accounts)?;
+        let usdc_mint = ctx.accounts.vault.usdc_mint;
+        Ok(RebalanceQuote {
+            total_usd: view.total,
+            legs: view.asset_legs(usdc_mint),
+            venue_legs: view.venue_legs(usdc_mint),
+        })
+    }
+
+    /// Permissionless: turns the `quote_rebalance` legs into an ordered plan (Lulo
+    /// redemptions, SOL sells, SOL buys, Lulo supplies) that keepers then run one
+    /// step per transaction with `execute_rebalance_step`. Steps are computed here,
+    /// so a keeper can only execute them as written, in order, before `expires_at`.
+    #[cfg(feature = "oracle")]
+    pub fn plan_rebalance(ctx: Context<PlanRebalance>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let plan = &ctx.accounts.plan;
+        require!(
+            plan.vault == Pubkey::default() || plan.is_finished(now),
+            EscrowError::RebalancePlanActive
+        );
+
+        let policy = &ctx.accounts.policy;
+        let view = PolicyView::load(policy, ctx.remaining_accounts)?;
+        let usdc_mint = policy.vault.usdc_mint;
+        let venue_legs = view.venue_legs(usdc_mint);
+        let lulo = venue_legs.iter().find(|l| l.venue_id == VENUE_LULO).map_or(0, |l| l.delta_usd);
+        let sol = view
+            .asset_legs(usdc_mint)
+            .iter()
+            .find(|l| l.mint == native_mint::ID)
+            .map_or(0, |l| l.delta_usd);
+        let sell_lamports = if sol < 0 {
+            let usdc_per_sol = sol_value_usdc(&policy.config, policy.sol_price.as_ref(), LAMPORTS_PER_SOL)?;
+            oracle_min_out(sol.unsigned_abs(), LAMPORTS_PER_SOL, usdc_per_sol, 0)?
+        } else {
+            0
+        };
+
+        let mut steps = Vec::new();
+        let mut push = |kind: u8, amount: u64| {
+            if amount > 0 {
+                steps.push(RebalanceStep { kind, amount, done: false });
+            }
+        };
+        push(REBALANCE_REDEEM, lulo.min(0).unsigned_abs());
+        push(REBALANCE_SELL_SOL, sell_lamports);
+        push(REBALANCE_BUY_SOL, sol.max(0) as u64);
+        push(REBALANCE_SUPPLY, lulo.max(0) as u64);
+        require!(!steps.is_empty(), EscrowError::NothingToRebalance);
+
+        let plan = &mut ctx.accounts.plan;
+        plan.vault = policy.vault.key();
+        plan.created_at = now;
+        plan.expires_at = now + RebalancePlan::TTL_SECS;
+        plan.next_step = 0;
+        plan.bump = ctx.bumps.plan;
+        plan.steps = steps;
+
+        emit!(RebalancePlanned {
+            vault: plan.vault,
+            seq: policy.vault.seq,
+            steps: plan.steps.len() as u8,
+            expires_at: plan.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Runs step `index` of the vault's `RebalancePlan` through `target_program`
+    /// (Lulo for redeem/supply, an allowlisted DEX for swaps) with `ix_data` and the
+    /// remaining accounts. Steps must go in order; the CPI has to move exactly the
+    /// planned amount in, and swaps must clear the oracle price less
+    /// `Config::max_swap_slippage_bps`.
+    #[cfg(feature = "oracle")]
+    pub fn execute_rebalance_step<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteRebalanceStep<'info>>,
+        index: u8,
+        ix_data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.vault.next_seq();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.vault.is_frozen(now), EscrowError::VaultFrozen);
+        let plan = &ctx.accounts.plan;
+        require!(now < plan.expires_at, EscrowError::RebalancePlanExpired);
+        require!(index == plan.next_step, EscrowError::RebalanceStepOutOfOrder);
+        let step = *plan
+            .steps
+            .get(index as usize)
+            .ok_or(EscrowError::RebalanceStepOutOfOrder)?;
+        check_exec_rate(&mut ctx.accounts.vault)?;
+
+        let program = ctx.accounts.target_program.key();
+        let is_venue_step = matches!(step.kind, REBALANCE_REDEEM | REBALANCE_SUPPLY);
+        if is_venue_step {
+            require_keys_eq!(program, LULO_PROGRAM_ID, EscrowError::InvalidProgram);
+        } else {
+            require!(DEX_PROGRAM_IDS.contains(&program), EscrowError::InvalidProgram);
+        }
+
+        let vault_key = ctx.accounts.vault.key();
+        let owner_key = ctx.accounts.vault.owner;
+        let vault_tokens = [
+            ctx.accounts.vault_usdc.key(),
+            get_associated_token_address(&vault_key, &native_mint::ID),
+        ];
+        for acc in ctx.remaining_accounts.iter() {
+            check_passthrough_account(acc, vault_key, owner_key, &vault_tokens)?;
+        }
+
+        let pot_hash = ctx.accounts.vault.pot_hash;
+        let bump = ctx.accounts.vault.bump;
+        let seeds: &[&[u8]] = &[seeds::POT_VAULT, owner_key.as_ref(), pot_hash.as_ref(), &[bump]];
+
+        if step.kind == REBALANCE_SELL_SOL {
+            // DEX routes sell from wSOL, so wrap whatever the ATA is short first.
+            let wsol = ctx.accounts.vault_wsol.as_ref().ok_or(EscrowError::BadVaultAccount)?;
+            let wrap = wsol_shortfall(step.amount, wsol.amount);
+            if wrap > 0 {
+                let vault_info = ctx.accounts.vault.to_account_info();
+                let min = Rent::get()?.minimum_balance(vault_info.data_len());
+                require!(withdrawable_lamports(vault_info.lamports(), min) >= wrap, EscrowError::InsufficientFunds);
+                transfer_lamports(&vault_info, &wsol.to_account_info(), wrap)?;
+                token::sync_native(CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    SyncNative {
+                        account: wsol.to_account_info(),
+                    },
+                ))?;
+            }
+        }
+
+        if let Some(wsol) = ctx.accounts.vault_wsol.as_mut() {
+            wsol.reload()?;
+        }
+        let usdc_before = ctx.accounts.vault_usdc.amount;
+        let wsol_before = ctx.accounts.vault_wsol.as_ref().map_or(0, |w| w.amount);
+
+        invoke_vault_passthrough(
+            &ctx.accounts.target_program,
+            vault_key,
+            ctx.remaining_accounts,
+            ix_data,
+            &[seeds],
+        )?;
+
+        ctx.accounts.vault_usdc.reload()?;
+        if let Some(wsol) = ctx.accounts.vault_wsol.as_mut() {
+            wsol.reload()?;
+        }
+        let usdc_after = ctx.accounts.vault_usdc.amount;
+        let wsol_after = ctx.accounts.vault_wsol.as_ref().map_or(0, |w| w.amount);
+        let slippage_bps = ctx.accounts.config.swap_slippage_bps();
+
+        let amount_out = match step.kind {
+            REBALANCE_REDEEM | REBALANCE_SUPPLY => {
+                let (usdc_out, usdc_in) = (usdc_before.saturating_sub(usdc_after), usdc_after.saturating_sub(usdc_before));
+                if step.kind == REBALANCE_SUPPLY {
+                    require!(usdc_out == step.amount, EscrowError::RebalanceStepMismatch);
+                } else {
+                    require!(usdc_out == 0 && usdc_in >= step.amount, EscrowError::RebalanceStepMismatch);
+                }
+                let position = ctx.accounts.position.as_mut().ok_or(EscrowError::BadVaultAccount)?;
+                if position.vault == Pubkey::default() {
+                    position.vault = vault_key;
+                    position.venue_id = VENUE_LULO;
+                    position.venue_account = LULO_PROGRAM_ID;
+                    position.bump = ctx.bumps.position.unwrap_or_default();
+                }
+                let principal_out = position.principal.min(usdc_in);
+                let realized = usdc_in - principal_out;
+                position.principal = position
+                    .principal
+                    .checked_sub(principal_out)
+                    .and_then(|v| v.checked_add(usdc_out))
+                    .ok_or(EscrowError::MathOverflow)?;
+                position.shares = position.principal;
+                position.last_update = now;
+                let principal = position.principal;
+                if realized > 0 {
+                    let v = &mut ctx.accounts.vault;
+                    v.pending_yield_usdc = v.pending_yield_usdc.checked_add(realized).ok_or(EscrowError::MathOverflow)?;
+                    book_yield(v, VENUE_LULO, realized, principal)?;
+                }
+                usdc_out.max(usdc_in)
+            }
+            _ => {
+                let usdc_per_sol = sol_swap_rate(&ctx.accounts.config, ctx.accounts.sol_price.as_ref())?;
+                let (spent, received, min_out) = if step.kind == REBALANCE_SELL_SOL {
+                    let spent = wsol_before.saturating_sub(wsol_after);
+                    let received = usdc_after.saturating_sub(usdc_before);
+                    (spent, received, oracle_min_out(spent, usdc_per_sol, LAMPORTS_PER_SOL, slippage_bps)?)
+                } else {
+                    let spent = usdc_before.saturating_sub(usdc_after);
+                    let received = wsol_after.saturating_sub(wsol_before);
+                    (spent, received, oracle_min_out(spent, LAMPORTS_PER_SOL, usdc_per_sol, slippage_bps)?)
+                };
+                require!(spent == step.amount, EscrowError::RebalanceStepMismatch);
+                require!(received >= min_out, EscrowError::SlippageExceeded);
+                received
+            }
+        };
+
+        let plan = &mut ctx.accounts.plan;
+        plan.steps[index as usize].done = true;
+        plan.next_step += 1;
+
+        emit!(RebalanceStepExecuted {
+            vault: vault_key,
+            seq: ctx.accounts.vault.seq,
+            index,
+            kind: step.kind,
+            amount_in: step.amount,
+            amount_out,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a compressed SOL-only pot: a Light Protocol compressed account at the
+    /// address derived from `[COMPRESSED_VAULT, owner, pot_hash]`, with its lamports
+    /// held in the shared `CompressedPool`. No rent is locked per pot. The Light
+    /// system accounts come in `remaining_accounts` and `proof` is the indexer's
+    /// non-inclusion proof for the new address.
+    #[cfg(feature = "compressed")]
+    pub fn open_compressed_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OpenCompressedVault<'info>>,
+        pot_hash: [u8; 32],
+        proof: ValidityProof,
+        address_tree_info: PackedAddressTreeInfo,
+        output_tree_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.pool.bump = ctx.bumps.pool;
+        let owner_key = ctx.accounts.owner.key();
+        let light = CpiAccounts::new(ctx.accounts.owner.as_ref(), ctx.remaining_accounts, LIGHT_CPI_SIGNER);
+        let tree = address_tree_info
+            .get_tree_pubkey(&light)
+            .map_err(|_| error!(EscrowError::BadVaultAccount))?;
+        let (address, address_seed) = derive_address(
+            &[seeds::COMPRESSED_VAULT, owner_key.as_ref(), pot_hash.as_ref()],
+            &tree,
+            &crate::ID,
+        );
+
+        let mut vault = LightAccount::<'_, CompressedVault>::new_init(&crate::ID, Some(address), output_tree_index);
+        vault.owner = owner_key;
+        vault.pot_hash = pot_hash;
+        CpiInputs::new_with_address(
+            proof,
+            vec![vault.to_account_info().map_err(ProgramError::from)?],
+            vec![address_tree_info.into_new_address_params_packed(address_seed)],
+        )
+        .invoke_light_system_program(light)
+        .map_err(ProgramError::from)?;
+
+        emit!(CompressedVaultOpened {
+            owner: owner_key,
+            vault: Pubkey::new_from_array(address),
+            pot_hash,
+        });
+        Ok(())
+    }
+
+    /// Deposits `lamports` from the owner into a compressed pot. `vault` is the
+    /// account's current state, proven by `proof` against the state tree in `meta`.
+    #[cfg(feature = "compressed")]
+    pub fn credit_compressed_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompressedVaultOp<'info>>,
+        proof: ValidityProof,
+        meta: CompressedAccountMeta,
+        vault: CompressedVault,
+        lamports: u64,
+    ) -> Result<()> {
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        let owner_key = ctx.accounts.owner.key();
+        check_attestation(ctx.accounts.config.as_ref(), ctx.accounts.attestation.as_ref(), owner_key, lamports)?;
+        let balance = vault.lamports.checked_add(lamports).ok_or(EscrowError::MathOverflow)?;
+
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &owner_key,
+            &ctx.accounts.pool.key(),
+            lamports,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.pool.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        let pool = &mut ctx.accounts.pool;
+        pool.total_lamports = pool.total_lamports.checked_add(lamports).ok_or(EscrowError::MathOverflow)?;
+
+        write_compressed_vault(&ctx.accounts.owner, ctx.remaining_accounts, proof, &meta, vault, Some(balance))?;
+        emit!(CompressedVaultCredited {
+            vault: Pubkey::new_from_array(meta.address),
+            amount: lamports,
+            balance,
+        });
+        Ok(())
+    }
+
+    /// Withdraws `lamports` from a compressed pot to the owner; proven like
+    /// `credit_compressed_vault`.
+    #[cfg(feature = "compressed")]
+    pub fn debit_compressed_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompressedVaultOp<'info>>,
+        proof: ValidityProof,
+        meta: CompressedAccountMeta,
+        vault: CompressedVault,
+        lamports: u64,
+    ) -> Result<()> {
+        require!(lamports > 0, EscrowError::InvalidAmount);
+        let balance = vault.lamports.checked_sub(lamports).ok_or(EscrowError::InsufficientFunds)?;
+        pay_out_compressed(ctx.accounts, lamports)?;
+
+        write_compressed_vault(&ctx.accounts.owner, ctx.remaining_accounts, proof, &meta, vault, Some(balance))?;
+        emit!(CompressedVaultDebited {
+            vault: Pubkey::new_from_array(meta.address),
+            amount: lamports,
+            balance,
+        });
+        Ok(())
+    }
+
+    /// Pays out whatever a compressed pot still holds and nullifies its account.
+    #[cfg(feature = "compressed")]
+    pub fn close_compressed_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CompressedVaultOp<'info>>,
+        proof: ValidityProof,
+        meta: CompressedAccountMeta,
+        vault: CompressedVault,
+    ) -> Result<()> {
+        let lamports = vault.lamports;
+        if lamports > 0 {
+            pay_out_compressed(ctx.accounts, lamports)?;
+        }
+
+        write_compressed_vault(&ctx.accounts.owner, ctx.remaining_accounts, proof, &meta, vault, None)?;
+        emit!(CompressedVaultClosed {
+            vault: Pubkey::new_from_array(meta.address),
+            owner: ctx.accounts.owner.key(),
+            lamports,
+        });
+        Ok(())
+    }
+}
+
+/// Policy in force for a vault (pot override, else pod policy) and its valuation,
+/// shared by `get_policy_effective` and `quote_rebalance`.
+struct PolicyView {
+    overridden: bool,
+    risk_state: u8,
+    usdc_in_lulo_bps: u16,
+    assets: Vec<AssetTarget>,
+    venues: Vec<VenueAllocation>,
+    buckets: [u64; SNAPSHOT_BUCKETS],
+    total: u64,
+}
+
+impl PolicyView {
+    fn load<'info>(a: &GetPolicyEffective<'info>, positions: &[AccountInfo<'info>]) -> Result<Self> {
+        let (overridden, risk_state, usdc_in_lulo_bps, assets, venues) = match a.pot_policy.as_ref() {
+            Some(p) if p.override_enabled => {
+                let venues = lulo_only_allocation(&p.assets, p.usdc_in_lulo_bps);
+                (true, p.risk_state, p.usdc_in_lulo_bps, p.assets.clone(), venues)
+            }
+            _ => {
+                let p = a.pod_policy.load()?;
+                (false, p.risk_state, p.usdc_in_lulo_bps, p.targets(), p.venue_allocations())
+            }
+        };
+        let buckets = value_vault(
+            &a.vault,
+            a.vault_usdc.amount,
+            a.vault_wsol.as_ref().map_or(0, |w| w.amount),
+            positions,
+            &a.config,
+            a.sol_price.as_ref(),
+            VALUATION_SPOT,
+        )?;
+        let total = buckets
+            .iter()
+            .try_fold(0u64, |acc, b| acc.checked_add(*b))
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(Self { overridden, risk_state, usdc_in_lulo_bps, assets, venues, buckets, total })
+    }
+
+    fn sol(&self) -> u64 {
+        self.buckets[SNAPSHOT_BUCKETS - 1]
+    }
+
+    /// Idle USDC plus every USDC venue position.
+    fn usdc(&self) -> u64 {
+        self.total - self.sol()
+    }
+
+    fn lulo(&self) -> u64 {
+        self.buckets[1 + VENUE_LULO as usize]
+    }
+
+    fn drifts(&self, usdc_mint: Pubkey) -> Vec<AssetDrift> {
+        self.assets
+            .iter()
+            .map(|a| {
+                let current_bps = bps_of(self.held(a.mint, usdc_mint), self.total);
+                AssetDrift {
+                    mint: a.mint,
+                    target_bps: a.bps,
+                    current_bps,
+                    drift_bps: current_bps as i32 - a.bps as i32,
+                }
+            })
+            .collect()
+    }
+
+    fn asset_legs(&self, usdc_mint: Pubkey) -> Vec<RebalanceLeg> {
+        self.assets
+            .iter()
+            .map(|a| {
+                let current_usd = self.held(a.mint, usdc_mint);
+                let target_usd = ((self.total as u128) * (a.bps as u128) / 10_000) as u64;
+                RebalanceLeg {
+                    mint: a.mint,
+                    current_usd,
+                    target_usd,
+                    delta_usd: target_usd as i64 - current_usd as i64,
+                }
+            })
+            .collect()
+    }
+
+    /// USDC venue legs; supplies are bounded by idle USDC, redemptions by the position.
+    fn venue_legs(&self, usdc_mint: Pubkey) -> Vec<VenueLeg> {
+        self.venues
+            .iter()
+            .filter(|a| a.mint == usdc_mint)
+            .filter_map(|a| {
+                let current_usd = self.venue(a.venue_id)?;
+                let target_usd = ((self.total as u128) * (a.bps as u128) / 10_000) as u64;
+                let delta_usd = if target_usd >= current_usd {
+                    (target_usd - current_usd).min(self.buckets[0]) as i64
+                } else {
+                    -((current_usd - target_usd) as i64)
+                };
+                Some(VenueLeg {
+                    venue_id: a.venue_id,
+                    current_usd,
+                    target_usd,
+                    delta_usd,
+                })
+            })
+            .collect()
+    }
+
+    /// Position value of a USDC venue; `None` for venues valued inside SOL.
+    fn venue(&self, venue_id: u8) -> Option<u64> {
+        if venue_id == VENUE_SANCTUM || !SNAPSHOT_POSITION_VENUES.contains(&venue_id) {
+            return None;
+        }
+        Some(self.buckets[1 + venue_id as usize])
+    }
+
+    /// Value held in `mint`; only USDC and SOL are valued.
+    fn held(&self, mint: Pubkey, usdc_mint: Pubkey) -> u64 {
+        if mint == usdc_mint {
+            self.usdc()
+        } else if mint == native_mint::ID {
+            self.sol()
+        } else {
+            0
+        }
+    }
+}
+
+fn bps_of(part: u64, whole: u64) -> u16 {
+    if whole == 0 {
+        0
+    } else {
+        ((part as u128) * 10_000 / whole as u128) as u16
+    }
+}
+
+/// How a fee-bearing withdrawal is paid out; the parts always sum to the amount
+/// debited from the vault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub net: u64,
+    pub admin_fee: u64,
+    pub referrer_cut: u64,
+}
+
+/// `referral_bps` is the Config referral share when the owner has a referrer.
+pub fn split_withdraw_fee(amount: u64, fee: u64, referral_bps: Option<u16>) -> Result<FeeSplit> {
+    require!(fee <= amount, EscrowError::InvalidFee);
+    let referrer_cut = match referral_bps {
+        Some(bps) => referral_share(fee, bps)?,
+        None => 0,
+    };
+    Ok(FeeSplit {
+        net: amount - fee,
+        admin_fee: fee.checked_sub(referrer_cut).ok_or(EscrowError::MathUnderflow)?,
+        referrer_cut,
+    })
+}
+
+/// Lamports a program-owned account can release while staying rent exempt.
+pub fn withdrawable_lamports(balance: u64, rent_minimum: u64) -> u64 {
+    balance.saturating_sub(rent_minimum)
+}
+
+pub fn referral_share(fee: u64, referral_bps: u16) -> Result<u64> {
+    let share = (fee as u128)
+        .checked_mul(referral_bps as u128)
+        .ok_or(EscrowError::MathOverflow)?
+        / 10_000;
+    Ok(share as u64)
+}
+
+/// Moves lamports out of a program-owned account by mutating balances directly.
+/// SystemProgram::transfer cannot debit accounts that carry data.
+fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+    require!(**from_lamports >= amount, EscrowError::InsufficientFunds);
+    **from_lamports = from_lamports.checked_sub(amount).ok_or(EscrowError::MathUnderflow)?;
+    **to_lamports = to_lamports.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+    Ok(())
+}
+
+/// Moves `lamports` of a compressed pot from the shared pool to its owner, through
+/// the same compliance and outflow gates as a `Vault` withdrawal.
+#[cfg(feature = "compressed")]
+fn pay_out_compressed(accounts: &mut CompressedVaultOp<'_>, lamports: u64) -> Result<()> {
+    let owner_key = accounts.owner.key();
+    check_attestation(accounts.config.as_ref(), accounts.attestation.as_ref(), owner_key, lamports)?;
+    check_outflow(
+        accounts.config.as_ref(),
+        accounts.outflow_breaker.as_mut(),
+        accounts.guardian.as_ref(),
+        Pubkey::default(),
+        lamports,
+    )?;
+    let pool = &mut accounts.pool;
+    pool.total_lamports = pool.total_lamports.checked_sub(lamports).ok_or(EscrowError::MathUnderflow)?;
+    transfer_lamports(&pool.to_account_info(), &accounts.owner.to_account_info(), lamports)
+}
+
+/// Replaces the owner's compressed pot `current` with one holding `lamports`, or
+/// nullifies it when `lamports` is `None`. The Light system program checks
+/// `current` against `proof`, so its fields can be trusted once the CPI succeeds.
+#[cfg(feature = "compressed")]
+fn write_compressed_vault<'info>(
+    owner: &Signer<'info>,
+    light_accounts: &[AccountInfo<'info>],
+    proof: ValidityProof,
+    meta: &CompressedAccountMeta,
+    current: CompressedVault,
+    lamports: Option<u64>,
+) -> Result<()> {
+    require_keys_eq!(current.owner, owner.key(), EscrowError::Unauthorized);
+    let info = match lamports {
+        Some(lamports) => {
+            let mut vault = LightAccount::<'_, CompressedVault>::new_mut(&crate::ID, meta, current)
+                .map_err(ProgramError::from)?;
+            vault.lamports = lamports;
+            vault.to_account_info()
+        }
+        None => LightAccount::<'_, CompressedVault>::new_close(&crate::ID, meta, current)
+            .map_err(ProgramError::from)?
+            .to_account_info(),
+    }
+    .map_err(ProgramError::from)?;
+    let light = CpiAccounts::new(owner.as_ref(), light_accounts, LIGHT_CPI_SIGNER);
+    CpiInputs::new(proof, vec![info])
+        .invoke_light_system_program(light)
+        .map_err(ProgramError::from)?;
+    Ok(())
+}
+
+/// Account set shared by the Kamino CPIs, so owner-signed and crank instructions
+/// can drive the same helpers.
+struct KaminoCpi<'info> {
+    kamino_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    reserve: AccountInfo<'info>,
+    lending_market: AccountInfo<'info>,
+    lending_market_authority: AccountInfo<'info>,
+    usdc_mint: AccountInfo<'info>,
+    reserve_liquidity_supply: AccountInfo<'info>,
+    reserve_collateral_mint: AccountInfo<'info>,
+    vault_usdc: AccountInfo<'info>,
+    vault_collateral: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    instructions_sysvar: AccountInfo<'info>,
+}
+
+macro_rules! impl_kamino_cpi {
+    ($accounts:ident) => {
+        impl<'info> $accounts<'info> {
+            fn kamino_cpi(&self) -> KaminoCpi<'info> {
+                KaminoCpi {
+                    kamino_program: self.kamino_program.to_account_info(),
+                    vault: self.vault.to_account_info(),
+                    reserve: self.reserve.to_account_info(),
+                    lending_market: self.lending_market.to_account_info(),
+                    lending_market_authority: self.lending_market_authority.to_account_info(),
+                    usdc_mint: self.usdc_mint.to_account_info(),
+                    reserve_liquidity_supply: self.reserve_liquidity_supply.to_account_info(),
+                    reserve_collateral_mint: self.reserve_collateral_mint.to_account_info(),
+                    vault_usdc: self.vault_usdc.to_account_info(),
+                    vault_collateral: self.vault_collateral.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                    instructions_sysvar: self.instructions_sysvar.to_account_info(),
+                }
+            }
+        }
+    };
+}
+
+impl_kamino_cpi!(KaminoLend);
+impl_kamino_cpi!(Compound);
+
+fn kamino_deposit_cpi(a: &KaminoCpi, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    let mut data = KAMINO_DEPOSIT_IX.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    let ix = Instruction {
+        program_id: a.kamino_program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(a.vault.key(), true),
+            AccountMeta::new(a.reserve.key(), false),
+            AccountMeta::new_readonly(a.lending_market.key(), false),
+            AccountMeta::new_readonly(a.lending_market_authority.key(), false),
+            AccountMeta::new_readonly(a.usdc_mint.key(), false),
+            AccountMeta::new(a.reserve_liquidity_supply.key(), false),
+            AccountMeta::new(a.reserve_collateral_mint.key(), false),
+            AccountMeta::new(a.vault_usdc.key(), false),
+            AccountMeta::new(a.vault_collateral.key(), false),
+            AccountMeta::new_readonly(a.token_program.key(), false),
+            AccountMeta::new_readonly(a.token_program.key(), false),
+            AccountMeta::new_readonly(a.instructions_sysvar.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            a.vault.to_account_info(),
+            a.reserve.to_account_info(),
+            a.lending_market.to_account_info(),
+            a.lending_market_authority.to_account_info(),
+            a.usdc_mint.to_account_info(),
+            a.reserve_liquidity_supply.to_account_info(),
+            a.reserve_collateral_mint.to_account_info(),
+            a.vault_usdc.to_account_info(),
+            a.vault_collateral.to_account_info(),
+            a.token_program.to_account_info(),
+            a.instructions_sysvar.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// `deposit_for_burn { amount, destination_domain, mint_recipient }` with the vault
+/// as burn authority and the owner paying the message account's rent.
+fn cctp_deposit_for_burn(
+    a: &WithdrawUsdcCrosschain,
+    amount: u64,
+    destination_domain: u32,
+    mint_recipient: [u8; 32],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = CCTP_DEPOSIT_FOR_BURN_IX.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&destination_domain.to_le_bytes());
+    data.extend_from_slice(&mint_recipient);
+    let ix = Instruction {
+        program_id: CCTP_TOKEN_MESSENGER_MINTER_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(a.vault.key(), true),
+            AccountMeta::new(a.owner.key(), true),
+            AccountMeta::new_readonly(a.sender_authority.key(), false),
+            AccountMeta::new(a.vault_usdc.key(), false),
+            AccountMeta::new(a.message_transmitter.key(), false),
+            AccountMeta::new_readonly(a.token_messenger.key(), false),
+            AccountMeta::new_readonly(a.remote_token_messenger.key(), false),
+            AccountMeta::new_readonly(a.token_minter.key(), false),
+            AccountMeta::new(a.local_token.key(), false),
+            AccountMeta::new(a.usdc_mint.key(), false),
+            AccountMeta::new(a.message_sent_event_data.key(), true),
+            AccountMeta::new_readonly(a.message_transmitter_program.key(), false),
+            AccountMeta::new_readonly(a.token_messenger_minter_program.key(), false),
+            AccountMeta::new_readonly(a.token_program.key(), false),
+            AccountMeta::new_readonly(a.system_program.key(), false),
+            AccountMeta::new_readonly(a.event_authority.key(), false),
+            AccountMeta::new_readonly(a.token_messenger_minter_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            a.vault.to_account_info(),
+            a.owner.to_account_info(),
+            a.sender_authority.to_account_info(),
+            a.vault_usdc.to_account_info(),
+            a.message_transmitter.to_account_info(),
+            a.token_messenger.to_account_info(),
+            a.remote_token_messenger.to_account_info(),
+            a.token_minter.to_account_info(),
+            a.local_token.to_account_info(),
+            a.usdc_mint.to_account_info(),
+            a.message_sent_event_data.to_account_info(),
+            a.message_transmitter_program.to_account_info(),
+            a.token_messenger_minter_program.to_account_info(),
+            a.token_program.to_account_info(),
+            a.system_program.to_account_info(),
+            a.event_authority.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+fn kamino_redeem_cpi(a: &KaminoCpi, shares: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    let mut data = KAMINO_REDEEM_IX.to_vec();
+    data.extend_from_slice(&shares.to_le_bytes());
+    let ix = Instruction {
+        program_id: a.kamino_program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(a.vault.key(), true),
+            AccountMeta::new_readonly(a.lending_market.key(), false),
+            AccountMeta::new(a.reserve.key(), false),
+            AccountMeta::new_readonly(a.lending_market_authority.key(), false),
+            AccountMeta::new_readonly(a.usdc_mint.key(), false),
+            AccountMeta::new(a.reserve_collateral_mint.key(), false),
+            AccountMeta::new(a.reserve_liquidity_supply.key(), false),
+            AccountMeta::new(a.vault_collateral.key(), false),
+            AccountMeta::new(a.vault_usdc.key(), false),
+            AccountMeta::new_readonly(a.token_program.key(), false),
+            AccountMeta::new_readonly(a.token_program.key(), false),
+            AccountMeta::new_readonly(a.instructions_sysvar.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            a.vault.to_account_info(),
+            a.lending_market.to_account_info(),
+            a.reserve.to_account_info(),
+            a.lending_market_authority.to_account_info(),
+            a.usdc_mint.to_account_info(),
+            a.reserve_collateral_mint.to_account_info(),
+            a.reserve_liquidity_supply.to_account_info(),
+            a.vault_collateral.to_account_info(),
+            a.vault_usdc.to_account_info(),
+            a.token_program.to_account_info(),
+            a.instructions_sysvar.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Reads `(total_lamports, pool_token_supply)` from an SPL `StakePool` whose pool
+/// mint is `lst_mint`.
+fn read_stake_pool_rate(stake_pool: &AccountInfo, lst_mint: Pubkey) -> Result<(u64, u64)> {
+    let data = stake_pool.try_borrow_data()?;
+    require!(data.len() >= STAKE_POOL_SUPPLY_OFFSET + 8, EscrowError::BadVaultAccount);
+    require!(
+        data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32] == lst_mint.to_bytes(),
+        EscrowError::BadMint
+    );
+    let read = |o: usize| u64::from_le_bytes(data[o..o + 8].try_into().unwrap());
+    let (total_lamports, pool_token_supply) = (read(STAKE_POOL_TOTAL_LAMPORTS_OFFSET), read(STAKE_POOL_SUPPLY_OFFSET));
+    require!(total_lamports > 0 && pool_token_supply > 0, EscrowError::InvalidAmount);
+    Ok((total_lamports, pool_token_supply))
+}
+
+/// Least acceptable output for `amount_in` at the `out_per / in_per` rate, less
+/// `slippage_bps`.
+pub fn oracle_min_out(amount_in: u64, out_per: u64, in_per: u64, slippage_bps: u16) -> Result<u64> {
+    require!(in_per > 0 && slippage_bps <= 10_000, EscrowError::InvalidAmount);
+    let expected = (amount_in as u128)
+        .checked_mul(out_per as u128)
+        .ok_or(EscrowError::MathOverflow)?
+        / in_per as u128;
+    let min = expected * (10_000 - slippage_bps as u128) / 10_000;
+    u64::try_from(min).map_err(|_| error!(EscrowError::MathOverflow))
+}
+
+/// Caps a withdrawal fee at the owner's `FeeTier`, if one is passed and active.
+fn apply_fee_tier(tier: Option<&Account<FeeTier>>, amount: u64, fee: u64) -> Result<u64> {
+    let Some(tier) = tier else {
+        return Ok(fee);
+    };
+    let cap_bps = tier.max_fee_bps(Clock::get()?.unix_timestamp);
+    if cap_bps >= 10_000 {
+        return Ok(fee);
+    }
+    let cap = ((amount as u128) * (cap_bps as u128) / 10_000) as u64;
+    Ok(fee.min(cap))
+}
+
+/// Minimum output of a stable-to-stable swap of `amount_in` at the alt stable's
+/// USD price `price * 10^expo`, less `slippage_bps`. `to_alt` divides by the price
+/// (USDC in), otherwise multiplies (alt stable in).
+pub fn stable_min_out(
+    amount_in: u64,
+    in_decimals: u8,
+    out_decimals: u8,
+    price: i64,
+    expo: i32,
+    to_alt: bool,
+    slippage_bps: u16,
+) -> Result<u64> {
+    require!(price > 0 && slippage_bps <= 10_000, EscrowError::InvalidAmount);
+    let scale = |e: i32| 10u128.checked_pow(e.unsigned_abs()).ok_or(EscrowError::MathOverflow);
+    let mut num = (amount_in as u128).checked_mul(scale(out_decimals as i32)?).ok_or(EscrowError::MathOverflow)?;
+    let mut den = scale(in_decimals as i32)?;
+    let price_scale = scale(expo)?;
+    let (price_num, price_den) = if expo >= 0 {
+        ((price as u128) * price_scale, 1)
+    } else {
+        (price as u128, price_scale)
+    };
+    if to_alt {
+        num = num.checked_mul(price_den).ok_or(EscrowError::MathOverflow)?;
+        den = den.checked_mul(price_num).ok_or(EscrowError::MathOverflow)?;
+    } else {
+        num = num.checked_mul(price_num).ok_or(EscrowError::MathOverflow)?;
+        den = den.checked_mul(price_den).ok_or(EscrowError::MathOverflow)?;
+    }
+    let min = num / den * (10_000 - slippage_bps as u128) / 10_000;
+    u64::try_from(min).map_err(|_| error!(EscrowError::MathOverflow))
+}
+
+/// Rejects the transaction if any other top-level instruction calls a DEX program,
+/// a stake-pool program or `venue_program` directly: a swap that shares its
+/// transaction with those is the usual shape of a sandwich.
+fn check_no_dex_instructions(instructions_sysvar: &AccountInfo, venue_program: Pubkey) -> Result<()> {
+    let current = load_current_index_checked(instructions_sysvar)? as usize;
+    let count = {
+        let data = instructions_sysvar.try_borrow_data()?;
+        require!(data.len() >= 2, EscrowError::BadVaultAccount);
+        u16::from_le_bytes([data[0], data[1]]) as usize
+    };
+    for index in (0..count).filter(|i| *i != current) {
+        let program_id = load_instruction_at_checked(index, instructions_sysvar)?.program_id;
+        require!(
+            program_id != venue_program
+                && !DEX_PROGRAM_IDS.contains(&program_id)
+                && !STAKE_POOL_PROGRAM_IDS.contains(&program_id),
+            EscrowError::SandwichRisk
+        );
+    }
+    Ok(())
+}
+
+/// Fails if the marginfi account carries any liability, keeping pots lend-only.
+/// Returns the integer part of the asset shares held in `bank`.
+fn assert_marginfi_lend_only(marginfi_account: &AccountInfo, bank: Pubkey) -> Result<u64> {
+    // Layout: discriminator, group, authority, then 16 balances of 104 bytes:
+    // active (1), bank_pk (32), padding (7), asset_shares (I80F48), liability_shares (I80F48), ...
+    const BALANCES_OFFSET: usize = 8 + 32 + 32;
+    const BALANCE_LEN: usize = 104;
+    const MAX_BALANCES: usize = 16;
+
+    let data = marginfi_account.try_borrow_data()?;
+    require!(
+        data.len() >= BALANCES_OFFSET + BALANCE_LEN * MAX_BALANCES,
+        EscrowError::BadVaultAccount
+    );
+
+    let mut shares = 0u64;
+    for i in 0..MAX_BALANCES {
+        let b = &data[BALANCES_OFFSET + i * BALANCE_LEN..BALANCES_OFFSET + (i + 1) * BALANCE_LEN];
+        if b[0] == 0 {
+            continue;
+        }
+        let liability = i128::from_le_bytes(b[56..72].try_into().unwrap());
+        require!(liability == 0, EscrowError::BorrowNotAllowed);
+        if b[1..33] == bank.to_bytes() {
+            let assets = i128::from_le_bytes(b[40..56].try_into().unwrap());
+            shares = (assets >> 48).max(0) as u64;
+        }
+    }
+    Ok(shares)
+}
+
+fn drift_account_infos<'info>(a: &DriftInsuranceFund<'info>) -> Vec<AccountInfo<'info>> {
+    vec![
+        a.drift_state.to_account_info(),
+        a.spot_market.to_account_info(),
+        a.insurance_fund_stake.to_account_info(),
+        a.user_stats.to_account_info(),
+        a.vault.to_account_info(),
+        a.spot_market_vault.to_account_info(),
+        a.insurance_fund_vault.to_account_info(),
+        a.drift_signer.to_account_info(),
+        a.vault_usdc.to_account_info(),
+        a.token_program.to_account_info(),
+    ]
+}
+
+/// Reads `if_shares` from a Drift InsuranceFundStake (discriminator, authority, if_shares: u128).
+fn drift_if_shares(stake: &AccountInfo) -> Result<u64> {
+    let data = stake.try_borrow_data()?;
+    require!(data.len() >= 56, EscrowError::BadVaultAccount);
+    let shares = u128::from_le_bytes(data[40..56].try_into().unwrap());
+    Ok(shares.min(u64::MAX as u128) as u64)
+}
+
+/// Forwards an opaque instruction to `program`, promoting the vault PDA to signer.
+fn invoke_vault_passthrough<'info>(
+    program: &AccountInfo<'info>,
+    vault_key: Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: *program.key,
+        accounts: passthrough_metas(remaining_accounts, vault_key),
+        data,
+    };
+    let vault_info = remaining_accounts.iter().find(|a| *a.key == vault_key);
+    let vault_data_before = match vault_info {
+        Some(info) => Some(info.try_borrow_data()?.to_vec()),
+        None => None,
+    };
+    invoke_signed(&ix, remaining_accounts, signer_seeds)?;
+    if let (Some(info), Some(before)) = (vault_info, vault_data_before) {
+        require!(info.try_borrow_data()?[..] == before[..], EscrowError::PostConditionViolated);
+    }
+    Ok(())
+}
+
+/// Builds the metas for a passthrough CPI in one allocation, promoting the vault PDA
+/// to signer.
+fn passthrough_metas(remaining_accounts: &[AccountInfo], vault_key: Pubkey) -> Vec<AccountMeta> {
+    let mut metas = Vec::with_capacity(remaining_accounts.len());
+    metas.extend(remaining_accounts.iter().map(|acc| AccountMeta {
+        pubkey: *acc.key,
+        is_signer: acc.is_signer || *acc.key == vault_key,
+        is_writable: acc.is_writable,
+    }));
+    metas
+}
+
+/// Rejects lulo_execute passthrough accounts that would let the vault signature reach
+/// the owner's wallet, another vault, or a vault-owned token account other than the
+/// pot's registered USDC ATA.
+fn check_lulo_account(acc: &AccountInfo, vault_key: Pubkey, owner: Pubkey, usdc_vault: Pubkey) -> Result<()> {
+    check_passthrough_account(acc, vault_key, owner, &[usdc_vault])
+}
+
+/// `check_lulo_account` with a set of vault token accounts the CPI may write to.
+/// Token accounts the vault owns or is the delegate of count as vault-owned.
+fn check_passthrough_account(acc: &AccountInfo, vault_key: Pubkey, owner: Pubkey, vault_tokens: &[Pubkey]) -> Result<()> {
+    require_keys_neq!(*acc.key, owner, EscrowError::DisallowedAccount);
+    if *acc.owner == crate::ID && *acc.key != vault_key {
+        let data = acc.try_borrow_data()?;
+        require!(!data.starts_with(Vault::DISCRIMINATOR), EscrowError::DisallowedAccount);
+    }
+    if acc.is_writable && *acc.owner == token::ID && !vault_tokens.contains(acc.key) {
+        let data = acc.try_borrow_data()?;
+        if data.len() == TokenAccount::LEN {
+            require!(data[32..64] != vault_key.to_bytes(), EscrowError::DisallowedAccount);
+            // A vault delegate could be drained with the vault's signature all the same.
+            let delegated = data[72..76] == 1u32.to_le_bytes();
+            require!(!delegated || data[76..108] != vault_key.to_bytes(), EscrowError::DisallowedAccount);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the SPL token balance of `key` if it was passed through to an external CPI.
+fn passthrough_token_amount(accounts: &[AccountInfo], key: Pubkey) -> Result<Option<u64>> {
+    if key == Pubkey::default() {
+        return Ok(None);
+    }
+    match accounts.iter().find(|a| *a.key == key && *a.owner == token::ID) {
+        // Reads the amount field directly rather than unpacking the whole account.
+        Some(info) => {
+            let data = info.try_borrow_data()?;
+            require!(data.len() == TokenAccount::LEN, EscrowError::BadVaultAccount);
+            Ok(Some(u64::from_le_bytes(data[64..72].try_into().unwrap())))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Adds `amount` to the vault's accrued yield and emits `YieldAccrued`.
+fn book_yield(vault: &mut Account<Vault>, venue_id: u8, amount: u64, principal: u64) -> Result<()> {
+    vault.accrued_yield = vault.accrued_yield.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+    emit!(YieldAccrued {
+        vault: vault.key(),
+        seq: vault.seq,
+        venue_id,
+        amount,
+        principal,
+        accrued_yield: vault.accrued_yield,
+    });
+    Ok(())
+}
+
+/// Values a marginfi position in liquidity units: asset_shares * bank.asset_share_value.
+fn marginfi_asset_value(marginfi_account: &AccountInfo, bank: &AccountInfo) -> Result<u64> {
+    // Bank layout: discriminator, mint, mint_decimals, group, padding (7), asset_share_value (I80F48).
+    const BANK_ASSET_SHARE_VALUE_OFFSET: usize = 8 + 32 + 1 + 32 + 7;
+    const BALANCES_OFFSET: usize = 8 + 32 + 32;
+    const BALANCE_LEN: usize = 104;
+    const MAX_BALANCES: usize = 16;
+
+    let share_value = {
+        let data = bank.try_borrow_data()?;
+        require!(data.len() >= BANK_ASSET_SHARE_VALUE_OFFSET + 16, EscrowError::BadVaultAccount);
+        i128::from_le_bytes(
+            data[BANK_ASSET_SHARE_VALUE_OFFSET..BANK_ASSET_SHARE_VALUE_OFFSET + 16]
+                .try_into()
+                .unwrap(),
+        )
+    };
+
+    let data = marginfi_account.try_borrow_data()?;
+    require!(
+        data.len() >= BALANCES_OFFSET + BALANCE_LEN * MAX_BALANCES,
+        EscrowError::BadVaultAccount
+    );
+    let bank_key = bank.key.to_bytes();
+    for i in 0..MAX_BALANCES {
+        let b = &data[BALANCES_OFFSET + i * BALANCE_LEN..BALANCES_OFFSET + (i + 1) * BALANCE_LEN];
+        if b[0] != 0 && b[1..33] == bank_key {
+            let shares = i128::from_le_bytes(b[40..56].try_into().unwrap()).max(0) as u128;
+            let value = (shares >> 24) * ((share_value.max(0) as u128) >> 24) >> 48;
+            return Ok(value.min(u64::MAX as u128) as u64);
+        }
+    }
+    Ok(0)
+}
+
+/// Fee owed on `net_yield_usdc` above the high-water mark; raises the mark so the
+/// same gain is never charged twice, and gains that only recover a loss are free.
+fn performance_fee(vault: &mut Account<Vault>, performance_fee_bps: u16) -> Result<u64> {
+    if vault.net_yield_usdc <= vault.fee_hwm_usdc {
+        return Ok(0);
+    }
+    let gain = (vault.net_yield_usdc - vault.fee_hwm_usdc) as u64;
+    vault.fee_hwm_usdc = vault.net_yield_usdc;
+    let fee = (gain as u128)
+        .checked_mul(performance_fee_bps as u128)
+        .ok_or(EscrowError::MathOverflow)?
+        / 10_000;
+    Ok(fee as u64)
+}
+
+/// `assets[0]` is the cash leg that `usdc_in_lulo_bps` lends out of.
+pub fn validate_policy_targets(risk_state: u8, assets: &[AssetTarget], usdc_in_lulo_bps: u16) -> Result<()> {
+    require!(risk_state <= 2, EscrowError::InvalidRiskState);
+    require!(
+        !assets.is_empty() && assets.len() <= MAX_POLICY_ASSETS,
+        EscrowError::InvalidAssetList
+    );
+    for (i, asset) in assets.iter().enumerate() {
+        require!(
+            assets[..i].iter().all(|a| a.mint != asset.mint),
+            EscrowError::InvalidAssetList
+        );
+    }
+
+    let target_sum: u32 = assets.iter().map(|a| a.bps as u32).sum();
+    require!(target_sum == 10_000, EscrowError::InvalidBps);
+    require!(
+        usdc_in_lulo_bps <= assets[0].bps,
+        EscrowError::InvalidLuloAllocation
+    );
+    Ok(())
+}
+
+/// Venues holding SOL (liquid staking) rather than lending a token.
+const SOL_ONLY_VENUES: [u8; 2] = [VENUE_SANCTUM, VENUE_MARINADE];
+
+/// Each allocation names one of `assets` and a known venue (liquid-staking venues
+/// only for SOL), once per pair, and no asset's allocations exceed its target bps.
+pub fn validate_venue_allocations(assets: &[AssetTarget], allocations: &[VenueAllocation]) -> Result<()> {
+    require!(allocations.len() <= MAX_POLICY_VENUES, EscrowError::InvalidVenueAllocation);
+    for (i, a) in allocations.iter().enumerate() {
+        require!(a.bps > 0 && a.venue_id <= VENUE_MARINADE, EscrowError::InvalidVenueAllocation);
+        require!(
+            assets.iter().any(|t| t.mint == a.mint),
+            EscrowError::InvalidVenueAllocation
+        );
+        require!(
+            !SOL_ONLY_VENUES.contains(&a.venue_id) || a.mint == native_mint::ID,
+            EscrowError::InvalidVenueAllocation
+        );
+        require!(
+            allocations[..i].iter().all(|b| (b.mint, b.venue_id) != (a.mint, a.venue_id)),
+            EscrowError::InvalidVenueAllocation
+        );
+    }
+    for asset in assets {
+        let allocated: u32 = allocations
+            .iter()
+            .filter(|a| a.mint == asset.mint)
+            .map(|a| a.bps as u32)
+            .sum();
+        require!(allocated <= asset.bps as u32, EscrowError::InvalidVenueAllocation);
+    }
+    Ok(())
+}
+
+/// The split implied by a bare `usdc_in_lulo_bps`: all of it in Lulo, out of the
+/// cash leg.
+fn lulo_only_allocation(assets: &[AssetTarget], usdc_in_lulo_bps: u16) -> Vec<VenueAllocation> {
+    match assets.first() {
+        Some(cash) if usdc_in_lulo_bps > 0 => vec![VenueAllocation {
+            mint: cash.mint,
+            venue_id: VENUE_LULO,
+            bps: usdc_in_lulo_bps,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Pod policy edits are open to its authority and appointed manager, and to pod
+/// admins who present their membership.
+fn require_policy_editor(policy: &PodPolicy, accounts: &UpdatePolicy) -> Result<()> {
+    let authority = accounts.authority.key();
+    if policy.authority == authority || policy.is_manager(&authority) {
+        return Ok(());
+    }
+    let pod = accounts.pod.as_ref().ok_or(EscrowError::Unauthorized)?;
+    let membership = accounts.pod_membership.as_ref().ok_or(EscrowError::Unauthorized)?;
+    require_keys_eq!(membership.member, authority, EscrowError::Unauthorized);
+    require_keys_eq!(membership.pod, pod.key(), EscrowError::Unauthorized);
+    require_pod_role(membership, &[PodRole::Admin])
+}
+
+/// Grows a vault created under an older `Vault` layout to `Vault::SPACE`; returns
+/// whether it had to. Fields have only ever been appended, so the zero-filled tail
+/// decodes as their defaults.
+fn grow_legacy_vault<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    if info.data_len() >= Vault::SPACE {
+        return Ok(false);
+    }
+    require_keys_eq!(*info.owner, crate::ID, EscrowError::BadVaultAccount);
+    {
+        // owner, pot_hash and bump lead every layout.
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 + 32 + 32 + 1 && data[..8] == *Vault::DISCRIMINATOR,
+            EscrowError::BadVaultAccount
+        );
+        let expected = Pubkey::create_program_address(&[seeds::POT_VAULT, &data[8..40], &data[40..72], &[data[72]]], &crate::ID)
+            .map_err(|_| EscrowError::BadVaultAccount)?;
+        require_keys_eq!(expected, *info.key, EscrowError::BadVaultAccount);
+    }
+    resize_account(info, payer, system_program, Vault::SPACE)?;
+    Ok(true)
+}
+
+/// Reallocates a program-owned account to `new_len`, topping up rent from `payer`
+/// or refunding the excess to it.
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_min = Rent::get()?.minimum_balance(new_len);
+    let current = account.lamports();
+    if rent_min > current {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(payer.key, account.key, rent_min - current);
+        anchor_lang::solana_program::program::invoke(&ix, &[payer.clone(), account.clone(), system_program.clone()])?;
+    } else if current > rent_min {
+        transfer_lamports(account, payer, current - rent_min)?;
+    }
+    account.resize(new_len)?;
+    Ok(())
+}
+
+const POLICY_HISTORY_ENTRIES_OFFSET: usize = 8 + std::mem::size_of::<PolicyHistory>();
+const POLICY_HISTORY_ENTRY_LEN: usize = std::mem::size_of::<PolicyHistoryEntry>();
+
+/// Writes `entry` at the ring head, overwriting the oldest update once full.
+fn append_policy_history(history: &AccountLoader<PolicyHistory>, entry: PolicyHistoryEntry) -> Result<()> {
+    let slot = {
+        let mut h = history.load_mut()?;
+        let slot = h.head as usize;
+        h.head = (h.head + 1) % h.capacity;
+        h.len = (h.len + 1).min(h.capacity);
+        slot
+    };
+
+    let info = history.to_account_info();
+    let mut data = info.try_borrow_mut_data()?;
+    let off = POLICY_HISTORY_ENTRIES_OFFSET + slot * POLICY_HISTORY_ENTRY_LEN;
+    data[off..off + POLICY_HISTORY_ENTRY_LEN].copy_from_slice(bytemuck::bytes_of(&entry));
+    Ok(())
+}
+
+/// Entries oldest first.
+fn read_policy_history(history: &AccountLoader<PolicyHistory>) -> Result<Vec<PolicyHistoryEntry>> {
+    let (head, len, capacity) = {
+        let h = history.load()?;
+        (h.head as usize, h.len as usize, h.capacity as usize)
+    };
+
+    let info = history.to_account_info();
+    let data = info.try_borrow_data()?;
+    let mut entries = Vec::with_capacity(len);
+    for i in 0..len {
+        let slot = (head + capacity - len + i) % capacity;
+        let off = POLICY_HISTORY_ENTRIES_OFFSET + slot * POLICY_HISTORY_ENTRY_LEN;
+        entries.push(bytemuck::pod_read_unaligned(&data[off..off + POLICY_HISTORY_ENTRY_LEN]));
+    }
+    Ok(entries)
+}
+
+/// Bumps `seq` on a vault read with `owner_vault_state` and writes it back.
+fn bump_vault_seq(vault_info: &AccountInfo, mut vault: Vault) -> Result<()> {
+    vault.next_seq();
+    let mut data = vault_info.try_borrow_mut_data()?;
+    vault.try_serialize(&mut &mut data[..])
+}
+
+/// Deserializes a vault passed through `remaining_accounts` and checks it is the
+/// `pot_vault` PDA of `owner`.
+fn owner_vault_state(vault_info: &AccountInfo, owner: &Pubkey) -> Result<Vault> {
+    require_keys_eq!(*vault_info.owner, crate::ID, EscrowError::BadVaultAccount);
+    let vault = {
+        let data = vault_info.try_borrow_data()?;
+        Vault::try_deserialize(&mut &data[..])?
+    };
+    require_keys_eq!(vault.owner, *owner, EscrowError::Unauthorized);
+    let expected = Pubkey::create_program_address(
+        &[seeds::POT_VAULT, owner.as_ref(), vault.pot_hash.as_ref(), &[vault.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| EscrowError::BadVaultAccount)?;
+    require_keys_eq!(expected, *vault_info.key, EscrowError::BadVaultAccount);
+    Ok(vault)
+}
+
+/// Transfers `amount` out of a vault-owned token account and closes it, sending
+/// its rent to `rent_to`.
+fn move_tokens_and_close<'info>(
+    token_program: &Program<'info, Token>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    rent_to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if amount > 0 {
+        let cpi = Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            authority: authority.clone(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.to_account_info(), cpi, signer_seeds),
+            amount,
+        )?;
+    }
+
+    let cpi = CloseAccount {
+        account: from.clone(),
+        destination: rent_to.clone(),
+        authority: authority.clone(),
+    };
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi,
+        signer_seeds,
+    ))
+}
+
+/// Closes an empty token account held by `authority` into `rent_to`; returns the
+/// lamports it held.
+fn close_empty_token_account<'info>(
+    token_program: &Program<'info, Token>,
+    account: &AccountInfo<'info>,
+    rent_to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    require_keys_eq!(*account.owner, token::ID, EscrowError::BadVaultAccount);
+    {
+        let data = account.try_borrow_data()?;
+        require!(data.len() == TokenAccount::LEN, EscrowError::BadVaultAccount);
+        require!(data[32..64] == authority.key.to_bytes(), EscrowError::BadVaultAccount);
+        require!(data[64..72] == [0u8; 8], EscrowError::VaultNotEmpty);
+    }
+    let lamports = account.lamports();
+    let cpi = CloseAccount {
+        account: account.clone(),
+        destination: rent_to.clone(),
+        authority: authority.clone(),
+    };
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi,
+        signer_seeds,
+    ))?;
+    Ok(lamports)
+}
+
+/// `registry_add`, initializing the registry on the owner's first pot.
+fn registry_init_and_add<'info>(
+    registry: &mut Account<'info, OwnerRegistry>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    pot_hash: [u8; 32],
+    registry_bump: u8,
+) -> Result<()> {
+    if registry.owner == Pubkey::default() {
+        registry.owner = owner.key();
+        registry.bump = registry_bump;
+    }
+    registry_add(registry, owner, system_program, pot_hash)
+}
+
+/// Appends `pot_hash` to the owner registry, growing the account by one entry.
+fn registry_add<'info>(
+    registry: &mut Account<'info, OwnerRegistry>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    pot_hash: [u8; 32],
+) -> Result<()> {
+    if registry.pot_hashes.contains(&pot_hash) {
+        return Ok(());
+    }
+    require!(
+        registry.pot_hashes.len() < OwnerRegistry::MAX_POTS,
+        EscrowError::PotLimitReached
+    );
+    let new_len = OwnerRegistry::space(registry.pot_hashes.len() + 1);
+    resize_account(&registry.to_account_info(), payer, system_program, new_len)?;
+    registry.pot_hashes.push(pot_hash);
+    Ok(())
+}
+
+/// Drops `pot_hash` from the owner registry and shrinks the account, refunding rent.
+fn registry_remove<'info>(
+    registry: &mut Account<'info, OwnerRegistry>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    pot_hash: [u8; 32],
+) -> Result<()> {
+    registry.pot_hashes.retain(|h| *h != pot_hash);
+    let new_len = OwnerRegistry::space(registry.pot_hashes.len());
+    resize_account(&registry.to_account_info(), owner, system_program, new_len)
+}
+
+/// Bumps the vault's deposit sequence, filling in the receipt for the current
+/// number when the caller asked for one. USDC deposits also count toward
+/// `total_in_usdc`.
+fn record_deposit(
+    vault: &mut Account<Vault>,
+    receipt: Option<&mut Account<DepositReceipt>>,
+    depositor: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    if let Some(r) = receipt {
+        r.vault = vault.key();
+        r.sequence = vault.sequence;
+        r.depositor = depositor;
+        r.mint = mint;
+        r.amount = amount;
+        r.slot = Clock::get()?.slot;
+    }
+    if mint == vault.usdc_mint {
+        vault.book_usdc_in(amount)?;
+    }
+    vault.sequence += 1;
+    Ok(())
+}
+
+/// Creates the `DepositMarker` for `key`, failing with `DuplicateOperation` if a
+/// deposit already claimed it. No-op without a key.
+fn claim_idempotency_key<'info>(
+    key: Option<[u8; 16]>,
+    marker: Option<&UncheckedAccount<'info>>,
+    vault: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+    let marker = marker.ok_or(EscrowError::DepositMarkerRequired)?;
+    let (expected, bump) =
+        Pubkey::find_program_address(&[seeds::DEPOSIT_MARKER, vault.as_ref(), key.as_ref()], &crate::ID);
+    require_keys_eq!(marker.key(), expected, EscrowError::DepositMarkerRequired);
+    require!(*marker.owner != crate::ID, EscrowError::DuplicateOperation);
+
+    let signer_seeds: &[&[u8]] = &[seeds::DEPOSIT_MARKER, vault.as_ref(), key.as_ref(), &[bump]];
+    let rent = Rent::get()?.minimum_balance(DepositMarker::SPACE);
+    let infos = [payer.clone(), marker.to_account_info(), system_program.to_account_info()];
+    let current = marker.lamports();
+    if current == 0 {
+        let ix = anchor_lang::solana_program::system_instruction::create_account(
+            payer.key,
+            &expected,
+            rent,
+            DepositMarker::SPACE as u64,
+            &crate::ID,
+        );
+        invoke_signed(&ix, &infos, &[signer_seeds])?;
+    } else {
+        // Pre-funded address: top up, then allocate and assign in place.
+        if current < rent {
+            let ix = anchor_lang::solana_program::system_instruction::transfer(payer.key, &expected, rent - current);
+            anchor_lang::solana_program::program::invoke(&ix, &infos)?;
+        }
+        let ix = anchor_lang::solana_program::system_instruction::allocate(&expected, DepositMarker::SPACE as u64);
+        invoke_signed(&ix, &infos, &[signer_seeds])?;
+        let ix = anchor_lang::solana_program::system_instruction::assign(&expected, &crate::ID);
+        invoke_signed(&ix, &infos, &[signer_seeds])?;
+    }
+
+    let record = DepositMarker {
+        vault: *vault,
+        slot: Clock::get()?.slot,
+        bump,
+    };
+    let info = marker.to_account_info();
+    let mut data = info.try_borrow_mut_data()?;
+    record.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Account set for Bubblegum's `mint_to_collection_v1`, with `receipt_authority`
+/// acting as tree delegate and collection authority.
+struct BubblegumCpi<'info> {
+    bubblegum_program: AccountInfo<'info>,
+    tree_authority: AccountInfo<'info>,
+    merkle_tree: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    receipt_authority: AccountInfo<'info>,
+    collection_authority_record: AccountInfo<'info>,
+    collection_mint: AccountInfo<'info>,
+    collection_metadata: AccountInfo<'info>,
+    collection_edition: AccountInfo<'info>,
+    bubblegum_signer: AccountInfo<'info>,
+    log_wrapper: AccountInfo<'info>,
+    compression_program: AccountInfo<'info>,
+    token_metadata_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+}
+
+macro_rules! impl_bubblegum_cpi {
+    ($accounts:ident) => {
+        impl<'info> $accounts<'info> {
+            fn bubblegum_cpi(&self) -> BubblegumCpi<'info> {
+                BubblegumCpi {
+                    bubblegum_program: self.bubblegum_program.to_account_info(),
+                    tree_authority: self.tree_authority.to_account_info(),
+                    merkle_tree: self.merkle_tree.to_account_info(),
+                    payer: self.payer.to_account_info(),
+                    receipt_authority: self.receipt_authority.to_account_info(),
+                    collection_authority_record: self.collection_authority_record.to_account_info(),
+                    collection_mint: self.collection_mint.to_account_info(),
+                    collection_metadata: self.collection_metadata.to_account_info(),
+                    collection_edition: self.collection_edition.to_account_info(),
+                    bubblegum_signer: self.bubblegum_signer.to_account_info(),
+                    log_wrapper: self.log_wrapper.to_account_info(),
+                    compression_program: self.compression_program.to_account_info(),
+                    token_metadata_program: self.token_metadata_program.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                }
+            }
+        }
+    };
+}
+
+impl_bubblegum_cpi!(MintMilestoneReceipt);
+
+fn bubblegum_mint_cpi(a: &BubblegumCpi, leaf_owner: &AccountInfo, uri: &str, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    let mut data = BUBBLEGUM_MINT_TO_COLLECTION_IX.to_vec();
+    // MetadataArgs
+    for field in [RECEIPT_NAME, RECEIPT_SYMBOL, uri] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field.as_bytes());
+    }
+    data.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+    data.push(0); // primary_sale_happened
+    data.push(0); // is_mutable
+    data.push(0); // edition_nonce: None
+    data.extend_from_slice(&[1, 0]); // token_standard: Some(NonFungible)
+    data.extend_from_slice(&[1, 0]); // collection: Some { verified: false, .. }
+    data.extend_from_slice(a.collection_mint.key.as_ref());
+    data.push(0); // uses: None
+    data.push(0); // token_program_version: Original
+    data.extend_from_slice(&0u32.to_le_bytes()); // creators: []
+
+    let ix = Instruction {
+        program_id: a.bubblegum_program.key(),
+        accounts: vec![
+            AccountMeta::new(a.tree_authority.key(), false),
+            AccountMeta::new_readonly(leaf_owner.key(), false),
+            AccountMeta::new_readonly(leaf_owner.key(), false),
+            AccountMeta::new(a.merkle_tree.key(), false),
+            AccountMeta::new(a.payer.key(), true),
+            AccountMeta::new_readonly(a.receipt_authority.key(), true),
+            AccountMeta::new_readonly(a.receipt_authority.key(), true),
+            AccountMeta::new_readonly(a.collection_authority_record.key(), false),
+            AccountMeta::new_readonly(a.collection_mint.key(), false),
+            AccountMeta::new(a.collection_metadata.key(), false),
+            AccountMeta::new_readonly(a.collection_edition.key(), false),
+            AccountMeta::new_readonly(a.bubblegum_signer.key(), false),
+            AccountMeta::new_readonly(a.log_wrapper.key(), false),
+            AccountMeta::new_readonly(a.compression_program.key(), false),
+            AccountMeta::new_readonly(a.token_metadata_program.key(), false),
+            AccountMeta::new_readonly(a.system_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            a.tree_authority.clone(),
+            leaf_owner.clone(),
+            a.merkle_tree.clone(),
+            a.payer.clone(),
+            a.receipt_authority.clone(),
+            a.collection_authority_record.clone(),
+            a.collection_mint.clone(),
+            a.collection_metadata.clone(),
+            a.collection_edition.clone(),
+            a.bubblegum_signer.clone(),
+            a.log_wrapper.clone(),
+            a.compression_program.clone(),
+            a.token_metadata_program.clone(),
+            a.system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Penalty owed on an early withdrawal of `amount`; zero once the commitment has ended.
+fn early_withdraw_penalty(vault: &Vault, amount: u64) -> Result<u64> {
+    if !vault.is_committed(Clock::get()?.unix_timestamp) {
+        return Ok(0);
+    }
+    let penalty = (amount as u128) * (vault.early_withdraw_penalty_bps as u128) / 10_000;
+    Ok(penalty as u64)
+}
+
+/// Slashed funds go to the pod's shared vault (`["pod_vault", pod_hash]`), or to
+/// the fee treasury when the pot is not in a pod.
+fn check_penalty_destination(vault: &Vault, config: Option<&Account<Config>>, destination: Pubkey) -> Result<()> {
+    let expected = if vault.pod_hash == [0u8; 32] {
+        config.ok_or(EscrowError::MissingConfig)?.fee_treasury
+    } else {
+        Pubkey::find_program_address(&[seeds::POD_VAULT, vault.pod_hash.as_ref()], &crate::ID).0
+    };
+    require_keys_eq!(destination, expected, EscrowError::BadPenaltyDestination);
+    Ok(())
+}
+
+/// Moves the early-withdraw penalty on `amount` lamports from the vault to
+/// `destination` and returns it; the caller pays out the rest.
+fn slash_lamports<'info>(
+    vault: &Account<'info, Vault>,
+    config: Option<&Account<'info, Config>>,
+    destination: Option<&UncheckedAccount<'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let penalty = early_withdraw_penalty(vault, amount)?;
+    if penalty == 0 {
+        return Ok(0);
+    }
+    let destination = destination.ok_or(EscrowError::BadPenaltyDestination)?;
+    check_penalty_destination(vault, config, destination.key())?;
+    transfer_lamports(&vault.to_account_info(), &destination.to_account_info(), penalty)?;
+    emit!(EarlyWithdrawPenalty {
+        vault: vault.key(),
+        seq: vault.seq,
+        destination: destination.key(),
+        mint: Pubkey::default(),
+        amount: penalty,
+    });
+    Ok(penalty)
+}
+
+/// `slash_lamports` for a vault-owned token account, paid in its own mint.
+fn slash_tokens<'info>(
+    vault: &Account<'info, Vault>,
+    vault_token: &Account<'info, TokenAccount>,
+    config: Option<&Account<'info, Config>>,
+    destination: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<u64> {
+    let penalty = early_withdraw_penalty(vault, amount)?;
+    if penalty == 0 {
+        return Ok(0);
+    }
+    let destination = destination.ok_or(EscrowError::BadPenaltyDestination)?;
+    check_penalty_destination(vault, config, destination.owner)?;
+    let cpi = Transfer {
+        from: vault_token.to_account_info(),
+        to: destination.to_account_info(),
+        authority: vault.to_account_info(),
+    };
+    token::transfer(CpiContext::new_with_signer(token_program.to_account_info(), cpi, signer_seeds), penalty)?;
+    emit!(EarlyWithdrawPenalty {
+        vault: vault.key(),
+        seq: vault.seq,
+        destination: destination.owner,
+        mint: vault_token.mint,
+        amount: penalty,
+    });
+    Ok(penalty)
+}
+
+/// Contribution plus its pro-rata slice of the pod vault's surplus (undistributed
+/// yield and retained penalties).
+fn pod_member_share(pod: &Pod, pod_balance: u64, contributed: u64) -> Result<u64> {
+    if pod.total_contributed == 0 {
+        return Ok(0);
+    }
+    let surplus = pod.surplus(pod_balance);
+    let extra = (surplus as u128) * (contributed as u128) / (pod.total_contributed as u128);
+    let share = contributed.checked_add(extra as u64).ok_or(EscrowError::MathOverflow)?;
+    Ok(share.min(pod_balance))
+}
+
+fn require_pod_role(membership: &Contribution, allowed: &[PodRole]) -> Result<()> {
+    require!(allowed.contains(&membership.role), EscrowError::Unauthorized);
+    Ok(())
+}
+
+/// Brings both weight accumulators to `now` and drops the member's weight from the
+/// pod total; returns the member's credited yield still owed to them.
+fn remove_member_weight(pod: &mut Pod, c: &mut Contribution, now: i64) -> u64 {
+    c.accrue(now);
+    pod.accrue(now);
+    pod.total_weight = pod.total_weight.saturating_sub(c.weight);
+    c.weight = 0;
+    c.yield_balance
+}
+
+/// Enforces the vault's cooldown between external executions and records this one.
+fn check_exec_rate(vault: &mut Vault) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    if vault.external_exec_cooldown_slots > 0 && vault.last_external_exec_slot > 0 {
+        require!(
+            slot >= vault.last_external_exec_slot.saturating_add(vault.external_exec_cooldown_slots),
+            EscrowError::TooFrequent
+        );
+    }
+    vault.last_external_exec_slot = slot;
+    Ok(())
+}
+
+/// Checks that `op` targets this vault, hasn't expired, was signed by the vault owner
+/// in the Ed25519 instruction immediately preceding the current one, and carries the
+/// vault's current `op_nonce` (which is then consumed).
+fn accept_signed_op(vault: &mut Vault, vault_key: Pubkey, instructions_sysvar: &AccountInfo, op: &SignedOp) -> Result<()> {
+    require_keys_eq!(op.vault, vault_key, EscrowError::BadSignature);
+    require!(Clock::get()?.unix_timestamp <= op.expires_at, EscrowError::SignatureExpired);
+
+    let current = load_current_index_checked(instructions_sysvar)?;
+    require!(current > 0, EscrowError::BadSignature);
+    let ix = load_instruction_at_checked((current - 1) as usize, instructions_sysvar)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, EscrowError::BadSignature);
+
+    // Ed25519 program layout: [num_signatures, padding, Ed25519SignatureOffsets..].
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, EscrowError::BadSignature);
+    let read = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+    require!(
+        read(4) == u16::MAX && read(8) == u16::MAX && read(14) == u16::MAX,
+        EscrowError::BadSignature
+    );
+    let pubkey_offset = read(6) as usize;
+    let message_offset = read(10) as usize;
+    let message_len = read(12) as usize;
+    let pubkey = data.get(pubkey_offset..pubkey_offset + 32).ok_or(EscrowError::BadSignature)?;
+    require!(pubkey == vault.owner.as_ref(), EscrowError::BadSignature);
+    let message = data
+        .get(message_offset..message_offset + message_len)
+        .ok_or(EscrowError::BadSignature)?;
+    require!(message == op.message().as_slice(), EscrowError::BadSignature);
+
+    vault.consume_op_nonce(op.nonce)
+}
+
+/// Compliance gate for moving `amount` in or out of `owner`'s vault: above the
+/// configured threshold the owner must present a live attestation from an allowlisted
+/// issuer.
+fn check_attestation(
+    config: Option<&Account<Config>>,
+    attestation: Option<&Account<Attestation>>,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let config = config.ok_or(EscrowError::MissingConfig)?;
+    if config.compliance_threshold == 0 || amount <= config.compliance_threshold {
+        return Ok(());
+    }
+    let a = attestation.ok_or(EscrowError::AttestationRequired)?;
+    require_keys_eq!(a.subject, owner, EscrowError::AttestationRequired);
+    require!(config.attestation_issuers.contains(&a.issuer), EscrowError::AttestationRequired);
+    require!(
+        a.expires_at == 0 || Clock::get()?.unix_timestamp < a.expires_at,
+        EscrowError::AttestationRequired
+    );
+    Ok(())
+}
+
+/// Meters a withdrawal against the program-wide outflow window: SOL (`mint` is the
+/// default key) in lamports, everything else in USDC base units. Once the window
+/// total for the asset passes its threshold, withdrawals above the per-user cap
+/// also need the guardian's signature until the window rolls over.
+fn check_outflow(
+    config: Option<&Account<Config>>,
+    breaker: Option<&mut Account<OutflowBreaker>>,
+    guardian: Option<&Signer>,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let config = config.ok_or(EscrowError::MissingConfig)?;
+    let limits = config.outflow_limits;
+    let is_sol = mint == Pubkey::default();
+    let (threshold, user_cap) = if is_sol {
+        (limits.lamports_threshold, limits.lamports_user_cap)
+    } else {
+        (limits.usdc_threshold, limits.usdc_user_cap)
+    };
+    if limits.window_secs == 0 || threshold == 0 {
+        return Ok(());
+    }
+    let breaker = breaker.ok_or(EscrowError::OutflowBreakerRequired)?;
+    let now = Clock::get()?.unix_timestamp;
+    let (before, after) = breaker.record(now, limits.window_secs, is_sol, amount);
+    if after <= threshold {
+        return Ok(());
+    }
+    if before <= threshold {
+        emit!(OutflowBreakerTripped {
+            mint,
+            window_start: breaker.window_start,
+            window_total: after,
+            threshold,
+        });
+    }
+    if amount > user_cap {
+        let cosigned = guardian.is_some_and(|g| config.guardian != Pubkey::default() && g.key() == config.guardian);
+        require!(cosigned, EscrowError::GuardianCosignRequired);
+    }
+    Ok(())
+}
+
+/// Shares minted for a deposit of `amount` into a pot holding `assets` with `supply`
+/// shares outstanding; 1:1 while either is zero. Rounds down, in the pot's favour.
+pub fn shares_for_deposit(amount: u64, supply: u64, assets: u64) -> Option<u64> {
+    if supply == 0 || assets == 0 {
+        return Some(amount);
+    }
+    u64::try_from((amount as u128) * (supply as u128) / (assets as u128)).ok()
+}
+
+/// Shares burned for a withdrawal of `amount`; rounds up, in the pot's favour, and
+/// never exceeds `supply`.
+pub fn shares_for_withdrawal(amount: u64, supply: u64, assets: u64) -> u64 {
+    if supply == 0 || assets == 0 {
+        return supply;
+    }
+    let shares = ((amount as u128) * (supply as u128)).div_ceil(assets as u128);
+    shares.min(supply as u128) as u64
+}
+
+/// The vault's share mint and the owner's share account, or `None` when the vault
+/// has no share mint.
+fn share_accounts<'a, 'info>(
+    vault: &Vault,
+    share_mint: Option<&'a Account<'info, Mint>>,
+    owner_shares: Option<&'a Account<'info, TokenAccount>>,
+) -> Result<Option<(&'a Account<'info, Mint>, &'a Account<'info, TokenAccount>)>> {
+    if vault.share_mint == Pubkey::default() {
+        return Ok(None);
+    }
+    let mint = share_mint.ok_or(EscrowError::MissingShareAccounts)?;
+    let shares = owner_shares.ok_or(EscrowError::MissingShareAccounts)?;
+    require_keys_eq!(mint.key(), vault.share_mint, EscrowError::BadMint);
+    require_keys_eq!(shares.mint, vault.share_mint, EscrowError::BadMint);
+    require_keys_eq!(shares.owner, vault.owner, EscrowError::Unauthorized);
+    Ok(Some((mint, shares)))
+}
+
+/// Mints the owner's shares for a USDC deposit; call before the deposit is booked.
+fn mint_pot_shares<'info>(
+    vault: &Account<'info, Vault>,
+    share_mint: Option<&Account<'info, Mint>>,
+    owner_shares: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let Some((mint, to)) = share_accounts(vault, share_mint, owner_shares)? else {
+        return Ok(());
+    };
+    let shares =
+        shares_for_deposit(amount, mint.supply, vault.share_assets_usdc()).ok_or(EscrowError::MathOverflow)?;
+    let seeds: &[&[u8]] = &[seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref(), &[vault.bump]];
+    let cpi = MintTo {
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: vault.to_account_info(),
+    };
+    token::mint_to(CpiContext::new_with_signer(token_program.to_account_info(), cpi, &[seeds]), shares)?;
+    emit!(PotSharesMinted {
+        vault: vault.key(),
+        seq: vault.seq,
+        amount,
+        shares,
+    });
+    Ok(())
+}
+
+/// Burns the owner's shares for a USDC withdrawal; call before the withdrawal is booked.
+fn burn_pot_shares<'info>(
+    vault: &Account<'info, Vault>,
+    share_mint: Option<&Account<'info, Mint>>,
+    owner_shares: Option<&Account<'info, TokenAccount>>,
+    owner: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let Some((mint, from)) = share_accounts(vault, share_mint, owner_shares)? else {
+        return Ok(());
+    };
+    let shares = shares_for_withdrawal(amount, mint.supply, vault.share_assets_usdc());
+    let cpi = Burn {
+        mint: mint.to_account_info(),
+        from: from.to_account_info(),
+        authority: owner.to_account_info(),
+    };
+    token::burn(CpiContext::new(token_program.to_account_info(), cpi), shares)?;
+    emit!(PotSharesBurned {
+        vault: vault.key(),
+        seq: vault.seq,
+        amount,
+        shares,
+    });
+    Ok(())
+}
+
+/// Enforces `Config::min_deposit_usdc` and `max_pot_balance_usdc` on a deposit of
+/// `deposit_lamports` SOL plus `deposit_usdc`, called before the funds move. The
+/// pot's balance is its net USDC deposits plus idle and staked SOL.
+fn check_deposit_limits(
+    config: Option<&Account<Config>>,
+    vault: &Vault,
+    vault_info: &AccountInfo,
+    sol_price: Option<&UncheckedAccount>,
+    deposit_lamports: u64,
+    deposit_usdc: u64,
+) -> Result<()> {
+    let config = config.ok_or(EscrowError::MissingConfig)?;
+    if vault.limits_exempt || !config.deposit_limits_active() {
+        return Ok(());
+    }
+    let deposit_value = sol_value_usdc(config, sol_price, deposit_lamports)?
+        .checked_add(deposit_usdc)
+        .ok_or(EscrowError::MathOverflow)?;
+    require!(deposit_value >= config.min_deposit_usdc, EscrowError::DepositBelowMinimum);
+
+    if config.max_pot_balance_usdc > 0 {
+        let sol = native_sol_lamports(vault_info, 0)?
+            .checked_add(vault.sol_staked_lamports)
+            .ok_or(EscrowError::MathOverflow)?;
+        let balance = vault
+            .total_in_usdc
+            .saturating_sub(vault.total_out_usdc)
+            .checked_add(sol_value_usdc(config, sol_price, sol)?)
+            .and_then(|b| b.checked_add(deposit_value))
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(balance <= config.max_pot_balance_usdc, EscrowError::PotBalanceCapExceeded);
+    }
+    Ok(())
+}
+
+/// Lamports per `MSOL_PRICE_DENOMINATOR` mSOL, as recorded in the Marinade state.
+fn read_msol_price(marinade_state: &AccountInfo) -> Result<u64> {
+    let data = marinade_state.try_borrow_data()?;
+    require!(data.len() >= MARINADE_STATE_MSOL_PRICE_OFFSET + 8, EscrowError::BadVaultAccount);
+    require!(data[8..40] == MSOL_MINT.to_bytes(), EscrowError::BadMint);
+    let price = u64::from_le_bytes(
+        data[MARINADE_STATE_MSOL_PRICE_OFFSET..MARINADE_STATE_MSOL_PRICE_OFFSET + 8]
+            .try_into()
+            .unwrap(),
+    );
+    require!(price > 0, EscrowError::InvalidAmount);
+    Ok(price)
+}
+
+/// USDC per SOL at the Config SOL/USD feed, read no older than
+/// `MAX_SWAP_PRICE_AGE_SECS` for pricing a swap in the same instruction.
+fn sol_swap_rate(config: &Config, sol_price: Option<&UncheckedAccount>) -> Result<u64> {
+    let feed = sol_price.ok_or(EscrowError::StalePrice)?;
+    require_keys_eq!(feed.key(), config.sol_usd_price_feed, EscrowError::StalePrice);
+    let (price, expo) = read_pyth_price(feed, MAX_SWAP_PRICE_AGE_SECS)?;
+    lamports_to_usdc(LAMPORTS_PER_SOL, price, expo)
+}
+
+/// USDC value of `lamports` at the Config SOL/USD feed; needs no feed for zero.
+fn sol_value_usdc(config: &Config, sol_price: Option<&UncheckedAccount>, lamports: u64) -> Result<u64> {
+    sol_value_usdc_at(config, sol_price, lamports, VALUATION_SPOT)
+}
+
+/// `sol_value_usdc` at the feed's spot or EMA price, per `valuation_mode`.
+fn sol_value_usdc_at(
+    config: &Config,
+    sol_price: Option<&UncheckedAccount>,
+    lamports: u64,
+    valuation_mode: u8,
+) -> Result<u64> {
+    if lamports == 0 {
+        return Ok(0);
+    }
+    let feed = sol_price.ok_or(EscrowError::StalePrice)?;
+    require_keys_eq!(feed.key(), config.sol_usd_price_feed, EscrowError::StalePrice);
+    let (price, expo) = if valuation_mode == VALUATION_EMA {
+        read_pyth_ema_price(feed, MAX_PRICE_AGE_SECS)?
+    } else {
+        read_pyth_price(feed, MAX_PRICE_AGE_SECS)?
+    };
+    lamports_to_usdc(lamports, price, expo)
+}
+
+/// Rejects withdrawal destinations on the admin-managed blocklist.
+fn check_not_blocked(blocklist: Option<&Account<Blocklist>>, destinations: &[Pubkey]) -> Result<()> {
+    let list = blocklist.ok_or(EscrowError::MissingBlocklist)?;
+    for d in destinations {
+        require!(!list.addresses.contains(d), EscrowError::AddressBlocked);
+    }
+    Ok(())
+}
+
+/// Requires a travel-rule memo hash on withdrawals above `Config::memo_threshold`.
+fn check_memo(config: Option<&Account<Config>>, amount: u64, memo_hash: &[u8; 32]) -> Result<()> {
+    let config = config.ok_or(EscrowError::MissingConfig)?;
+    if config.memo_threshold > 0 && amount > config.memo_threshold {
+        require!(*memo_hash != [0u8; 32], EscrowError::MemoRequired);
+    }
+    Ok(())
+}
+
+/// Values the vault in USDC base units per snapshot bucket. `positions` are the
+/// vault's Position PDAs for `SNAPSHOT_POSITION_VENUES`, in that order (positions
+/// never opened are passed uninitialized). The SOL bucket is native SOL (see
+/// `native_sol_lamports`) plus staked SOL, priced per `valuation_mode`; `sol_price`
+/// is required once it is non-zero.
+fn value_vault(
+    vault: &Account<Vault>,
+    usdc_balance: u64,
+    wsol_balance: u64,
+    positions: &[AccountInfo],
+    config: &Config,
+    sol_price: Option<&UncheckedAccount>,
+    valuation_mode: u8,
+) -> Result<[u64; SNAPSHOT_BUCKETS]> {
+    require!(positions.len() == SNAPSHOT_POSITION_VENUES.len(), EscrowError::BadVaultAccount);
+
+    let vault_key = vault.key();
+    let mut buckets = [0u64; SNAPSHOT_BUCKETS];
+    buckets[0] = usdc_balance;
+
+    let native = native_sol_lamports(&vault.to_account_info(), wsol_balance)?;
+    let mut sol_lamports = native.checked_add(vault.sol_staked_lamports).ok_or(EscrowError::MathOverflow)?;
+
+    for (info, venue_id) in positions.iter().zip(SNAPSHOT_POSITION_VENUES) {
+        let (expected, _) = Pubkey::find_program_address(&[seeds::POSITION, vault_key.as_ref(), &[venue_id]], &crate::ID);
+        require_keys_eq!(*info.key, expected, EscrowError::BadVaultAccount);
+        if *info.owner != crate::ID {
+            continue;
+        }
+        let principal = {
+            let data = info.try_borrow_data()?;
+            Position::try_deserialize(&mut &data[..])?.principal
+        };
+        match venue_id {
+            VENUE_SANCTUM => {
+                sol_lamports = sol_lamports.checked_add(principal).ok_or(EscrowError::MathOverflow)?;
+            }
+            _ => buckets[venue_id as usize + 1] = principal,
+        }
+    }
+
+    buckets[SNAPSHOT_BUCKETS - 1] = sol_value_usdc_at(config, sol_price, sol_lamports, valuation_mode)?;
+    Ok(buckets)
+}
+
+/// The vault's native SOL: lamports above its own rent minimum plus whatever is
+/// already wrapped in its wSOL ATA. Venue positions are valued separately.
+fn native_sol_lamports(vault_info: &AccountInfo, wsol_balance: u64) -> Result<u64> {
+    let rent_minimum = Rent::get()?.minimum_balance(vault_info.data_len());
+    withdrawable_lamports(vault_info.lamports(), rent_minimum)
+        .checked_add(wsol_balance)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))
+}
+
+/// Lamports to wrap so the wSOL ATA covers a `swap_lamports` swap leg; 0 when the
+/// existing wSOL balance already does.
+pub fn wsol_shortfall(swap_lamports: u64, wsol_balance: u64) -> u64 {
+    swap_lamports.saturating_sub(wsol_balance)
+}
+
+/// Reads a fully verified Pyth `PriceUpdateV2` account and returns `(price, exponent)`,
+/// rejecting updates older than `max_age_secs`.
+fn read_pyth_price(info: &AccountInfo, max_age_secs: i64) -> Result<(i64, i32)> {
+    read_pyth_field(info, max_age_secs, PYTH_SPOT_PRICE_OFFSET)
+}
+
+/// `read_pyth_price` for the update's `ema_price`.
+fn read_pyth_ema_price(info: &AccountInfo, max_age_secs: i64) -> Result<(i64, i32)> {
+    read_pyth_field(info, max_age_secs, PYTH_EMA_PRICE_OFFSET)
+}
+
+fn read_pyth_field(info: &AccountInfo, max_age_secs: i64, price_offset: usize) -> Result<(i64, i32)> {
+    let receiver = PYTH_RECEIVER_PROGRAM_ID;
+    require_keys_eq!(*info.owner, receiver, EscrowError::StalePrice);
+    let data = info.try_borrow_data()?;
+    // discriminator, write_authority, then `VerificationLevel::Full` as a single tag byte.
+    require!(
+        data.len() >= PYTH_PRICE_MESSAGE_OFFSET + 60.max(price_offset + 8),
+        EscrowError::StalePrice
+    );
+    require!(data[8 + 32] == 1, EscrowError::StalePrice);
+    let read = |off: usize, len: usize| &data[PYTH_PRICE_MESSAGE_OFFSET + off..PYTH_PRICE_MESSAGE_OFFSET + off + len];
+    let price = i64::from_le_bytes(read(price_offset, 8).try_into().unwrap());
+    let expo = i32::from_le_bytes(read(48, 4).try_into().unwrap());
+    let publish_time = i64::from_le_bytes(read(52, 8).try_into().unwrap());
+    require!(price > 0, EscrowError::StalePrice);
+    require!(
+        Clock::get()?.unix_timestamp.saturating_sub(publish_time) <= max_age_secs,
+        EscrowError::StalePrice
+    );
+    Ok((price, expo))
+}
+
+/// Values `lamports` in USDC base units at a Pyth SOL/USD `price * 10^expo`.
+fn lamports_to_usdc(lamports: u64, price: i64, expo: i32) -> Result<u64> {
+    // lamports (1e-9 SOL) -> USDC (1e-6): divide by 1e3 on top of the price exponent.
+    let mut value = (lamports as u128).checked_mul(price as u128).ok_or(EscrowError::MathOverflow)?;
+    let scale = expo - 3;
+    if scale >= 0 {
+        value = value.checked_mul(10u128.pow(scale as u32)).ok_or(EscrowError::MathOverflow)?;
+    } else {
+        value /= 10u128.pow((-scale) as u32);
+    }
+    u64::try_from(value).map_err(|_| error!(EscrowError::MathOverflow))
+}
+
+/// Values `units` (6 decimals) of an asset in USDC base units at a Pyth USD
+/// `price * 10^expo`.
+fn units_to_usdc(units: u64, price: i64, expo: i32) -> Result<u64> {
+    let mut value = (units as u128).checked_mul(price as u128).ok_or(EscrowError::MathOverflow)?;
+    if expo >= 0 {
+        value = value.checked_mul(10u128.pow(expo as u32)).ok_or(EscrowError::MathOverflow)?;
+    } else {
+        value /= 10u128.pow((-expo) as u32);
+    }
+    u64::try_from(value).map_err(|_| error!(EscrowError::MathOverflow))
+}
+
+const SNAPSHOT_ENTRIES_OFFSET: usize = 8 + std::mem::size_of::<SnapshotBuffer>();
+const SNAPSHOT_ENTRY_LEN: usize = std::mem::size_of::<PortfolioSnapshot>();
+
+/// Writes `entry` at the ring head, overwriting the oldest day once full.
+fn append_snapshot(buffer: &AccountLoader<SnapshotBuffer>, entry: PortfolioSnapshot) -> Result<()> {
+    let slot = {
+        let mut b = buffer.load_mut()?;
+        let slot = b.head as usize;
+        b.head = (b.head + 1) % SnapshotBuffer::CAPACITY;
+        b.len = (b.len + 1).min(SnapshotBuffer::CAPACITY);
+        b.last_day = entry.day;
+        slot
+    };
+
+    let info = buffer.to_account_info();
+    let mut data = info.try_borrow_mut_data()?;
+    let off = SNAPSHOT_ENTRIES_OFFSET + slot * SNAPSHOT_ENTRY_LEN;
+    data[off..off + SNAPSHOT_ENTRY_LEN].copy_from_slice(bytemuck::bytes_of(&entry));
+    Ok(())
+}
+
+const ACTIVITY_ENTRIES_OFFSET: usize = 8 + std::mem::size_of::<ActivityLog>();
+const ACTIVITY_ENTRY_LEN: usize = std::mem::size_of::<ActivityEntry>();
+
+/// Appends an action to the vault's activity ring when the log was passed.
+fn append_activity(
+    log: Option<&AccountLoader<ActivityLog>>,
+    kind: u8,
+    counterparty: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let Some(log) = log else {
+        return Ok(());
+    };
+    let entry = ActivityEntry {
+        slot: Clock::get()?.slot,
+        amount,
+        counterparty,
+        mint,
+        kind,
+        _padding: [0; 7],
+    };
+    let slot = {
+        let mut l = log.load_mut()?;
+        let slot = l.head as usize;
+        l.head = (l.head + 1) % ActivityLog::CAPACITY;
+        l.len = (l.len + 1).min(ActivityLog::CAPACITY);
+        slot
+    };
+
+    let info = log.to_account_info();
+    let mut data = info.try_borrow_mut_data()?;
+    let off = ACTIVITY_ENTRIES_OFFSET + slot * ACTIVITY_ENTRY_LEN;
+    data[off..off + ACTIVITY_ENTRY_LEN].copy_from_slice(bytemuck::bytes_of(&entry));
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct InitPotVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Vault::SPACE,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = OwnerRegistry::space(0),
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.usdc_mint == usdc_mint.key() @ EscrowError::BadMint
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct InitPotVaultIdempotent<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Vault::SPACE,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = OwnerRegistry::space(0),
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.usdc_mint == usdc_mint.key() @ EscrowError::BadMint
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = DepositReceipt::SPACE,
+        seeds = [seeds::DEPOSIT_RECEIPT, vault.key().as_ref(), &vault.sequence.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Option<Account<'info, DepositReceipt>>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and SOL is involved.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// CHECK: `[DEPOSIT_MARKER, vault, idempotency_key]`, verified and created by
+    /// `claim_idempotency_key`; required when a key is passed.
+    #[account(mut)]
+    pub deposit_marker: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Required for solo pots under an active no-withdraw commitment.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// CHECK: pod shared vault or fee treasury, verified by `check_penalty_destination`
+    #[account(mut)]
+    pub penalty_destination: Option<UncheckedAccount<'info>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawWithFee<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, seeds = [seeds::FEE_TREASURY], bump = fee_treasury.bump)]
+    pub fee_treasury: Box<Account<'info, FeeTreasury>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::REFERRAL, owner.key().as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// CHECK: must match referral.referrer, only credited lamports
+    #[account(mut)]
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The owner's fee tier; caps `fee_lamports` when passed.
+    #[account(seeds = [seeds::FEE_TIER, owner.key().as_ref()], bump = fee_tier.bump)]
+    pub fee_tier: Option<Account<'info, FeeTier>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// CHECK: pod shared vault or fee treasury, verified by `check_penalty_destination`
+    #[account(mut)]
+    pub penalty_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawUsdcWithFee<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = owner
+    )]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds = [seeds::FEE_TREASURY], bump = fee_treasury.bump)]
+    pub fee_treasury: Box<Account<'info, FeeTreasury>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = fee_treasury
+    )]
+    pub treasury_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::REFERRAL, owner.key().as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub referrer_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The owner's fee tier; caps `fee_amount` and accrues withdrawal volume.
+    #[account(mut, seeds = [seeds::FEE_TIER, owner.key().as_ref()], bump = fee_tier.bump)]
+    pub fee_tier: Option<Account<'info, FeeTier>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct DepositUsdc<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = owner
+    )]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = DepositReceipt::SPACE,
+        seeds = [seeds::DEPOSIT_RECEIPT, vault.key().as_ref(), &vault.sequence.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Option<Account<'info, DepositReceipt>>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and SOL is involved.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// CHECK: `[DEPOSIT_MARKER, vault, idempotency_key]`, verified and created by
+    /// `claim_idempotency_key`; required when a key is passed.
+    #[account(mut)]
+    pub deposit_marker: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawUsdc<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = owner
+    )]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for solo pots under an active no-withdraw commitment.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct LuloExecute<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: validated against constant program id
+    pub lulo_program: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_LULO]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct UpdatePolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PodPolicy::SPACE,
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Optional and last so callers that predate the history keep working.
+    #[account(mut, seeds = [seeds::POLICY_HISTORY, pod_hash.as_ref()], bump)]
+    pub policy_history: Option<AccountLoader<'info, PolicyHistory>>,
+
+    /// Lets pod admins other than `pod_policy.authority` edit the policy.
+    #[account(seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Option<Account<'info, Pod>>,
+
+    pub pod_membership: Option<Account<'info, Contribution>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct MigratePodPolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: legacy layout, discriminator and authority checked in handler
+    #[account(mut, owner = crate::ID, seeds = [seeds::POD_POLICY, pod_hash.as_ref()], bump)]
+    pub pod_policy: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [seeds::CONFIG],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ EscrowError::InvalidProgram)]
+    pub program: Program<'info, crate::program::KobafinEscrow>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ EscrowError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+/// `UpdateConfig` for the fee admin role.
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitOutflowBreaker<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, has_one = admin @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = OutflowBreaker::SPACE,
+        seeds = [seeds::OUTFLOW_BREAKER],
+        bump
+    )]
+    pub outflow_breaker: Account<'info, OutflowBreaker>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeRole<'info> {
+    pub holder: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptRole<'info> {
+    pub pending: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferral<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Referral::SPACE,
+        seeds = [seeds::REFERRAL, owner.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: [u8; 32])]
+pub struct OpenEscrow<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// CHECK: only recorded as the party allowed to claim
+    pub counterparty: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = ConditionalEscrow::SPACE,
+        seeds = [seeds::COND_ESCROW, depositor.key().as_ref(), escrow_id.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: [u8; 32])]
+pub struct OpenEscrowToken<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// CHECK: only recorded as the party allowed to claim
+    pub counterparty: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = ConditionalEscrow::SPACE,
+        seeds = [seeds::COND_ESCROW, depositor.key().as_ref(), escrow_id.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = depositor
+    )]
+    pub depositor_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = depositor,
+        associated_token::mint = mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithPreimage<'info> {
+    #[account(mut)]
+    pub counterparty: Signer<'info>,
+
+    /// CHECK: receives the escrow rent on close, matched via has_one
+    #[account(mut)]
+    pub depositor: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::COND_ESCROW, escrow.depositor.as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = depositor @ EscrowError::Unauthorized,
+        has_one = counterparty @ EscrowError::Unauthorized,
+        close = depositor
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithPreimageToken<'info> {
+    #[account(mut)]
+    pub counterparty: Signer<'info>,
+
+    /// CHECK: receives the escrow rent on close, matched via has_one
+    #[account(mut)]
+    pub depositor: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::COND_ESCROW, escrow.depositor.as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = depositor @ EscrowError::Unauthorized,
+        has_one = counterparty @ EscrowError::Unauthorized,
+        close = depositor
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = counterparty,
+        associated_token::mint = mint,
+        associated_token::authority = counterparty
+    )]
+    pub counterparty_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundAfterExpiry<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::COND_ESCROW, depositor.key().as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = depositor @ EscrowError::Unauthorized,
+        close = depositor
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct RefundAfterExpiryToken<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::COND_ESCROW, depositor.key().as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = depositor @ EscrowError::Unauthorized,
+        close = depositor
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = depositor
+    )]
+    pub depositor_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: [u8; 32])]
+pub struct OpenMilestoneEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: only recorded as the milestone recipient
+    pub payee: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MilestoneEscrow::SPACE,
+        seeds = [seeds::MILESTONE_ESCROW, payer.key().as_ref(), escrow_id.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestMilestone<'info> {
+    pub payee: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MILESTONE_ESCROW, escrow.payer.as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = payee @ EscrowError::Unauthorized
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    pub payer: Signer<'info>,
+
+    /// CHECK: matched via has_one, only credited lamports
+    #[account(mut)]
+    pub payee: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MILESTONE_ESCROW, payer.key().as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = payer @ EscrowError::Unauthorized,
+        has_one = payee @ EscrowError::Unauthorized
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeMilestone<'info> {
+    pub party: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MILESTONE_ESCROW, escrow.payer.as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMilestone<'info> {
+    pub arbiter: Signer<'info>,
+
+    /// CHECK: matched via has_one, only credited lamports
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// CHECK: matched via has_one, only credited lamports
+    #[account(mut)]
+    pub payee: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MILESTONE_ESCROW, escrow.payer.as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = arbiter @ EscrowError::Unauthorized,
+        has_one = payer @ EscrowError::Unauthorized,
+        has_one = payee @ EscrowError::Unauthorized
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMilestoneEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::MILESTONE_ESCROW, payer.key().as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = payer @ EscrowError::Unauthorized,
+        close = payer
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], offer_id: [u8; 32])]
+pub struct CreateSwapOffer<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = vault
+    )]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = SwapOffer::SPACE,
+        seeds = [seeds::SWAP_OFFER, vault.key().as_ref(), offer_id.as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, SwapOffer>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_a,
+        associated_token::authority = offer
+    )]
+    pub offer_token_a: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Required when the amount exceeds `Config::compliance_threshold` or the
+    /// outflow breaker is on.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs outflows above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct AcceptSwap<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        constraint = vault.key() == offer.taker_vault @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SWAP_OFFER, offer.maker_vault.as_ref(), offer.offer_id.as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, SwapOffer>,
+
+    #[account(address = offer.mint_b @ EscrowError::BadMint)]
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = vault
+    )]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_b,
+        associated_token::authority = offer
+    )]
+    pub offer_token_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Required when the amount exceeds `Config::compliance_threshold` or the
+    /// outflow breaker is on.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs outflows above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSwap<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(mut, address = offer.maker_vault @ EscrowError::BadVaultAccount)]
+    pub maker_vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, address = offer.taker_vault @ EscrowError::BadVaultAccount)]
+    pub taker_vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: receives offer rent, must be the maker vault owner
+    #[account(mut, address = maker_vault.owner @ EscrowError::Unauthorized)]
+    pub maker_owner: UncheckedAccount<'info>,
+
+    /// CHECK: receives offer_token_b rent, must be the taker vault owner
+    #[account(mut, address = taker_vault.owner @ EscrowError::Unauthorized)]
+    pub taker_owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SWAP_OFFER, offer.maker_vault.as_ref(), offer.offer_id.as_ref()],
+        bump = offer.bump,
+        close = maker_owner
+    )]
+    pub offer: Account<'info, SwapOffer>,
+
+    #[account(address = offer.mint_a @ EscrowError::BadMint)]
+    pub mint_a: Box<Account<'info, Mint>>,
+
+    #[account(address = offer.mint_b @ EscrowError::BadMint)]
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = offer
+    )]
+    pub offer_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = offer
+    )]
+    pub offer_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker_vault
+    )]
+    pub taker_vault_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker_vault
+    )]
+    pub maker_vault_token_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(mut, address = offer.maker_vault @ EscrowError::BadVaultAccount)]
+    pub maker_vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, address = offer.taker_vault @ EscrowError::BadVaultAccount)]
+    pub taker_vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: receives offer rent, must be the maker vault owner
+    #[account(mut, address = maker_vault.owner @ EscrowError::Unauthorized)]
+    pub maker_owner: UncheckedAccount<'info>,
+
+    /// CHECK: receives offer_token_b rent, must be the taker vault owner
+    #[account(mut, address = taker_vault.owner @ EscrowError::Unauthorized)]
+    pub taker_owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SWAP_OFFER, offer.maker_vault.as_ref(), offer.offer_id.as_ref()],
+        bump = offer.bump,
+        close = maker_owner
+    )]
+    pub offer: Account<'info, SwapOffer>,
+
+    #[account(address = offer.mint_a @ EscrowError::BadMint)]
+    pub mint_a: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = offer
+    )]
+    pub offer_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker_vault
+    )]
+    pub maker_vault_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = get_associated_token_address(&offer.key(), &offer.mint_b) @ EscrowError::BadVaultAccount
+    )]
+    pub offer_token_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub taker_vault_token_b: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct StakeSolMarinade<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    /// CHECK: data-less PDA used as the system-owned funding account for Marinade
+    #[account(mut, seeds = [seeds::SOL_STAGE, vault.key().as_ref()], bump)]
+    pub sol_stage: UncheckedAccount<'info>,
+
+    #[account(mut, address = MSOL_MINT @ EscrowError::BadMint)]
+    pub msol_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = msol_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_msol: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    pub msol_mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: validated against constant program id
+    #[account(address = MARINADE_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub marinade_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct UnstakeSolMarinade<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, address = MSOL_MINT @ EscrowError::BadMint)]
+    pub msol_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = msol_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_msol: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    /// CHECK: validated by the Marinade program
+    #[account(mut)]
+    pub treasury_msol_account: UncheckedAccount<'info>,
+
+    /// CHECK: validated against constant program id
+    #[account(address = MARINADE_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub marinade_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitProtocolRegistry<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.has_role(ConfigRole::RegistryAdmin, &admin.key()) @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolRegistry::SPACE,
+        seeds = [seeds::PROTOCOL_REGISTRY],
+        bump
+    )]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocol<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.has_role(ConfigRole::RegistryAdmin, &admin.key()) @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct KaminoLend<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_KAMINO]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub reserve_collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reserve_collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_collateral: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated by the Kamino program
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    pub lending_market: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    pub lending_market_authority: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    #[account(mut)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub kamino_program: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar, address checked
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Required by `kamino_harvest` for the performance fee.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub fee_treasury_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct MarginfiInitAccount<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_MARGINFI]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: validated by the marginfi program
+    pub marginfi_group: UncheckedAccount<'info>,
+
+    /// CHECK: fresh keypair, initialized by the marginfi program with the vault as authority
+    #[account(mut)]
+    pub marginfi_account: Signer<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub marginfi_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct MarginfiLend<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_MARGINFI]],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated by the marginfi program
+    pub marginfi_group: UncheckedAccount<'info>,
+
+    /// CHECK: must be the account recorded on the position
+    #[account(mut, address = position.venue_account @ EscrowError::BadVaultAccount)]
+    pub marginfi_account: UncheckedAccount<'info>,
+
+    /// CHECK: validated by the marginfi program
+    #[account(mut)]
+    pub bank: UncheckedAccount<'info>,
+    /// CHECK: validated by the marginfi program
+    #[account(mut)]
+    pub bank_liquidity_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the marginfi program
+    pub bank_liquidity_vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub marginfi_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct DriftInit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_DRIFT]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: validated by the Drift program
+    #[account(mut)]
+    pub drift_state: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    pub spot_market: UncheckedAccount<'info>,
+    /// CHECK: Drift PDA for (vault, market_index), created by the Drift program
+    #[account(mut)]
+    pub insurance_fund_stake: UncheckedAccount<'info>,
+    /// CHECK: Drift PDA for the vault, created by the Drift program if missing
+    #[account(mut)]
+    pub user_stats: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub drift_program: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct DriftInsuranceFund<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_DRIFT]],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated by the Drift program
+    pub drift_state: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    #[account(mut)]
+    pub spot_market: UncheckedAccount<'info>,
+    /// CHECK: must be the stake account recorded on the position
+    #[account(mut, address = position.venue_account @ EscrowError::BadVaultAccount)]
+    pub insurance_fund_stake: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    #[account(mut)]
+    pub user_stats: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    #[account(mut)]
+    pub spot_market_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    #[account(mut)]
+    pub insurance_fund_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the Drift program
+    pub drift_signer: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub drift_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SanctumSwap<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_SANCTUM]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(address = native_mint::ID @ EscrowError::BadMint)]
+    pub wsol_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub lst_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = lst_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_lst: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: validated against the protocol registry
+    pub sanctum_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: owner must be a known stake-pool program, pool_mint checked in handler
+    #[account(
+        constraint = STAKE_POOL_PROGRAM_IDS
+            .iter()
+            .any(|id| id == stake_pool.owner) @ EscrowError::InvalidProgram
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: instructions sysvar, address checked; enables the sandwich guard
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> SanctumSwap<'info> {
+    fn check_sandwich_guard(&self) -> Result<()> {
+        match self.instructions_sysvar.as_ref() {
+            Some(sysvar) => check_no_dex_instructions(sysvar, self.sanctum_program.key()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct MarginfiHarvest<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_MARGINFI]],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: must be the account recorded on the position
+    #[account(address = position.venue_account @ EscrowError::BadVaultAccount)]
+    pub marginfi_account: UncheckedAccount<'info>,
+
+    /// CHECK: owner checked against the registered marginfi program
+    pub bank: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MarinadeHarvest<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: owner checked against the Marinade program id, msol_mint checked in handler
+    #[account(owner = MARINADE_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub marinade_state: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SanctumHarvest<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_SANCTUM]],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: owner must be a known stake-pool program, pool_mint checked in handler
+    #[account(
+        constraint = STAKE_POOL_PROGRAM_IDS
+            .iter()
+            .any(|id| id == stake_pool.owner) @ EscrowError::InvalidProgram
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetVaultPod<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [seeds::POD_POLICY, vault.pod_hash.as_ref()], bump = pod_policy.load()?.bump)]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+
+    /// Takes precedence over `pod_policy` when present and `override_enabled`.
+    #[account(seeds = [seeds::POT_POLICY, vault.key().as_ref()], bump = pot_policy.bump)]
+    pub pot_policy: Option<Account<'info, PotPolicy>>,
+
+    #[account(seeds = [seeds::PROTOCOL_REGISTRY], bump = registry.bump)]
+    pub registry: Account<'info, ProtocolRegistry>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_KAMINO]],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub reserve_collateral_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reserve_collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_collateral: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: must be the reserve recorded on the position
+    #[account(mut, address = position.venue_account @ EscrowError::BadVaultAccount)]
+    pub reserve: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    pub lending_market: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    pub lending_market_authority: UncheckedAccount<'info>,
+    /// CHECK: validated by the Kamino program
+    #[account(mut)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the protocol registry
+    pub kamino_program: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar, address checked
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetPotPolicy<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = PotPolicy::SPACE,
+        seeds = [seeds::POT_POLICY, vault.key().as_ref()],
+        bump
+    )]
+    pub pot_policy: Account<'info, PotPolicy>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], capacity: u32)]
+pub struct InitPolicyHistory<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump = pod_policy.load()?.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PolicyHistory::space(capacity),
+        seeds = [seeds::POLICY_HISTORY, pod_hash.as_ref()],
+        bump
+    )]
+    pub policy_history: AccountLoader<'info, PolicyHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct ResizePolicyHistory<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump = pod_policy.load()?.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+
+    #[account(mut, seeds = [seeds::POLICY_HISTORY, pod_hash.as_ref()], bump)]
+    pub policy_history: AccountLoader<'info, PolicyHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct ClosePodPolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump = pod_policy.load()?.bump,
+        has_one = authority @ EscrowError::Unauthorized,
+        close = authority
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct TransferPolicyAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump = pod_policy.load()?.bump,
+        has_one = authority @ EscrowError::Unauthorized
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct AcceptPolicyAuthority<'info> {
+    pub pending_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD_POLICY, pod_hash.as_ref()],
+        bump = pod_policy.load()?.bump,
+        has_one = pending_authority @ EscrowError::Unauthorized
+    )]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct BatchDeposit<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+}
+
+#[derive(Accounts)]
+#[instruction(source_pot_hash: [u8; 32], destination_pot_hash: [u8; 32])]
+pub struct MergePots<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), source_pot_hash.as_ref()],
+        bump = source_vault.bump,
+        close = destination_vault
+    )]
+    pub source_vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), destination_pot_hash.as_ref()],
+        bump = destination_vault.bump
+    )]
+    pub destination_vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, address = source_vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub source_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = destination_vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub destination_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::authority = source_vault,
+        constraint = source_msol.mint == MSOL_MINT @ EscrowError::BadMint
+    )]
+    pub source_msol: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::authority = destination_vault,
+        constraint = destination_msol.mint == MSOL_MINT @ EscrowError::BadMint
+    )]
+    pub destination_msol: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump = owner_registry.bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(source_pot_hash: [u8; 32], new_pot_hash: [u8; 32])]
+pub struct SplitPot<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), source_pot_hash.as_ref()],
+        bump = source_vault.bump
+    )]
+    pub source_vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Vault::SPACE,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), new_pot_hash.as_ref()],
+        bump
+    )]
+    pub new_vault: Box<Account<'info, Vault>>,
+
+    #[account(address = source_vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = source_vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub source_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = new_vault
+    )]
+    pub new_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::authority = source_vault,
+        constraint = source_msol.mint == MSOL_MINT @ EscrowError::BadMint
+    )]
+    pub source_msol: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::authority = new_vault,
+        constraint = new_msol.mint == MSOL_MINT @ EscrowError::BadMint
+    )]
+    pub new_msol: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump = owner_registry.bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SetSplitRule<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = SplitRule::SPACE,
+        seeds = [seeds::SPLIT_RULE, owner.key().as_ref()],
+        bump
+    )]
+    pub split_rule: Account<'info, SplitRule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositWithSplit<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::SPLIT_RULE, owner.key().as_ref()],
+        bump = split_rule.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub split_rule: Account<'info, SplitRule>,
+
+    #[account(mut, token::authority = owner)]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and a pot holds SOL.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepAll<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, token::authority = owner)]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct ClosePotVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump = owner_registry.bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SweepDust<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct RegisterPot<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: only the PDA address is used; legacy vault layouts are accepted
+    #[account(
+        owner = crate::ID @ EscrowError::BadVaultAccount,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = OwnerRegistry::space(0),
+        seeds = [seeds::OWNER_REGISTRY, owner.key().as_ref()],
+        bump
+    )]
+    pub owner_registry: Account<'info, OwnerRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetPotMetadata<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = PotMetadata::SPACE,
+        seeds = [seeds::POT_METADATA, vault.key().as_ref()],
+        bump
+    )]
+    pub pot_metadata: Account<'info, PotMetadata>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], preimage: Vec<u8>)]
+pub struct RevealPotCommitment<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PotCommitment::space(preimage.len()),
+        seeds = [seeds::POT_COMMITMENT, vault.key().as_ref()],
+        bump
+    )]
+    pub pot_commitment: Account<'info, PotCommitment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawTo<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: any wallet; only credited lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    #[account(seeds = [seeds::BLOCKLIST], bump = blocklist.bump)]
+    pub blocklist: Option<Account<'info, Blocklist>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// CHECK: pod shared vault or fee treasury, verified by `check_penalty_destination`
+    #[account(mut)]
+    pub penalty_destination: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawUsdcTo<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    #[account(seeds = [seeds::BLOCKLIST], bump = blocklist.bump)]
+    pub blocklist: Option<Account<'info, Blocklist>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Appended to when passed; see `init_activity_log`.
+    #[account(mut, seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()], bump)]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WrapSol<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = native_mint::ID @ EscrowError::BadMint)]
+    pub wsol_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct EnableShareMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [seeds::SHARE_MINT, vault.key().as_ref()],
+        bump,
+        mint::decimals = usdc_mint.decimals,
+        mint::authority = vault
+    )]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = share_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_shares: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct RegisterMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct DeregisterMint<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ EscrowError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct UnwrapSol<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        associated_token::mint = native_mint::ID,
+        associated_token::authority = vault
+    )]
+    pub vault_wsol: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct MintMilestoneReceipt<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::MILESTONE_ESCROW, payer.key().as_ref(), escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        has_one = payer @ EscrowError::Unauthorized
+    )]
+    pub escrow: Account<'info, MilestoneEscrow>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CnftReceipt::SPACE,
+        seeds = [seeds::CNFT_RECEIPT, escrow.key().as_ref(), &[index]],
+        bump
+    )]
+    pub cnft_receipt: Account<'info, CnftReceipt>,
+
+    /// CHECK: PDA signer delegated on the tree and collection
+    #[account(seeds = [seeds::RECEIPT_AUTHORITY], bump)]
+    pub receipt_authority: UncheckedAccount<'info>,
+
+    /// CHECK: validated by Bubblegum
+    #[account(mut)]
+    pub tree_authority: UncheckedAccount<'info>,
+    /// CHECK: must be the configured tree
+    #[account(mut, address = config.receipt_tree @ EscrowError::ReceiptsDisabled)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Bubblegum program id when no delegate record is used
+    pub collection_authority_record: UncheckedAccount<'info>,
+    /// CHECK: must be the configured collection
+    #[account(address = config.receipt_collection @ EscrowError::BadMint)]
+    pub collection_mint: UncheckedAccount<'info>,
+    /// CHECK: validated by Bubblegum
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+    /// CHECK: validated by Bubblegum
+    pub collection_edition: UncheckedAccount<'info>,
+    /// CHECK: validated by Bubblegum
+    pub bubblegum_signer: UncheckedAccount<'info>,
+
+    /// CHECK: validated against constant program id
+    #[account(address = SPL_NOOP_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: validated against constant program id
+    #[account(address = SPL_COMPRESSION_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub compression_program: UncheckedAccount<'info>,
+    /// CHECK: validated against constant program id
+    #[account(address = TOKEN_METADATA_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: validated against constant program id
+    #[account(address = BUBBLEGUM_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], envelope_id: u8)]
+pub struct SetEnvelope<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Envelope::SPACE,
+        seeds = [seeds::ENVELOPE, vault.key().as_ref(), &[envelope_id]],
+        bump
+    )]
+    pub envelope: Account<'info, Envelope>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], envelope_id: u8)]
+pub struct SpendFromEnvelope<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::ENVELOPE, vault.key().as_ref(), &[envelope_id]],
+        bump = envelope.bump
+    )]
+    pub envelope: Account<'info, Envelope>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = vault_usdc.mint)]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = vault.usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], merchant: Pubkey)]
+pub struct CreateSubscription<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Subscription::SPACE,
+        seeds = [seeds::SUBSCRIPTION, vault.key().as_ref(), merchant.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(token::mint = vault.usdc_mint)]
+    pub merchant_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], merchant: Pubkey)]
+pub struct UpdateSubscription<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SUBSCRIPTION, vault.key().as_ref(), merchant.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], merchant: Pubkey)]
+pub struct CancelSubscription<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [seeds::SUBSCRIPTION, vault.key().as_ref(), merchant.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+#[derive(Accounts)]
+pub struct ChargeSubscription<'info> {
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SUBSCRIPTION, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = subscription.bump,
+        has_one = merchant_usdc
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub merchant_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = vault.usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], recipient: Pubkey, stream_id: u64)]
+pub struct CreateStream<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Stream::SPACE,
+        seeds = [seeds::STREAM, vault.key().as_ref(), recipient.as_ref(), &stream_id.to_le_bytes()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::STREAM, vault.key().as_ref(), recipient.key().as_ref(), &stream.stream_id.to_le_bytes()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = vault.usdc_mint)]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct CancelStream<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [seeds::STREAM, vault.key().as_ref(), stream.recipient.as_ref(), &stream.stream_id.to_le_bytes()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = vault.usdc_mint, token::authority = stream.recipient)]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct CloseStream<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [seeds::STREAM, vault.key().as_ref(), stream.recipient.as_ref(), &stream.stream_id.to_le_bytes()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct CreatePayoutPlan<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::POT_METADATA, vault.key().as_ref()], bump = pot_metadata.bump)]
+    pub pot_metadata: Account<'info, PotMetadata>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PayoutPlan::SPACE,
+        seeds = [seeds::PAYOUT_PLAN, vault.key().as_ref()],
+        bump
+    )]
+    pub payout_plan: Account<'info, PayoutPlan>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankPayout<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::PAYOUT_PLAN, vault.key().as_ref()],
+        bump = payout_plan.bump,
+        has_one = vault
+    )]
+    pub payout_plan: Account<'info, PayoutPlan>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = vault.usdc_mint, token::authority = payout_plan.recipient)]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: receives the plan's rent once the pot is drained.
+    #[account(mut, address = vault.owner @ EscrowError::Unauthorized)]
+    pub owner: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = vault.usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct CancelPayoutPlan<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [seeds::PAYOUT_PLAN, vault.key().as_ref()],
+        bump = payout_plan.bump
+    )]
+    pub payout_plan: Account<'info, PayoutPlan>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct DepositRoundup<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, token::mint = vault.usdc_mint, token::authority = owner)]
+    pub user_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and SOL is involved.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct CommitNoWithdraw<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct CreatePod<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Pod::SPACE,
+        seeds = [seeds::POD, pod_hash.as_ref()],
+        bump
+    )]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Contribution::SPACE,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), admin.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pod_vault
+    )]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], invite_hash: [u8; 32])]
+pub struct CreateInvite<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POD, pod_hash.as_ref()],
+        bump = pod.bump
+    )]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), admin.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role == PodRole::Admin @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = PodInvite::SPACE,
+        seeds = [seeds::POD_INVITE, pod.key().as_ref(), invite_hash.as_ref()],
+        bump
+    )]
+    pub invite: Account<'info, PodInvite>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct JoinWithInvite<'info> {
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD_INVITE, pod.key().as_ref(), invite.invite_hash.as_ref()],
+        bump = invite.bump,
+        has_one = pod
+    )]
+    pub invite: Account<'info, PodInvite>,
+
+    #[account(
+        init,
+        payer = member,
+        space = Contribution::SPACE,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct UpdatePod<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD, pod_hash.as_ref()],
+        bump = pod.bump
+    )]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), admin.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role == PodRole::Admin @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct ContributeToPod<'info> {
+    pub member: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.key().as_ref()],
+        bump = contribution.bump,
+        constraint = contribution.role != PodRole::Viewer @ EscrowError::Unauthorized
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(mut, token::mint = pod.usdc_mint, token::authority = member)]
+    pub member_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct LeavePod<'info> {
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        close = member,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.key().as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump = pod.pod_vault_bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = pod.usdc_mint)]
+    pub member_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct StartRosca<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POD, pod_hash.as_ref()],
+        bump = pod.bump
+    )]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), admin.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role == PodRole::Admin @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    /// CHECK: ORAO network state, validated by the VRF program
+    #[account(mut)]
+    pub network_state: UncheckedAccount<'info>,
+
+    /// CHECK: ORAO fee treasury, validated by the VRF program
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: randomness request PDA for the seed, checked in the handler
+    #[account(mut)]
+    pub randomness: UncheckedAccount<'info>,
+
+    /// CHECK: ORAO VRF program
+    #[account(address = ORAO_VRF_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub vrf_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct FinalizeRoscaOrder<'info> {
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    /// CHECK: fulfilled ORAO randomness account, checked in the handler
+    pub randomness: UncheckedAccount<'info>,
+
+    /// CHECK: ORAO VRF program
+    #[account(address = ORAO_VRF_PROGRAM_ID @ EscrowError::InvalidProgram)]
+    pub vrf_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct RoscaContribute<'info> {
+    pub member: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.key().as_ref()],
+        bump = contribution.bump,
+        constraint = contribution.role != PodRole::Viewer @ EscrowError::Unauthorized
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(mut, token::mint = pod.usdc_mint, token::authority = member)]
+    pub member_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], member: Pubkey)]
+pub struct FlagLate<'info> {
+    #[account(seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct RoscaPayout<'info> {
+    pub treasurer: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), treasurer.key().as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    /// Recipient's record for this round.
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), contribution.member.as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump = pod.pod_vault_bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = pod.usdc_mint,
+        token::authority = contribution.member
+    )]
+    pub recipient_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], member: Pubkey)]
+pub struct SetMemberRole<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), admin.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role == PodRole::Admin @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], target: Pubkey)]
+pub struct ProposeRemoval<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), proposer.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role != PodRole::Viewer @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    #[account(seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), target.as_ref()], bump = target_membership.bump)]
+    pub target_membership: Account<'info, Contribution>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = RemovalProposal::SPACE,
+        seeds = [seeds::REMOVAL, pod.key().as_ref(), target.as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, RemovalProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], target: Pubkey)]
+pub struct VoteRemoval<'info> {
+    pub voter: Signer<'info>,
+
+    #[account(seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+        constraint = membership.role != PodRole::Viewer @ EscrowError::Unauthorized
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        seeds = [seeds::REMOVAL, pod.key().as_ref(), target.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, RemovalProposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32], target: Pubkey)]
+pub struct ExecuteRemoval<'info> {
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [seeds::REMOVAL, pod.key().as_ref(), target.as_ref()],
+        bump = proposal.bump,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, RemovalProposal>,
+
+    /// CHECK: receives the proposal rent
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = target_wallet,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), target.as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// CHECK: the removed member; receives their membership rent
+    #[account(mut, address = target)]
+    pub target_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump = pod.pod_vault_bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = pod.usdc_mint, token::authority = target)]
+    pub target_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct DistributeYield<'info> {
+    pub treasurer: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), treasurer.key().as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, Contribution>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump = pod.pod_vault_bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pod_hash: [u8; 32])]
+pub struct ClaimPodYield<'info> {
+    pub member: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::POD, pod_hash.as_ref()], bump = pod.bump, has_one = pod_usdc)]
+    pub pod: Account<'info, Pod>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONTRIBUTION, pod.key().as_ref(), member.key().as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// CHECK: PDA signer over `pod_usdc`
+    #[account(seeds = [seeds::POD_VAULT, pod_hash.as_ref()], bump = pod.pod_vault_bump)]
+    pub pod_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pod_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = pod.usdc_mint)]
+    pub member_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianVault<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        has_one = guardian @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVaultParams<'info> {
+    /// Pays the rent for vaults grown from an older layout.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminVault<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetPublicDeposits<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct Donate<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct DonateUsdc<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, token::mint = vault.usdc_mint, token::authority = donor)]
+    pub donor_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and the pot holds SOL.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Required while the pot runs a crowdfund.
+    #[account(
+        init_if_needed,
+        payer = donor,
+        space = Donation::SPACE,
+        seeds = [
+            seeds::DONATION,
+            vault.key().as_ref(),
+            donor.key().as_ref(),
+            &vault.crowdfund_round.to_le_bytes()
+        ],
+        bump
+    )]
+    pub donation: Option<Account<'info, Donation>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct OwnerCrowdfund<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundDonor<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::DONATION, vault.key().as_ref(), donor.key().as_ref(), &donation.round.to_le_bytes()],
+        bump = donation.bump,
+        has_one = vault,
+        has_one = donor
+    )]
+    pub donation: Account<'info, Donation>,
+
+    /// CHECK: receives the `Donation` rent; matched by `has_one`.
+    #[account(mut)]
+    pub donor: UncheckedAccount<'info>,
+
+    #[account(mut, token::mint = vault.usdc_mint, token::authority = donor)]
+    pub donor_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDonation<'info> {
+    #[account(
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = donor,
+        seeds = [seeds::DONATION, vault.key().as_ref(), donor.key().as_ref(), &donation.round.to_le_bytes()],
+        bump = donation.bump,
+        has_one = vault,
+        has_one = donor
+    )]
+    pub donation: Account<'info, Donation>,
+
+    /// CHECK: receives the `Donation` rent; matched by `has_one`.
+    #[account(mut)]
+    pub donor: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetExecCooldown<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct AdvanceOpNonce<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct RelayUsdc<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: vault owner; authorizes via the Ed25519 instruction, not as a signer
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = owner
+    )]
+    pub owner_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: instructions sysvar, address checked
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// needed while deposit limits are on and SOL is involved.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct IssueAttestation<'info> {
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = issuer,
+        space = Attestation::SPACE,
+        seeds = [seeds::ATTESTATION, issuer.key().as_ref(), subject.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct RevokeAttestation<'info> {
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = issuer,
+        seeds = [seeds::ATTESTATION, issuer.key().as_ref(), subject.as_ref()],
+        bump = attestation.bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+}
+
+#[derive(Accounts)]
+pub struct SetBlocked<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, has_one = admin @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Blocklist::SPACE,
+        seeds = [seeds::BLOCKLIST],
+        bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG],
+        bump = config.bump,
+        constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = FeeTreasury::SPACE,
+        seeds = [seeds::FEE_TREASURY],
+        bump
+    )]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [seeds::FEE_TREASURY], bump = fee_treasury.bump)]
+    pub fee_treasury: Account<'info, FeeTreasury>,
+
+    /// CHECK: receives swept lamports, or owns `destination_token`
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub treasury_token: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub destination_token: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct OpenFeeTier<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = FeeTier::SPACE,
+        seeds = [seeds::FEE_TIER, owner.key().as_ref()],
+        bump
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct AssignFeeTier<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FeeTier::SPACE,
+        seeds = [seeds::FEE_TIER, owner.as_ref()],
+        bump
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct RevokeFeeTier<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [seeds::FEE_TIER, owner.as_ref()], bump = fee_tier.bump)]
+    pub fee_tier: Account<'info, FeeTier>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeTierSchedule<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::FeeAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FeeTierSchedule::SPACE,
+        seeds = [seeds::FEE_TIER_SCHEDULE],
+        bump
+    )]
+    pub schedule: Account<'info, FeeTierSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshFeeTier<'info> {
+    #[account(mut, seeds = [seeds::FEE_TIER, fee_tier.owner.as_ref()], bump = fee_tier.bump)]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    #[account(seeds = [seeds::FEE_TIER_SCHEDULE], bump = schedule.bump)]
+    pub schedule: Account<'info, FeeTierSchedule>,
+}
+
+#[derive(Accounts)]
+pub struct SetStableRoute<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::RegistryAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    pub alt_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = StableRoute::SPACE,
+        seeds = [seeds::STABLE_ROUTE, alt_mint.key().as_ref()],
+        bump
+    )]
+    pub route: Account<'info, StableRoute>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct ConvertStable<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::STABLE_ROUTE, route.alt_mint.as_ref()], bump = route.bump)]
+    pub route: Box<Account<'info, StableRoute>>,
+
+    #[account(address = vault.usdc_mint @ EscrowError::BadMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = route.alt_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_alt: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pyth USD price of the alt stable, matched against the route
+    pub price_feed: UncheckedAccount<'info>,
+
+    /// CHECK: matched against the route's DEX program
+    pub dex_program: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar, address checked; enables the sandwich guard
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawStable<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub alt_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = alt_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_alt: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = alt_mint, token::authority = owner)]
+    pub user_alt: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = alt_mint)]
+    pub penalty_alt: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Pot share mint and the owner's share account; required once the vault has a
+    /// share mint (see `enable_share_mint`).
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCrosschainDomain<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump, constraint = config.has_role(ConfigRole::RegistryAdmin, &admin.key()) @ EscrowError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = CrosschainDomains::SPACE,
+        seeds = [seeds::CROSSCHAIN_DOMAINS],
+        bump
+    )]
+    pub domains: Account<'info, CrosschainDomains>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], domain: u32, recipient: [u8; 32])]
+pub struct AddCrosschainRecipient<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CROSSCHAIN_DOMAINS], bump = domains.bump)]
+    pub domains: Account<'info, CrosschainDomains>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = CrosschainRecipient::SPACE,
+        seeds = [seeds::CROSSCHAIN_RECIPIENT, vault.key().as_ref(), &domain.to_le_bytes(), recipient.as_ref()],
+        bump
+    )]
+    pub recipient: Account<'info, CrosschainRecipient>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32], domain: u32, recipient: [u8; 32])]
+pub struct RemoveCrosschainRecipient<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [seeds::CROSSCHAIN_RECIPIENT, vault.key().as_ref(), &domain.to_le_bytes(), recipient.as_ref()],
+        bump = recipient.bump
+    )]
+    pub recipient: Account<'info, CrosschainRecipient>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct WithdrawUsdcCrosschain<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [seeds::CROSSCHAIN_DOMAINS], bump = domains.bump)]
+    pub domains: Box<Account<'info, CrosschainDomains>>,
+
+    #[account(has_one = vault @ EscrowError::BadCrosschainRoute)]
+    pub recipient: Box<Account<'info, CrosschainRecipient>>,
+
+    /// CCTP accounts below are checked by TokenMessengerMinter itself; only the
+    /// two program ids are pinned here.
+    /// CHECK: TokenMessengerMinter `sender_authority` PDA.
+    pub sender_authority: UncheckedAccount<'info>,
+    /// CHECK: MessageTransmitter state.
+    #[account(mut)]
+    pub message_transmitter: UncheckedAccount<'info>,
+    /// CHECK: TokenMessenger state.
+    pub token_messenger: UncheckedAccount<'info>,
+    /// CHECK: RemoteTokenMessenger for the destination domain.
+    pub remote_token_messenger: UncheckedAccount<'info>,
+    /// CHECK: TokenMinter state.
+    pub token_minter: UncheckedAccount<'info>,
+    /// CHECK: LocalToken entry for USDC.
+    #[account(mut)]
+    pub local_token: UncheckedAccount<'info>,
+    /// CHECK: fresh keypair; CCTP creates the message account here.
+    #[account(mut)]
+    pub message_sent_event_data: Signer<'info>,
+    /// CHECK: TokenMessengerMinter event authority PDA.
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: pinned program id
+    #[account(address = CCTP_MESSAGE_TRANSMITTER_ID @ EscrowError::InvalidProgram)]
+    pub message_transmitter_program: UncheckedAccount<'info>,
+    /// CHECK: pinned program id
+    #[account(address = CCTP_TOKEN_MESSENGER_MINTER_ID @ EscrowError::InvalidProgram)]
+    pub token_messenger_minter_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    #[account(mut)]
+    pub share_mint: Option<Box<Account<'info, Mint>>>,
+
+    #[account(mut)]
+    pub owner_shares: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+
+    /// Pod shared vault's or fee treasury's token account; required while a
+    /// `commit_no_withdraw` commitment is active.
+    #[account(mut, token::mint = usdc_mint)]
+    pub penalty_usdc: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitSnapshotBuffer<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SnapshotBuffer::SPACE,
+        seeds = [seeds::SNAPSHOT_BUFFER, vault.key().as_ref()],
+        bump
+    )]
+    pub snapshot_buffer: AccountLoader<'info, SnapshotBuffer>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitActivityLog<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ActivityLog::SPACE,
+        seeds = [seeds::ACTIVITY_LOG, vault.key().as_ref()],
+        bump
+    )]
+    pub activity_log: AccountLoader<'info, ActivityLog>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Snapshot<'info> {
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [seeds::SNAPSHOT_BUFFER, vault.key().as_ref()],
+        bump
+    )]
+    pub snapshot_buffer: AccountLoader<'info, SnapshotBuffer>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// The vault's wSOL ATA, counted as native SOL when passed.
+    #[account(address = get_associated_token_address(&vault.key(), &native_mint::ID) @ EscrowError::BadVaultAccount)]
+    pub vault_wsol: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Required when the pot has a basket goal, so its progress is tracked.
+    #[account(mut, seeds = [seeds::GOAL_BASKET, vault.key().as_ref()], bump = goal_basket.bump)]
+    pub goal_basket: Option<Account<'info, GoalBasket>>,
+
+    /// The vault's pod policy, for its `valuation_mode`.
+    #[account(seeds = [seeds::POD_POLICY, vault.pod_hash.as_ref()], bump = pod_policy.load()?.bump)]
+    pub pod_policy: Option<AccountLoader<'info, PodPolicy>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pot_hash: [u8; 32])]
+pub struct SetGoalBasket<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::POT_VAULT, owner.key().as_ref(), pot_hash.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = GoalBasket::SPACE,
+        seeds = [seeds::GOAL_BASKET, vault.key().as_ref()],
+        bump
+    )]
+    pub goal_basket: Account<'info, GoalBasket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetVaultSummary<'info> {
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteWithdraw<'info> {
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Required to quote a USDC withdrawal.
+    #[account(address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(seeds = [seeds::FEE_TIER, vault.owner.as_ref()], bump = fee_tier.bump)]
+    pub fee_tier: Option<Account<'info, FeeTier>>,
+
+    #[account(seeds = [seeds::REFERRAL, vault.owner.as_ref()], bump = referral.bump)]
+    pub referral: Option<Account<'info, Referral>>,
+}
+
+#[derive(Accounts)]
+pub struct GetPolicyEffective<'info> {
+    #[account(seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [seeds::POD_POLICY, vault.pod_hash.as_ref()], bump = pod_policy.load()?.bump)]
+    pub pod_policy: AccountLoader<'info, PodPolicy>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// Takes precedence over `pod_policy` when present and `override_enabled`.
+    #[account(seeds = [seeds::POT_POLICY, vault.key().as_ref()], bump = pot_policy.bump)]
+    pub pot_policy: Option<Account<'info, PotPolicy>>,
+
+    /// The vault's wSOL ATA, counted as native SOL when passed.
+    #[account(address = get_associated_token_address(&vault.key(), &native_mint::ID) @ EscrowError::BadVaultAccount)]
+    pub vault_wsol: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+pub struct PlanRebalance<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub policy: GetPolicyEffective<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = RebalancePlan::SPACE,
+        seeds = [seeds::REBALANCE_PLAN, policy.vault.key().as_ref()],
+        bump
+    )]
+    pub plan: Account<'info, RebalancePlan>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRebalanceStep<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
+        seeds = [seeds::POT_VAULT, vault.owner.as_ref(), vault.pot_hash.as_ref()],
         bump = vault.bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(mut, seeds = [seeds::REBALANCE_PLAN, vault.key().as_ref()], bump = plan.bump, has_one = vault)]
+    pub plan: Account<'info, RebalancePlan>,
+
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Lulo or an allowlisted DEX program, per the step's kind
+    pub target_program: UncheckedAccount<'info>,
+
+    #[account(mut, address = vault.usdc_vault @ EscrowError::BadVaultAccount)]
+    pub vault_usdc: Box<Account<'info, TokenAccount>>,
+
+    /// Required for swap steps.
+    #[account(mut, address = get_associated_token_address(&vault.key(), &native_mint::ID) @ EscrowError::BadVaultAccount)]
+    pub vault_wsol: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked against `Config::sol_usd_price_feed`;
+    /// required for swap steps.
+    pub sol_price: Option<UncheckedAccount<'info>>,
+
+    /// The vault's Lulo position; required for redeem and supply steps.
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = Position::SPACE,
+        seeds = [seeds::POSITION, vault.key().as_ref(), &[VENUE_LULO]],
+        bump
+    )]
+    pub position: Option<Account<'info, Position>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "compressed")]
+#[derive(Accounts)]
+pub struct OpenCompressedVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Created by the first compressed pot.
+    #[account(init_if_needed, payer = owner, space = CompressedPool::SPACE, seeds = [seeds::COMPRESSED_POOL], bump)]
+    pub pool: Account<'info, CompressedPool>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "compressed")]
+#[derive(Accounts)]
+pub struct CompressedVaultOp<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::COMPRESSED_POOL], bump = pool.bump)]
+    pub pool: Account<'info, CompressedPool>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Required for the compliance gate; see `check_attestation`.
+    #[account(seeds = [seeds::CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Presented when the amount exceeds `Config::compliance_threshold`.
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// Program-wide outflow counter; required while `Config::outflow_limits` is on.
+    #[account(mut, seeds = [seeds::OUTFLOW_BREAKER], bump = outflow_breaker.bump)]
+    pub outflow_breaker: Option<Account<'info, OutflowBreaker>>,
+
+    /// Co-signs withdrawals above the per-user cap while the breaker is tripped.
+    pub guardian: Option<Signer<'info>>,
+}
+
+#[account]
+pub struct Config {
+    /// Upgrade admin: program-level settings and the fallback for unset roles.
+    pub admin: Pubkey,
+    pub referral_bps: u16,
+    pub bump: u8,
+    pub min_compound_amount: u64,
+    pub compound_cooldown_secs: i64,
+    /// Share of realized yield above the vault's high-water mark sent to `fee_treasury`.
+    pub performance_fee_bps: u16,
+    pub fee_treasury: Pubkey,
+    /// Bubblegum tree and verified collection for cNFT receipts; default when disabled.
+    pub receipt_tree: Pubkey,
+    pub receipt_collection: Pubkey,
+    pub receipt_uri: String,
+    /// Pause guardian: may temporarily freeze vault withdrawals; default disables
+    /// freezing.
+    pub guardian: Pubkey,
+    /// Amount above which deposits/withdrawals need an `Attestation`; 0 disables.
+    pub compliance_threshold: u64,
+    pub attestation_issuers: Vec<Pubkey>,
+    /// Withdrawals above this need a non-zero `memo_hash`; 0 disables.
+    pub memo_threshold: u64,
+    /// Pyth `PriceUpdateV2` account used to value SOL holdings.
+    pub sol_usd_price_feed: Pubkey,
+    /// Largest token balance `sweep_dust` treats as dust.
+    pub dust_threshold: u64,
+    /// Slippage allowed below the oracle rate on venue swaps; 0 uses
+    /// `DEFAULT_SWAP_SLIPPAGE_BPS`.
+    pub max_swap_slippage_bps: u16,
+    /// Smallest deposit accepted, in USDC units (SOL valued at the oracle); 0 disables.
+    pub min_deposit_usdc: u64,
+    /// Cap on a pot's net USDC plus idle and staked SOL, in USDC units; 0 disables.
+    pub max_pot_balance_usdc: u64,
+    /// Canonical USDC mint for this cluster; new vaults must use it.
+    pub usdc_mint: Pubkey,
+    /// Bumped by every `set_vault_params`; vaults below it are due a migration.
+    pub vault_params_version: u32,
+    pub vault_params: VaultParams,
+    /// Fees, fee tiers and the fee treasury; `admin` while unset.
+    pub fee_admin: Pubkey,
+    /// Protocol registry, stable routes and cross-chain domains; `admin` while unset.
+    pub registry_admin: Pubkey,
+    /// Two-step hand-over targets per role (`propose_role` / `accept_role`).
+    pub pending_admin: Pubkey,
+    pub pending_fee_admin: Pubkey,
+    pub pending_guardian: Pubkey,
+    pub pending_registry_admin: Pubkey,
+    pub outflow_limits: OutflowLimits,
+}
+
+/// Program-wide circuit breaker on withdrawals, metered in `OutflowBreaker`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct OutflowLimits {
+    /// Rolling window length; 0 disables the breaker.
+    pub window_secs: i64,
+    pub lamports_threshold: u64,
+    pub lamports_user_cap: u64,
+    /// USDC base units; other stablecoins count at their USDC-decimal equivalent.
+    pub usdc_threshold: u64,
+    pub usdc_user_cap: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigRole {
+    UpgradeAdmin,
+    FeeAdmin,
+    PauseGuardian,
+    RegistryAdmin,
+}
+
+/// Aggregate withdrawals in the current outflow window.
+#[account]
+pub struct OutflowBreaker {
+    pub bump: u8,
+    pub window_start: i64,
+    pub window_lamports: u64,
+    pub window_usdc: u64,
+}
+
+impl OutflowBreaker {
+    pub const SPACE: usize = 8 + 1 + 8 + 8 + 8;
+
+    /// Adds `amount` to the window, starting a fresh one once `window_secs` have
+    /// passed, and returns the asset's window total before and after.
+    pub fn record(&mut self, now: i64, window_secs: i64, is_sol: bool, amount: u64) -> (u64, u64) {
+        if now.saturating_sub(self.window_start) >= window_secs {
+            self.window_start = now;
+            self.window_lamports = 0;
+            self.window_usdc = 0;
+        }
+        let total = if is_sol { &mut self.window_lamports } else { &mut self.window_usdc };
+        let before = *total;
+        *total = total.saturating_add(amount);
+        (before, *total)
+    }
+}
+
+/// Per-vault parameters rolled out by `migrate_vault_params`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct VaultParams {
+    pub performance_fee_bps: u16,
+    pub external_exec_cooldown_slots: u64,
+}
+
+impl Config {
+    pub const MAX_PERFORMANCE_FEE_BPS: u16 = 5_000;
+    pub const MAX_RECEIPT_URI_LEN: usize = 200;
+    pub const MAX_ATTESTATION_ISSUERS: usize = 8;
+    pub const SPACE: usize = 8 + 32 + 2 + 1 + 8 + 8 + 2 + 32 + 32 + 32 + 4 + Self::MAX_RECEIPT_URI_LEN + 32
+        + 8
+        + 4
+        + 32 * Self::MAX_ATTESTATION_ISSUERS
+        + 8
+        + 32
+        + 8
+        + 2
+        + 8
+        + 8
+        + 32
+        + 4
+        + 2
+        + 8
+        + 32 * 6
+        + 8 * 5;
+
+    /// Key currently holding `role`. Unset fee/registry roles (and an unset
+    /// guardian, for hand-over purposes) fall back to `admin`.
+    pub fn role_holder(&self, role: ConfigRole) -> Pubkey {
+        let key = match role {
+            ConfigRole::UpgradeAdmin => self.admin,
+            ConfigRole::FeeAdmin => self.fee_admin,
+            ConfigRole::PauseGuardian => self.guardian,
+            ConfigRole::RegistryAdmin => self.registry_admin,
+        };
+        if key == Pubkey::default() {
+            self.admin
+        } else {
+            key
+        }
+    }
+
+    pub fn has_role(&self, role: ConfigRole, key: &Pubkey) -> bool {
+        self.role_holder(role) == *key
+    }
+
+    fn pending_role_mut(&mut self, role: ConfigRole) -> &mut Pubkey {
+        match role {
+            ConfigRole::UpgradeAdmin => &mut self.pending_admin,
+            ConfigRole::FeeAdmin => &mut self.pending_fee_admin,
+            ConfigRole::PauseGuardian => &mut self.pending_guardian,
+            ConfigRole::RegistryAdmin => &mut self.pending_registry_admin,
+        }
+    }
+
+    fn role_mut(&mut self, role: ConfigRole) -> &mut Pubkey {
+        match role {
+            ConfigRole::UpgradeAdmin => &mut self.admin,
+            ConfigRole::FeeAdmin => &mut self.fee_admin,
+            ConfigRole::PauseGuardian => &mut self.guardian,
+            ConfigRole::RegistryAdmin => &mut self.registry_admin,
+        }
+    }
+
+    pub fn deposit_limits_active(&self) -> bool {
+        self.min_deposit_usdc > 0 || self.max_pot_balance_usdc > 0
+    }
+
+    pub fn swap_slippage_bps(&self) -> u16 {
+        match self.max_swap_slippage_bps {
+            0 => DEFAULT_SWAP_SLIPPAGE_BPS,
+            bps => bps,
+        }
+    }
+}
+
+#[account]
+pub struct Referral {
+    pub owner: Pubkey,
+    pub referrer: Pubkey,
+    pub total_paid_lamports: u64,
+    pub total_paid_usdc: u64,
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct Vault {
+    pub owner: Pubkey,
+    pub pot_hash: [u8; 32],
+    pub bump: u8,
+    pub usdc_mint: Pubkey,
+    pub usdc_vault: Pubkey,
+    pub msol_amount: u64,
+    pub sol_staked_lamports: u64,
+    pub accrued_yield: u64,
+    pub pod_hash: [u8; 32],
+    /// Realized USDC yield sitting idle in the vault ATA, waiting for `compound`.
+    pub pending_yield_usdc: u64,
+    pub last_compound_ts: i64,
+    /// Cumulative realized USDC yield net of realized losses.
+    pub net_yield_usdc: i64,
+    /// Highest `net_yield_usdc` the performance fee has been charged on.
+    pub fee_hwm_usdc: i64,
+    /// Number of deposits so far; seeds the next `DepositReceipt`.
+    pub sequence: u64,
+    /// USDC reserved for open streams; not withdrawable by the owner.
+    pub stream_locked_usdc: u64,
+    /// Lifetime USDC saved through `deposit_roundup`.
+    pub roundup_total: u64,
+    /// End of the owner's no-withdraw commitment (0 = none).
+    pub no_withdraw_until: i64,
+    pub early_withdraw_penalty_bps: u16,
+    /// Withdrawals blocked until this time by a guardian freeze.
+    pub frozen_until: i64,
+    pub last_external_exec_slot: u64,
+    pub external_exec_cooldown_slots: u64,
+    /// Nonce the next relayer-submitted `SignedOp` must carry; bumped on every use.
+    pub op_nonce: u64,
+    /// Lifetime USDC deposited into the vault ATA.
+    pub total_in_usdc: u64,
+    /// Lifetime USDC paid out of the vault ATA by withdrawals, fees, penalties and streams.
+    pub total_out_usdc: u64,
+    /// Mints besides `usdc_mint` the vault holds through its own ATAs (wrapped BTC,
+    /// ETH, ... from the policy). `usdc_mint` stays the primary and is never listed.
+    pub registered_mints: Vec<Pubkey>,
+    /// Whitelisted by the admin: `Config` deposit limits don't apply.
+    pub limits_exempt: bool,
+    /// SPL mint for pot shares; default until `enable_share_mint`.
+    pub share_mint: Pubkey,
+    /// Anyone may `donate` into the pot; withdrawals stay owner-only.
+    pub public_deposits: bool,
+    /// Bumped by every `start_crowdfund`; seeds that round's `Donation`s.
+    pub crowdfund_round: u64,
+    /// Goal of the running crowdfund; 0 when none is running.
+    pub crowdfund_goal_usdc: u64,
+    pub crowdfund_deadline: i64,
+    /// USDC donated to the running crowdfund; locked until it settles.
+    pub crowdfund_raised_usdc: u64,
+    /// `Config::vault_params_version` last applied by `migrate_vault_params`; 0
+    /// means the vault still reads the Config-wide parameters.
+    pub params_version: u32,
+    /// Performance fee migrated from `Config::vault_params`.
+    pub performance_fee_bps: u16,
+    /// Bumped by every instruction that writes the vault and carried by every
+    /// vault event, so indexers can spot missed events across RPC gaps.
+    pub seq: u64,
+}
+
+impl Vault {
+    pub const MAX_REGISTERED_MINTS: usize = 8;
+    pub const FREEZE_SECS: i64 = 72 * 60 * 60;
+    pub const FREEZE_COOLDOWN_SECS: i64 = 72 * 60 * 60;
+
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 32 + 32 + 8 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 8 + 8
+        + 4
+        + 32 * Self::MAX_REGISTERED_MINTS
+        + 1
+        + 32
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 4
+        + 2
+        + 8;
+
+    pub fn next_seq(&mut self) {
+        self.seq = self.seq.wrapping_add(1);
+    }
+
+    /// Performance fee charged on this vault's yield: its migrated parameter once
+    /// `migrate_vault_params` has reached it, the Config-wide fee before that.
+    pub fn performance_fee_bps(&self, config: &Config) -> u16 {
+        if self.params_version > 0 {
+            self.performance_fee_bps
+        } else {
+            config.performance_fee_bps
+        }
+    }
+
+    /// SOL allocation in lamports: liquid lamports above rent plus mSOL valued at
+    /// Marinade's `msol_price`.
+    pub fn sol_allocation_lamports(&self, liquid_lamports: u64, msol_price: u64) -> u64 {
+        let staked = (self.msol_amount as u128) * (msol_price as u128) / MSOL_PRICE_DENOMINATOR;
+        liquid_lamports.saturating_add(staked as u64)
+    }
+
+    /// USDC backing the pot's shares: net deposits plus realized yield.
+    pub fn share_assets_usdc(&self) -> u64 {
+        let net = self.total_in_usdc as i128 - self.total_out_usdc as i128 + self.net_yield_usdc as i128;
+        net.clamp(0, u64::MAX as i128) as u64
+    }
+
+    /// Fills in a freshly created vault.
+    pub fn init(&mut self, owner: Pubkey, pot_hash: [u8; 32], bump: u8, usdc_mint: Pubkey, usdc_vault: Pubkey) {
+        self.owner = owner;
+        self.pot_hash = pot_hash;
+        self.bump = bump;
+        self.usdc_mint = usdc_mint;
+        self.usdc_vault = usdc_vault;
+    }
+
+    pub fn is_frozen(&self, now: i64) -> bool {
+        now < self.frozen_until
+    }
+
+    pub fn is_committed(&self, now: i64) -> bool {
+        now < self.no_withdraw_until
+    }
+
+    /// Accepts `nonce` only if it is the vault's current `op_nonce`, then advances it
+    /// so the same signed payload can't be replayed.
+    pub fn consume_op_nonce(&mut self, nonce: u64) -> Result<()> {
+        require!(nonce == self.op_nonce, EscrowError::BadNonce);
+        self.op_nonce = self.op_nonce.checked_add(1).ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// USDC the owner may move out, excluding amounts reserved for streams.
+    pub fn unlocked_usdc(&self, balance: u64) -> u64 {
+        balance
+            .saturating_sub(self.stream_locked_usdc)
+            .saturating_sub(self.crowdfund_raised_usdc)
+    }
+
+    pub fn crowdfund_active(&self) -> bool {
+        self.crowdfund_goal_usdc > 0
+    }
+
+    /// Settles the running crowdfund: claimed, or fully refunded.
+    pub fn end_crowdfund(&mut self) {
+        self.crowdfund_goal_usdc = 0;
+        self.crowdfund_deadline = 0;
+        self.crowdfund_raised_usdc = 0;
+    }
+
+    /// True for `usdc_mint` and every mint added with `register_mint`.
+    pub fn is_registered_mint(&self, mint: &Pubkey) -> bool {
+        *mint == self.usdc_mint || self.registered_mints.contains(mint)
+    }
+
+    pub fn book_usdc_in(&mut self, amount: u64) -> Result<()> {
+        self.total_in_usdc = self.total_in_usdc.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// `amount` is everything debited from the vault ATA, fee and penalty included.
+    pub fn book_usdc_out(&mut self, amount: u64) -> Result<()> {
+        self.total_out_usdc = self.total_out_usdc.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AssetTarget {
+    pub mint: Pubkey,
+    pub bps: u16,
+}
+
+impl AssetTarget {
+    pub const SPACE: usize = 32 + 2;
+}
+
+/// Zero-copy so instructions that only read a field or two don't pay for a Borsh
+/// decode of the whole asset list.
+#[account(zero_copy)]
+pub struct PodPolicy {
+    pub authority: Pubkey,
+    pub pod_hash: [u8; 32],
+    /// Set by `transfer_policy_authority`; becomes `authority` once it accepts.
+    pub pending_authority: Pubkey,
+    pub updated_at: i64,
+    /// Target allocation per asset, summing to 10_000 bps; the first `asset_count`
+    /// slots are in use.
+    pub assets: [PolicyAsset; MAX_POLICY_ASSETS],
+    pub usdc_in_lulo_bps: u16,
+    pub risk_state: u8,
+    pub bump: u8,
+    pub asset_count: u8,
+    pub _padding: [u8; 3],
+    /// Per-asset venue split, in portfolio bps; the first `venue_count` slots are
+    /// in use. Empty means the Lulo-only split in `usdc_in_lulo_bps`.
+    pub venues: [PolicyVenue; MAX_POLICY_VENUES],
+    /// Weight drift that warrants a rebalance; 0 uses `DEFAULT_MAX_DRIFT_BPS`.
+    pub max_drift_bps: u16,
+    pub venue_count: u8,
+    /// `VALUATION_SPOT` or `VALUATION_EMA`, for snapshots.
+    pub valuation_mode: u8,
+    /// Room for new fields without another migration.
+    pub _reserved: [u8; 28],
+    /// Set by the authority with `set_policy_manager`; may edit allocations only.
+    pub policy_manager: Pubkey,
+}
+
+#[zero_copy]
+pub struct PolicyAsset {
+    pub mint: Pubkey,
+    pub bps: u16,
+}
+
+/// Venue allocation stored by asset slot, so it fits the zero-copy layout.
+#[zero_copy]
+pub struct PolicyVenue {
+    pub asset_index: u8,
+    pub venue_id: u8,
+    pub bps: u16,
+}
+
+/// One asset's allocation to one venue, in portfolio bps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VenueAllocation {
+    pub mint: Pubkey,
+    pub venue_id: u8,
+    pub bps: u16,
+}
+
+impl PodPolicy {
+    /// Layout with fixed USDC/BTC/ETH/SOL bps fields, before `assets`.
+    pub const LEGACY_SPACE: usize = 8 + 32 + 32 + 1 + 2 + 2 + 2 + 2 + 2 + 1 + 8;
+    /// Borsh layout with `assets: Vec<AssetTarget>`, before the zero-copy conversion.
+    pub const BORSH_SPACE: usize = 8 + 32 + 32 + 1 + 2 + 1 + 8 + 4 + MAX_POLICY_ASSETS * AssetTarget::SPACE + 32;
+    /// Zero-copy layout before `policy_manager`, otherwise identical.
+    pub const V1_SPACE: usize = 8 + 448;
+    pub const SPACE: usize = 8 + std::mem::size_of::<PodPolicy>();
+
+    pub fn is_manager(&self, key: &Pubkey) -> bool {
+        self.policy_manager != Pubkey::default() && self.policy_manager == *key
+    }
+
+    pub fn targets(&self) -> Vec<AssetTarget> {
+        self.assets[..self.asset_count as usize]
+            .iter()
+            .map(|a| AssetTarget { mint: a.mint, bps: a.bps })
+            .collect()
+    }
+
+    /// Callers validate `assets` first (`validate_policy_targets`).
+    pub fn set_targets(&mut self, assets: &[AssetTarget]) {
+        self.assets = [PolicyAsset { mint: Pubkey::default(), bps: 0 }; MAX_POLICY_ASSETS];
+        for (slot, a) in self.assets.iter_mut().zip(assets) {
+            *slot = PolicyAsset { mint: a.mint, bps: a.bps };
+        }
+        self.asset_count = assets.len() as u8;
+    }
+
+    pub fn venue_allocations(&self) -> Vec<VenueAllocation> {
+        if self.venue_count == 0 {
+            return lulo_only_allocation(&self.targets(), self.usdc_in_lulo_bps);
+        }
+        self.venues[..self.venue_count as usize]
+            .iter()
+            .map(|v| VenueAllocation {
+                mint: self.assets[v.asset_index as usize].mint,
+                venue_id: v.venue_id,
+                bps: v.bps,
+            })
+            .collect()
+    }
+
+    /// Callers validate `allocations` against the current targets first
+    /// (`validate_venue_allocations`). Keeps `usdc_in_lulo_bps` in step for
+    /// readers that only know the Lulo split.
+    pub fn set_venue_allocations(&mut self, allocations: &[VenueAllocation]) {
+        self.venues = [PolicyVenue { asset_index: 0, venue_id: 0, bps: 0 }; MAX_POLICY_VENUES];
+        for (slot, a) in self.venues.iter_mut().zip(allocations) {
+            let asset_index = self.assets[..self.asset_count as usize]
+                .iter()
+                .position(|t| t.mint == a.mint)
+                .unwrap_or_default();
+            *slot = PolicyVenue { asset_index: asset_index as u8, venue_id: a.venue_id, bps: a.bps };
+        }
+        self.venue_count = allocations.len() as u8;
+        self.usdc_in_lulo_bps = allocations
+            .iter()
+            .find(|a| a.venue_id == VENUE_LULO && a.mint == self.assets[0].mint)
+            .map_or(0, |a| a.bps);
+    }
+
+    pub fn drift_threshold_bps(&self) -> u16 {
+        match self.max_drift_bps {
+            0 => DEFAULT_MAX_DRIFT_BPS,
+            bps => bps,
+        }
+    }
+
+    /// The venue holding the largest share of the cash leg (`assets[0]`); with no
+    /// venue split set, Lulo if `usdc_in_lulo_bps` lends any.
+    pub fn cash_venue(&self) -> Option<u8> {
+        if self.venue_count == 0 {
+            return (self.usdc_in_lulo_bps > 0).then_some(VENUE_LULO);
+        }
+        self.venues[..self.venue_count as usize]
+            .iter()
+            .filter(|v| v.asset_index == 0 && v.bps > 0)
+            .max_by_key(|v| v.bps)
+            .map(|v| v.venue_id)
+    }
+}
+
+/// `PodPolicy` body (after authority) in the Borsh layout, read by `migrate_pod_policy`.
+#[derive(AnchorDeserialize)]
+struct BorshPodPolicy {
+    _pod_hash: [u8; 32],
+    risk_state: u8,
+    usdc_in_lulo_bps: u16,
+    bump: u8,
+    updated_at: i64,
+    assets: Vec<AssetTarget>,
+    pending_authority: Pubkey,
+}
+
+#[account]
+pub struct ConditionalEscrow {
+    pub depositor: Pubkey,
+    pub counterparty: Pubkey,
+    pub escrow_id: [u8; 32],
+    pub hashlock: [u8; 32],
+    pub timelock: i64,
+    /// `Pubkey::default()` for native SOL escrows.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl ConditionalEscrow {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 32 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneStatus {
+    Pending,
+    Requested,
+    Released,
+    Disputed,
+    Refunded,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Milestone {
+    pub amount: u64,
+    pub status: MilestoneStatus,
+}
+
+#[account]
+pub struct MilestoneEscrow {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub arbiter: Pubkey,
+    pub escrow_id: [u8; 32],
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+    pub bump: u8,
+    pub milestones: Vec<Milestone>,
+}
+
+impl MilestoneEscrow {
+    pub const MAX_MILESTONES: usize = 8;
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 4 + Self::MAX_MILESTONES * (8 + 1);
+}
+
+/// Two-leg swap between pot vaults. Each leg is locked in a token account owned
+/// by the offer PDA, so neither owner can withdraw it while the offer is live.
+#[account]
+pub struct SwapOffer {
+    pub maker_vault: Pubkey,
+    pub taker_vault: Pubkey,
+    pub offer_id: [u8; 32],
+    pub mint_a: Pubkey,
+    pub amount_a: u64,
+    pub mint_b: Pubkey,
+    pub amount_b: u64,
+    pub accepted: bool,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl SwapOffer {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 32 + 8 + 1 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ProtocolEntry {
+    pub venue_id: u8,
+    pub program_id: Pubkey,
+    /// Output mint for venues that route into a token (e.g. the configured LST).
+    pub mint: Pubkey,
+    pub enabled: bool,
+}
+
+#[account]
+pub struct ProtocolRegistry {
+    pub entries: Vec<ProtocolEntry>,
+    pub bump: u8,
+}
+
+impl ProtocolRegistry {
+    pub const MAX_ENTRIES: usize = 16;
+    pub const SPACE: usize = 8 + 4 + Self::MAX_ENTRIES * (1 + 32 + 32 + 1) + 1;
+
+    pub fn require_enabled(&self, venue_id: u8, program_id: Pubkey) -> Result<&ProtocolEntry> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.venue_id == venue_id)
+            .ok_or(EscrowError::VenueDisabled)?;
+        require!(entry.enabled, EscrowError::VenueDisabled);
+        require_keys_eq!(entry.program_id, program_id, EscrowError::InvalidProgram);
+        Ok(entry)
+    }
+}
+
+/// Per-venue position of a vault. `principal` is in the venue's liquidity units,
+/// `shares` in its receipt token.
+#[account]
+pub struct Position {
+    pub vault: Pubkey,
+    pub venue_id: u8,
+    /// Venue-side account the position lives in (reserve, marginfi account, ...).
+    pub venue_account: Pubkey,
+    pub principal: u64,
+    pub shares: u64,
+    pub last_update: i64,
+    pub bump: u8,
+}
+
+impl Position {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// Ordered steps written by `plan_rebalance`; `next_step` is the only one
+/// `execute_rebalance_step` will run.
+#[account]
+pub struct RebalancePlan {
+    pub vault: Pubkey,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub next_step: u8,
+    pub bump: u8,
+    pub steps: Vec<RebalanceStep>,
+}
+
+impl RebalancePlan {
+    pub const MAX_STEPS: usize = 4;
+    pub const TTL_SECS: i64 = 10 * 60;
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 1 + 4 + Self::MAX_STEPS * RebalanceStep::SPACE;
+
+    /// Every step ran, or the plan lapsed; either way a new one may replace it.
+    pub fn is_finished(&self, now: i64) -> bool {
+        self.next_step as usize >= self.steps.len() || now >= self.expires_at
+    }
+}
+
+/// `amount` is what the step spends: USDC for supply and buy, lamports for sell,
+/// and the USDC that a redemption must return at least.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RebalanceStep {
+    pub kind: u8,
+    pub amount: u64,
+    pub done: bool,
+}
+
+impl RebalanceStep {
+    pub const SPACE: usize = 1 + 8 + 1;
+}
+
+#[account]
+pub struct PotPolicy {
+    pub vault: Pubkey,
+    pub risk_state: u8,
+    pub usdc_in_lulo_bps: u16,
+    pub override_enabled: bool,
+    pub bump: u8,
+    pub updated_at: i64,
+    pub assets: Vec<AssetTarget>,
+}
+
+impl PotPolicy {
+    pub const SPACE: usize = 8 + 32 + 1 + 2 + 1 + 1 + 8 + 4 + MAX_POLICY_ASSETS * AssetTarget::SPACE;
+}
+
+/// Ring buffer of `update_policy` calls. The header is followed by `capacity`
+/// `PolicyHistoryEntry` records; `head` is the next slot to write.
+#[account(zero_copy)]
+pub struct PolicyHistory {
+    pub pod_hash: [u8; 32],
+    pub capacity: u32,
+    pub len: u32,
+    pub head: u32,
+    pub bump: u8,
+    pub _padding: [u8; 3],
+}
 
-    pub usdc_mint: Account<'info, Mint>,
+impl PolicyHistory {
+    pub const DEFAULT_CAPACITY: u32 = 32;
+    pub const MAX_CAPACITY: u32 = 1024;
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = owner
-    )]
-    pub user_usdc: Account<'info, TokenAccount>,
+    pub fn space(capacity: u32) -> usize {
+        POLICY_HISTORY_ENTRIES_OFFSET + capacity as usize * POLICY_HISTORY_ENTRY_LEN
+    }
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = vault
-    )]
-    pub vault_usdc: Account<'info, TokenAccount>,
+#[zero_copy]
+pub struct PolicyHistoryEntry {
+    pub timestamp: i64,
+    /// Bps of the policy's assets at the time, in `assets` order.
+    pub target_bps: [u16; MAX_POLICY_ASSETS],
+    pub usdc_in_lulo_bps: u16,
+    pub risk_state: u8,
+    pub asset_count: u8,
+    pub _padding: [u8; 4],
+}
 
-    pub token_program: Program<'info, Token>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BatchDepositEntry {
+    pub pot_hash: [u8; 32],
+    pub lamports: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct WithdrawUsdc<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+/// An owner's standing split of incoming deposits across their pots.
+#[account]
+pub struct SplitRule {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub entries: Vec<SplitEntry>,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+impl SplitRule {
+    pub const MAX_POTS: usize = 8;
+    pub const SPACE: usize = 8 + 32 + 1 + 4 + Self::MAX_POTS * (32 + 2);
+}
 
-    pub usdc_mint: Account<'info, Mint>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SplitEntry {
+    pub pot_hash: [u8; 32],
+    pub bps: u16,
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = owner
-    )]
-    pub user_usdc: Account<'info, TokenAccount>,
+/// Pots of one owner, so clients can list them without a program-account scan.
+#[account]
+pub struct OwnerRegistry {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub pot_hashes: Vec<[u8; 32]>,
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = vault
-    )]
-    pub vault_usdc: Account<'info, TokenAccount>,
+impl OwnerRegistry {
+    pub const MAX_POTS: usize = 64;
+
+    pub fn space(count: usize) -> usize {
+        8 + 32 + 1 + 4 + count * 32
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PotCategory {
+    General,
+    Emergency,
+    Travel,
+    Education,
+    Housing,
+    Vehicle,
+    Celebration,
+    Other,
+}
+
+/// Client-portable display data for a pot.
+#[account]
+pub struct PotMetadata {
+    pub vault: Pubkey,
+    /// UTF-8, at most `MAX_NAME_LEN` bytes.
+    pub name: String,
+    pub category: PotCategory,
+    /// Unix timestamp, 0 when the pot has no deadline.
+    pub target_date: i64,
+    pub icon_index: u8,
+    pub bump: u8,
+}
+
+impl PotMetadata {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const SPACE: usize = 8 + 32 + 4 + Self::MAX_NAME_LEN + 1 + 8 + 1 + 1;
+}
+
+/// Revealed preimage of a vault's `pot_hash`; `hash(preimage) == pot_hash` is
+/// checked on creation.
+#[account]
+pub struct PotCommitment {
+    pub vault: Pubkey,
+    pub bump: u8,
+    pub preimage: Vec<u8>,
+}
+
+impl PotCommitment {
+    pub const MAX_PREIMAGE_LEN: usize = 512;
+
+    pub fn space(preimage_len: usize) -> usize {
+        8 + 32 + 1 + 4 + preimage_len
+    }
+}
+
+/// Immutable record of one deposit, keyed by (vault, sequence).
+#[account]
+pub struct DepositReceipt {
+    pub vault: Pubkey,
+    pub sequence: u64,
+    pub depositor: Pubkey,
+    /// `Pubkey::default()` for native SOL.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+impl DepositReceipt {
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 32 + 8 + 8;
+}
+
+/// Claimed idempotency key of one deposit into `vault`; see `claim_idempotency_key`.
+#[account]
+pub struct DepositMarker {
+    pub vault: Pubkey,
+    pub slot: u64,
+    pub bump: u8,
+}
+
+impl DepositMarker {
+    pub const SPACE: usize = 8 + 32 + 8 + 1;
+}
+
+/// Marks that a cNFT receipt was minted for `source`/`index`, so each is minted once.
+#[account]
+pub struct CnftReceipt {
+    pub source: Pubkey,
+    pub index: u8,
+    pub leaf_owner: Pubkey,
+    pub minted_at: i64,
+}
+
+impl CnftReceipt {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 8;
+}
+
+/// Recurring USDC spending budget under a vault (e.g. "food: 200 USDC / month").
+#[account]
+pub struct Envelope {
+    pub vault: Pubkey,
+    pub envelope_id: u8,
+    pub budget: u64,
+    pub period_secs: i64,
+    pub period_start: i64,
+    /// Spent in the current period.
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl Envelope {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 1;
+
+    /// Starts a new period (skipping idle ones) once the current one has elapsed.
+    pub fn roll_period(&mut self, now: i64) {
+        let elapsed = now - self.period_start;
+        if elapsed >= self.period_secs {
+            self.period_start += elapsed - elapsed % self.period_secs;
+            self.spent = 0;
+        }
+    }
+}
+
+/// Merchant pull-payment authorization over a vault's USDC.
+#[account]
+pub struct Subscription {
+    pub vault: Pubkey,
+    pub merchant: Pubkey,
+    pub merchant_usdc: Pubkey,
+    /// Per-charge cap.
+    pub amount: u64,
+    pub interval_secs: i64,
+    pub grace_secs: i64,
+    pub next_charge_ts: i64,
+    pub paused: bool,
+    pub missed_charges: u32,
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 4 + 1;
+}
+
+/// One donor's contributions to one crowdfund round of a vault.
+#[account]
+pub struct Donation {
+    pub vault: Pubkey,
+    pub donor: Pubkey,
+    pub round: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl Donation {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// A pot goal denominated in a reference basket instead of USD, so a long-horizon
+/// goal keeps its purchasing power.
+#[account]
+pub struct GoalBasket {
+    pub vault: Pubkey,
+    pub bump: u8,
+    /// Goal size in basket units (6 decimals).
+    pub goal_units: u64,
+    pub components: Vec<BasketComponent>,
+    /// Goal and progress as of the last `snapshot`.
+    pub last_goal_usd: u64,
+    pub last_progress_bps: u32,
+}
+
+impl GoalBasket {
+    pub const MAX_COMPONENTS: usize = 4;
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 4 + Self::MAX_COMPONENTS * (32 + 2) + 8 + 4;
+
+    /// The goal in USDC base units; `feeds` are the components' Pyth price updates
+    /// in order.
+    pub fn goal_usd(&self, feeds: &[AccountInfo]) -> Result<u64> {
+        require!(feeds.len() == self.components.len(), EscrowError::InvalidBasket);
+        let mut unit_usd: u128 = 0;
+        for (c, feed) in self.components.iter().zip(feeds) {
+            require_keys_eq!(*feed.key, c.price_feed, EscrowError::InvalidBasket);
+            let (price, expo) = read_pyth_price(feed, MAX_PRICE_AGE_SECS)?;
+            let value = units_to_usdc(self.goal_units, price, expo)?;
+            unit_usd += (value as u128) * (c.weight_bps as u128) / 10_000;
+        }
+        u64::try_from(unit_usd).map_err(|_| error!(EscrowError::MathOverflow))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BasketComponent {
+    /// Pyth `PriceUpdateV2` account quoting the component in USD.
+    pub price_feed: Pubkey,
+    pub weight_bps: u16,
+}
+
+/// Fixed USDC installments out of a vault after its target date.
+#[account]
+pub struct PayoutPlan {
+    pub vault: Pubkey,
+    /// Owner of the USDC account installments go to; may be the vault owner.
+    pub recipient: Pubkey,
+    pub installment_usdc: u64,
+    pub interval_secs: i64,
+    pub next_payout_ts: i64,
+    pub bump: u8,
+}
+
+impl PayoutPlan {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// Linear per-second USDC stream out of a vault.
+#[account]
+pub struct Stream {
+    pub vault: Pubkey,
+    pub recipient: Pubkey,
+    pub rate_per_second: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub total: u64,
+    pub withdrawn: u64,
+    pub bump: u8,
+    /// Distinguishes streams from the same vault to the same recipient.
+    pub stream_id: u64,
+}
+
+impl Stream {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+
+    pub fn vested(&self, now: i64) -> u64 {
+        if now >= self.end_ts {
+            return self.total;
+        }
+        let elapsed = now.saturating_sub(self.start_ts).max(0) as u64;
+        self.rate_per_second.saturating_mul(elapsed).min(self.total)
+    }
+}
+
+/// Shared savings group keyed by `pod_hash` (same hash as `PodPolicy`).
+#[account]
+pub struct Pod {
+    pub pod_hash: [u8; 32],
+    pub admin: Pubkey,
+    pub max_members: u16,
+    pub member_count: u16,
+    pub bump: u8,
+    pub usdc_mint: Pubkey,
+    /// Pod shared vault: USDC ATA of the `["pod_vault", pod_hash]` PDA.
+    pub pod_usdc: Pubkey,
+    pub pod_vault_bump: u8,
+    /// Sum of live members' `Contribution::amount`.
+    pub total_contributed: u64,
+    pub early_exit_penalty_bps: u16,
+    /// ORAO VRF seed requested by `start_rosca`; membership is frozen once set.
+    pub rosca_seed: [u8; 32],
+    pub rosca_started_at: i64,
+    pub rosca_round: u16,
+    /// Member paid out in each round, derived from the VRF output.
+    pub payout_order: Vec<Pubkey>,
+    pub round_amount: u64,
+    pub round_secs: i64,
+    pub contribution_window_secs: i64,
+    pub late_penalty_bps: u16,
+    /// See `set_removal_quorum`.
+    pub removal_quorum_bps: u16,
+    /// Sum of members' contribution-seconds since the last `distribute_yield`.
+    pub total_weight: u128,
+    pub weight_updated_at: i64,
+    /// Yield credited to members' internal balances and not yet claimed.
+    pub yield_credited: u64,
+    /// ROSCA contributions collected but not yet paid out.
+    pub rosca_held: u64,
+}
+
+impl Pod {
+    pub const MAX_MEMBERS: u16 = 32;
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 2 + 1 + 32 + 32 + 1 + 8 + 2 + 32 + 8 + 2
+        + 4 + 32 * Self::MAX_MEMBERS as usize
+        + 8 + 8 + 8 + 2
+        + 2
+        + 16 + 8 + 8 + 8;
+
+    /// Advances `total_weight` to `now` at the current `total_contributed`.
+    pub fn accrue(&mut self, now: i64) {
+        let elapsed = now.saturating_sub(self.weight_updated_at).max(0) as u128;
+        self.total_weight += self.total_contributed as u128 * elapsed;
+        self.weight_updated_at = now;
+    }
+
+    /// Vault balance not owed to anyone: yield and retained penalties.
+    pub fn surplus(&self, balance: u64) -> u64 {
+        balance
+            .saturating_sub(self.total_contributed)
+            .saturating_sub(self.yield_credited)
+            .saturating_sub(self.rosca_held)
+    }
+
+    /// Last moment to pay round `round` without being late.
+    pub fn round_deadline(&self, round: u16) -> i64 {
+        self.rosca_started_at + self.round_secs * round as i64 + self.contribution_window_secs
+    }
+}
+
+/// Admins manage policy and membership, treasurers trigger payouts, contributors
+/// deposit; viewers are read-only members.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PodRole {
+    Admin,
+    Treasurer,
+    Contributor,
+    Viewer,
+}
+
+/// A member's record in a pod; exists for as long as they are a member.
+#[account]
+pub struct Contribution {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub joined_at: i64,
+    pub bump: u8,
+    /// USDC paid into the pod vault.
+    pub amount: u64,
+    /// ROSCA rounds paid so far.
+    pub rounds_paid: u16,
+    pub late_count: u16,
+    /// `round + 1` of the last round flagged late (0 = never).
+    pub late_flagged_round: u16,
+    /// Withheld from the member's next ROSCA payout.
+    pub pending_penalty_bps: u16,
+    pub role: PodRole,
+    /// Contribution-seconds since the last `distribute_yield`.
+    pub weight: u128,
+    pub weight_updated_at: i64,
+    /// Distributed yield not yet claimed.
+    pub yield_balance: u64,
+}
+
+impl Contribution {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1 + 8 + 2 + 2 + 2 + 2 + 1 + 16 + 8 + 8;
+
+    pub fn accrue(&mut self, now: i64) {
+        let elapsed = now.saturating_sub(self.weight_updated_at).max(0) as u128;
+        self.weight += self.amount as u128 * elapsed;
+        self.weight_updated_at = now;
+    }
+}
+
+#[account]
+pub struct PodInvite {
+    pub pod: Pubkey,
+    pub invite_hash: [u8; 32],
+    pub max_uses: u16,
+    pub uses: u16,
+    pub expiry: i64,
+    pub bump: u8,
+}
+
+impl PodInvite {
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 2 + 8 + 1;
+}
+
+#[account]
+pub struct RemovalProposal {
+    pub pod: Pubkey,
+    pub target: Pubkey,
+    pub proposer: Pubkey,
+    pub created_at: i64,
+    /// Members in favour, proposer included.
+    pub voters: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl RemovalProposal {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 4 + 32 * Pod::MAX_MEMBERS as usize + 1;
+}
+
+/// Payload an owner signs off-chain to authorize a relayer-submitted operation.
+/// `nonce` must equal the vault's `op_nonce` at execution time.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SignedOp {
+    pub vault: Pubkey,
+    pub kind: u8,
+    pub amount: u64,
+    pub nonce: u64,
+    pub expires_at: i64,
+}
+
+impl SignedOp {
+    pub const DEPOSIT_USDC: u8 = 1;
+    pub const WITHDRAW_USDC: u8 = 2;
+    pub const DOMAIN: &'static [u8] = b"kobafin:signed-op:v1";
+
+    /// Exact bytes the owner signs: the domain tag followed by the fields, little-endian.
+    pub fn message(&self) -> Vec<u8> {
+        let mut m = Vec::with_capacity(Self::DOMAIN.len() + 32 + 1 + 8 + 8 + 8);
+        m.extend_from_slice(Self::DOMAIN);
+        m.extend_from_slice(self.vault.as_ref());
+        m.push(self.kind);
+        m.extend_from_slice(&self.amount.to_le_bytes());
+        m.extend_from_slice(&self.nonce.to_le_bytes());
+        m.extend_from_slice(&self.expires_at.to_le_bytes());
+        m
+    }
+}
+
+/// Slot and timestamp bounds a pre-signed instruction may execute within; 0 leaves
+/// that bound open.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ExecWindow {
+    pub valid_after_slot: u64,
+    pub valid_until_slot: u64,
+    pub valid_after_ts: i64,
+    pub valid_until_ts: i64,
+}
+
+impl ExecWindow {
+    pub fn check(&self) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            self.valid_after_slot == 0 || clock.slot >= self.valid_after_slot,
+            EscrowError::OutsideValidWindow
+        );
+        require!(
+            self.valid_until_slot == 0 || clock.slot <= self.valid_until_slot,
+            EscrowError::OutsideValidWindow
+        );
+        require!(
+            self.valid_after_ts == 0 || clock.unix_timestamp >= self.valid_after_ts,
+            EscrowError::OutsideValidWindow
+        );
+        require!(
+            self.valid_until_ts == 0 || clock.unix_timestamp <= self.valid_until_ts,
+            EscrowError::OutsideValidWindow
+        );
+        Ok(())
+    }
+}
+
+/// Issuer-signed statement that `subject` passed the issuer's compliance checks.
+#[account]
+pub struct Attestation {
+    pub subject: Pubkey,
+    pub issuer: Pubkey,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Attestation {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// Protocol fee account: holds SOL fees itself and token fees in its ATAs, with
+/// cumulative per-mint counters (`Pubkey::default()` for SOL).
+#[account]
+pub struct FeeTreasury {
+    pub bump: u8,
+    pub totals: Vec<FeeTotal>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct FeeTotal {
+    pub mint: Pubkey,
+    pub collected: u64,
+    pub swept: u64,
+}
+
+impl FeeTreasury {
+    pub const MAX_MINTS: usize = 8;
+    pub const SPACE: usize = 8 + 1 + 4 + Self::MAX_MINTS * (32 + 8 + 8);
+
+    fn total_mut(&mut self, mint: Pubkey) -> Result<&mut FeeTotal> {
+        if let Some(i) = self.totals.iter().position(|t| t.mint == mint) {
+            return Ok(&mut self.totals[i]);
+        }
+        require!(self.totals.len() < Self::MAX_MINTS, EscrowError::RegistryFull);
+        self.totals.push(FeeTotal { mint, collected: 0, swept: 0 });
+        Ok(self.totals.last_mut().unwrap())
+    }
+
+    pub fn record(&mut self, mint: Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let t = self.total_mut(mint)?;
+        t.collected = t.collected.checked_add(amount).ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Books a sweep of the whole `balance`; anything beyond what was recorded
+    /// counts as collected.
+    pub fn sweep(&mut self, mint: Pubkey, balance: u64) -> Result<FeeTotal> {
+        let t = self.total_mut(mint)?;
+        t.swept = t.swept.checked_add(balance).ok_or(EscrowError::MathOverflow)?;
+        t.collected = t.collected.max(t.swept);
+        Ok(*t)
+    }
+}
+
+/// Per-owner fee cap: an admin-assigned promo/loyalty tier and a level earned
+/// from USDC withdrawal volume. The lower active cap wins, on SOL withdrawals too.
+#[account]
+pub struct FeeTier {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub assigned: bool,
+    pub assigned_fee_bps: u16,
+    /// Assigned tier expiry; 0 = none.
+    pub assigned_until: i64,
+    /// From `FeeTierSchedule` at the last `refresh_fee_tier`; `NO_CAP` when none.
+    pub earned_fee_bps: u16,
+    /// USDC withdrawn through fee-paying `withdraw_usdc_with_fee` calls while the tier
+    /// was passed. SOL withdrawals don't count: there is no price on that path to
+    /// value them at.
+    pub volume_usdc: u64,
+}
+
+impl FeeTier {
+    pub const NO_CAP: u16 = u16::MAX;
+    pub const SPACE: usize = 8 + 32 + 1 + 1 + 2 + 8 + 2 + 8;
+
+    /// Fee cap in bps at `now`; `NO_CAP` when neither tier applies.
+    pub fn max_fee_bps(&self, now: i64) -> u16 {
+        let assigned_active = self.assigned && (self.assigned_until == 0 || now < self.assigned_until);
+        let assigned = if assigned_active { self.assigned_fee_bps } else { Self::NO_CAP };
+        assigned.min(self.earned_fee_bps)
+    }
+}
+
+#[account]
+pub struct FeeTierSchedule {
+    pub bump: u8,
+    pub levels: Vec<FeeTierLevel>,
+}
+
+impl FeeTierSchedule {
+    pub const MAX_LEVELS: usize = 8;
+    pub const SPACE: usize = 8 + 1 + 4 + Self::MAX_LEVELS * (8 + 2);
+
+    pub fn earned_fee_bps(&self, volume_usdc: u64) -> u16 {
+        self.levels
+            .iter()
+            .rev()
+            .find(|l| volume_usdc >= l.min_volume_usdc)
+            .map_or(FeeTier::NO_CAP, |l| l.max_fee_bps)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct FeeTierLevel {
+    pub min_volume_usdc: u64,
+    pub max_fee_bps: u16,
+}
+
+/// Admin-approved conversion route between USDC and one alt stablecoin.
+#[account]
+pub struct StableRoute {
+    pub alt_mint: Pubkey,
+    pub alt_decimals: u8,
+    /// One of `DEX_PROGRAM_IDS`.
+    pub dex_program: Pubkey,
+    /// Pyth `PriceUpdateV2` quoting the alt stable in USD.
+    pub price_feed: Pubkey,
+    pub max_slippage_bps: u16,
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+impl StableRoute {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 32 + 2 + 1 + 1;
+}
+
+/// CCTP destination domains cross-chain withdrawals may target.
+#[account]
+pub struct CrosschainDomains {
+    pub bump: u8,
+    pub domains: Vec<u32>,
+}
+
+impl CrosschainDomains {
+    pub const MAX_DOMAINS: usize = 16;
+    pub const SPACE: usize = 8 + 1 + 4 + 4 * Self::MAX_DOMAINS;
+}
+
+/// One allowlisted off-chain recipient of a pot's cross-chain withdrawals.
+#[account]
+pub struct CrosschainRecipient {
+    pub vault: Pubkey,
+    pub domain: u32,
+    /// Destination address, left-padded to 32 bytes for EVM chains.
+    pub recipient: [u8; 32],
+    pub bump: u8,
+}
+
+impl CrosschainRecipient {
+    pub const SPACE: usize = 8 + 32 + 4 + 32 + 1;
+}
+
+/// Sanctioned addresses that may not receive funds through `withdraw_to`.
+#[account]
+pub struct Blocklist {
+    pub bump: u8,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl Blocklist {
+    pub const MAX_ADDRESSES: usize = 128;
+    pub const SPACE: usize = 8 + 1 + 4 + 32 * Self::MAX_ADDRESSES;
+}
+
+/// Holds the lamports of every compressed pot; each pot's part is the `lamports`
+/// of its `CompressedVault`.
+#[cfg(feature = "compressed")]
+#[account]
+pub struct CompressedPool {
+    pub bump: u8,
+    pub total_lamports: u64,
+}
 
-    pub token_program: Program<'info, Token>,
+#[cfg(feature = "compressed")]
+impl CompressedPool {
+    pub const SPACE: usize = 8 + 1 + 8;
 }
 
-#[derive(Accounts)]
-#[instruction(pot_hash: [u8; 32])]
-pub struct LuloExecute<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+/// A SOL-only pot kept as a Light Protocol compressed account rather than a
+/// `Vault` PDA: no rent, and none of the venue, policy or USDC features.
+#[cfg(feature = "compressed")]
+#[derive(Clone, Debug, Default, AnchorSerialize, AnchorDeserialize, LightDiscriminator, LightHasher)]
+pub struct CompressedVault {
+    #[hash]
+    pub owner: Pubkey,
+    #[hash]
+    pub pot_hash: [u8; 32],
+    pub lamports: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"pot_vault", owner.key().as_ref(), pot_hash.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+/// Last `CAPACITY` daily valuations of one vault, oldest overwritten first. The
+/// header is followed by `PortfolioSnapshot` records; `head` is the next slot.
+#[account(zero_copy)]
+pub struct SnapshotBuffer {
+    pub vault: Pubkey,
+    pub last_day: i64,
+    pub len: u32,
+    pub head: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
 
-    /// CHECK: validated against constant program id
-    pub lulo_program: UncheckedAccount<'info>,
+impl SnapshotBuffer {
+    pub const CAPACITY: u32 = 90;
+    pub const SPACE: usize = SNAPSHOT_ENTRIES_OFFSET + Self::CAPACITY as usize * SNAPSHOT_ENTRY_LEN;
 }
 
-#[derive(Accounts)]
-#[instruction(pod_hash: [u8; 32])]
-pub struct UpdatePolicy<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[zero_copy]
+pub struct PortfolioSnapshot {
+    /// Days since the Unix epoch.
+    pub day: i64,
+    /// Vault value in USDC base units; venue positions at principal.
+    pub total_usd: u64,
+    /// `Vault::net_yield_usdc` at snapshot time.
+    pub yield_to_date: i64,
+    /// Share of `total_usd` per bucket: idle USDC, Lulo, Kamino, marginfi, Drift, SOL.
+    pub weight_bps: [u16; SNAPSHOT_BUCKETS],
+    pub _padding: [u8; 4],
+}
 
-    #[account(
-        init_if_needed,
-        payer = authority,
-        space = PodPolicy::SPACE,
-        seeds = [b"pod_policy", pod_hash.as_ref()],
-        bump
-    )]
-    pub pod_policy: Account<'info, PodPolicy>,
+/// Last `CAPACITY` deposits and withdrawals of one vault, oldest overwritten
+/// first, so a client can render history from a single fetch. The header is
+/// followed by `ActivityEntry` records; `head` is the next slot.
+#[account(zero_copy)]
+pub struct ActivityLog {
+    pub vault: Pubkey,
+    pub len: u32,
+    pub head: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
 
-    pub system_program: Program<'info, System>,
+impl ActivityLog {
+    pub const CAPACITY: u32 = 64;
+    pub const SPACE: usize = ACTIVITY_ENTRIES_OFFSET + Self::CAPACITY as usize * ACTIVITY_ENTRY_LEN;
 }
 
-#[account]
-pub struct Vault {
-    pub owner: Pubkey,
-    pub pot_hash: [u8; 32],
-    pub bump: u8,
-    pub usdc_mint: Pubkey,
-    pub usdc_vault: Pubkey,
+#[zero_copy]
+pub struct ActivityEntry {
+    pub slot: u64,
+    pub amount: u64,
+    /// The owner, or the recipient of a `withdraw_to`.
+    pub counterparty: Pubkey,
+    /// Default key for SOL.
+    pub mint: Pubkey,
+    /// One of the `ACTIVITY_*` kinds.
+    pub kind: u8,
+    pub _padding: [u8; 7],
 }
 
-impl Vault {
-    pub const SPACE: usize = 8 + 32 + 32 + 1 + 32 + 32;
+/// Return data of `get_vault_summary`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultSummary {
+    /// Lamports above rent; 0 while frozen.
+    pub withdrawable_lamports: u64,
+    /// USDC not reserved for streams; 0 while frozen.
+    pub withdrawable_usdc: u64,
+    /// Penalty `withdraw` / `withdraw_usdc` would charge right now.
+    pub early_withdraw_penalty_bps: u16,
+    pub frozen_until: i64,
+    pub no_withdraw_until: i64,
+    pub stream_locked_usdc: u64,
+    pub pending_yield_usdc: u64,
+    pub net_yield_usdc: i64,
+    pub op_nonce: u64,
 }
 
-#[account]
-pub struct PodPolicy {
-    pub authority: Pubkey,
-    pub pod_hash: [u8; 32],
+/// Return data of `get_policy_effective`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PolicyEffective {
+    /// True when the pot policy override is in force.
+    pub overridden: bool,
     pub risk_state: u8,
-    pub target_usdc_bps: u16,
-    pub target_btc_bps: u16,
-    pub target_eth_bps: u16,
-    pub target_sol_bps: u16,
     pub usdc_in_lulo_bps: u16,
-    pub bump: u8,
-    pub updated_at: i64,
+    /// Share of the vault's USDC currently in Lulo.
+    pub current_lulo_bps: u16,
+    pub total_usd: u64,
+    pub targets: Vec<AssetDrift>,
+    /// Venue split in force (the Lulo-only split when none is set).
+    pub venues: Vec<VenueAllocation>,
 }
 
-impl PodPolicy {
-    pub const SPACE: usize = 8 + 32 + 32 + 1 + 2 + 2 + 2 + 2 + 2 + 1 + 8;
+/// Return data of `needs_rebalance`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RebalanceCheck {
+    pub needs_rebalance: bool,
+    /// Threshold applied (the default when the policy leaves it unset).
+    pub max_drift_bps: u16,
+    pub drifts: Vec<AssetDrift>,
+}
+
+/// Return data of `quote_withdraw`; `penalty + admin_fee + referrer_cut + net`
+/// always equals `amount`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WithdrawQuote {
+    pub amount: u64,
+    pub penalty: u64,
+    pub admin_fee: u64,
+    pub referrer_cut: u64,
+    pub net: u64,
+    /// What is still withdrawable in the same asset afterwards.
+    pub withdrawable_after: u64,
+    pub attestation_required: bool,
+    pub memo_required: bool,
+}
+
+/// Return data of `quote_rebalance`, in USDC base units.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RebalanceQuote {
+    pub total_usd: u64,
+    pub legs: Vec<RebalanceLeg>,
+    pub venue_legs: Vec<VenueLeg>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RebalanceLeg {
+    pub mint: Pubkey,
+    pub current_usd: u64,
+    pub target_usd: u64,
+    /// Positive: buy; negative: sell.
+    pub delta_usd: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VenueLeg {
+    pub venue_id: u8,
+    pub current_usd: u64,
+    pub target_usd: u64,
+    /// Positive: supply to the venue; negative: withdraw from it.
+    pub delta_usd: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AssetDrift {
+    pub mint: Pubkey,
+    pub target_bps: u16,
+    pub current_bps: u16,
+    /// `current_bps - target_bps`.
+    pub drift_bps: i32,
 }
 
 #[error_code]
@@ -474,4 +15010,975 @@ pub enum EscrowError {
     InvalidBps,
     #[msg("Invalid usdc_in_lulo bps")]
     InvalidLuloAllocation,
+    #[msg("Missing config account")]
+    MissingConfig,
+    #[msg("Missing referrer account")]
+    MissingReferrer,
+    #[msg("Bad referrer")]
+    BadReferrer,
+    #[msg("Invalid timelock")]
+    InvalidTimelock,
+    #[msg("Invalid counterparty")]
+    InvalidCounterparty,
+    #[msg("Preimage does not match hashlock")]
+    HashlockMismatch,
+    #[msg("Escrow expired")]
+    EscrowExpired,
+    #[msg("Escrow not expired")]
+    EscrowNotExpired,
+    #[msg("Invalid milestones")]
+    InvalidMilestones,
+    #[msg("Invalid milestone status")]
+    InvalidMilestoneStatus,
+    #[msg("Swap already accepted")]
+    SwapAlreadyAccepted,
+    #[msg("Swap not accepted")]
+    SwapNotAccepted,
+    #[msg("Venue not enabled in registry")]
+    VenueDisabled,
+    #[msg("Protocol registry full")]
+    RegistryFull,
+    #[msg("Borrowing is not allowed")]
+    BorrowNotAllowed,
+    #[msg("Slippage exceeded")]
+    SlippageExceeded,
+    #[msg("Swap spent more than authorized")]
+    ExcessiveSpend,
+    #[msg("Compounding not applicable for this policy")]
+    CompoundNotApplicable,
+    #[msg("Compound cooldown active")]
+    CompoundCooldown,
+    #[msg("Compound amount below minimum")]
+    CompoundTooSmall,
+    #[msg("Bad fee treasury")]
+    BadFeeTreasury,
+    #[msg("Invalid policy history capacity")]
+    InvalidHistoryCapacity,
+    #[msg("Invalid asset list")]
+    InvalidAssetList,
+    #[msg("Account already migrated")]
+    AlreadyMigrated,
+    #[msg("Invalid batch")]
+    InvalidBatch,
+    #[msg("Too many pots for this owner")]
+    PotLimitReached,
+    #[msg("Vault not empty")]
+    VaultNotEmpty,
+    #[msg("Invalid pot metadata")]
+    InvalidMetadata,
+    #[msg("cNFT receipts are not configured")]
+    ReceiptsDisabled,
+    #[msg("Account is not allowed in this passthrough")]
+    DisallowedAccount,
+    #[msg("External CPI left vault accounts in an unexpected state")]
+    PostConditionViolated,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Arithmetic underflow")]
+    MathUnderflow,
+    #[msg("Envelope budget exceeded")]
+    BudgetExceeded,
+    #[msg("Subscription paused")]
+    SubscriptionPaused,
+    #[msg("Subscription charge not due yet")]
+    ChargeNotDue,
+    #[msg("No-withdraw commitment active")]
+    CommitmentActive,
+    #[msg("Bad penalty destination")]
+    BadPenaltyDestination,
+    #[msg("Invalid pod")]
+    InvalidPod,
+    #[msg("Pod is full")]
+    PodFull,
+    #[msg("Invite has no uses left")]
+    InviteExhausted,
+    #[msg("ROSCA already started")]
+    RoscaStarted,
+    #[msg("ROSCA not started")]
+    RoscaNotStarted,
+    #[msg("Bad randomness account")]
+    BadRandomness,
+    #[msg("Randomness not fulfilled yet")]
+    RandomnessNotFulfilled,
+    #[msg("ROSCA finished")]
+    RoscaFinished,
+    #[msg("Invalid ROSCA round")]
+    InvalidRound,
+    #[msg("Member is not late")]
+    NotLate,
+    #[msg("Already voted")]
+    AlreadyVoted,
+    #[msg("Quorum not met")]
+    QuorumNotMet,
+    #[msg("Vault frozen by guardian")]
+    VaultFrozen,
+    #[msg("External execution too frequent")]
+    TooFrequent,
+    #[msg("Operation nonce does not match the vault")]
+    BadNonce,
+    #[msg("Signed operation failed verification")]
+    BadSignature,
+    #[msg("Signed operation has expired")]
+    SignatureExpired,
+    #[msg("Instruction executed outside its valid window")]
+    OutsideValidWindow,
+    #[msg("Valid compliance attestation required")]
+    AttestationRequired,
+    #[msg("Destination address is blocked")]
+    AddressBlocked,
+    #[msg("Blocklist account required")]
+    MissingBlocklist,
+    #[msg("Memo hash required for this withdrawal amount")]
+    MemoRequired,
+    #[msg("Price feed missing, unverified or stale")]
+    StalePrice,
+    #[msg("Snapshot already taken today")]
+    SnapshotTooSoon,
+    #[msg("Owner did not receive exactly the closed accounts' rent")]
+    RentRefundMismatch,
+    #[msg("Token balance is above the dust threshold")]
+    NotDust,
+    #[msg("Transaction also calls a DEX program directly")]
+    SandwichRisk,
+    #[msg("Deposit is below the minimum")]
+    DepositBelowMinimum,
+    #[msg("Deposit would take the pot above its balance cap")]
+    PotBalanceCapExceeded,
+    #[msg("Pot already has a share mint")]
+    ShareMintExists,
+    #[msg("Pot has a share mint; pass it and the owner's share account")]
+    MissingShareAccounts,
+    #[msg("Pot doesn't accept public deposits")]
+    DepositsNotPublic,
+    #[msg("A crowdfund is running")]
+    CrowdfundActive,
+    #[msg("No crowdfund is running")]
+    NoCrowdfund,
+    #[msg("Crowdfunds take USDC only")]
+    CrowdfundUsdcOnly,
+    #[msg("Crowdfund deadline has passed")]
+    CrowdfundEnded,
+    #[msg("Crowdfund deadline hasn't passed")]
+    CrowdfundNotEnded,
+    #[msg("Crowdfund goal not reached")]
+    CrowdfundGoalNotMet,
+    #[msg("Crowdfund goal was reached")]
+    CrowdfundGoalMet,
+    #[msg("Crowdfund donations need the donor's Donation account")]
+    MissingDonation,
+    #[msg("Donation belongs to another round")]
+    BadDonation,
+    #[msg("Split needs 1-8 distinct pots with shares adding up to 100%")]
+    InvalidSplit,
+    #[msg("Pot has no target date")]
+    NoTargetDate,
+    #[msg("Next payout isn't due yet")]
+    PayoutNotDue,
+    #[msg("Basket needs 1-4 components weighing 100% and their price feeds in order")]
+    InvalidBasket,
+    #[msg("No vault parameter set has been published")]
+    NoVaultParams,
+    #[msg("Cross-chain domain or recipient isn't allowlisted")]
+    BadCrosschainRoute,
+    #[msg("Mint already registered on this vault")]
+    MintAlreadyRegistered,
+    #[msg("Mint not registered on this vault")]
+    MintNotRegistered,
+    #[msg("Vault has too many registered mints")]
+    MintLimitReached,
+    #[msg("Outflow breaker account is required while the breaker is enabled")]
+    OutflowBreakerRequired,
+    #[msg("Outflows are above the breaker threshold; this withdrawal needs the guardian's co-sign")]
+    GuardianCosignRequired,
+    #[msg("This idempotency key was already used for a deposit into this vault")]
+    DuplicateOperation,
+    #[msg("Deposit marker account missing or not the PDA for this idempotency key")]
+    DepositMarkerRequired,
+    #[msg("Venue allocation names an unknown asset or venue, repeats a pair, or exceeds the asset's target")]
+    InvalidVenueAllocation,
+    #[msg("The vault already has an unfinished rebalance plan")]
+    RebalancePlanActive,
+    #[msg("The vault is already at its targets")]
+    NothingToRebalance,
+    #[msg("The rebalance plan has expired")]
+    RebalancePlanExpired,
+    #[msg("Rebalance steps must be executed in plan order")]
+    RebalanceStepOutOfOrder,
+    #[msg("The executed CPI did not move the planned amount")]
+    RebalanceStepMismatch,
+    #[msg("Valuation mode must be VALUATION_SPOT or VALUATION_EMA")]
+    InvalidValuationMode,
+    #[msg("The stream still has USDC owed to the recipient")]
+    StreamNotFinished,
+    #[msg("This instruction cannot move USDC while the pot has a share mint")]
+    SharesUnsupported,
+    #[msg("The early-withdraw penalty exceeds what is left after fees")]
+    PenaltyExceedsPayout,
+    #[msg("Pod policy has not been initialized")]
+    PolicyNotInitialized,
+}
+
+#[event]
+pub struct ReferralRegistered {
+    pub owner: Pubkey,
+    pub referrer: Pubkey,
+}
+
+#[event]
+pub struct ReferralFeePaid {
+    pub owner: Pubkey,
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EscrowOpened {
+    pub escrow: Pubkey,
+    pub depositor: Pubkey,
+    pub counterparty: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub hashlock: [u8; 32],
+    pub timelock: i64,
+}
+
+#[event]
+pub struct EscrowClaimed {
+    pub escrow: Pubkey,
+    pub counterparty: Pubkey,
+    pub amount: u64,
+    pub preimage: Vec<u8>,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub escrow: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneEscrowOpened {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub arbiter: Pubkey,
+    pub total_amount: u64,
+    pub milestone_count: u8,
+}
+
+#[event]
+pub struct MilestoneUpdated {
+    pub escrow: Pubkey,
+    pub index: u8,
+    pub status: MilestoneStatus,
+    pub actor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneEscrowClosed {
+    pub escrow: Pubkey,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+}
+
+#[event]
+pub struct SwapOffered {
+    pub offer: Pubkey,
+    pub maker_vault: Pubkey,
+    pub seq: u64,
+    pub taker_vault: Pubkey,
+    pub mint_a: Pubkey,
+    pub amount_a: u64,
+    pub mint_b: Pubkey,
+    pub amount_b: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct SwapAccepted {
+    pub offer: Pubkey,
+    pub taker_vault: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct SwapSettled {
+    pub offer: Pubkey,
+    pub maker_vault: Pubkey,
+    pub maker_seq: u64,
+    pub taker_vault: Pubkey,
+    pub taker_seq: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct SwapCancelled {
+    pub offer: Pubkey,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct MarinadeStaked {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub lamports: u64,
+    pub msol_minted: u64,
+}
+
+#[event]
+pub struct MarinadeUnstaked {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub msol_amount: u64,
+    pub lamports_received: u64,
+}
+
+#[event]
+pub struct ProtocolUpdated {
+    pub venue_id: u8,
+    pub program_id: Pubkey,
+    pub mint: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct RebalancePlanned {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub steps: u8,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct RebalanceStepExecuted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub index: u8,
+    pub kind: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[event]
+pub struct VenueDeposited {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub venue_id: u8,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct VenueWithdrawn {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub venue_id: u8,
+    pub shares: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct YieldAccrued {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub venue_id: u8,
+    pub amount: u64,
+    pub principal: u64,
+    pub accrued_yield: u64,
+}
+
+#[event]
+pub struct YieldCompounded {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub venue_id: u8,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PerformanceFeeCharged {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub high_water_mark: i64,
+}
+
+#[event]
+pub struct PotVaultClosed {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub owner: Pubkey,
+    pub token_accounts_closed: u16,
+    /// Rent returned to the owner for the token accounts and the vault.
+    pub lamports_returned: u64,
+}
+
+#[event]
+pub struct VaultParamsPublished {
+    pub version: u32,
+    pub performance_fee_bps: u16,
+    pub external_exec_cooldown_slots: u64,
+}
+
+#[event]
+pub struct VaultParamsMigrated {
+    pub version: u32,
+    pub migrated: u16,
+    pub skipped: u16,
+    /// Vaults grown from an older layout in this batch.
+    pub resized: u16,
+}
+
+#[event]
+pub struct VaultLimitsExemptSet {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub exempt: bool,
+}
+
+#[event]
+pub struct DonationReceived {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub donor: Pubkey,
+    /// `Pubkey::default()` for native SOL.
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Hash of the donor's off-chain message; zero when none was attached.
+    pub memo_hash: [u8; 32],
+}
+
+#[event]
+pub struct SplitDeposited {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub pot_count: u8,
+}
+
+#[event]
+pub struct CrowdfundStarted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub round: u64,
+    pub goal_usdc: u64,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct CrowdfundClaimed {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub round: u64,
+    pub raised_usdc: u64,
+}
+
+#[event]
+pub struct DonorRefunded {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub donor: Pubkey,
+    pub round: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ShareMintEnabled {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub share_mint: Pubkey,
+    pub initial_shares: u64,
+}
+
+#[event]
+pub struct PotSharesMinted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PotSharesBurned {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct MintRegistered {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+}
+
+#[event]
+pub struct MintDeregistered {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct UsdcMintSet {
+    pub previous: Pubkey,
+    pub usdc_mint: Pubkey,
+}
+
+#[event]
+pub struct DustSwept {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub token_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Burned rather than sent to the fee treasury.
+    pub burned: bool,
+}
+
+#[event]
+pub struct PolicyClosed {
+    pub policy: Pubkey,
+    pub pod_hash: [u8; 32],
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PolicyAuthorityTransferStarted {
+    pub policy: Pubkey,
+    pub pod_hash: [u8; 32],
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct PolicyManagerSet {
+    pub policy: Pubkey,
+    pub pod_hash: [u8; 32],
+    pub authority: Pubkey,
+    pub manager: Pubkey,
+}
+
+#[event]
+pub struct PolicyAuthorityTransferred {
+    pub policy: Pubkey,
+    pub pod_hash: [u8; 32],
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PotsMerged {
+    pub owner: Pubkey,
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub seq: u64,
+    pub lamports: u64,
+    pub usdc: u64,
+    pub msol: u64,
+}
+
+#[event]
+pub struct PotSplit {
+    pub owner: Pubkey,
+    pub source_vault: Pubkey,
+    pub seq: u64,
+    pub new_vault: Pubkey,
+    pub lamports: u64,
+    pub usdc: u64,
+    pub msol: u64,
+}
+
+#[event]
+pub struct AllPotsSwept {
+    pub owner: Pubkey,
+    pub vault_count: u8,
+    pub lamports: u64,
+    pub usdc: u64,
+}
+
+#[event]
+pub struct WithdrawnTo {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    /// `Pubkey::default()` for native SOL.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub memo_hash: [u8; 32],
+}
+
+#[event]
+pub struct ReceiptMinted {
+    pub source: Pubkey,
+    pub index: u8,
+    pub leaf_owner: Pubkey,
+    pub tree: Pubkey,
+}
+
+#[event]
+pub struct EnvelopeSpent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub envelope_id: u8,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct SubscriptionCharged {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub merchant: Pubkey,
+    pub amount: u64,
+    pub next_charge_ts: i64,
+}
+
+#[event]
+pub struct SubscriptionChargeFailed {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub merchant: Pubkey,
+    pub amount: u64,
+    pub grace_ends: i64,
+}
+
+#[event]
+pub struct SubscriptionCancelled {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct StreamCreated {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    pub rate_per_second: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub stream_id: u64,
+}
+
+#[event]
+pub struct GoalProgress {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub day: i64,
+    pub total_usd: u64,
+    /// Basket goal re-priced in USD at today's oracle prices.
+    pub goal_usd: u64,
+    pub progress_bps: u32,
+}
+
+#[event]
+pub struct PayoutPlanCreated {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    pub installment_usdc: u64,
+    pub interval_secs: i64,
+    pub start_ts: i64,
+}
+
+#[event]
+pub struct PayoutPaid {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub next_payout_ts: i64,
+    /// The pot ran out of USDC and the plan was closed.
+    pub drained: bool,
+}
+
+#[event]
+pub struct StreamWithdrawn {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub withdrawn: u64,
+}
+
+#[event]
+pub struct StreamCancelled {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    /// Vested USDC paid to the recipient on cancel.
+    pub paid: u64,
+    /// Unvested USDC returned to the vault's unlocked balance.
+    pub released: u64,
+}
+
+#[event]
+pub struct StreamClosed {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct RoundupDeposited {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub count: u16,
+    pub amount: u64,
+    pub roundup_total: u64,
+}
+
+#[event]
+pub struct NoWithdrawCommitted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub until: i64,
+    pub penalty_bps: u16,
+}
+
+#[event]
+pub struct EarlyWithdrawPenalty {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PodMemberJoined {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub member_count: u16,
+}
+
+#[event]
+pub struct PodMemberLeft {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub refund: u64,
+    pub penalty: u64,
+}
+
+#[event]
+pub struct RoscaOrderFinalized {
+    pub pod: Pubkey,
+    pub payout_order: Vec<Pubkey>,
+}
+
+#[event]
+pub struct MemberFlaggedLate {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub round: u16,
+    pub pending_penalty_bps: u16,
+}
+
+#[event]
+pub struct RoscaPaidOut {
+    pub pod: Pubkey,
+    pub round: u16,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+}
+
+#[event]
+pub struct PodRoleChanged {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub role: PodRole,
+}
+
+#[event]
+pub struct RemovalProposed {
+    pub pod: Pubkey,
+    pub target: Pubkey,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct MemberRemoved {
+    pub pod: Pubkey,
+    pub member: Pubkey,
+    pub refund: u64,
+}
+
+#[event]
+pub struct PodYieldDistributed {
+    pub pod: Pubkey,
+    pub amount: u64,
+    /// Portion credited to internal balances rather than transferred.
+    pub credited: u64,
+}
+
+#[event]
+pub struct VaultFreezeChanged {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub frozen_until: i64,
+}
+
+#[event]
+pub struct OpNonceAdvanced {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub op_nonce: u64,
+}
+
+#[event]
+pub struct SignedOpExecuted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub relayer: Pubkey,
+    pub kind: u8,
+    pub amount: u64,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct OutflowBreakerTripped {
+    pub mint: Pubkey,
+    pub window_start: i64,
+    pub window_total: u64,
+    pub threshold: u64,
+}
+
+#[event]
+pub struct RoleTransferStarted {
+    pub role: ConfigRole,
+    pub holder: Pubkey,
+    pub pending: Pubkey,
+}
+
+#[event]
+pub struct RoleTransferred {
+    pub role: ConfigRole,
+    pub previous: Pubkey,
+    pub holder: Pubkey,
+}
+
+#[event]
+pub struct FeesSwept {
+    /// `Pubkey::default()` for SOL.
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub total_collected: u64,
+    pub total_swept: u64,
+}
+
+#[event]
+pub struct FeeTierUpdated {
+    pub owner: Pubkey,
+    /// `FeeTier::NO_CAP` when no tier is assigned.
+    pub assigned_fee_bps: u16,
+    pub assigned_until: i64,
+    pub earned_fee_bps: u16,
+}
+
+#[event]
+pub struct StableRouteSet {
+    pub alt_mint: Pubkey,
+    pub dex_program: Pubkey,
+    pub price_feed: Pubkey,
+    pub max_slippage_bps: u16,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct StableConverted {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub alt_mint: Pubkey,
+    /// USDC into the alt stable when set, the alt stable back into USDC otherwise.
+    pub to_alt: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[event]
+pub struct CrosschainDomainUpdated {
+    pub domain: u32,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct CrosschainRecipientAdded {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub domain: u32,
+    pub recipient: [u8; 32],
+}
+
+#[event]
+pub struct CrosschainWithdrawn {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub destination_domain: u32,
+    pub mint_recipient: [u8; 32],
+    /// CCTP `MessageSent` account the attestation service picks up.
+    pub message: Pubkey,
+    pub memo_hash: [u8; 32],
+}
+
+#[event]
+pub struct BlocklistUpdated {
+    pub address: Pubkey,
+    pub blocked: bool,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// `Pubkey::default()` for native SOL.
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Hash of the off-chain beneficiary record; zero when none was attached.
+    pub memo_hash: [u8; 32],
+}
+
+#[cfg(feature = "compressed")]
+#[event]
+pub struct CompressedVaultOpened {
+    pub owner: Pubkey,
+    /// Address of the compressed account.
+    pub vault: Pubkey,
+    pub pot_hash: [u8; 32],
+}
+
+#[cfg(feature = "compressed")]
+#[event]
+pub struct CompressedVaultCredited {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[cfg(feature = "compressed")]
+#[event]
+pub struct CompressedVaultDebited {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[cfg(feature = "compressed")]
+#[event]
+pub struct CompressedVaultClosed {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
 }
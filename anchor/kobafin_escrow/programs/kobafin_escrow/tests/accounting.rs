@@ -0,0 +1,103 @@
+//! Property tests for the vault's USDC ledger: whatever sequence of deposits,
+//! fee-bearing withdrawals and performance fees runs, `total_in_usdc -
+//! total_out_usdc` tracks the balance those flows leave in the vault ATA.
+//! Pot shares round in the pot's favour, so a deposit and an equal withdrawal
+//! never burn fewer shares than they minted.
+
+use anchor_lang::prelude::Pubkey;
+use kobafin_escrow::{shares_for_deposit, shares_for_withdrawal, split_withdraw_fee, stable_min_out, FeeSplit, Vault};
+use proptest::prelude::*;
+
+const MAX_BPS: u16 = 10_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit(u64),
+    /// Withdraws `share_bps` of the balance, `fee_bps` of it as fee.
+    Withdraw { share_bps: u16, fee_bps: u16, referral_bps: Option<u16> },
+    /// Performance fee of `share_bps` of the balance.
+    Fee { share_bps: u16 },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (1..=u32::MAX as u64).prop_map(Op::Deposit),
+        (0..=MAX_BPS, 0..=MAX_BPS, proptest::option::of(0..=MAX_BPS))
+            .prop_map(|(share_bps, fee_bps, referral_bps)| Op::Withdraw { share_bps, fee_bps, referral_bps }),
+        (0..=MAX_BPS).prop_map(|share_bps| Op::Fee { share_bps }),
+    ]
+}
+
+fn portion(amount: u64, bps: u16) -> u64 {
+    ((amount as u128) * (bps as u128) / MAX_BPS as u128) as u64
+}
+
+proptest! {
+    #[test]
+    fn ledger_tracks_balance(ops in proptest::collection::vec(op(), 1..64)) {
+        let mut vault = Vault { usdc_mint: Pubkey::new_unique(), ..Vault::default() };
+        let mut balance: u64 = 0;
+
+        for op in ops {
+            match op {
+                Op::Deposit(amount) => {
+                    vault.book_usdc_in(amount).unwrap();
+                    balance += amount;
+                }
+                Op::Withdraw { share_bps, fee_bps, referral_bps } => {
+                    let amount = portion(balance, share_bps);
+                    let fee = portion(amount, fee_bps);
+                    let FeeSplit { net, admin_fee, referrer_cut } =
+                        split_withdraw_fee(amount, fee, referral_bps).unwrap();
+                    prop_assert_eq!(net + admin_fee + referrer_cut, amount);
+                    prop_assert!(referrer_cut <= fee);
+                    balance -= net;
+                    balance -= admin_fee;
+                    balance -= referrer_cut;
+                    vault.book_usdc_out(amount).unwrap();
+                }
+                Op::Fee { share_bps } => {
+                    let fee = portion(balance, share_bps);
+                    balance -= fee;
+                    vault.book_usdc_out(fee).unwrap();
+                }
+            }
+            prop_assert!(vault.total_in_usdc >= vault.total_out_usdc);
+            prop_assert_eq!(vault.total_in_usdc - vault.total_out_usdc, balance);
+        }
+    }
+
+    #[test]
+    fn fee_above_amount_is_rejected(amount in 0..u64::MAX, excess in 1..=u32::MAX as u64, referral_bps in proptest::option::of(0..=MAX_BPS)) {
+        let fee = amount.saturating_add(excess);
+        prop_assume!(fee > amount);
+        prop_assert!(split_withdraw_fee(amount, fee, referral_bps).is_err());
+    }
+
+    #[test]
+    fn share_round_trip_never_profits(
+        supply in 1..=u64::MAX / 4,
+        assets in 1..=u64::MAX / 4,
+        amount in 1..=u32::MAX as u64,
+    ) {
+        let minted = shares_for_deposit(amount, supply, assets).unwrap();
+        let burned = shares_for_withdrawal(amount, supply + minted, assets + amount);
+        prop_assert!(burned >= minted);
+        prop_assert!(burned <= supply + minted);
+    }
+
+    #[test]
+    fn stable_round_trip_never_profits(
+        amount in 1..=u32::MAX as u64,
+        price in 50_000_000i64..=200_000_000,
+        slippage_bps in 0..=500u16,
+    ) {
+        // Alt stable quoted at 0.5-2.0 USD (expo -8), both mints at 6 decimals.
+        let alt = stable_min_out(amount, 6, 6, price, -8, true, slippage_bps).unwrap();
+        let back = stable_min_out(alt, 6, 6, price, -8, false, slippage_bps).unwrap();
+        prop_assert!(back <= amount);
+        if slippage_bps == 0 && price == 100_000_000 {
+            prop_assert_eq!(back, amount);
+        }
+    }
+}
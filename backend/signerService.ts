@@ -93,6 +93,32 @@ function u16le(v: number): Buffer {
   return b;
 }
 
+function u32le(v: number): Buffer {
+  const b = Buffer.alloc(4);
+  b.writeUInt32LE(v, 0);
+  return b;
+}
+
+// Mints for the policy's asset list, in on-chain order (USDC first: usdc_in_lulo_bps lends out of assets[0]).
+function policyAssetMints(): Record<keyof AllocationBps, PublicKey> {
+  return {
+    usdc: new PublicKey(requireEnv("POLICY_MINT_USDC")),
+    btc: new PublicKey(requireEnv("POLICY_MINT_BTC")),
+    eth: new PublicKey(requireEnv("POLICY_MINT_ETH")),
+    sol: new PublicKey(requireEnv("POLICY_MINT_SOL")),
+  };
+}
+
+function encodeAssetTargets(targetBps: AllocationBps): Buffer {
+  const mints = policyAssetMints();
+  const keys: Array<keyof AllocationBps> = ["usdc", "btc", "eth", "sol"];
+  const entries = keys.filter((k) => k === "usdc" || targetBps[k] > 0);
+  return Buffer.concat([
+    u32le(entries.length),
+    ...entries.map((k) => Buffer.concat([mints[k].toBuffer(), u16le(targetBps[k])])),
+  ]);
+}
+
 function riskStateToCode(risk: RiskState): number {
   if (risk === "NORMAL") return 0;
   if (risk === "CAUTION") return 1;
@@ -171,10 +197,7 @@ async function submitUpdateTx(params: {
     anchorDiscriminator("update_policy"),
     podIdHash(podId),
     Buffer.from([riskStateToCode(riskState)]),
-    u16le(targetBps.usdc),
-    u16le(targetBps.btc),
-    u16le(targetBps.eth),
-    u16le(targetBps.sol),
+    encodeAssetTargets(targetBps),
     u16le(usdcInLuloBps),
   ]);
 
@@ -0,0 +1,32 @@
+import assert from "node:assert/strict";
+import { PublicKey } from "@solana/web3.js";
+import {
+  MAX_ADDRESSES_PER_EXTEND,
+  buildCreateLookupTableIxs,
+  buildExtendLookupTableIxs,
+  protocolLookupTableAddresses,
+} from "../lib/alt";
+
+const authority = new PublicKey("11111111111111111111111111111111");
+const programId = new PublicKey("2uULLkMPZ7rJMhsixb1TLsgFCdE87xx9efdi2Z657sA6");
+const usdcMint = new PublicKey("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+const addresses = protocolLookupTableAddresses({
+  escrowProgramId: programId,
+  luloProgramId: new PublicKey("FL3X2pRsQ9zHENpZSKDRREtccwJuei8yg9fwDu9UN69Q"),
+  mints: [usdcMint, null, usdcMint],
+});
+const unique = new Set(addresses.map((k) => k.toBase58()));
+assert.equal(unique.size, addresses.length, "addresses should be deduplicated");
+assert.ok(unique.has(usdcMint.toBase58()), "mints should be included");
+
+const many = Array.from({ length: MAX_ADDRESSES_PER_EXTEND + 5 }, (_, i) =>
+  PublicKey.findProgramAddressSync([Buffer.from([i])], programId)[0]
+);
+const { lookupTable, instructions } = buildCreateLookupTableIxs(authority, authority, 1, many);
+assert.equal(instructions.length, 3, "create plus two extend chunks");
+
+const again = buildExtendLookupTableIxs(lookupTable, authority, authority, many, many.slice(0, 10));
+assert.equal(again.length, 1, "existing entries should be skipped");
+
+console.log("lookup table helpers: ok");
@@ -29,7 +29,8 @@ import {
   LAMPORTS_PER_SOL,
 } from "@solana/web3.js";
 
-import { deriveVaultPdaFromPotId } from "./lib/escrow";
+import { deriveLuloPositionPda, deriveOwnerRegistryPda, deriveVaultPdaFromPotId } from "./lib/escrow";
+import { deriveConfigPda, deriveFeeTreasuryPda } from "./lib/alt";
 import {
   anchorDiscriminator,
   decodeInstructionData,
@@ -1139,6 +1140,8 @@ await prisma.user.upsert({
             { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
             { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: deriveOwnerRegistryPda(owner, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: deriveConfigPda(escrowProgramId)[0], isSigner: false, isWritable: false },
           ],
           data,
         })
@@ -1160,6 +1163,7 @@ await prisma.user.upsert({
           { pubkey: userUsdc, isSigner: false, isWritable: true },
           { pubkey: vaultUsdc, isSigner: false, isWritable: true },
           { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
         data: depositData,
       })
@@ -1315,6 +1319,8 @@ await prisma.user.upsert({
             { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
             { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: deriveOwnerRegistryPda(owner, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: deriveConfigPda(escrowProgramId)[0], isSigner: false, isWritable: false },
           ],
           data,
         })
@@ -1397,14 +1403,16 @@ await prisma.user.upsert({
         const makeVaultLuloIx = (ix: any) => {
           if (!ix) return;
           const luloKeys = toLuloKeys(ix.keys || [], vaultPda);
-          const data = encodeLuloExecuteData(potHash, decodeLuloIxData(ix.data));
+          const data = encodeLuloExecuteData(potHash, usdcBase, decodeLuloIxData(ix.data));
           luloExecute.push(
             new TransactionInstruction({
               programId: escrowProgramId,
               keys: [
-                { pubkey: owner, isSigner: true, isWritable: false },
+                { pubkey: owner, isSigner: true, isWritable: true },
                 { pubkey: vaultPda, isSigner: false, isWritable: true },
                 { pubkey: luloProgramId!, isSigner: false, isWritable: false },
+                { pubkey: deriveLuloPositionPda(vaultPda, escrowProgramId)[0], isSigner: false, isWritable: true },
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
                 ...luloKeys,
               ],
               data,
@@ -1651,14 +1659,16 @@ await prisma.user.upsert({
     const makeVaultLuloIx = (ix: any) => {
       if (!ix) return;
       const luloKeys = toLuloKeys(ix.keys || [], vaultPda);
-      const data = encodeLuloExecuteData(potHash, decodeLuloIxData(ix.data));
+      const data = encodeLuloExecuteData(potHash, Math.round(body.usd * 1_000_000), decodeLuloIxData(ix.data));
       instructions.push(
         new TransactionInstruction({
           programId: escrowProgramId,
           keys: [
-            { pubkey: owner, isSigner: true, isWritable: false },
+            { pubkey: owner, isSigner: true, isWritable: true },
             { pubkey: vaultPda, isSigner: false, isWritable: true },
             { pubkey: luloProgramId!, isSigner: false, isWritable: false },
+            { pubkey: deriveLuloPositionPda(vaultPda, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
             ...luloKeys,
           ],
           data,
@@ -1830,14 +1840,16 @@ await prisma.user.upsert({
     const makeVaultLuloIx = (ix: any) => {
       if (!ix) return;
       const luloKeys = toLuloKeys(ix.keys || [], vaultPda);
-      const data = encodeLuloExecuteData(potHash, decodeLuloIxData(ix.data));
+      const data = encodeLuloExecuteData(potHash, 0, decodeLuloIxData(ix.data));
       instructions.push(
         new TransactionInstruction({
           programId: escrowProgramId,
           keys: [
-            { pubkey: owner, isSigner: true, isWritable: false },
+            { pubkey: owner, isSigner: true, isWritable: true },
             { pubkey: vaultPda, isSigner: false, isWritable: true },
             { pubkey: luloProgramId!, isSigner: false, isWritable: false },
+            { pubkey: deriveLuloPositionPda(vaultPda, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
             ...luloKeys,
           ],
           data,
@@ -2043,14 +2055,16 @@ await prisma.user.upsert({
       if (!completeIx) return reply.code(400).send({ error: "lulo_withdraw_ix_missing" });
 
       const luloKeys = toLuloKeys(completeIx.keys || [], vaultPda);
-      const data = encodeLuloExecuteData(potHash, decodeLuloIxData(completeIx.data));
+      const data = encodeLuloExecuteData(potHash, 0, decodeLuloIxData(completeIx.data));
       instructions.push(
         new TransactionInstruction({
           programId: escrowProgramId,
           keys: [
-            { pubkey: owner, isSigner: true, isWritable: false },
+            { pubkey: owner, isSigner: true, isWritable: true },
             { pubkey: vaultPda, isSigner: false, isWritable: true },
             { pubkey: luloProgramId!, isSigner: false, isWritable: false },
+            { pubkey: deriveLuloPositionPda(vaultPda, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
             ...luloKeys,
           ],
           data,
@@ -2228,10 +2242,11 @@ await prisma.user.upsert({
     });
   }
 
-  function encodeLuloExecuteData(potHash: Uint8Array, ixData: Buffer) {
+  function encodeLuloExecuteData(potHash: Uint8Array, maxUsdcOut: number | bigint, ixData: Buffer) {
     return Buffer.concat([
       Buffer.from(anchorDiscriminator("lulo_execute")),
       Buffer.from(potHash),
+      u64le(maxUsdcOut),
       u32le(ixData.length),
       ixData,
     ]);
@@ -2374,7 +2389,7 @@ await prisma.user.upsert({
     type: "deposit" | "withdraw";
     usdcMint: PublicKey | null;
     feeLamports?: number;
-    adminVault?: PublicKey | null;
+    feeTreasury?: PublicKey | null;
   }) {
     const { pda, potHash: ph } = deriveVaultPdaFromPotId(
       params.owner,
@@ -2414,6 +2429,8 @@ await prisma.user.upsert({
             { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
             { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: deriveOwnerRegistryPda(params.owner, escrowProgramId)[0], isSigner: false, isWritable: true },
+            { pubkey: deriveConfigPda(escrowProgramId)[0], isSigner: false, isWritable: false },
           ],
           data,
         })
@@ -2421,23 +2438,25 @@ await prisma.user.upsert({
     }
 
     let ixName = params.type === "deposit" ? "deposit" : "withdraw";
-    let data = Buffer.concat([anchorDiscriminator(ixName), ph, u64le(params.lamports)]);
+    // Withdrawals carry a trailing 32-byte travel-rule memo hash (zero = none).
+    const memoHash = params.type === "withdraw" ? [Buffer.alloc(32)] : [];
+    let data = Buffer.concat([anchorDiscriminator(ixName), ph, u64le(params.lamports), ...memoHash]);
     let keys: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[] = [
       { pubkey: params.owner, isSigner: true, isWritable: true },
       { pubkey: pda, isSigner: false, isWritable: true },
     ];
 
     if (params.type === "withdraw" && params.feeLamports && params.feeLamports > 0) {
-      if (!params.adminVault) throw new Error("admin_vault_missing");
-      if (params.adminVault.equals(pda)) throw new Error("admin_vault_conflict");
+      if (!params.feeTreasury) throw new Error("fee_treasury_missing");
       ixName = "withdraw_with_fee";
       data = Buffer.concat([
         anchorDiscriminator(ixName),
         ph,
         u64le(params.lamports),
         u64le(params.feeLamports),
+        ...memoHash,
       ]);
-      keys.push({ pubkey: params.adminVault, isSigner: false, isWritable: true });
+      keys.push({ pubkey: params.feeTreasury, isSigner: false, isWritable: true });
     }
 
     keys.push({ pubkey: SystemProgram.programId, isSigner: false, isWritable: false });
@@ -2453,13 +2472,6 @@ await prisma.user.upsert({
     return { tx, vaultPda: pda.toBase58() };
   }
 
-  function getAdminVaultPda() {
-    if (!adminWallet || !adminPotId) {
-      throw new Error("admin_vault_missing");
-    }
-    return deriveVaultPdaFromPotId(adminWallet, adminPotId, escrowProgramId).pda;
-  }
-
   function getMessageAccountKeys(msg: any): PublicKey[] {
     if (Array.isArray((msg as any).staticAccountKeys)) return (msg as any).staticAccountKeys;
     if (Array.isArray((msg as any).accountKeys)) return (msg as any).accountKeys;
@@ -2712,7 +2724,7 @@ await prisma.user.upsert({
     const locked = !!pot.isLocked;
     const feeLamports = locked ? calcFeeLamports(lamports) : 0;
     const feeUsd = locked ? (body.usd * LOCKED_WITHDRAW_FEE_BPS) / 10_000 : 0;
-    let adminVault: PublicKey | null = null;
+    let feeTreasury: PublicKey | null = null;
 
     // Extra safety: ensure the per-pot PDA vault exists and actually has enough
     // lamports on-chain. If not, Phantom often bubbles up an "Unexpected error"
@@ -2739,24 +2751,11 @@ await prisma.user.upsert({
     }
 
     if (locked) {
-      try {
-        adminVault = getAdminVaultPda();
-      } catch (err: any) {
-        return reply.code(400).send({ error: err?.message || "admin_vault_missing" });
-      }
-      if (adminVault.equals(pda)) {
-        return reply.code(400).send({
-          error: "admin_vault_conflict",
-          message:
-            "Admin vault resolves to the same PDA as the withdrawing pot. Set KOBA_ADMIN_POT_ID (or KOBA_ADMIN_WALLET) to a different admin pot.",
-        });
-      }
-      const adminInfo = await connection.getAccountInfo(adminVault, { commitment: "confirmed" });
-      if (!adminInfo) {
-        return reply.code(400).send({ error: "admin_vault_not_initialized" });
-      }
-      if (adminInfo.data && adminInfo.data.length < VAULT_SPACE) {
-        return reply.code(400).send({ error: "admin_vault_upgrade_required" });
+      // Withdrawal fees go to the program's FeeTreasury PDA.
+      feeTreasury = deriveFeeTreasuryPda(escrowProgramId)[0];
+      const treasuryInfo = await connection.getAccountInfo(feeTreasury, { commitment: "confirmed" });
+      if (!treasuryInfo) {
+        return reply.code(400).send({ error: "fee_treasury_not_initialized" });
       }
     }
 
@@ -2769,7 +2768,7 @@ await prisma.user.upsert({
         type: "withdraw",
         usdcMint,
         feeLamports: locked ? feeLamports : undefined,
-        adminVault,
+        feeTreasury,
       });
     } catch (err: any) {
       if (err?.message === 'vault_not_initialized') {
@@ -2781,13 +2780,6 @@ await prisma.user.upsert({
       if (err?.message === "usdc_mint_missing") {
         return reply.code(400).send({ error: "usdc_mint_missing" });
       }
-      if (err?.message === "admin_vault_conflict") {
-        return reply.code(400).send({
-          error: "admin_vault_conflict",
-          message:
-            "Admin vault resolves to the same PDA as the withdrawing pot. Set KOBA_ADMIN_POT_ID (or KOBA_ADMIN_WALLET) to a different admin pot.",
-        });
-      }
       throw err;
     }
     const txBase64 = built.tx.serialize({ requireAllSignatures: false }).toString('base64');
@@ -0,0 +1,124 @@
+import {
+  AddressLookupTableAccount,
+  AddressLookupTableProgram,
+  Connection,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  TransactionInstruction,
+} from "@solana/web3.js";
+
+const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const MARINADE_PROGRAM_ID = new PublicKey("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+const MSOL_MINT = new PublicKey("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+const PYTH_RECEIVER_PROGRAM_ID = new PublicKey("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// Keeps each extend transaction well under the 1232-byte packet limit.
+export const MAX_ADDRESSES_PER_EXTEND = 20;
+
+export type ProtocolLookupTableInput = {
+  escrowProgramId: PublicKey;
+  luloProgramId: PublicKey;
+  // Common mints (USDC, wSOL, BTC, ETH, ...); nulls are skipped.
+  mints: (PublicKey | null)[];
+  // Oracle accounts, e.g. the Pyth SOL/USD price update set in Config.
+  oracles?: PublicKey[];
+  // Venue programs and accounts beyond the defaults (Kamino, marginfi, Drift, ...).
+  extra?: PublicKey[];
+};
+
+export function deriveConfigPda(escrowProgramId: PublicKey) {
+  return PublicKey.findProgramAddressSync([Buffer.from("config")], escrowProgramId);
+}
+
+export function deriveFeeTreasuryPda(escrowProgramId: PublicKey) {
+  return PublicKey.findProgramAddressSync([Buffer.from("fee_treasury")], escrowProgramId);
+}
+
+/**
+ * Accounts shared by every rebalance/DEX flow, deduplicated and in a stable order so
+ * repeated runs extend the table only with what's new.
+ */
+export function protocolLookupTableAddresses(input: ProtocolLookupTableInput): PublicKey[] {
+  const [config] = deriveConfigPda(input.escrowProgramId);
+  return dedupe([
+    input.escrowProgramId,
+    config,
+    SystemProgram.programId,
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    input.luloProgramId,
+    MARINADE_PROGRAM_ID,
+    MSOL_MINT,
+    PYTH_RECEIVER_PROGRAM_ID,
+    ...input.mints.filter((m): m is PublicKey => m !== null),
+    ...(input.oracles ?? []),
+    ...(input.extra ?? []),
+  ]);
+}
+
+/**
+ * Creates a lookup table owned by `authority` (the protocol admin) and fills it with
+ * `addresses`. The create instruction must land in the first transaction; each extend
+ * instruction should go in its own transaction after it.
+ */
+export function buildCreateLookupTableIxs(
+  authority: PublicKey,
+  payer: PublicKey,
+  recentSlot: number,
+  addresses: PublicKey[]
+): { lookupTable: PublicKey; instructions: TransactionInstruction[] } {
+  const [create, lookupTable] = AddressLookupTableProgram.createLookupTable({
+    authority,
+    payer,
+    recentSlot,
+  });
+  return {
+    lookupTable,
+    instructions: [create, ...buildExtendLookupTableIxs(lookupTable, authority, payer, addresses)],
+  };
+}
+
+/** Extends `lookupTable` with the entries of `addresses` not already in `existing`. */
+export function buildExtendLookupTableIxs(
+  lookupTable: PublicKey,
+  authority: PublicKey,
+  payer: PublicKey,
+  addresses: PublicKey[],
+  existing: PublicKey[] = []
+): TransactionInstruction[] {
+  const have = new Set(existing.map((k) => k.toBase58()));
+  const missing = dedupe(addresses).filter((k) => !have.has(k.toBase58()));
+  const instructions: TransactionInstruction[] = [];
+  for (let i = 0; i < missing.length; i += MAX_ADDRESSES_PER_EXTEND) {
+    instructions.push(
+      AddressLookupTableProgram.extendLookupTable({
+        lookupTable,
+        authority,
+        payer,
+        addresses: missing.slice(i, i + MAX_ADDRESSES_PER_EXTEND),
+      })
+    );
+  }
+  return instructions;
+}
+
+export async function fetchLookupTable(
+  connection: Connection,
+  lookupTable: PublicKey
+): Promise<AddressLookupTableAccount | null> {
+  const res = await connection.getAddressLookupTable(lookupTable);
+  return res.value;
+}
+
+function dedupe(keys: PublicKey[]): PublicKey[] {
+  const seen = new Set<string>();
+  return keys.filter((k) => {
+    const s = k.toBase58();
+    if (seen.has(s)) return false;
+    seen.add(s);
+    return true;
+  });
+}
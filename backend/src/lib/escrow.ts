@@ -29,3 +29,17 @@ export function deriveVaultPdaFromPotId(
   const [pda, bump] = derivePotVaultPda(owner, potHash, escrowProgramId);
   return { pda, bump, potHash };
 }
+
+export function deriveOwnerRegistryPda(owner: PublicKey, escrowProgramId: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), owner.toBuffer()],
+    escrowProgramId
+  );
+}
+
+export function deriveLuloPositionPda(vaultPda: PublicKey, escrowProgramId: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("position"), vaultPda.toBuffer(), Buffer.from([0])],
+    escrowProgramId
+  );
+}